@@ -26,6 +26,7 @@ import {
 	sensorsText,
 	setThemeInFile,
 	widgetTypesText,
+	type CatalogFile,
 	type LayoutFile,
 	type NowPlaying,
 	type StateFile
@@ -53,6 +54,20 @@ function statePath(): string {
 	return process.env.WIDGETSACK_STATE ?? path.join(configDir(), 'mcp', 'state.json');
 }
 
+// ...and the backend sensor catalog whenever it grows (catalog.rs write_catalog_snapshot).
+function catalogPath(): string {
+	return path.join(path.dirname(statePath()), 'sensors.json');
+}
+
+function readCatalog(): CatalogFile {
+	try {
+		const c = JSON.parse(fs.readFileSync(catalogPath(), 'utf8'));
+		return Array.isArray(c) ? (c as CatalogFile) : null;
+	} catch {
+		return null; // app never run / no catalog yet — the tools fall back to the curated list
+	}
+}
+
 function readLayout(): LayoutFile | null {
 	try {
 		return JSON.parse(fs.readFileSync(layoutPath(), 'utf8')) as LayoutFile;
@@ -206,7 +221,8 @@ const TOOLS = [
 	},
 	{
 		name: 'list_sensors',
-		description: 'List the bindable sensor ids (cpu/gpu/memory/network/…) a widget can show.',
+		description:
+			'List the bindable sensors (cpu/gpu/memory/network/…) a widget can show — with label, unit, expected range, kind and source once the app has run.',
 		inputSchema: { type: 'object', properties: {} }
 	},
 	{
@@ -298,9 +314,9 @@ async function callTool(name: string, args: Record<string, unknown>): Promise<un
 	try {
 		switch (name) {
 			case 'list_widget_types':
-				return text(widgetTypesText());
+				return text(widgetTypesText(readCatalog()));
 			case 'list_sensors':
-				return text(sensorsText());
+				return text(sensorsText(readCatalog()));
 			case 'read_sensors':
 				return text(describeSensorsText(readState()));
 			case 'now_playing':
@@ -17,6 +17,7 @@ export const EVENTS = {
 	layoutChanged: 'layout_changed',
 	themesChanged: 'themes_changed',
 	controlsChanged: 'controls_changed',
+	automationsChanged: 'automations_changed',
 	// tray / global-hotkey / single-instance broadcasts (main.rs; toggle_edit is also
 	// emitted by the client's Ctrl+E handler)
 	toggleEdit: 'toggle_edit',
@@ -90,6 +91,92 @@ export const COMMANDS = {
 	systemFonts: 'system_fonts',
 	// sensors / telemetry demand-gating (sensors.rs)
 	setActiveSensors: 'set_active_sensors',
+	// backend sensor catalog (catalog.rs)
+	listSensors: 'list_sensors',
+	// process watch list (procwatch.rs)
+	saveProcwatchConfig: 'save_procwatch_config',
+	procwatchConfigStatus: 'procwatch_config_status',
+	// power/energy accounting (energy.rs)
+	saveEnergyConfig: 'save_energy_config',
+	energyConfigStatus: 'energy_config_status',
+	// derived sensors (derived.rs)
+	saveDerivedConfig: 'save_derived_config',
+	derivedConfigStatus: 'derived_config_status',
+	// synthetic generators (synthetic.rs)
+	saveSyntheticConfig: 'save_synthetic_config',
+	syntheticConfigStatus: 'synthetic_config_status',
+	// last-known-value cache (lastknown.rs)
+	saveLastKnownConfig: 'save_last_known_config',
+	lastKnownConfigStatus: 'last_known_config_status',
+	// source health / staleness (health.rs)
+	sourceHealth: 'source_health',
+	// HTTP/JSON poller (httppoll.rs)
+	saveHttpConfig: 'save_http_config',
+	httpConfigStatus: 'http_config_status',
+	httpTestEndpoint: 'http_test_endpoint',
+	// command source (cmdsource.rs)
+	saveCommandsConfig: 'save_commands_config',
+	commandsConfigStatus: 'commands_config_status',
+	runCommandSource: 'run_command_source',
+	// Prometheus scraper (prom.rs)
+	savePromConfig: 'save_prom_config',
+	promConfigStatus: 'prom_config_status',
+	promTestTarget: 'prom_test_target',
+	// Prometheus /metrics exporter (exporter.rs)
+	saveExporterConfig: 'save_exporter_config',
+	exporterStatus: 'exporter_status',
+	// InfluxDB line-protocol sink (influx.rs)
+	saveInfluxConfig: 'save_influx_config',
+	influxStatus: 'influx_status',
+	// network latency probes (netprobe.rs)
+	saveNetprobeConfig: 'save_netprobe_config',
+	netprobeConfigStatus: 'netprobe_config_status',
+	// TLS certificate expiry (certwatch.rs)
+	saveCertConfig: 'save_cert_config',
+	certConfigStatus: 'cert_config_status',
+	// folder watches (folder.rs)
+	saveFolderConfig: 'save_folder_config',
+	folderConfigStatus: 'folder_config_status',
+	// log-file tail (tail.rs)
+	saveTailConfig: 'save_tail_config',
+	tailConfigStatus: 'tail_config_status',
+	// iCalendar agenda (calendar.rs)
+	saveCalendarConfig: 'save_calendar_config',
+	calendarConfigStatus: 'calendar_config_status',
+	// RSS/Atom feeds (feed.rs)
+	saveFeedConfig: 'save_feed_config',
+	feedConfigStatus: 'feed_config_status',
+	feedMarkRead: 'feed_mark_read',
+	// weather source (weather.rs)
+	saveWeatherConfig: 'save_weather_config',
+	weatherConfigStatus: 'weather_config_status',
+	weatherConnect: 'weather_connect',
+	weatherDisconnect: 'weather_disconnect',
+	// astronomy source (astro.rs)
+	saveAstroConfig: 'save_astro_config',
+	astroConfigStatus: 'astro_config_status',
+	// git repos (gitrepo.rs)
+	saveGitConfig: 'save_git_config',
+	gitConfigStatus: 'git_config_status',
+	// task lists (tasks.rs)
+	saveTasksConfig: 'save_tasks_config',
+	tasksConfigStatus: 'tasks_config_status',
+	taskToggle: 'task_toggle',
+	// threshold alerts (alerts.rs)
+	saveAlertsConfig: 'save_alerts_config',
+	alertsConfigStatus: 'alerts_config_status',
+	activeAlerts: 'active_alerts',
+	// backend automations (automations.rs)
+	automationsStatus: 'automations_status',
+	saveAutomations: 'save_automations',
+	testAutomation: 'test_automation',
+	// telemetry record/replay (recorder.rs)
+	startRecording: 'start_recording',
+	stopRecording: 'stop_recording',
+	listRecordings: 'list_recordings',
+	startReplay: 'start_replay',
+	stopReplay: 'stop_replay',
+	recorderStatus: 'recorder_status',
 	// audio spectrum (audio.rs)
 	startSpectrum: 'start_spectrum',
 	stopSpectrum: 'stop_spectrum',
@@ -2,11 +2,12 @@
 // merge them with whatever the telemetry hub has actually seen live (per-core CPU,
 // GPU presence, etc.). Framework-agnostic, unit-tested.
 
-// Curated, STABLE sensor ids (always offered in the picker). Dynamic ids — per-core cpu.core.N
-// and per-drive disk.<letter>.* — are intentionally NOT listed here; they surface automatically
-// via the live merge in sensorCatalog once the backend emits them (see sensorCatalog). The
-// percent ids (mem.used/swap.used/gpu.vram) are kept for backward compat; the byte absolutes
-// (mem.total, gpu.vram.used, …) are added alongside them. Mirrors widgetsack/src/sensors.rs.
+// Curated, STABLE sensor ids (always offered in the picker). Dynamic ids — per-core cpu.core.N,
+// per-drive disk.<letter>.*, user-defined derived.<name> (derived.rs) and synthetic.<id>
+// (synthetic.rs) — are intentionally NOT listed here; they surface automatically via the live merge
+// in sensorCatalog once the backend emits them (see sensorCatalog). The percent ids
+// (mem.used/swap.used/gpu.vram) are kept for backward compat; the byte absolutes (mem.total,
+// gpu.vram.used, …) are added alongside them. Mirrors widgetsack/src/sensors.rs.
 export const KNOWN_SENSORS = [
 	// CPU (cpu.core.N.freq is dynamic — surfaces via the live merge, like cpu.core.N)
 	'cpu.total',
@@ -49,11 +50,13 @@ export const KNOWN_SENSORS = [
 	'host.idle',
 	'host.handles',
 	'host.threads',
-	// Processes — the busiest + hungriest process (gated)
+	// Processes — the busiest + hungriest process, and the top-N table (gated). Watch-list ids
+	// (proc.<alias>.*) are dynamic — they surface via the live merge.
 	'proc.cpu.top.name',
 	'proc.cpu.top.pct',
 	'proc.mem.top.name',
 	'proc.mem.top.bytes',
+	'proc.top',
 	// GPU (NVIDIA / NVML)
 	'gpu.util',
 	'gpu.mem.util',
@@ -74,9 +77,30 @@ export const KNOWN_SENSORS = [
 	'battery.time',
 	'battery.rate',
 	'battery.capacity.full',
-	'battery.capacity.remaining'
+	'battery.capacity.remaining',
+	// Power / energy (power.cpu only where RAPL is exposed; energy.today.* reset at local midnight)
+	'power.cpu',
+	'power.gpu',
+	'power.system.estimate',
+	'energy.today.kwh',
+	'energy.today.cost'
 ];
 
+/** One row of the backend's authoritative sensor catalog (`list_sensors`, and `mcp/sensors.json`
+ * for the MCP server). Mirrors `SensorInfo` in widgetsack/src/catalog.rs. `live` = emitted this
+ * session; `group` = the demand gate (or proxy source) that decides whether it is produced. */
+export type SensorInfo = {
+	id: string;
+	kind: 'scalar' | 'text' | 'series' | 'json';
+	unit?: string;
+	min?: number;
+	max?: number;
+	source: string;
+	group: string;
+	label: string;
+	live: boolean;
+};
+
 /** Sorted, de-duped union of the curated list and the live sensor ids. */
 export function sensorCatalog(live: string[]): string[] {
 	return Array.from(new Set([...KNOWN_SENSORS, ...live])).sort();
@@ -33,6 +33,21 @@ describe('appendSample', () => {
 		);
 		expect(s.history).toEqual([3]);
 	});
+
+	it('flags stale samples without extending history, and clears on the next live one', () => {
+		const live = (v: number) => ({
+			sensor: 'ha.temp',
+			ts_ms: v,
+			value: { kind: 'scalar' as const, value: v }
+		});
+		let s = appendSample(emptySensorState(), live(1), 10);
+		s = appendSample(s, { ...live(1), stale: true }, 10);
+		expect(s.stale).toBe(true);
+		expect(s.history).toEqual([1]);
+		s = appendSample(s, live(2), 10);
+		expect(s.stale).toBeUndefined();
+		expect(s.history).toEqual([1, 2]);
+	});
 });
 
 describe('createTelemetryHub', () => {
@@ -9,10 +9,15 @@ export type SensorValue =
 	| { kind: 'series'; value: number[] }
 	| { kind: 'json'; value: unknown };
 
-export type SensorSample = { sensor: string; ts_ms: number; value: SensorValue };
+// `stale` is set only on a value that is no longer live — restored after a restart (lastknown.rs),
+// or re-sent by the backend when its source disconnected or it stopped updating (health.rs). Live
+// samples omit it.
+export type SensorSample = { sensor: string; ts_ms: number; value: SensorValue; stale?: boolean };
 export type TelemetryBatch = SensorSample[];
 
-export type SensorState = { value: SensorValue | null; history: number[] };
+// `stale` is true while the latest sample was a stale one (widgets grey the value out); the next
+// live sample clears it.
+export type SensorState = { value: SensorValue | null; history: number[]; stale?: boolean };
 
 // A single frozen empty state, shared so `getSnapshot()` is referentially stable
 // before any sample arrives (required for React's useSyncExternalStore).
@@ -33,6 +38,8 @@ export function appendSample(
 	sample: SensorSample,
 	historyLen: number
 ): SensorState {
+	// A stale re-send repeats an old value: keep it visible, but don't extend the history with it.
+	if (sample.stale) return { value: sample.value, history: state.history, stale: true };
 	const n = numericOf(sample.value);
 	if (n === null) return { value: sample.value, history: state.history };
 	const next = [...state.history, n];
@@ -5,7 +5,8 @@
 import * as tauriEvent from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 import type { TelemetryBatch, TelemetryHub } from '../core/telemetry';
-import { registerSource, type SensorSource } from '../core/plugin';
+import { registerSource, type SensorCatalogEntry, type SensorSource } from '../core/plugin';
+import type { SensorInfo } from '../core/sensors';
 import { isStudioWindow } from '../overlay';
 import { COMMANDS, EVENTS } from '../bridge/contract';
 
@@ -59,11 +60,59 @@ export async function startTelemetrySource(hub: TelemetryHub): Promise<tauriEven
 	};
 }
 
+/** How often the backend catalog is re-read while running — dynamic ids (disks, watch-list aliases)
+ * join it as they are first emitted. */
+const CATALOG_REFRESH_MS = 30_000;
+
+// The backend sensor catalog (catalog.rs `list_sensors`), cached for the synchronous catalogEntries.
+let catalog: SensorInfo[] = [];
+
+/** Re-read the backend sensor catalog. Silent on failure (keeps the prior list). */
+export async function refreshSensorCatalog(): Promise<SensorInfo[]> {
+	try {
+		catalog = await invoke<SensorInfo[]>(COMMANDS.listSensors);
+	} catch {
+		// backend unavailable (dev mock / tests): keep the prior list
+	}
+	return catalog;
+}
+
+/** Catalog sources computed inside the backend — the system loop and what is derived from it or
+ * from local state. Mirrors `is_local_source` in widgetsack/src/catalog.rs. */
+const LOCAL_SOURCES = new Set([
+	'system',
+	'netprobe',
+	'derived',
+	'synthetic',
+	'alerts',
+	'automations',
+	'astro'
+]);
+
+/** The system feed's rows as inspector dropdown entries (label + unit). Proxy-source ids (ha/mqtt/
+ * stocks) are left to those sources' own catalogs, which carry friendlier entity names. */
+function systemCatalogEntries(): SensorCatalogEntry[] {
+	return catalog
+		.filter((s) => LOCAL_SOURCES.has(s.source))
+		.map((s) => ({ id: s.id, label: s.label, unit: s.unit }));
+}
+
 /** The built-in `system` source: the Rust `telemetry` feed as a SensorSource (Phase 8b).
- * Importing this module registers it; plugins (e.g. Home Assistant) register their own. */
+ * Importing this module registers it; plugins (e.g. Home Assistant) register their own. Its catalog
+ * is the backend's `list_sensors`, refreshed while running. */
 export const systemSource: SensorSource = {
 	id: 'system',
-	start: (hub) => startTelemetrySource(hub)
+	start: async (hub) => {
+		const stop = await startTelemetrySource(hub);
+		void refreshSensorCatalog();
+		const timer = setInterval(() => void refreshSensorCatalog(), CATALOG_REFRESH_MS);
+		return () => {
+			clearInterval(timer);
+			stop();
+		};
+	},
+	catalog: () => systemCatalogEntries().map((e) => e.id),
+	catalogEntries: () => systemCatalogEntries()
 };
 
 registerSource(systemSource);
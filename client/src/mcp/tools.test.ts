@@ -39,6 +39,37 @@ describe('mcp tools', () => {
 		expect(sensorsText()).toContain('cpu.total');
 	});
 
+	it('sensorsText prefers the backend catalog (with units + ranges) when present', () => {
+		const catalog = [
+			{
+				id: 'gpu.temp',
+				kind: 'scalar' as const,
+				unit: '°C',
+				source: 'system',
+				group: 'gpu',
+				label: 'GPU temperature',
+				live: true
+			},
+			{
+				id: 'cpu.total',
+				kind: 'scalar' as const,
+				unit: '%',
+				min: 0,
+				max: 100,
+				source: 'system',
+				group: 'always',
+				label: 'CPU usage',
+				live: true
+			}
+		];
+		const txt = sensorsText(catalog);
+		expect(txt).toContain('gpu.temp — GPU temperature [°C] (scalar, system)');
+		expect(txt).toContain('cpu.total — CPU usage [%, 0..100] (scalar, system)');
+		expect(widgetTypesText(catalog)).toContain('gpu.temp');
+		// An empty / missing catalog falls back to the curated list.
+		expect(sensorsText([])).toContain('curated');
+	});
+
 	it('applies ops to the named monitor and preserves library/theme/tokens', () => {
 		const before = fileWith('DELL-1');
 		const { file, monitorKey, result } = applyOpsToFile(
@@ -16,7 +16,7 @@ import {
 	type AssistantOp
 } from '../lib/core/llm';
 import { listMetas } from '../lib/core/widget';
-import { KNOWN_SENSORS } from '../lib/core/sensors';
+import { KNOWN_SENSORS, type SensorInfo } from '../lib/core/sensors';
 
 /** The raw parsed widgets.json shape (frontend-owned): `{ version, monitors, library?, theme?, tokens? }`.
  * We keep it loose and only touch `version` + `monitors`, preserving every other key verbatim. */
@@ -38,14 +38,39 @@ function resolveKey(file: LayoutFile | null, requested?: string): string | null
 	return Object.keys(monitorsOf(file))[0] ?? null;
 }
 
+/** The backend sensor catalog the running app mirrors to `<config>/mcp/sensors.json` (catalog.rs);
+ * null when the app hasn't run yet. */
+export type CatalogFile = SensorInfo[] | null;
+
+/** Bindable ids: the backend catalog when the app has written one, else the curated client list. */
+function bindableIds(catalog: CatalogFile): string[] {
+	return catalog?.length ? catalog.map((s) => s.id) : (KNOWN_SENSORS as unknown as string[]);
+}
+
 /** The widget catalog an agent should read before emitting ops: type, what it binds, its config keys,
  * and a one-line description — the same data the in-app assistant's system prompt is built from. */
-export function widgetTypesText(): string {
-	return buildLayoutSystemPrompt(listMetas(), KNOWN_SENSORS as unknown as string[]);
+export function widgetTypesText(catalog: CatalogFile = null): string {
+	return buildLayoutSystemPrompt(listMetas(), bindableIds(catalog));
 }
 
-/** The curated bindable sensor ids (dynamic per-core/per-disk ids only appear once the app is live). */
-export function sensorsText(): string {
+/** One catalog row for an agent: `id — label [unit, min..max] (kind, source)`. */
+function sensorInfoLine(s: SensorInfo): string {
+	const range =
+		s.min !== undefined || s.max !== undefined ? `${s.min ?? ''}..${s.max ?? ''}` : '';
+	const meta = [s.unit, range].filter(Boolean).join(', ');
+	return `${s.id} — ${s.label}${meta ? ` [${meta}]` : ''} (${s.kind}, ${s.source})`;
+}
+
+/** The bindable sensor ids. With the backend catalog: every id the app can produce, with label, unit,
+ * expected range, kind and source. Without it (app never run): the curated id list. */
+export function sensorsText(catalog: CatalogFile = null): string {
+	if (catalog?.length) {
+		return [
+			`Bindable sensors (${catalog.length}, from the running app's catalog):`,
+			'',
+			...catalog.map(sensorInfoLine)
+		].join('\n');
+	}
 	return [
 		'Bindable sensor ids (curated; the running app also exposes dynamic ids like cpu.core.N, disk.<letter>.*):',
 		'',
@@ -0,0 +1,925 @@
+//! Threshold alerts: rules evaluated against every sample on the telemetry bus (any source, derived
+//! sensors included), so something going wrong is TOLD rather than just drawn.
+//!
+//! Declared in `plugins/alerts.json`:
+//! `{ "rules": [{ "name": "gpu_hot", "when": "gpu.temp > 85 for 30s", "hysteresis": 5,
+//!    "cooldown": "10m" }], "quiet_hours": { "start": "22:00", "end": "07:00" } }`
+//! `when` is `<sensor> <op> <value> [for <duration>]` with `>`/`>=`/`<`/`<=` (numeric) or `==`/`!=`
+//! (numeric, or text: a Text sample, or a Json sample's `state` — e.g. `ha.binary_sensor.door == on`).
+//! The condition must hold continuously for the `for` duration before the alert fires.
+//!
+//! - Hysteresis: a firing numeric alert resolves only once the value is `hysteresis` back past the
+//!   threshold (`> 85` with 5 → resolves below 80), so a value hovering at the line doesn't flap.
+//! - Cooldown: the minimum time between two notifications for the same rule. Only a notification
+//!   actually shown starts it.
+//! - Quiet hours: no desktop notifications inside the (local, possibly overnight) window; the alert
+//!   still fires, logs and shows in `alert.<name>.active`, and if it is still firing when the
+//!   window ends its notification is shown then.
+//! - Staleness: when the rule's sensor goes stale (health.rs: its source went down, or it missed
+//!   its learned update interval) a firing alert resolves instead of staying active forever.
+//!
+//! Firing publishes `alert.<name>.active` = 1 (0 when clear, every second), writes a `warn` log
+//! record, and raises an OS desktop notification (`notify: false` opts a rule out). `active_alerts`
+//! lists what is firing now. Pure seams (`parse_condition`, `RuleState`, `in_quiet_hours`) are
+//! unit-tested without the app.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus;
+use crate::derived::parse_duration;
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+
+/// How often `alert.*.active` is published and saved rules are picked up.
+const TICK_MS: u64 = 1000;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+fn default_notify() -> bool {
+    true
+}
+
+/// One alert rule. `cooldown` is a duration string (`10m`); empty = notify on every firing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub when: String,
+    #[serde(default)]
+    pub hysteresis: f64,
+    #[serde(default)]
+    pub cooldown: String,
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+    /// Optional notification body; defaults to the condition and the value that tripped it.
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Local `HH:MM` window with no notifications; `start > end` spans midnight.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+/// `plugins/alerts.json`. `#[serde(default)]` so a partial file parses.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertsConfig {
+    pub rules: Vec<AlertRule>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// One currently-firing alert, as returned by `active_alerts`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveAlert {
+    pub name: String,
+    pub sensor: String,
+    pub when: String,
+    pub since_ms: u64,
+    /// The value that tripped it, rendered for display.
+    pub value: String,
+}
+
+/// Managed state: the live rules (+ a generation counter the engine polls, like derived.rs) and the
+/// firing list the engine keeps current for `active_alerts`.
+#[derive(Default)]
+pub struct Alerts {
+    config: Mutex<AlertsConfig>,
+    generation: AtomicU64,
+    active: Mutex<Vec<ActiveAlert>>,
+}
+
+impl Alerts {
+    fn replace(&self, cfg: AlertsConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn alerts_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("alerts.json"))
+}
+
+pub fn load_alerts_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<AlertsConfig>, String> {
+    let path = alerts_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed rules from disk; bad hand-edited rules are logged and skipped.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_alerts_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("alerts", "skipping alert rule")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<Alerts>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("alerts", "failed to read alerts.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+// ---- conditions (pure) ----
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub sensor: String,
+    pub op: Op,
+    pub operand: Operand,
+    /// How long the condition must hold before firing (ms).
+    pub hold_ms: u64,
+}
+
+/// Parse `<sensor> <op> <value> [for <duration>]`.
+pub fn parse_condition(src: &str) -> Result<Condition, String> {
+    let (expr, hold_ms) = match src.rsplit_once(" for ") {
+        Some((expr, dur)) => (
+            expr,
+            parse_duration(dur).ok_or_else(|| format!("bad duration `{}`", dur.trim()))?,
+        ),
+        None => (src, 0),
+    };
+    // Two-char operators first so `>=` isn't read as `>` followed by `=85`.
+    let (sensor, op, value) = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+    ]
+    .into_iter()
+    .find_map(|(tok, op)| expr.split_once(tok).map(|(l, r)| (l.trim(), op, r.trim())))
+    .ok_or_else(|| format!("expected `sensor > value`, got `{}`", src.trim()))?;
+    if sensor.is_empty() || sensor.contains(char::is_whitespace) {
+        return Err(format!("bad sensor id `{sensor}`"));
+    }
+    if value.is_empty() {
+        return Err("missing comparison value".into());
+    }
+    let operand = match value.parse::<f64>() {
+        Ok(n) => Operand::Number(n),
+        Err(_) if matches!(op, Op::Eq | Op::Ne) => {
+            Operand::Text(value.trim_matches(|c| c == '"' || c == '\'').to_string())
+        }
+        Err(_) => return Err(format!("`{value}` is not a number")),
+    };
+    Ok(Condition {
+        sensor: sensor.to_string(),
+        op,
+        operand,
+        hold_ms,
+    })
+}
+
+/// A sample's value as the condition sees it: a number, or text (a Json sample's `state`).
+fn observed(value: &SensorValue) -> Option<Operand> {
+    match value {
+        SensorValue::Scalar(n) => Some(Operand::Number(*n)),
+        SensorValue::Text(t) => Some(match t.trim().parse::<f64>() {
+            Ok(n) => Operand::Number(n),
+            Err(_) => Operand::Text(t.clone()),
+        }),
+        SensorValue::Json(v) => match v.get("state")? {
+            serde_json::Value::String(s) => Some(Operand::Text(s.clone())),
+            serde_json::Value::Number(n) => n.as_f64().map(Operand::Number),
+            _ => None,
+        },
+        SensorValue::Series(_) => None,
+    }
+}
+
+fn render(v: &Operand) -> String {
+    match v {
+        Operand::Number(n) => format!("{}", (n * 100.0).round() / 100.0),
+        Operand::Text(t) => t.clone(),
+    }
+}
+
+impl Condition {
+    /// Does `v` trip the condition? `None` when the types don't compare (text vs `> 85`).
+    fn tripped(&self, v: &Operand) -> Option<bool> {
+        Some(match (&self.operand, v) {
+            (Operand::Number(t), Operand::Number(n)) => match self.op {
+                Op::Gt => n > t,
+                Op::Ge => n >= t,
+                Op::Lt => n < t,
+                Op::Le => n <= t,
+                Op::Eq => n == t,
+                Op::Ne => n != t,
+            },
+            (Operand::Text(t), Operand::Text(s)) => {
+                let eq = s.eq_ignore_ascii_case(t);
+                match self.op {
+                    Op::Eq => eq,
+                    Op::Ne => !eq,
+                    _ => return None,
+                }
+            }
+            // `== on` against a number: never equal.
+            (Operand::Text(_), Operand::Number(_)) => self.op == Op::Ne,
+            (Operand::Number(_), Operand::Text(_)) => return None,
+        })
+    }
+
+    /// Has a firing alert cleared? Ordered comparisons require the value `hysteresis` back past
+    /// the threshold; equality ones clear as soon as the condition stops holding.
+    fn cleared(&self, v: &Operand, hysteresis: f64) -> Option<bool> {
+        match (&self.operand, v) {
+            (Operand::Number(t), Operand::Number(n)) => Some(match self.op {
+                Op::Gt => *n <= t - hysteresis,
+                Op::Ge => *n < t - hysteresis,
+                Op::Lt => *n >= t + hysteresis,
+                Op::Le => *n > t + hysteresis,
+                Op::Eq | Op::Ne => !self.tripped(v)?,
+            }),
+            _ => self.tripped(v).map(|t| !t),
+        }
+    }
+}
+
+/// Minutes since local midnight for `HH:MM`.
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+/// Is `now` (minutes since local midnight) inside `[start, end)`? A window with `start > end`
+/// wraps midnight (22:00–07:00); `start == end` is empty.
+fn in_quiet_hours(start: u32, end: u32, now: u32) -> bool {
+    if start <= end {
+        (start..end).contains(&now)
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// Sanitize names, compile conditions and durations, drop duplicates. Returns the kept config plus
+/// one message per rejected entry.
+fn normalize_config(cfg: AlertsConfig) -> (AlertsConfig, Vec<String>) {
+    let mut rules: Vec<AlertRule> = Vec::new();
+    let mut errors = Vec::new();
+    for rule in cfg.rules {
+        let Some(name) = id_segment(&rule.name) else {
+            errors.push(format!("`{}`: invalid name", rule.name));
+            continue;
+        };
+        if rules.iter().any(|r| r.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        if let Err(err) = parse_condition(&rule.when) {
+            errors.push(format!("`{name}`: {err}"));
+            continue;
+        }
+        let cooldown = rule.cooldown.trim().to_string();
+        if !cooldown.is_empty() && parse_duration(&cooldown).is_none() {
+            errors.push(format!("`{name}`: bad cooldown `{cooldown}`"));
+            continue;
+        }
+        rules.push(AlertRule {
+            name,
+            when: rule.when.trim().to_string(),
+            hysteresis: rule.hysteresis.abs(),
+            cooldown,
+            ..rule
+        });
+    }
+    let quiet_hours = match cfg.quiet_hours {
+        Some(q) if parse_hhmm(&q.start).is_none() || parse_hhmm(&q.end).is_none() => {
+            errors.push(format!(
+                "quiet hours: expected HH:MM, got `{}`–`{}`",
+                q.start, q.end
+            ));
+            None
+        }
+        other => other,
+    };
+    (AlertsConfig { rules, quiet_hours }, errors)
+}
+
+// ---- per-rule state machine (pure) ----
+
+/// What a sample did to a rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transition {
+    Fired { value: String, notify: bool },
+    Resolved,
+}
+
+/// One rule's evaluation state.
+#[derive(Debug)]
+pub struct RuleState {
+    rule: AlertRule,
+    cond: Condition,
+    cooldown_ms: u64,
+    /// When the condition started holding (while not yet firing).
+    pending_since: Option<u64>,
+    /// When the alert fired, while it is firing.
+    active_since: Option<u64>,
+    last_value: String,
+    /// When a notification was last shown (the cooldown runs from here).
+    last_notified: Option<u64>,
+    /// A due notification held back by quiet hours, shown once they end if still firing.
+    deferred: bool,
+}
+
+impl RuleState {
+    pub fn new(rule: AlertRule) -> Result<Self, String> {
+        let cond = parse_condition(&rule.when)?;
+        let cooldown_ms = parse_duration(&rule.cooldown).unwrap_or(0);
+        Ok(RuleState {
+            rule,
+            cond,
+            cooldown_ms,
+            pending_since: None,
+            active_since: None,
+            last_value: String::new(),
+            last_notified: None,
+            deferred: false,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_since.is_some()
+    }
+
+    /// Feed one sample of this rule's sensor. `notify` on a firing says whether a notification is
+    /// due (the rule wants one and the cooldown has passed); quiet hours are the caller's call, and
+    /// the caller reports a notification it actually showed with `notified`.
+    pub fn ingest(&mut self, ts: u64, value: &SensorValue) -> Option<Transition> {
+        let v = observed(value)?;
+        if self.is_active() {
+            if self.cond.cleared(&v, self.rule.hysteresis)? {
+                self.active_since = None;
+                self.pending_since = None;
+                self.deferred = false;
+                return Some(Transition::Resolved);
+            }
+            self.last_value = render(&v);
+            return None;
+        }
+        if !self.cond.tripped(&v)? {
+            self.pending_since = None;
+            return None;
+        }
+        let since = *self.pending_since.get_or_insert(ts);
+        if ts.saturating_sub(since) < self.cond.hold_ms {
+            return None;
+        }
+        self.active_since = Some(ts);
+        self.last_value = render(&v);
+        let notify = self.rule.notify
+            && self
+                .last_notified
+                .is_none_or(|at| ts.saturating_sub(at) >= self.cooldown_ms);
+        Some(Transition::Fired {
+            value: self.last_value.clone(),
+            notify,
+        })
+    }
+
+    /// A notification for this rule was shown at `ts`: start the cooldown.
+    pub fn notified(&mut self, ts: u64) {
+        self.last_notified = Some(ts);
+        self.deferred = false;
+    }
+
+    /// The sensor went stale: a firing alert resolves (its value no longer says anything), and a
+    /// half-held condition starts over.
+    pub fn expire(&mut self) -> Option<Transition> {
+        self.pending_since = None;
+        self.deferred = false;
+        self.active_since.take().map(|_| Transition::Resolved)
+    }
+
+    fn active(&self) -> Option<ActiveAlert> {
+        Some(ActiveAlert {
+            name: self.rule.name.clone(),
+            sensor: self.cond.sensor.clone(),
+            when: self.rule.when.clone(),
+            since_ms: self.active_since?,
+            value: self.last_value.clone(),
+        })
+    }
+}
+
+/// Build rule states for a new config, carrying over the state of rules whose definition is
+/// unchanged (a save doesn't re-fire everything that is already firing).
+fn reconfigure(old: Vec<RuleState>, rules: &[AlertRule]) -> Vec<RuleState> {
+    let mut old = old;
+    rules
+        .iter()
+        .filter_map(|rule| match old.iter().position(|s| &s.rule == rule) {
+            Some(pos) => Some(old.swap_remove(pos)),
+            None => RuleState::new(rule.clone()).ok(),
+        })
+        .collect()
+}
+
+// ---- the engine task ----
+
+fn notify<R: Runtime>(app: &AppHandle<R>, rule: &AlertRule, value: &str) {
+    let body = if rule.message.trim().is_empty() {
+        format!("{} (now {value})", rule.when)
+    } else {
+        rule.message.clone()
+    };
+    if let Err(err) = app
+        .notification()
+        .builder()
+        .title(format!("Alert: {}", rule.name))
+        .body(body)
+        .show()
+    {
+        log::warn("alerts", "failed to show desktop notification")
+            .field("error", err.to_string())
+            .emit();
+    }
+}
+
+fn quiet_now(quiet: Option<&QuietHours>) -> bool {
+    let Some(q) = quiet else {
+        return false;
+    };
+    let (Some(start), Some(end)) = (parse_hhmm(&q.start), parse_hhmm(&q.end)) else {
+        return false;
+    };
+    let now = chrono::Local::now();
+    in_quiet_hours(start, end, now.hour() * 60 + now.minute())
+}
+
+/// Run each sample through the rules for its sensor and carry out the side effects of any
+/// transition (log record, `notify` — or, inside quiet hours, a deferred one). Returns true when
+/// the firing set changed.
+fn ingest_batch(
+    states: &mut [RuleState],
+    quiet: bool,
+    batch: &[SensorSample],
+    mut notify: impl FnMut(&AlertRule, &str),
+) -> bool {
+    let mut changed = false;
+    for s in batch {
+        for st in states.iter_mut().filter(|st| st.cond.sensor == s.sensor) {
+            // A stale value (a lastknown restore, or health.rs flagging a silent sensor) says
+            // nothing about the present: it never fires, and it resolves what is firing.
+            let transition = if s.stale {
+                st.expire()
+            } else {
+                st.ingest(s.ts_ms, &s.value)
+            };
+            match transition {
+                Some(Transition::Fired { value, notify: due }) => {
+                    changed = true;
+                    log::warn("alerts", "alert fired")
+                        .field("name", st.rule.name.clone())
+                        .field("when", st.rule.when.clone())
+                        .field("value", value.clone())
+                        .emit();
+                    if due && quiet {
+                        st.deferred = true;
+                    } else if due {
+                        notify(&st.rule, &value);
+                        st.notified(s.ts_ms);
+                    }
+                }
+                Some(Transition::Resolved) => {
+                    changed = true;
+                    log::info("alerts", "alert resolved")
+                        .field("name", st.rule.name.clone())
+                        .field("stale", s.stale)
+                        .emit();
+                }
+                None => {}
+            }
+        }
+    }
+    changed
+}
+
+/// Show the notifications quiet hours held back for alerts that are still firing.
+fn notify_deferred(
+    states: &mut [RuleState],
+    quiet: bool,
+    ts: u64,
+    mut notify: impl FnMut(&AlertRule, &str),
+) {
+    if quiet {
+        return;
+    }
+    for st in states.iter_mut().filter(|st| st.deferred && st.is_active()) {
+        notify(&st.rule, &st.last_value);
+        st.notified(ts);
+    }
+}
+
+/// The alert task: subscribe to the bus, evaluate every sample, publish `alert.<name>.active` once
+/// a second. Picks up saved rules via the state's generation counter. Runs until the bus closes.
+pub async fn run_alerts<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let mut states: Vec<RuleState> = Vec::new();
+    let mut quiet: Option<QuietHours> = None;
+    let mut generation = u64::MAX;
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        let changed = tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => {
+                    let batch = bus::actionable(&batch, bus::replaying(&app));
+                    let quiet = quiet_now(quiet.as_ref());
+                    ingest_batch(&mut states, quiet, batch, |rule, value| {
+                        notify(&app, rule, value)
+                    })
+                }
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("alerts", "alert engine fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                    false
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticker.tick() => {
+                let state: State<Alerts> = app.state();
+                let current = state.generation.load(Ordering::Relaxed);
+                let mut changed = false;
+                if current != generation {
+                    let cfg = state.config.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                    states = reconfigure(std::mem::take(&mut states), &cfg.rules);
+                    quiet = cfg.quiet_hours;
+                    generation = current;
+                    changed = true;
+                }
+                let ts = now_ms();
+                notify_deferred(&mut states, quiet_now(quiet.as_ref()), ts, |rule, value| {
+                    notify(&app, rule, value)
+                });
+                let batch: Vec<SensorSample> = states
+                    .iter()
+                    .map(|st| {
+                        let on = if st.is_active() { 1.0 } else { 0.0 };
+                        SensorSample::scalar(format!("alert.{}.active", st.rule.name), ts, on)
+                    })
+                    .collect();
+                if !batch.is_empty() {
+                    let _ = bus::publish(&app, &batch);
+                }
+                changed
+            }
+        };
+        if changed {
+            let firing: Vec<ActiveAlert> = states.iter().filter_map(RuleState::active).collect();
+            *app.state::<Alerts>()
+                .active
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = firing;
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/alerts.json` and swap the live rules (picked up within a second; unchanged
+/// rules keep their firing state). Studio-window-guarded; any invalid rule rejects the save.
+#[tauri::command]
+pub async fn save_alerts_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Alerts>,
+    config: AlertsConfig,
+) -> Result<AlertsConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_alerts_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(config);
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = alerts_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    state.replace(cfg.clone());
+    Ok(cfg)
+}
+
+/// The live alert rules and quiet hours.
+#[tauri::command]
+pub fn alerts_config_status(state: State<'_, Alerts>) -> AlertsConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// The alerts firing right now, oldest first.
+#[tauri::command]
+pub fn active_alerts(state: State<'_, Alerts>) -> Vec<ActiveAlert> {
+    let mut firing = state
+        .active
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    firing.sort_by_key(|a| a.since_ms);
+    firing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(when: &str) -> AlertRule {
+        AlertRule {
+            name: "r".into(),
+            when: when.into(),
+            hysteresis: 0.0,
+            cooldown: String::new(),
+            notify: true,
+            message: String::new(),
+        }
+    }
+
+    fn num(n: f64) -> SensorValue {
+        SensorValue::Scalar(n)
+    }
+
+    #[test]
+    fn parses_conditions() {
+        assert_eq!(
+            parse_condition("gpu.temp > 85 for 30s").unwrap(),
+            Condition {
+                sensor: "gpu.temp".into(),
+                op: Op::Gt,
+                operand: Operand::Number(85.0),
+                hold_ms: 30_000
+            }
+        );
+        assert_eq!(parse_condition("disk.c.used.pct >= 95").unwrap().op, Op::Ge);
+        assert_eq!(
+            parse_condition("ha.binary_sensor.door == on")
+                .unwrap()
+                .operand,
+            Operand::Text("on".into())
+        );
+        for bad in [
+            "gpu.temp",
+            "gpu.temp > hot",
+            "> 5",
+            "x > 5 for ever",
+            "x ==",
+            "a b > 1",
+        ] {
+            assert!(parse_condition(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn fires_only_after_holding_for_the_duration() {
+        let mut st = RuleState::new(rule("gpu.temp > 85 for 30s")).unwrap();
+        assert_eq!(st.ingest(0, &num(90.0)), None);
+        assert_eq!(st.ingest(20_000, &num(90.0)), None);
+        assert_eq!(st.ingest(25_000, &num(80.0)), None); // dipped: the hold restarts
+        assert_eq!(st.ingest(30_000, &num(90.0)), None);
+        assert!(matches!(
+            st.ingest(60_000, &num(91.0)),
+            Some(Transition::Fired { notify: true, .. })
+        ));
+        assert_eq!(st.active().unwrap().since_ms, 60_000);
+    }
+
+    #[test]
+    fn hysteresis_keeps_a_hovering_value_from_flapping() {
+        let mut st = RuleState::new(AlertRule {
+            hysteresis: 5.0,
+            ..rule("gpu.temp > 85")
+        })
+        .unwrap();
+        assert!(matches!(
+            st.ingest(0, &num(86.0)),
+            Some(Transition::Fired { .. })
+        ));
+        assert_eq!(st.ingest(1, &num(84.0)), None); // below the line but inside the band
+        assert_eq!(st.ingest(2, &num(81.0)), None);
+        assert_eq!(st.ingest(3, &num(80.0)), Some(Transition::Resolved));
+        assert!(!st.is_active());
+    }
+
+    #[test]
+    fn a_replayed_batch_neither_fires_nor_notifies() {
+        let mut states = vec![RuleState::new(rule("gpu.temp > 85")).unwrap()];
+        let hot = [SensorSample::scalar("gpu.temp", 0, 95.0)];
+        let mut sent = Vec::new();
+        let replayed = crate::bus::actionable(&hot, true);
+        assert!(!ingest_batch(&mut states, false, replayed, |r, v| {
+            sent.push((r.name.clone(), v.to_string()))
+        }));
+        assert!(sent.is_empty());
+        assert!(!states[0].is_active());
+
+        let live = crate::bus::actionable(&hot, false);
+        assert!(ingest_batch(&mut states, false, live, |r, v| {
+            sent.push((r.name.clone(), v.to_string()))
+        }));
+        assert_eq!(sent, [("r".to_string(), "95".to_string())]);
+    }
+
+    #[test]
+    fn cooldown_suppresses_repeat_notifications_not_firing() {
+        let mut st = RuleState::new(AlertRule {
+            cooldown: "10m".into(),
+            ..rule("cpu.total > 90")
+        })
+        .unwrap();
+        assert!(matches!(
+            st.ingest(0, &num(95.0)),
+            Some(Transition::Fired { notify: true, .. })
+        ));
+        st.notified(0);
+        st.ingest(1_000, &num(50.0));
+        assert!(matches!(
+            st.ingest(2_000, &num(95.0)),
+            Some(Transition::Fired { notify: false, .. })
+        ));
+        st.ingest(3_000, &num(50.0));
+        assert!(matches!(
+            st.ingest(601_000, &num(95.0)),
+            Some(Transition::Fired { notify: true, .. })
+        ));
+    }
+
+    #[test]
+    fn quiet_hours_defer_the_notification_without_spending_the_cooldown() {
+        let mut states = vec![
+            RuleState::new(AlertRule {
+                cooldown: "10m".into(),
+                ..rule("cpu.total > 90")
+            })
+            .unwrap(),
+        ];
+        let mut sent = Vec::new();
+        let hot = [SensorSample::scalar("cpu.total", 0, 95.0)];
+        assert!(ingest_batch(&mut states, true, &hot, |r, _| {
+            sent.push(r.name.clone())
+        }));
+        assert!(sent.is_empty());
+        // Still quiet: nothing yet. Quiet hours over and still firing: shown now.
+        notify_deferred(&mut states, true, 1_000, |r, _| sent.push(r.name.clone()));
+        assert!(sent.is_empty());
+        notify_deferred(&mut states, false, 2_000, |r, _| sent.push(r.name.clone()));
+        assert_eq!(sent, ["r"]);
+        notify_deferred(&mut states, false, 3_000, |r, _| sent.push(r.name.clone()));
+        assert_eq!(sent.len(), 1);
+
+        // A suppressed firing that resolves during quiet hours is never shown, and doesn't start
+        // the cooldown either.
+        let mut states = vec![RuleState::new(rule("cpu.total > 90")).unwrap()];
+        let cool = [SensorSample::scalar("cpu.total", 1, 50.0)];
+        ingest_batch(&mut states, true, &hot, |_, _| {});
+        ingest_batch(&mut states, true, &cool, |_, _| {});
+        notify_deferred(&mut states, false, 2, |r, _| sent.push(r.name.clone()));
+        assert_eq!(sent.len(), 1);
+        assert_eq!(states[0].last_notified, None);
+    }
+
+    #[test]
+    fn a_stale_sensor_resolves_its_alert() {
+        let mut states = vec![RuleState::new(rule("gpu.temp > 85")).unwrap()];
+        let hot = [SensorSample::scalar("gpu.temp", 0, 95.0)];
+        assert!(ingest_batch(&mut states, false, &hot, |_, _| {}));
+        assert!(states[0].is_active());
+        let stale = [SensorSample {
+            stale: true,
+            ..SensorSample::scalar("gpu.temp", 5_000, 95.0)
+        }];
+        assert!(ingest_batch(&mut states, false, &stale, |_, _| {}));
+        assert!(!states[0].is_active());
+        // A stale value never fires on its own.
+        assert!(!ingest_batch(&mut states, false, &stale, |_, _| {}));
+        assert!(!states[0].is_active());
+    }
+
+    #[test]
+    fn text_conditions_read_json_state() {
+        let mut st = RuleState::new(rule("ha.binary_sensor.door == on")).unwrap();
+        let door = |s: &str| SensorValue::Json(serde_json::json!({ "state": s }));
+        assert_eq!(st.ingest(0, &door("off")), None);
+        assert!(matches!(
+            st.ingest(1, &door("ON")),
+            Some(Transition::Fired { .. })
+        ));
+        assert_eq!(st.ingest(2, &door("off")), Some(Transition::Resolved));
+        // A numeric rule ignores values it can't compare.
+        let mut n = RuleState::new(rule("x > 1")).unwrap();
+        assert_eq!(n.ingest(0, &SensorValue::Text("n/a".into())), None);
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight() {
+        let (start, end) = (parse_hhmm("22:00").unwrap(), parse_hhmm("07:00").unwrap());
+        assert!(in_quiet_hours(start, end, 23 * 60));
+        assert!(in_quiet_hours(start, end, 3 * 60));
+        assert!(!in_quiet_hours(start, end, 12 * 60));
+        assert!(in_quiet_hours(60, 120, 90) && !in_quiet_hours(60, 120, 120));
+        assert!(!in_quiet_hours(60, 60, 60));
+        assert_eq!(parse_hhmm("24:00"), None);
+    }
+
+    #[test]
+    fn reconfigure_keeps_unchanged_rules_firing() {
+        let hot = AlertRule {
+            name: "hot".into(),
+            ..rule("gpu.temp > 85")
+        };
+        let mut st = RuleState::new(hot.clone()).unwrap();
+        st.ingest(0, &num(90.0));
+        let kept = reconfigure(vec![st], std::slice::from_ref(&hot));
+        assert!(kept[0].is_active());
+        let changed = AlertRule {
+            hysteresis: 2.0,
+            ..hot
+        };
+        assert!(!reconfigure(kept, &[changed])[0].is_active());
+    }
+
+    #[test]
+    fn normalize_config_reports_bad_rules_and_quiet_hours() {
+        let (cfg, errors) = normalize_config(AlertsConfig {
+            rules: vec![
+                AlertRule {
+                    name: "GPU Hot".into(),
+                    ..rule("gpu.temp > 85")
+                },
+                AlertRule {
+                    name: "gpu_hot".into(),
+                    ..rule("gpu.temp > 90")
+                },
+                AlertRule {
+                    name: "slow".into(),
+                    cooldown: "soon".into(),
+                    ..rule("x > 1")
+                },
+            ],
+            quiet_hours: Some(QuietHours {
+                start: "late".into(),
+                end: "07:00".into(),
+            }),
+        });
+        assert_eq!(cfg.rules.len(), 1);
+        assert_eq!(cfg.rules[0].name, "gpu_hot");
+        assert_eq!(cfg.quiet_hours, None);
+        assert_eq!(errors.len(), 3);
+    }
+}
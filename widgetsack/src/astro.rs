@@ -0,0 +1,524 @@
+//! Offline astronomy source: sun and moon for a configured location, computed locally (no network,
+//! no ephemeris files) and published every `TICK` as `astro.*`:
+//!   `astro.dawn` / `.sunrise` / `.noon` / `.sunset` / `.dusk`   today's times, RFC 3339 in local
+//!                                   time (empty when the event doesn't happen, e.g. polar day)
+//!   `astro.<event>.in_secs`         seconds until its next occurrence (−1 when none within a day)
+//!   `astro.day_length`              seconds from sunrise to sunset (0 / 86400 at the poles)
+//!   `astro.sun.elevation`           degrees above the horizon (negative below)
+//!   `astro.sun.azimuth`             degrees clockwise from north
+//!   `astro.sun.up`                  1 between sunrise and sunset
+//!   `astro.sun.phase`               `day` / `civil twilight` / `nautical twilight` /
+//!                                   `astronomical twilight` / `night`
+//!   `astro.moon.phase`              0 = new, 0.5 = full, → 1 at the next new moon
+//!   `astro.moon.illumination`       illuminated fraction of the disc, %
+//!   `astro.moon.name`               `Waxing crescent`, `Full moon`, …
+//! Dawn/dusk are civil (sun 6° below the horizon); sunrise/sunset use the standard −0.833°
+//! (refraction plus the sun's radius). Accuracy is about a minute for the sun and a few hours of
+//! moon age — plenty for an overlay or a theme switch.
+//!
+//! Configured in `plugins/astro.json`: `{ "latitude": 51.5074, "longitude": -0.1278 }`.
+//! Like derived.rs, it's computed in the backend, so it counts as the system feed. The same times
+//! drive automations.rs' `sun` trigger (`{ "type": "sun", "event": "sunset", "offset": "-15m" }`);
+//! `astro.sun.up` also works as a `sensor` trigger. The solar and lunar math is unit-tested.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::log;
+use crate::sensors::SensorSample;
+
+/// How often the sensors are recomputed.
+const TICK: Duration = Duration::from_secs(60);
+const DAY_MS: i64 = 86_400_000;
+const HOUR_MS: f64 = 3_600_000.0;
+/// Sun altitudes (degrees) for the events and twilight bands.
+const RISE_SET: f64 = -0.833;
+const CIVIL: f64 = -6.0;
+const NAUTICAL: f64 = -12.0;
+const ASTRONOMICAL: f64 = -18.0;
+/// The sun events, in the order they happen.
+pub const EVENTS: [&str; 5] = ["dawn", "sunrise", "noon", "sunset", "dusk"];
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// `plugins/astro.json`: the observer's location.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AstroConfig {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Managed state: the configured location (`None` until one is saved).
+#[derive(Default)]
+pub struct AstroSource {
+    config: Mutex<Option<AstroConfig>>,
+}
+
+impl AstroSource {
+    fn replace(&self, cfg: Option<AstroConfig>) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+    }
+
+    fn location(&self) -> Option<AstroConfig> {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("astro.json"))
+}
+
+pub fn load_astro_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<AstroConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn validate(cfg: &AstroConfig) -> Result<(), String> {
+    if !cfg.latitude.is_finite() || !(-90.0..=90.0).contains(&cfg.latitude) {
+        return Err("latitude must be between -90 and 90".into());
+    }
+    if !cfg.longitude.is_finite() || !(-180.0..=180.0).contains(&cfg.longitude) {
+        return Err("longitude must be between -180 and 180".into());
+    }
+    Ok(())
+}
+
+// ---- solar / lunar math (pure) ----
+
+fn sin_d(deg: f64) -> f64 {
+    deg.to_radians().sin()
+}
+fn cos_d(deg: f64) -> f64 {
+    deg.to_radians().cos()
+}
+
+/// Days since J2000.0 (2000-01-01 12:00 UTC).
+fn j2000_days(ms: i64) -> f64 {
+    ms as f64 / DAY_MS as f64 - 10_957.5
+}
+
+/// The sun's declination and right ascension (degrees) and the equation of time (minutes) —
+/// the low-precision almanac formulas, good to about 0.01° this century.
+fn sun_coords(ms: i64) -> (f64, f64, f64) {
+    let d = j2000_days(ms);
+    let g = 357.529 + 0.985_600_28 * d;
+    let q = 280.459 + 0.985_647_36 * d;
+    let l = q + 1.915 * sin_d(g) + 0.020 * sin_d(2.0 * g);
+    let e = 23.439 - 0.000_000_36 * d;
+    let ra = (cos_d(e) * sin_d(l)).atan2(cos_d(l)).to_degrees();
+    let dec = (sin_d(e) * sin_d(l)).asin().to_degrees();
+    let eqt = ((q - ra + 180.0).rem_euclid(360.0) - 180.0) * 4.0;
+    (dec, ra, eqt)
+}
+
+/// The sun's elevation and azimuth (degrees, azimuth clockwise from north) at `ms`.
+pub fn sun_position(ms: i64, lat: f64, lon: f64) -> (f64, f64) {
+    let (dec, ra, _) = sun_coords(ms);
+    let gmst = 280.460_618_37 + 360.985_647_366_29 * j2000_days(ms);
+    let h = gmst + lon - ra;
+    let elevation = (sin_d(lat) * sin_d(dec) + cos_d(lat) * cos_d(dec) * cos_d(h))
+        .asin()
+        .to_degrees();
+    let azimuth = sin_d(h)
+        .atan2(cos_d(h) * sin_d(lat) - dec.to_radians().tan() * cos_d(lat))
+        .to_degrees()
+        + 180.0;
+    (elevation, azimuth.rem_euclid(360.0))
+}
+
+/// One day's sun events (Unix ms, UTC). `None` where the sun never crosses that altitude.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SunDay {
+    pub dawn: Option<i64>,
+    pub sunrise: Option<i64>,
+    pub noon: Option<i64>,
+    pub sunset: Option<i64>,
+    pub dusk: Option<i64>,
+}
+
+impl SunDay {
+    pub fn event(&self, name: &str) -> Option<i64> {
+        match name {
+            "dawn" => self.dawn,
+            "sunrise" => self.sunrise,
+            "noon" => self.noon,
+            "sunset" => self.sunset,
+            "dusk" => self.dusk,
+            _ => None,
+        }
+    }
+}
+
+/// When the sun crosses `altitude` before (`rising`) or after solar `noon`, refined against the
+/// declination at the crossing itself.
+fn crossing(noon: i64, lat: f64, altitude: f64, rising: bool) -> Option<i64> {
+    let mut t = noon;
+    for _ in 0..3 {
+        let (dec, _, _) = sun_coords(t);
+        let cos_h = (sin_d(altitude) - sin_d(lat) * sin_d(dec)) / (cos_d(lat) * cos_d(dec));
+        if !(-1.0..=1.0).contains(&cos_h) {
+            return None;
+        }
+        let offset = (cos_h.acos().to_degrees() / 15.0 * HOUR_MS) as i64;
+        t = if rising { noon - offset } else { noon + offset };
+    }
+    Some(t)
+}
+
+/// The sun events of the solar day `date` at the location (the day whose noon falls on that date
+/// at that longitude, i.e. the local calendar day).
+pub fn sun_day(date: NaiveDate, lat: f64, lon: f64) -> SunDay {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .map_or(0, |d| d.and_utc().timestamp_millis());
+    let mean_noon = midnight + ((12.0 - lon / 15.0) * HOUR_MS) as i64;
+    let mut noon = mean_noon;
+    for _ in 0..2 {
+        noon = mean_noon - (sun_coords(noon).2 * 60_000.0) as i64;
+    }
+    SunDay {
+        dawn: crossing(noon, lat, CIVIL, true),
+        sunrise: crossing(noon, lat, RISE_SET, true),
+        noon: Some(noon),
+        sunset: crossing(noon, lat, RISE_SET, false),
+        dusk: crossing(noon, lat, CIVIL, false),
+    }
+}
+
+/// The twilight band for a sun elevation.
+fn sun_phase(elevation: f64) -> &'static str {
+    if elevation >= RISE_SET {
+        "day"
+    } else if elevation >= CIVIL {
+        "civil twilight"
+    } else if elevation >= NAUTICAL {
+        "nautical twilight"
+    } else if elevation >= ASTRONOMICAL {
+        "astronomical twilight"
+    } else {
+        "night"
+    }
+}
+
+/// The moon's phase (0 new → 0.5 full → 1) and illuminated fraction (0..1), from the mean
+/// elongation plus its six largest periodic terms (Meeus ch. 48).
+pub fn moon(ms: i64) -> (f64, f64) {
+    let t = j2000_days(ms) / 36_525.0;
+    let d = 297.850_192_1 + 445_267.111_403_4 * t;
+    let m = 357.529_109_2 + 35_999.050_290_9 * t;
+    let mp = 134.963_396_4 + 477_198.867_505_5 * t;
+    let phase_angle = 180.0 - d - 6.289 * sin_d(mp) + 2.100 * sin_d(m)
+        - 1.274 * sin_d(2.0 * d - mp)
+        - 0.658 * sin_d(2.0 * d)
+        - 0.214 * sin_d(2.0 * mp)
+        - 0.110 * sin_d(d);
+    let phase = (180.0 - phase_angle).rem_euclid(360.0) / 360.0;
+    let illumination = (1.0 + cos_d(phase_angle)) / 2.0;
+    (phase, illumination)
+}
+
+/// The conventional name for a phase (each named phase spans an eighth of the cycle).
+fn moon_name(phase: f64) -> &'static str {
+    const NAMES: [&str; 8] = [
+        "New moon",
+        "Waxing crescent",
+        "First quarter",
+        "Waxing gibbous",
+        "Full moon",
+        "Waning gibbous",
+        "Last quarter",
+        "Waning crescent",
+    ];
+    NAMES[((phase * 8.0 + 0.5).floor() as usize) % 8]
+}
+
+fn local_text(ms: Option<i64>) -> String {
+    ms.and_then(|ms| chrono::DateTime::from_timestamp(ms.div_euclid(1000), 0))
+        .map(|t| t.with_timezone(&Local).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Every `astro.*` sample at `now` for the location, given the local calendar day. Pure apart from
+/// the local-time formatting.
+fn samples(cfg: &AstroConfig, today: NaiveDate, now: i64, ts: u64) -> Vec<SensorSample> {
+    let (lat, lon) = (cfg.latitude, cfg.longitude);
+    let day = sun_day(today, lat, lon);
+    let next_day = today
+        .succ_opt()
+        .map(|d| sun_day(d, lat, lon))
+        .unwrap_or_default();
+    let mut out = Vec::new();
+    for name in EVENTS {
+        let next = [day.event(name), next_day.event(name)]
+            .into_iter()
+            .flatten()
+            .find(|&t| t > now);
+        out.push(SensorSample::text(
+            format!("astro.{name}"),
+            ts,
+            local_text(day.event(name)),
+        ));
+        out.push(SensorSample::scalar(
+            format!("astro.{name}.in_secs"),
+            ts,
+            next.map_or(-1.0, |t| ((t - now) / 1000) as f64),
+        ));
+    }
+    let (elevation, azimuth) = sun_position(now, lat, lon);
+    let day_length = match (day.sunrise, day.sunset, day.noon) {
+        (Some(rise), Some(set), _) => (set - rise) as f64 / 1000.0,
+        // No crossing: the sun stays up (polar day) or down all day.
+        (_, _, Some(noon)) if sun_position(noon, lat, lon).0 > RISE_SET => 86_400.0,
+        _ => 0.0,
+    };
+    let (phase, illumination) = moon(now);
+    out.extend([
+        SensorSample::scalar("astro.day_length", ts, day_length.round()),
+        SensorSample::scalar("astro.sun.elevation", ts, elevation),
+        SensorSample::scalar("astro.sun.azimuth", ts, azimuth),
+        SensorSample::scalar(
+            "astro.sun.up",
+            ts,
+            if elevation >= RISE_SET { 1.0 } else { 0.0 },
+        ),
+        SensorSample::text("astro.sun.phase", ts, sun_phase(elevation)),
+        SensorSample::scalar("astro.moon.phase", ts, phase),
+        SensorSample::scalar("astro.moon.illumination", ts, illumination * 100.0),
+        SensorSample::text("astro.moon.name", ts, moon_name(phase)),
+    ]);
+    out
+}
+
+// ---- runtime ----
+
+fn publish<R: Runtime>(app: &AppHandle<R>, cfg: &AstroConfig) {
+    let now = now_ms();
+    let batch = samples(cfg, Local::now().date_naive(), now as i64, now);
+    let _ = bus::publish(app, &batch);
+}
+
+/// Yesterday's, today's and tomorrow's sun events (local dates) as Unix ms, for automations.rs'
+/// `sun` trigger — the neighbouring days let an offset carry a trigger across midnight. Empty
+/// until a location is configured.
+pub(crate) fn sun_events<R: Runtime>(app: &AppHandle<R>) -> Vec<(&'static str, i64)> {
+    let Some(cfg) = app.try_state::<AstroSource>().and_then(|s| s.location()) else {
+        return Vec::new();
+    };
+    let today = Local::now().date_naive();
+    [today.pred_opt(), Some(today), today.succ_opt()]
+        .into_iter()
+        .flatten()
+        .flat_map(|date| {
+            let day = sun_day(date, cfg.latitude, cfg.longitude);
+            EVENTS
+                .into_iter()
+                .filter_map(move |name| Some((name, day.event(name)?)))
+        })
+        .collect()
+}
+
+/// Load the location, then recompute every `TICK` for the app's lifetime.
+pub async fn run_astro<R: Runtime>(app: AppHandle<R>) {
+    match load_astro_config(&app) {
+        Ok(cfg) => match cfg.as_ref().map(validate).transpose() {
+            Ok(_) => app.state::<AstroSource>().replace(cfg),
+            Err(err) => log::warn("astro", "invalid astro.json")
+                .field("error", err)
+                .emit(),
+        },
+        Err(err) => log::warn("astro", "failed to read astro.json")
+            .field("error", err)
+            .emit(),
+    }
+    loop {
+        if let Some(cfg) = app.state::<AstroSource>().location() {
+            publish(&app, &cfg);
+        }
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/astro.json` and publish for the new location right away. Studio-window-guarded.
+#[tauri::command]
+pub fn save_astro_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, AstroSource>,
+    config: AstroConfig,
+) -> Result<(), String> {
+    if window.label() != "studio" {
+        return Err("save_astro_config is only allowed from the studio window".into());
+    }
+    validate(&config)?;
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    state.replace(Some(config));
+    publish(&app, &config);
+    Ok(())
+}
+
+/// The configured location, if any.
+#[tauri::command]
+pub fn astro_config_status(state: State<'_, AstroSource>) -> Option<AstroConfig> {
+    state.location()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// Within `minutes` of the expected UTC time.
+    fn near(actual: Option<i64>, expected: &str, minutes: i64) -> bool {
+        actual.is_some_and(|t| (t - utc(expected)).abs() <= minutes * 60_000)
+    }
+
+    #[test]
+    fn london_midsummer_matches_the_almanac() {
+        // Almanac (UTC): civil dawn 02:55, sunrise 03:43, noon 12:02, sunset 20:21, dusk 21:09.
+        let day = sun_day(date("2024-06-20"), 51.5074, -0.1278);
+        assert!(near(day.dawn, "2024-06-20T02:55:00Z", 2));
+        assert!(near(day.sunrise, "2024-06-20T03:43:00Z", 2));
+        assert!(near(day.noon, "2024-06-20T13:02:00+01:00", 2));
+        assert!(near(day.sunset, "2024-06-20T20:21:00Z", 2));
+        assert!(near(day.dusk, "2024-06-20T21:09:00Z", 2));
+    }
+
+    #[test]
+    fn far_east_and_west_days_land_on_their_own_date() {
+        // Auckland, winter solstice: sunrise 07:33 NZST (UTC+12), sunset 17:11.
+        let akl = sun_day(date("2024-06-21"), -36.85, 174.76);
+        assert!(near(akl.sunrise, "2024-06-21T07:33:00+12:00", 3));
+        assert!(near(akl.sunset, "2024-06-21T17:11:00+12:00", 3));
+        // Honolulu: sunrise 05:51 HST (UTC−10), sunset 19:16.
+        let hnl = sun_day(date("2024-06-21"), 21.31, -157.86);
+        assert!(near(hnl.sunrise, "2024-06-21T05:51:00-10:00", 3));
+        assert!(near(hnl.sunset, "2024-06-21T19:16:00-10:00", 3));
+    }
+
+    #[test]
+    fn polar_day_and_night_have_no_crossings() {
+        let summer = sun_day(date("2024-06-21"), 69.65, 18.96);
+        assert_eq!((summer.sunrise, summer.sunset), (None, None));
+        assert!(summer.noon.is_some());
+        let winter = sun_day(date("2024-12-21"), 69.65, 18.96);
+        assert_eq!((winter.sunrise, winter.sunset), (None, None));
+        // Tromsø still gets civil twilight at midwinter.
+        assert!(winter.dawn.is_some() && winter.dusk.is_some());
+
+        let cfg = AstroConfig {
+            latitude: 69.65,
+            longitude: 18.96,
+        };
+        let now = utc("2024-06-21T12:00:00Z");
+        let s = samples(&cfg, date("2024-06-21"), now, 0);
+        let get = |id: &str| {
+            let v = serde_json::to_value(s.iter().find(|x| x.sensor == id).unwrap()).unwrap();
+            v["value"]["value"].clone()
+        };
+        assert_eq!(get("astro.day_length"), 86_400.0);
+        assert_eq!(get("astro.sunrise"), "");
+        assert_eq!(get("astro.sunrise.in_secs"), -1.0);
+        assert_eq!(get("astro.sun.up"), 1.0);
+        assert_eq!(get("astro.sun.phase"), "day");
+    }
+
+    #[test]
+    fn sun_position_tracks_elevation_and_azimuth() {
+        // London, 2024-06-20 12:02 UTC (solar noon): due south, 90 − 51.5 + 23.4 ≈ 61.9°.
+        let (el, az) = sun_position(utc("2024-06-20T12:02:00Z"), 51.5074, -0.1278);
+        assert!((el - 61.9).abs() < 0.3, "{el}");
+        assert!((az - 180.0).abs() < 1.5, "{az}");
+        // Mid-morning the sun is in the east-southeast.
+        let (el, az) = sun_position(utc("2024-06-20T08:00:00Z"), 51.5074, -0.1278);
+        assert!(el > 30.0 && el < 45.0, "{el}");
+        assert!(az > 90.0 && az < 130.0, "{az}");
+        // Midnight: well below the horizon, in the north.
+        let (el, az) = sun_position(utc("2024-06-20T00:02:00Z"), 51.5074, -0.1278);
+        assert!(el < -10.0 && !(10.0..=350.0).contains(&az), "{el} {az}");
+        assert_eq!(sun_phase(el), "astronomical twilight");
+    }
+
+    #[test]
+    fn moon_phase_and_illumination() {
+        // Full moon 2024-06-22 01:08 UTC, new moon 2024-07-05 22:57 UTC, first quarter
+        // 2024-07-13 22:49 UTC.
+        let (phase, lit) = moon(utc("2024-06-22T01:08:00Z"));
+        assert!((phase - 0.5).abs() < 0.01 && lit > 0.99, "{phase} {lit}");
+        assert_eq!(moon_name(phase), "Full moon");
+        let (phase, lit) = moon(utc("2024-07-05T22:57:00Z"));
+        assert!(
+            (phase.min(1.0 - phase)) < 0.01 && lit < 0.01,
+            "{phase} {lit}"
+        );
+        assert_eq!(moon_name(phase), "New moon");
+        let (phase, lit) = moon(utc("2024-07-13T22:49:00Z"));
+        assert!(
+            (phase - 0.25).abs() < 0.02 && (lit - 0.5).abs() < 0.03,
+            "{phase} {lit}"
+        );
+        assert_eq!(moon_name(phase), "First quarter");
+        assert_eq!(moon_name(0.1), "Waxing crescent");
+        assert_eq!(moon_name(0.85), "Waning crescent");
+    }
+
+    #[test]
+    fn countdowns_roll_over_to_tomorrow() {
+        let cfg = AstroConfig {
+            latitude: 51.5074,
+            longitude: -0.1278,
+        };
+        // 22:00 UTC: tonight's dusk has passed, so dawn and dusk count down to tomorrow's.
+        let now = utc("2024-06-20T22:00:00Z");
+        let s = samples(&cfg, date("2024-06-20"), now, 0);
+        let secs = |id: &str| {
+            let v = serde_json::to_value(s.iter().find(|x| x.sensor == id).unwrap()).unwrap();
+            v["value"]["value"].as_f64().unwrap()
+        };
+        let dawn = secs("astro.dawn.in_secs");
+        assert!((dawn - 5.0 * 3600.0).abs() < 300.0, "{dawn}");
+        let dusk = secs("astro.dusk.in_secs");
+        assert!((dusk - 23.1 * 3600.0).abs() < 600.0, "{dusk}");
+        let length = secs("astro.day_length");
+        assert!(
+            (length - (16.0 * 3600.0 + 38.0 * 60.0)).abs() < 240.0,
+            "{length}"
+        );
+        assert_eq!(secs("astro.sun.up"), 0.0);
+    }
+}
@@ -0,0 +1,1504 @@
+//! Backend automations: trigger → actions rules that run in Rust, with or without any window open
+//! (the frontend macros only run when a button in a live overlay is clicked).
+//!
+//! Rules live in `<app_config_dir>/automations.json` (next to `controls.json`) and hot-reload via the
+//! shared config watcher (command.rs `watch_automations`):
+//! `{ "automations": [{ "name": "door_pause", "trigger": { "type": "ha", "entity":
+//!    "binary_sensor.door", "to": "on" }, "actions": [{ "type": "media_control", "action": "pause" }],
+//!    "cooldown": "1m" }] }`
+//!
+//! Triggers (`type`):
+//!   `sensor` `{when}`          an alerts.rs condition (`gpu.temp > 85 for 30s`); fires on the rising
+//!                              edge and re-arms once it clears
+//!   `time`   `{at, days?}`     local `HH:MM`, optionally only on `["mon", "fri", …]`
+//!   `cron`   `{cron}`          5-field cron (`*/15 9-17 * * mon-fri`), local time
+//!   `sun`    `{event, offset?}` `dawn` / `sunrise` / `noon` / `sunset` / `dusk` at the astro.rs
+//!                              location, shifted by `offset` (`-15m`, `+1h`)
+//!   `media`  `{source?}`       the now-playing track changed (optional source glob, e.g. `spotify*`)
+//!   `ha`     `{entity, to?}`   a Home Assistant entity's state changed (optionally: to this value)
+//!   `mqtt`   `{topic, payload?}` a message on the topic (an MQTT filter: `+` one level, `#` the
+//!                              rest; optionally: this exact payload)
+//!   `window` `{exe?, title?}`  a new top-level window matching the globs appeared (Windows)
+//!
+//! Actions (`type`), run in order; a failing action is logged and the rest still run:
+//!   `ha_call_service {domain, service, data?}`, `media_control {action, source?, value?}`,
+//!   `mqtt_publish {topic, payload, retain?}`, `layout {name, monitor?}` (a saved layout onto a
+//!   monitor, default the primary), `notify {title, body?}`, `llm_prompt {prompt, system?}` (the
+//!   reply is published as `automation.<name>.llm`).
+//! String fields take `{placeholders}` from the trigger: `{name}`, `{sensor}`, `{value}`, `{title}`,
+//! `{artist}`, `{source}`, `{entity}`, `{state}`, `{topic}`, `{payload}`, `{exe}`, `{time}`,
+//! `{event}`.
+//!
+//! `test_automation` validates a rule and, in dry-run, returns what each action WOULD do (rendered)
+//! without touching anything. Pure seams (`Cron`, `validate`, `render`, `Engine`) are unit-tested.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::alerts::{AlertRule, RuleState, Transition, parse_condition};
+use crate::astro::EVENTS;
+use crate::bus;
+use crate::derived::parse_duration;
+use crate::log;
+use crate::mqtt::{topic_matches, valid_filter};
+use crate::procwatch::glob_match;
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+
+/// Engine tick: config generation check, the minute boundary for time/cron triggers.
+const TICK_MS: u64 = 1000;
+
+/// Window triggers poll the top-level window list every this many ticks (like the overlay's
+/// conditional-container "is app X open" poll).
+const WINDOW_POLL_TICKS: u32 = 2;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_monitor() -> String {
+    "default".to_string()
+}
+
+// ---- config ----
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    Sensor {
+        when: String,
+    },
+    Time {
+        at: String,
+        #[serde(default)]
+        days: Vec<String>,
+    },
+    Cron {
+        cron: String,
+    },
+    Sun {
+        event: String,
+        #[serde(default)]
+        offset: String,
+    },
+    Media {
+        #[serde(default)]
+        source: String,
+    },
+    Ha {
+        entity: String,
+        #[serde(default)]
+        to: String,
+    },
+    Mqtt {
+        topic: String,
+        #[serde(default)]
+        payload: String,
+    },
+    Window {
+        #[serde(default)]
+        exe: String,
+        #[serde(default)]
+        title: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    HaCallService {
+        domain: String,
+        service: String,
+        #[serde(default)]
+        data: Value,
+    },
+    MediaControl {
+        action: String,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        value: Option<f64>,
+    },
+    MqttPublish {
+        topic: String,
+        #[serde(default)]
+        payload: String,
+        #[serde(default)]
+        retain: bool,
+    },
+    Layout {
+        name: String,
+        #[serde(default = "default_monitor")]
+        monitor: String,
+    },
+    Notify {
+        title: String,
+        #[serde(default)]
+        body: String,
+    },
+    LlmPrompt {
+        prompt: String,
+        #[serde(default)]
+        system: String,
+    },
+}
+
+/// One rule. `cooldown` (a duration, e.g. `5m`) is the minimum time between two firings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Automation {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub trigger: Trigger,
+    pub actions: Vec<Action>,
+    #[serde(default)]
+    pub cooldown: String,
+}
+
+/// `automations.json`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutomationsConfig {
+    #[serde(default)]
+    pub automations: Vec<Automation>,
+}
+
+/// Per-rule run record for `automations_status`.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunStatus {
+    pub last_fired_ms: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// One row of `automations_status`: the rule plus its run record.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationStatus {
+    #[serde(flatten)]
+    pub automation: Automation,
+    #[serde(flatten)]
+    pub run: RunStatus,
+}
+
+/// A now-playing track change, handed over from the media-session loop (main.rs).
+#[derive(Clone, Debug)]
+pub struct MediaEvent {
+    pub source: String,
+    pub title: String,
+    pub artist: String,
+}
+
+/// Managed state: the live rules (+ the generation counter the engine polls, like derived.rs), the
+/// media-event channel into the engine, and per-rule run records.
+pub struct Automations {
+    config: Mutex<AutomationsConfig>,
+    generation: AtomicU64,
+    media_tx: mpsc::UnboundedSender<MediaEvent>,
+    media_rx: Mutex<Option<mpsc::UnboundedReceiver<MediaEvent>>>,
+    runs: Mutex<HashMap<String, RunStatus>>,
+}
+
+impl Default for Automations {
+    fn default() -> Self {
+        let (media_tx, media_rx) = mpsc::unbounded_channel();
+        Automations {
+            config: Mutex::new(AutomationsConfig::default()),
+            generation: AtomicU64::new(0),
+            media_tx,
+            media_rx: Mutex::new(Some(media_rx)),
+            runs: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Automations {
+    fn replace(&self, cfg: AutomationsConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record(&self, name: &str, update: impl FnOnce(&mut RunStatus)) {
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        update(runs.entry(name.to_string()).or_default());
+    }
+}
+
+/// Path to the automation rules (`automations.json` in the app config dir).
+pub(crate) fn automations_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("automations.json"))
+}
+
+pub fn load_automations_config(app: &AppHandle) -> Result<Option<AutomationsConfig>, String> {
+    let path = automations_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// (Re)load the rules from disk into managed state — at startup and from the file watcher on every
+/// change. Invalid rules are logged and skipped; an unparseable file keeps the previous rules.
+pub fn load_into_state(app: &AppHandle) {
+    match load_automations_config(app) {
+        Ok(cfg) => {
+            let (kept, errors) = normalize_config(cfg.unwrap_or_default());
+            for err in errors {
+                log::warn("automations", "skipping automation")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<Automations>().replace(kept);
+        }
+        Err(err) => log::warn("automations", "failed to read automations.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Hand a now-playing update to the engine (called from the media-session loop). Cheap and
+/// non-blocking; the engine dedupes unchanged tracks.
+pub fn media_changed(app: &AppHandle, source: &str, title: &str, artist: &str) {
+    if let Some(state) = app.try_state::<Automations>() {
+        let _ = state.media_tx.send(MediaEvent {
+            source: source.to_string(),
+            title: title.to_string(),
+            artist: artist.to_string(),
+        });
+    }
+}
+
+// ---- schedules (pure) ----
+
+/// `-15m` / `+1h` / `30m` → signed whole minutes; empty is no offset.
+fn parse_offset(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(0);
+    }
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    parse_duration(rest).map(|ms| sign * (ms / 60_000) as i64)
+}
+
+/// A local wall-clock minute, the unit time and cron triggers match on. `dow` is 0 = Sunday.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocalMinute {
+    pub minute: u32,
+    pub hour: u32,
+    pub dom: u32,
+    pub month: u32,
+    pub dow: u32,
+}
+
+impl LocalMinute {
+    fn now() -> (Self, String) {
+        let now = chrono::Local::now();
+        let m = LocalMinute {
+            minute: now.minute(),
+            hour: now.hour(),
+            dom: now.day(),
+            month: now.month(),
+            dow: now.weekday().num_days_from_sunday(),
+        };
+        (m, now.format("%Y-%m-%d %H:%M").to_string())
+    }
+}
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// `mon` / `Monday` / `1` → 1 (Sunday is 0; cron's 7 is also Sunday).
+fn parse_day(s: &str) -> Option<u32> {
+    let s = s.trim().to_lowercase();
+    if let Ok(n) = s.parse::<u32>() {
+        return (n <= 7).then_some(n % 7);
+    }
+    let prefix = s.get(..3)?;
+    DAY_NAMES
+        .iter()
+        .position(|d| *d == prefix)
+        .map(|i| i as u32)
+}
+
+/// Minutes since midnight for `HH:MM`.
+fn parse_hhmm(s: &str) -> Option<(u32, u32)> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some((h, m))
+}
+
+/// A parsed 5-field cron expression (minute hour day-of-month month day-of-week), each field a
+/// bitmask. Supports `*`, `n`, `a-b`, `*/n`, `a-b/n`, comma lists, and day names in the last field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cron {
+    minute: u64,
+    hour: u64,
+    dom: u64,
+    month: u64,
+    dow: u64,
+    dom_any: bool,
+    dow_any: bool,
+}
+
+fn cron_field(field: &str, min: u32, max: u32, dow: bool) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        // A numeric 7 stays 7 here (Sunday at the top of a range); `% 7` applies per bit below.
+        let n = match s.parse().ok() {
+            None if dow => parse_day(s),
+            n => n,
+        };
+        n.filter(|n| (min..=max).contains(n) || (dow && *n <= 7))
+            .ok_or_else(|| format!("`{s}` is out of range {min}-{max}"))
+    };
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (
+                r,
+                s.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("bad step in `{part}`"))?,
+            ),
+            None => (part, 1),
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            r => match r.split_once('-') {
+                Some((a, b)) => {
+                    let (lo, hi) = (value(a)?, value(b)?);
+                    // `fri-sun`: a named Sunday closing a range is day 7, not 0.
+                    (lo, if dow && hi == 0 && lo > 0 { 7 } else { hi })
+                }
+                None if step > 1 => (value(r)?, max),
+                None => (value(r)?, value(r)?),
+            },
+        };
+        if lo > hi {
+            return Err(format!("empty range `{range}`"));
+        }
+        for n in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << (if dow { n % 7 } else { n });
+        }
+    }
+    Ok(mask)
+}
+
+impl Cron {
+    pub fn parse(src: &str) -> Result<Cron, String> {
+        let fields: Vec<&str> = src.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(format!("expected 5 cron fields, got {}", fields.len()));
+        };
+        Ok(Cron {
+            minute: cron_field(minute, 0, 59, false)?,
+            hour: cron_field(hour, 0, 23, false)?,
+            dom: cron_field(dom, 1, 31, false)?,
+            month: cron_field(month, 1, 12, false)?,
+            dow: cron_field(dow, 0, 6, true)?,
+            dom_any: dom == "*",
+            dow_any: dow == "*",
+        })
+    }
+
+    /// Standard cron semantics: when both day fields are restricted, EITHER may match.
+    pub fn matches(&self, t: &LocalMinute) -> bool {
+        let bit = |mask: u64, n: u32| mask & (1 << n) != 0;
+        let dom = bit(self.dom, t.dom);
+        let dow = bit(self.dow, t.dow);
+        let day = match (self.dom_any, self.dow_any) {
+            (false, false) => dom || dow,
+            _ => dom && dow,
+        };
+        bit(self.minute, t.minute) && bit(self.hour, t.hour) && bit(self.month, t.month) && day
+    }
+}
+
+// ---- validation + templates (pure) ----
+
+fn validate_action(action: &Action) -> Result<(), String> {
+    let require = |v: &str, what: &str| {
+        if v.trim().is_empty() {
+            Err(format!("{what} is required"))
+        } else {
+            Ok(())
+        }
+    };
+    match action {
+        Action::HaCallService {
+            domain, service, ..
+        } => {
+            require(domain, "ha_call_service domain")?;
+            require(service, "ha_call_service service")
+        }
+        Action::MediaControl { action, .. } => require(action, "media_control action"),
+        Action::MqttPublish { topic, .. } => require(topic, "mqtt_publish topic"),
+        Action::Layout { name, .. } => require(name, "layout name"),
+        Action::Notify { title, .. } => require(title, "notify title"),
+        Action::LlmPrompt { prompt, .. } => require(prompt, "llm_prompt prompt"),
+    }
+}
+
+/// Check one rule (trigger syntax, actions, cooldown). Errors name what is wrong, for the editor.
+pub fn validate(a: &Automation) -> Result<(), String> {
+    match &a.trigger {
+        Trigger::Sensor { when } => {
+            parse_condition(when)?;
+        }
+        Trigger::Time { at, days } => {
+            parse_hhmm(at).ok_or_else(|| format!("time trigger: expected HH:MM, got `{at}`"))?;
+            if let Some(bad) = days.iter().find(|d| parse_day(d).is_none()) {
+                return Err(format!("time trigger: unknown day `{bad}`"));
+            }
+        }
+        Trigger::Cron { cron } => {
+            Cron::parse(cron).map_err(|e| format!("cron trigger: {e}"))?;
+        }
+        Trigger::Sun { event, offset } => {
+            if !EVENTS.iter().any(|e| e.eq_ignore_ascii_case(event.trim())) {
+                return Err(format!(
+                    "sun trigger: unknown event `{event}` (dawn, sunrise, noon, sunset, dusk)"
+                ));
+            }
+            parse_offset(offset)
+                .ok_or_else(|| format!("sun trigger: bad offset `{}`", offset.trim()))?;
+        }
+        Trigger::Media { .. } => {}
+        Trigger::Ha { entity, .. } if entity.trim().is_empty() => {
+            return Err("ha trigger: entity is required".into());
+        }
+        Trigger::Mqtt { topic, .. } if topic.trim().is_empty() => {
+            return Err("mqtt trigger: topic is required".into());
+        }
+        Trigger::Mqtt { topic, .. } if !valid_filter(topic) => {
+            return Err(format!(
+                "mqtt trigger: bad topic filter `{topic}` (`+`/`#` must be whole levels, `#` last)"
+            ));
+        }
+        Trigger::Ha { .. } | Trigger::Mqtt { .. } | Trigger::Window { .. } => {}
+    }
+    if a.actions.is_empty() {
+        return Err("at least one action is required".into());
+    }
+    for action in &a.actions {
+        validate_action(action)?;
+    }
+    if !a.cooldown.trim().is_empty() && parse_duration(&a.cooldown).is_none() {
+        return Err(format!("bad cooldown `{}`", a.cooldown.trim()));
+    }
+    Ok(())
+}
+
+/// Sanitize names, validate rules, drop duplicates. Returns the kept config plus one message per
+/// rejected rule.
+fn normalize_config(cfg: AutomationsConfig) -> (AutomationsConfig, Vec<String>) {
+    let mut automations: Vec<Automation> = Vec::new();
+    let mut errors = Vec::new();
+    for a in cfg.automations {
+        let Some(name) = id_segment(&a.name) else {
+            errors.push(format!("`{}`: invalid name", a.name));
+            continue;
+        };
+        if automations.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        if let Err(err) = validate(&a) {
+            errors.push(format!("`{name}`: {err}"));
+            continue;
+        }
+        automations.push(Automation { name, ..a });
+    }
+    (AutomationsConfig { automations }, errors)
+}
+
+/// The trigger's values, substituted into action strings as `{key}`.
+pub type Context = Vec<(&'static str, String)>;
+
+/// Replace each `{key}` in `template` with its context value; unknown placeholders are left as-is.
+pub fn render(template: &str, ctx: &Context) -> String {
+    ctx.iter().fold(template.to_string(), |s, (k, v)| {
+        s.replace(&format!("{{{k}}}"), v)
+    })
+}
+
+fn render_value(v: &Value, ctx: &Context) -> Value {
+    match v {
+        Value::String(s) => Value::String(render(s, ctx)),
+        Value::Array(items) => Value::Array(items.iter().map(|i| render_value(i, ctx)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, ctx)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+impl Action {
+    /// This action with the trigger context substituted into its strings.
+    pub fn rendered(&self, ctx: &Context) -> Action {
+        let r = |s: &str| render(s, ctx);
+        match self {
+            Action::HaCallService {
+                domain,
+                service,
+                data,
+            } => Action::HaCallService {
+                domain: r(domain),
+                service: r(service),
+                data: render_value(data, ctx),
+            },
+            Action::MediaControl {
+                action,
+                source,
+                value,
+            } => Action::MediaControl {
+                action: r(action),
+                source: source.as_deref().map(r),
+                value: *value,
+            },
+            Action::MqttPublish {
+                topic,
+                payload,
+                retain,
+            } => Action::MqttPublish {
+                topic: r(topic),
+                payload: r(payload),
+                retain: *retain,
+            },
+            Action::Layout { name, monitor } => Action::Layout {
+                name: r(name),
+                monitor: r(monitor),
+            },
+            Action::Notify { title, body } => Action::Notify {
+                title: r(title),
+                body: r(body),
+            },
+            Action::LlmPrompt { prompt, system } => Action::LlmPrompt {
+                prompt: r(prompt),
+                system: r(system),
+            },
+        }
+    }
+
+    /// One line saying what the action does — the dry-run report.
+    pub fn describe(&self) -> String {
+        match self {
+            Action::HaCallService {
+                domain,
+                service,
+                data,
+            } => format!("call HA service {domain}.{service} with {data}"),
+            Action::MediaControl {
+                action,
+                source,
+                value,
+            } => {
+                let mut s = format!("media {action}");
+                if let Some(v) = value {
+                    s.push_str(&format!(" {v}"));
+                }
+                if let Some(src) = source {
+                    s.push_str(&format!(" on {src}"));
+                }
+                s
+            }
+            Action::MqttPublish {
+                topic,
+                payload,
+                retain,
+            } => format!(
+                "publish `{payload}` to MQTT {topic}{}",
+                if *retain { " (retained)" } else { "" }
+            ),
+            Action::Layout { name, monitor } => {
+                format!("switch monitor `{monitor}` to saved layout `{name}`")
+            }
+            Action::Notify { title, body } => format!("notify \"{title}\": {body}"),
+            Action::LlmPrompt { prompt, .. } => format!("ask the LLM: {prompt}"),
+        }
+    }
+}
+
+/// A sample value as trigger-context text.
+fn value_text(v: &SensorValue) -> Option<String> {
+    match v {
+        SensorValue::Scalar(n) => Some(format!("{n}")),
+        SensorValue::Text(t) => Some(t.clone()),
+        SensorValue::Json(j) => Some(match j.get("state") {
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+            None => j.to_string(),
+        }),
+        SensorValue::Series(_) => None,
+    }
+}
+
+// ---- the engine (pure; the async loop below drives it) ----
+
+/// One rule plus its trigger state.
+#[derive(Debug)]
+struct Armed {
+    auto: Automation,
+    cooldown_ms: u64,
+    last_fired: Option<u64>,
+    /// Sensor triggers: the condition's id and edge state (alerts.rs' state machine).
+    sensor: Option<(String, RuleState)>,
+    cron: Option<Cron>,
+    /// HA triggers: the entity's last state (the first one seen only primes it).
+    ha_last: Option<String>,
+}
+
+impl Armed {
+    fn new(auto: Automation) -> Option<Armed> {
+        let sensor = match &auto.trigger {
+            Trigger::Sensor { when } => {
+                let id = parse_condition(when).ok()?.sensor;
+                let rule = AlertRule {
+                    name: auto.name.clone(),
+                    when: when.clone(),
+                    hysteresis: 0.0,
+                    cooldown: String::new(),
+                    notify: false,
+                    message: String::new(),
+                };
+                Some((id, RuleState::new(rule).ok()?))
+            }
+            _ => None,
+        };
+        let cron = match &auto.trigger {
+            Trigger::Cron { cron } => Some(Cron::parse(cron).ok()?),
+            _ => None,
+        };
+        Some(Armed {
+            cooldown_ms: parse_duration(&auto.cooldown).unwrap_or(0),
+            auto,
+            last_fired: None,
+            sensor,
+            cron,
+            ha_last: None,
+        })
+    }
+
+    /// Fire unless disabled or inside the cooldown.
+    fn fire(&mut self, ts: u64, mut ctx: Context) -> Option<Firing> {
+        if !self.auto.enabled
+            || self
+                .last_fired
+                .is_some_and(|at| ts.saturating_sub(at) < self.cooldown_ms)
+        {
+            return None;
+        }
+        self.last_fired = Some(ts);
+        ctx.insert(0, ("name", self.auto.name.clone()));
+        Some(Firing {
+            automation: self.auto.clone(),
+            ctx,
+        })
+    }
+}
+
+/// A rule that fired, with its trigger context.
+#[derive(Clone, Debug)]
+pub struct Firing {
+    pub automation: Automation,
+    pub ctx: Context,
+}
+
+/// Every armed rule plus the shared state of the media and window triggers.
+#[derive(Default)]
+pub struct Engine {
+    armed: Vec<Armed>,
+    media_last: HashMap<String, (String, String)>,
+    /// Window handles seen on the previous poll (`None` until the first poll primes it, so the
+    /// windows already open at startup don't all fire).
+    windows_seen: Option<HashSet<i64>>,
+    /// Sun events around today as Unix ms (astro.rs), for sun triggers.
+    sun: Vec<(&'static str, i64)>,
+}
+
+impl Engine {
+    /// Swap in a new rule list; rules whose definition is unchanged keep their state (edge, cooldown).
+    pub fn configure(&mut self, autos: &[Automation]) {
+        let mut old = std::mem::take(&mut self.armed);
+        for a in autos {
+            match old.iter().position(|x| &x.auto == a) {
+                Some(pos) => self.armed.push(old.swap_remove(pos)),
+                None => self.armed.extend(Armed::new(a.clone())),
+            }
+        }
+    }
+
+    /// Refresh today's sun events (empty without an astro location: sun triggers never fire).
+    pub fn set_sun(&mut self, events: Vec<(&'static str, i64)>) {
+        self.sun = events;
+    }
+
+    fn wants_windows(&self) -> bool {
+        self.armed
+            .iter()
+            .any(|a| matches!(a.auto.trigger, Trigger::Window { .. }))
+    }
+
+    /// Sensor, HA and MQTT triggers, from one published batch.
+    pub fn on_batch(&mut self, batch: &[SensorSample]) -> Vec<Firing> {
+        let mut out = Vec::new();
+        // Restored last-known values (lastknown.rs) replay the past; they never trigger.
+        for s in batch.iter().filter(|s| !s.stale) {
+            for armed in &mut self.armed {
+                let ctx = match &armed.auto.trigger {
+                    Trigger::Sensor { .. } => {
+                        let Some((id, state)) = armed.sensor.as_mut() else {
+                            continue;
+                        };
+                        if *id != s.sensor {
+                            continue;
+                        }
+                        let Some(Transition::Fired { value, .. }) = state.ingest(s.ts_ms, &s.value)
+                        else {
+                            continue;
+                        };
+                        vec![("sensor", s.sensor.clone()), ("value", value)]
+                    }
+                    Trigger::Ha { entity, to } => {
+                        if s.sensor.strip_prefix("ha.") != Some(entity.as_str()) {
+                            continue;
+                        }
+                        let Some(state) = value_text(&s.value) else {
+                            continue;
+                        };
+                        let prev = armed.ha_last.replace(state.clone());
+                        if prev.is_none_or(|p| p == state)
+                            || (!to.is_empty() && !state.eq_ignore_ascii_case(to))
+                        {
+                            continue;
+                        }
+                        vec![("entity", entity.clone()), ("state", state)]
+                    }
+                    Trigger::Mqtt { topic, payload } => {
+                        let Some(t) = s.sensor.strip_prefix("mqtt.") else {
+                            continue;
+                        };
+                        // The Text sample is the raw payload; skip the derived `.value` Scalar.
+                        let SensorValue::Text(body) = &s.value else {
+                            continue;
+                        };
+                        if !topic_matches(topic, t) || (!payload.is_empty() && body != payload) {
+                            continue;
+                        }
+                        vec![("topic", t.to_string()), ("payload", body.clone())]
+                    }
+                    _ => continue,
+                };
+                out.extend(armed.fire(s.ts_ms, ctx));
+            }
+        }
+        out
+    }
+
+    /// Time, cron and sun triggers, once per local minute.
+    pub fn on_minute(&mut self, t: &LocalMinute, ts: u64) -> Vec<Firing> {
+        let time = format!("{:02}:{:02}", t.hour, t.minute);
+        let now_minute = (ts / 60_000) as i64;
+        let mut out = Vec::new();
+        for armed in &mut self.armed {
+            let mut ctx = vec![("time", time.clone())];
+            let due = match &armed.auto.trigger {
+                Trigger::Time { at, days } => {
+                    parse_hhmm(at) == Some((t.hour, t.minute))
+                        && (days.is_empty() || days.iter().any(|d| parse_day(d) == Some(t.dow)))
+                }
+                Trigger::Cron { .. } => armed.cron.as_ref().is_some_and(|c| c.matches(t)),
+                Trigger::Sun { event, offset } => {
+                    let event = event.trim().to_lowercase();
+                    // From the full timestamp, so an offset past midnight lands on the right day.
+                    let due = parse_offset(offset).is_some_and(|off| {
+                        self.sun.iter().any(|&(name, at)| {
+                            name == event && (at + off * 60_000).div_euclid(60_000) == now_minute
+                        })
+                    });
+                    ctx.push(("event", event));
+                    due
+                }
+                _ => false,
+            };
+            if due {
+                out.extend(armed.fire(ts, ctx));
+            }
+        }
+        out
+    }
+
+    /// Media triggers, on a track change (same source, different title/artist).
+    pub fn on_media(&mut self, ev: &MediaEvent, ts: u64) -> Vec<Firing> {
+        if ev.title.is_empty() {
+            return Vec::new();
+        }
+        let track = (ev.title.clone(), ev.artist.clone());
+        if self.media_last.get(&ev.source) == Some(&track) {
+            return Vec::new();
+        }
+        self.media_last.insert(ev.source.clone(), track);
+        let mut out = Vec::new();
+        for armed in &mut self.armed {
+            let Trigger::Media { source } = &armed.auto.trigger else {
+                continue;
+            };
+            if !source.is_empty() && !glob_match(source, &ev.source) {
+                continue;
+            }
+            let ctx = vec![
+                ("source", ev.source.clone()),
+                ("title", ev.title.clone()),
+                ("artist", ev.artist.clone()),
+            ];
+            out.extend(armed.fire(ts, ctx));
+        }
+        out
+    }
+
+    /// Window triggers, from one poll of `(hwnd, exe, title)`: only windows new since the last poll.
+    pub fn on_windows(&mut self, windows: &[(i64, String, String)], ts: u64) -> Vec<Firing> {
+        let current: HashSet<i64> = windows.iter().map(|(h, _, _)| *h).collect();
+        let Some(seen) = self.windows_seen.replace(current) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for (_, exe, title) in windows.iter().filter(|(h, _, _)| !seen.contains(h)) {
+            for armed in &mut self.armed {
+                let Trigger::Window { exe: e, title: t } = &armed.auto.trigger else {
+                    continue;
+                };
+                let hit =
+                    |pattern: &str, text: &str| pattern.is_empty() || glob_match(pattern, text);
+                if hit(e, exe) && hit(t, title) {
+                    let ctx = vec![("exe", exe.clone()), ("title", title.clone())];
+                    out.extend(armed.fire(ts, ctx));
+                }
+            }
+        }
+        out
+    }
+}
+
+// ---- actions (I/O) ----
+
+/// Carry out one (already rendered) action. Returns a short outcome line.
+async fn run_action(app: &AppHandle, automation: &str, action: Action) -> Result<String, String> {
+    let line = action.describe();
+    match action {
+        Action::HaCallService {
+            domain,
+            service,
+            data,
+        } => {
+            let data = if data.is_null() {
+                Value::Object(Default::default())
+            } else {
+                data
+            };
+            crate::ha::ha_call_service(app.clone(), domain, service, data).await?;
+        }
+        Action::MediaControl {
+            action,
+            source,
+            value,
+        } => crate::media::media_control(action, source, value).await?,
+        Action::MqttPublish {
+            topic,
+            payload,
+            retain,
+        } => crate::mqtt::publish_message(app, &topic, &payload, retain).await?,
+        Action::Layout { name, monitor } => {
+            crate::command::activate_saved_layout(app, &name, &monitor)?
+        }
+        Action::Notify { title, body } => app
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+            .map_err(|e| e.to_string())?,
+        Action::LlmPrompt { prompt, system } => {
+            let mut messages = Vec::new();
+            if !system.is_empty() {
+                messages.push(crate::llm::ChatMessage {
+                    role: "system".into(),
+                    content: system,
+                });
+            }
+            messages.push(crate::llm::ChatMessage {
+                role: "user".into(),
+                content: prompt,
+            });
+            let reply = crate::llm::llm_complete(app.clone(), messages, None, None).await?;
+            let sample =
+                SensorSample::text(format!("automation.{automation}.llm"), now_ms(), reply);
+            let _ = bus::publish(app, &[sample]);
+        }
+    }
+    Ok(line)
+}
+
+/// Run a firing's actions in order, logging each failure and recording the run.
+async fn execute(app: AppHandle, firing: Firing) {
+    let name = firing.automation.name.clone();
+    log::info("automations", "automation fired")
+        .field("name", name.clone())
+        .field("trigger", format!("{:?}", firing.ctx))
+        .emit();
+    let mut last_error = None;
+    for action in &firing.automation.actions {
+        if let Err(err) = run_action(&app, &name, action.rendered(&firing.ctx)).await {
+            log::warn("automations", "automation action failed")
+                .field("name", name.clone())
+                .field("action", action.describe())
+                .field("error", err.clone())
+                .emit();
+            last_error = Some(err);
+        }
+    }
+    app.state::<Automations>().record(&name, |run| {
+        run.last_fired_ms = Some(now_ms());
+        run.last_error = last_error;
+    });
+}
+
+/// The automation task: bus samples, media events, and a 1 s tick (rule reloads, time/cron on the
+/// minute boundary, window polling). Each firing runs its actions on its own task so a slow action
+/// (an LLM call) never stalls trigger evaluation. Runs until the bus closes.
+pub async fn run_automations(app: AppHandle) {
+    load_into_state(&app);
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let Some(mut media_rx) = app
+        .state::<Automations>()
+        .media_rx
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    else {
+        return;
+    };
+    let mut engine = Engine::default();
+    let mut generation = u64::MAX;
+    let mut minute_key = String::new();
+    let mut tick: u32 = 0;
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        let firings = tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => engine.on_batch(bus::actionable(&batch, bus::replaying(&app))),
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("automations", "automation engine fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                    Vec::new()
+                }
+                Err(RecvError::Closed) => return,
+            },
+            Some(ev) = media_rx.recv() => engine.on_media(&ev, now_ms()),
+            _ = ticker.tick() => {
+                let state: State<Automations> = app.state();
+                let current = state.generation.load(Ordering::Relaxed);
+                if current != generation {
+                    let autos = state.config.lock().unwrap_or_else(|e| e.into_inner()).automations.clone();
+                    engine.configure(&autos);
+                    generation = current;
+                }
+                let mut out = Vec::new();
+                // The first tick only records the minute: starting mid-minute never fires it.
+                let (minute, key) = LocalMinute::now();
+                if key != minute_key {
+                    engine.set_sun(crate::astro::sun_events(&app));
+                    if !minute_key.is_empty() {
+                        out.extend(engine.on_minute(&minute, now_ms()));
+                    }
+                    minute_key = key;
+                }
+                tick = tick.wrapping_add(1);
+                if engine.wants_windows()
+                    && tick.is_multiple_of(WINDOW_POLL_TICKS)
+                    && let Ok(windows) = crate::windowmgr::list_arrangeable()
+                {
+                    let windows: Vec<(i64, String, String)> =
+                        windows.into_iter().map(|w| (w.hwnd, w.exe, w.title)).collect();
+                    out.extend(engine.on_windows(&windows, now_ms()));
+                }
+                out
+            }
+        };
+        for firing in firings {
+            tauri::async_runtime::spawn(execute(app.clone(), firing));
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// The rules with each one's last run (time + last error, if any).
+#[tauri::command]
+pub fn automations_status(state: State<'_, Automations>) -> Vec<AutomationStatus> {
+    let cfg = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let runs = state.runs.lock().unwrap_or_else(|e| e.into_inner());
+    cfg.automations
+        .into_iter()
+        .map(|a| AutomationStatus {
+            run: runs.get(&a.name).cloned().unwrap_or_default(),
+            automation: a,
+        })
+        .collect()
+}
+
+/// Write `automations.json` atomically and swap the live rules (the file watcher's reload is then
+/// a no-op). Studio-window-guarded; any invalid rule rejects the save with every problem listed.
+#[tauri::command]
+pub fn save_automations(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Automations>,
+    config: AutomationsConfig,
+) -> Result<AutomationsConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_automations is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(config);
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = automations_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    crate::command::atomic_write(&path, &txt)?;
+    state.replace(cfg.clone());
+    Ok(cfg)
+}
+
+/// Validate `automation` and report what its actions would do, rendered against `context` (sample
+/// trigger values, e.g. `{ "title": "Song" }`). With `dry_run: false` the actions really run, in
+/// order, and each line reports its outcome. Studio-window-guarded.
+#[tauri::command]
+pub async fn test_automation(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    automation: Automation,
+    context: Option<HashMap<String, String>>,
+    dry_run: bool,
+) -> Result<Vec<String>, String> {
+    if window.label() != "studio" {
+        return Err("test_automation is only allowed from the studio window".into());
+    }
+    validate(&automation)?;
+    let keys = [
+        "name", "sensor", "value", "title", "artist", "source", "entity", "state", "topic",
+        "payload", "exe", "time", "event",
+    ];
+    let mut ctx: Context = vec![("name", automation.name.clone())];
+    for (k, v) in context.unwrap_or_default() {
+        if let Some(key) = keys.iter().find(|key| **key == k) {
+            ctx.push((key, v));
+        }
+    }
+    let mut report = Vec::new();
+    for action in &automation.actions {
+        let action = action.rendered(&ctx);
+        report.push(if dry_run {
+            format!("would {}", action.describe())
+        } else {
+            match run_action(&app, &automation.name, action).await {
+                Ok(line) => format!("ok: {line}"),
+                Err(err) => format!("failed: {err}"),
+            }
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn auto(trigger: Trigger) -> Automation {
+        Automation {
+            name: "a".into(),
+            enabled: true,
+            trigger,
+            actions: vec![Action::Notify {
+                title: "{name}: {value}".into(),
+                body: String::new(),
+            }],
+            cooldown: String::new(),
+        }
+    }
+
+    fn at(hour: u32, minute: u32, dow: u32) -> LocalMinute {
+        LocalMinute {
+            minute,
+            hour,
+            dom: 15,
+            month: 6,
+            dow,
+        }
+    }
+
+    #[test]
+    fn parses_the_config_shape() {
+        let cfg: AutomationsConfig = serde_json::from_value(json!({ "automations": [{
+            "name": "door",
+            "trigger": { "type": "ha", "entity": "binary_sensor.door", "to": "on" },
+            "actions": [
+                { "type": "media_control", "action": "pause" },
+                { "type": "ha_call_service", "domain": "light", "service": "turn_on",
+                  "data": { "entity_id": "light.hall" } },
+                { "type": "layout", "name": "away" }
+            ]
+        }]}))
+        .unwrap();
+        let a = &cfg.automations[0];
+        assert!(a.enabled);
+        assert_eq!(
+            a.actions[2],
+            Action::Layout {
+                name: "away".into(),
+                monitor: "default".into()
+            }
+        );
+        assert!(validate(a).is_ok());
+    }
+
+    #[test]
+    fn cron_fields_ranges_steps_and_days() {
+        let c = Cron::parse("*/15 9-17 * * mon-fri").unwrap();
+        assert!(c.matches(&at(9, 0, 1)));
+        assert!(c.matches(&at(17, 45, 5)));
+        assert!(!c.matches(&at(9, 5, 1))); // not a quarter hour
+        assert!(!c.matches(&at(12, 0, 0))); // Sunday
+        assert!(!c.matches(&at(18, 0, 3)));
+        // Both day fields restricted: either may match (standard cron).
+        let d = Cron::parse("0 8 1 * sun").unwrap();
+        assert!(d.matches(&at(8, 0, 0)));
+        assert!(Cron::parse("0 0 * * 7").unwrap().matches(&at(0, 0, 0)));
+        // Ranges that end on Sunday, as 7 or by name, and steps over the week.
+        for weekend in ["0 0 * * 5-7", "0 0 * * fri-sun"] {
+            let c = Cron::parse(weekend).unwrap();
+            assert!(c.matches(&at(0, 0, 5)) && c.matches(&at(0, 0, 6)) && c.matches(&at(0, 0, 0)));
+            assert!(!c.matches(&at(0, 0, 4)), "{weekend}");
+        }
+        let every_other = Cron::parse("0 0 * * */2").unwrap();
+        assert!(every_other.matches(&at(0, 0, 0)) && every_other.matches(&at(0, 0, 6)));
+        assert!(!every_other.matches(&at(0, 0, 1)));
+        for bad in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 * * funday",
+        ] {
+            assert!(Cron::parse(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_triggers_actions_and_cooldowns() {
+        let bad_time = auto(Trigger::Time {
+            at: "25:00".into(),
+            days: vec![],
+        });
+        assert!(validate(&bad_time).is_err());
+        let bad_day = auto(Trigger::Time {
+            at: "07:30".into(),
+            days: vec!["caturday".into()],
+        });
+        assert!(validate(&bad_day).unwrap_err().contains("caturday"));
+        let no_actions = Automation {
+            actions: vec![],
+            ..auto(Trigger::Media {
+                source: String::new(),
+            })
+        };
+        assert!(validate(&no_actions).is_err());
+        let bad_cooldown = Automation {
+            cooldown: "later".into(),
+            ..auto(Trigger::Media {
+                source: String::new(),
+            })
+        };
+        assert!(validate(&bad_cooldown).is_err());
+        let bad_event = auto(Trigger::Sun {
+            event: "teatime".into(),
+            offset: String::new(),
+        });
+        assert!(validate(&bad_event).unwrap_err().contains("teatime"));
+        let bad_offset = auto(Trigger::Sun {
+            event: "sunset".into(),
+            offset: "soon".into(),
+        });
+        assert!(validate(&bad_offset).is_err());
+        assert!(
+            validate(&auto(Trigger::Sensor {
+                when: "gpu.temp hot".into()
+            }))
+            .is_err()
+        );
+        let bad_filter = auto(Trigger::Mqtt {
+            topic: "home/#/doorbell".into(),
+            payload: String::new(),
+        });
+        assert!(
+            validate(&bad_filter)
+                .unwrap_err()
+                .contains("home/#/doorbell")
+        );
+    }
+
+    #[test]
+    fn render_substitutes_context_into_actions() {
+        let ctx: Context = vec![("title", "Song".into()), ("artist", "Band".into())];
+        assert_eq!(
+            render("{artist} – {title} {missing}", &ctx),
+            "Band – Song {missing}"
+        );
+        let action = Action::HaCallService {
+            domain: "notify".into(),
+            service: "mobile".into(),
+            data: json!({ "message": "Now: {title}", "n": 1 }),
+        };
+        let Action::HaCallService { data, .. } = action.rendered(&ctx) else {
+            unreachable!()
+        };
+        assert_eq!(data, json!({ "message": "Now: Song", "n": 1 }));
+    }
+
+    #[test]
+    fn sensor_trigger_fires_on_the_rising_edge_only() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Sensor {
+            when: "gpu.temp > 85".into(),
+        })]);
+        let hot = |ts| [SensorSample::scalar("gpu.temp", ts, 90.0)];
+        let fired = engine.on_batch(&hot(1));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(
+            render("{name}: {value}", &fired[0].ctx),
+            "a: 90",
+            "context carries the rule name and value"
+        );
+        assert!(engine.on_batch(&hot(2)).is_empty()); // still hot: no re-fire
+        engine.on_batch(&[SensorSample::scalar("gpu.temp", 3, 60.0)]);
+        assert_eq!(engine.on_batch(&hot(4)).len(), 1);
+    }
+
+    #[test]
+    fn replayed_batches_run_no_actions() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Sensor {
+            when: "gpu.temp > 85".into(),
+        })]);
+        let hot = [SensorSample::scalar("gpu.temp", 1, 90.0)];
+        assert!(
+            engine
+                .on_batch(crate::bus::actionable(&hot, true))
+                .is_empty()
+        );
+        assert_eq!(
+            engine.on_batch(crate::bus::actionable(&hot, false)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn ha_trigger_needs_a_change_and_matches_to() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Ha {
+            entity: "binary_sensor.door".into(),
+            to: "on".into(),
+        })]);
+        let door = |s: &str| {
+            [SensorSample {
+                sensor: "ha.binary_sensor.door".into(),
+                ts_ms: 1,
+                value: SensorValue::Json(json!({ "state": s })),
+                stale: false,
+            }]
+        };
+        assert!(engine.on_batch(&door("on")).is_empty()); // first sighting only primes
+        assert!(engine.on_batch(&door("off")).is_empty()); // wrong target state
+        assert_eq!(engine.on_batch(&door("on")).len(), 1);
+        assert!(engine.on_batch(&door("on")).is_empty()); // unchanged
+    }
+
+    #[test]
+    fn mqtt_trigger_matches_topic_filters_and_payload() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Mqtt {
+            topic: "home/+/doorbell".into(),
+            payload: "ring".into(),
+        })]);
+        let msg = |topic: &str, body: &str| {
+            [SensorSample::text(
+                format!("mqtt.{topic}"),
+                1,
+                body.to_string(),
+            )]
+        };
+        assert_eq!(
+            engine.on_batch(&msg("home/front/doorbell", "ring")).len(),
+            1
+        );
+        assert!(
+            engine
+                .on_batch(&msg("home/front/doorbell", "idle"))
+                .is_empty()
+        );
+        assert!(engine.on_batch(&msg("home/front/light", "ring")).is_empty());
+        // `+` is one level: it doesn't reach across a `/`.
+        assert!(
+            engine
+                .on_batch(&msg("home/front/porch/doorbell", "ring"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn time_trigger_respects_days_and_cooldown_applies() {
+        let mut engine = Engine::default();
+        engine.configure(&[Automation {
+            cooldown: "2m".into(),
+            ..auto(Trigger::Time {
+                at: "07:30".into(),
+                days: vec!["Mon".into(), "fri".into()],
+            })
+        }]);
+        assert!(engine.on_minute(&at(7, 30, 0), 0).is_empty()); // Sunday
+        assert_eq!(engine.on_minute(&at(7, 30, 1), 60_000).len(), 1);
+        assert!(engine.on_minute(&at(7, 30, 5), 90_000).is_empty()); // inside the cooldown
+        assert!(engine.on_minute(&at(7, 31, 1), 600_000).is_empty());
+    }
+
+    #[test]
+    fn sun_trigger_fires_at_the_offset_event_minute() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Sun {
+            event: "Sunset".into(),
+            offset: "-15m".into(),
+        })]);
+        let ms = |h: u64, m: u64| (h * 60 + m) * 60_000;
+        // No location yet: nothing to match.
+        assert!(engine.on_minute(&at(20, 45, 3), ms(20, 45)).is_empty());
+        engine.set_sun(vec![
+            ("sunrise", ms(5, 10) as i64),
+            ("sunset", ms(21, 0) as i64),
+        ]);
+        assert!(engine.on_minute(&at(21, 0, 3), ms(21, 0)).is_empty());
+        let fired = engine.on_minute(&at(20, 45, 3), ms(20, 45) + 5_000);
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].ctx.contains(&("event", "sunset".into())));
+
+        // An offset past midnight fires the next morning, not this morning.
+        let mut late = Engine::default();
+        late.configure(&[auto(Trigger::Sun {
+            event: "sunset".into(),
+            offset: "+30m".into(),
+        })]);
+        late.set_sun(vec![("sunset", ms(23, 50) as i64)]);
+        assert!(late.on_minute(&at(0, 20, 3), ms(0, 20)).is_empty());
+        assert_eq!(late.on_minute(&at(0, 20, 4), ms(24, 20)).len(), 1);
+        assert_eq!(parse_offset("+1h"), Some(60));
+        assert_eq!(parse_offset(""), Some(0));
+    }
+
+    #[test]
+    fn media_trigger_fires_on_track_change_per_source() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Media {
+            source: "spotify*".into(),
+        })]);
+        let ev = |source: &str, title: &str| MediaEvent {
+            source: source.into(),
+            title: title.into(),
+            artist: "Band".into(),
+        };
+        assert_eq!(engine.on_media(&ev("Spotify.exe", "One"), 1).len(), 1);
+        assert!(engine.on_media(&ev("Spotify.exe", "One"), 2).is_empty()); // timeline update
+        assert!(engine.on_media(&ev("chrome.exe", "Video"), 3).is_empty()); // other source
+        assert_eq!(engine.on_media(&ev("Spotify.exe", "Two"), 4).len(), 1);
+    }
+
+    #[test]
+    fn window_trigger_fires_for_new_windows_after_the_first_poll() {
+        let mut engine = Engine::default();
+        engine.configure(&[auto(Trigger::Window {
+            exe: "obs*.exe".into(),
+            title: String::new(),
+        })]);
+        let obs = (7, "obs64.exe".to_string(), "OBS".to_string());
+        let notepad = (8, "notepad.exe".to_string(), "Untitled".to_string());
+        assert!(engine.on_windows(std::slice::from_ref(&obs), 0).is_empty()); // already open at start
+        assert!(engine.on_windows(&[], 1).is_empty());
+        assert!(
+            engine
+                .on_windows(std::slice::from_ref(&notepad), 2)
+                .is_empty()
+        );
+        assert_eq!(engine.on_windows(&[notepad, obs], 3).len(), 1);
+    }
+
+    #[test]
+    fn disabled_rules_never_fire_and_configure_keeps_state() {
+        let mut engine = Engine::default();
+        let rule = auto(Trigger::Sensor {
+            when: "x > 1".into(),
+        });
+        engine.configure(&[Automation {
+            enabled: false,
+            ..rule.clone()
+        }]);
+        assert!(
+            engine
+                .on_batch(&[SensorSample::scalar("x", 1, 5.0)])
+                .is_empty()
+        );
+        engine.configure(std::slice::from_ref(&rule));
+        assert_eq!(
+            engine.on_batch(&[SensorSample::scalar("x", 2, 5.0)]).len(),
+            1
+        );
+        engine.configure(std::slice::from_ref(&rule)); // unchanged: stays armed-and-fired
+        assert!(
+            engine
+                .on_batch(&[SensorSample::scalar("x", 3, 5.0)])
+                .is_empty()
+        );
+    }
+}
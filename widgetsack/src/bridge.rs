@@ -6,7 +6,7 @@
 //! Command names are deliberately NOT centralized here: a `#[tauri::command]`'s wire name is
 //! its fn name (the macro owns it); the TS side mirrors those in `COMMANDS`.
 
-/// 1 Hz sensor batches (sensors.rs; ha/mqtt/stocks push onto the same event).
+/// 1 Hz sensor batches (sensors.rs; ha/mqtt/stocks push onto the same event — all via bus.rs).
 pub const TELEMETRY_EVENT: &str = "telemetry";
 
 /// Streamed LLM tokens (llm.rs → lib/llm/source.ts).
@@ -26,6 +26,7 @@ pub const SESSION_DELETE_EVENT: &str = "session_delete";
 pub const LAYOUT_CHANGED_EVENT: &str = "layout_changed";
 pub const THEMES_CHANGED_EVENT: &str = "themes_changed";
 pub const CONTROLS_CHANGED_EVENT: &str = "controls_changed";
+pub const AUTOMATIONS_CHANGED_EVENT: &str = "automations_changed";
 
 /// Tray / global-hotkey / single-instance broadcasts (main.rs). `toggle_edit` is also emitted by
 /// the client's own Ctrl+E handler.
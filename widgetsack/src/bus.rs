@@ -0,0 +1,134 @@
+//! Telemetry fan-out: the ONE call every source (sensors, ha, mqtt, stocks, derived) makes to hand
+//! over a batch. It emits the `telemetry` event to the webviews exactly as before, records each id
+//! in the backend sensor catalog (catalog.rs) so `list_sensors` knows what is actually being
+//! produced — including the dynamic ids (`disk.<letter>.*`, `ha.<entity>`, `mqtt.<topic>`) no static
+//! list has — keeps the latest value per id, and broadcasts the batch to backend consumers (the
+//! derived-sensor engine) that need every source's samples, not just their own.
+//!
+//! Sources publish here instead of calling `app.emit(TELEMETRY_EVENT, ..)` directly, so a new
+//! backend consumer of the stream is added in one place rather than at every emit site. That also
+//! makes replay (recorder.rs) a switch here: while a recording plays, live batches are dropped and
+//! only `publish_replayed` reaches the consumers and webviews.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::broadcast;
+
+use crate::bridge::TELEMETRY_EVENT;
+use crate::catalog;
+use crate::sensors::{SensorSample, SensorValue};
+
+/// Batches buffered per backend subscriber. A consumer that falls further behind than this gets
+/// `RecvError::Lagged` and skips ahead — it must never back-pressure the sources.
+const CAPACITY: usize = 256;
+
+/// One published batch, shared (not copied) between every backend subscriber.
+pub type Batch = Arc<Vec<SensorSample>>;
+
+/// Managed state: the backend broadcast channel and the latest sample per sensor id across ALL
+/// sources (what the MCP `state.json` snapshot mirrors).
+pub struct Bus {
+    tx: broadcast::Sender<Batch>,
+    latest: Mutex<HashMap<String, SensorSample>>,
+    /// A recording is replaying: live `publish` calls are dropped.
+    replaying: AtomicBool,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Bus {
+            tx,
+            latest: Mutex::new(HashMap::new()),
+            replaying: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Publish one batch: note its ids in the catalog, update the latest-value map, hand it to any
+/// backend subscribers, then emit it to the webviews. Returns the emit result so a caller that logs
+/// failures (the system loop) still can; the proxy sources ignore it. A no-op while a recording is
+/// replaying.
+pub fn publish<R: Runtime>(app: &AppHandle<R>, batch: &[SensorSample]) -> tauri::Result<()> {
+    if replaying(app) {
+        return Ok(());
+    }
+    fan_out(app, batch)
+}
+
+/// Publish a batch from a recording (recorder.rs) — the same path as `publish`, past the replay gate.
+pub fn publish_replayed<R: Runtime>(
+    app: &AppHandle<R>,
+    batch: &[SensorSample],
+) -> tauri::Result<()> {
+    fan_out(app, batch)
+}
+
+fn fan_out<R: Runtime>(app: &AppHandle<R>, batch: &[SensorSample]) -> tauri::Result<()> {
+    catalog::observe(app, batch);
+    if let Some(bus) = app.try_state::<Bus>() {
+        {
+            let mut latest = bus.latest.lock().unwrap_or_else(|e| e.into_inner());
+            for s in batch {
+                latest.insert(s.sensor.clone(), s.clone());
+            }
+        }
+        // No subscriber → no copy. A send error only means every receiver has gone away.
+        if bus.tx.receiver_count() > 0 {
+            let _ = bus.tx.send(Arc::new(batch.to_vec()));
+        }
+    }
+    app.emit(TELEMETRY_EVENT, batch)
+}
+
+/// Whether a recording is replaying (live sources are muted).
+pub fn replaying<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.try_state::<Bus>()
+        .is_some_and(|bus| bus.replaying.load(Ordering::Relaxed))
+}
+
+/// What a consumer with real side effects (alerts, automations, the metrics exporter) should act
+/// on: the batch, or nothing while a recording replays — a replayed session must not notify, run
+/// actions or reach scrapers as if it were live.
+pub fn actionable(batch: &[SensorSample], replaying: bool) -> &[SensorSample] {
+    if replaying { &[] } else { batch }
+}
+
+/// Mute (or unmute) live publishing for a replay.
+pub fn set_replaying<R: Runtime>(app: &AppHandle<R>, on: bool) {
+    if let Some(bus) = app.try_state::<Bus>() {
+        bus.replaying.store(on, Ordering::Relaxed);
+    }
+}
+
+/// A receiver for every batch published from now on. `None` if the bus isn't managed.
+pub fn subscribe<R: Runtime>(app: &AppHandle<R>) -> Option<broadcast::Receiver<Batch>> {
+    app.try_state::<Bus>().map(|bus| bus.tx.subscribe())
+}
+
+/// A copy of the latest value per sensor id, across every source.
+pub fn latest<R: Runtime>(app: &AppHandle<R>) -> HashMap<String, SensorValue> {
+    app.try_state::<Bus>()
+        .map(|bus| {
+            let latest = bus.latest.lock().unwrap_or_else(|e| e.into_inner());
+            latest
+                .iter()
+                .map(|(id, s)| (id.clone(), s.value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A copy of the latest sample per sensor id, `stale` flag included — for a consumer seeding its
+/// view of what is live (a value restored by lastknown.rs is still stale here).
+pub fn latest_samples<R: Runtime>(app: &AppHandle<R>) -> Vec<SensorSample> {
+    app.try_state::<Bus>()
+        .map(|bus| {
+            let latest = bus.latest.lock().unwrap_or_else(|e| e.into_inner());
+            latest.values().cloned().collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,1732 @@
+//! iCalendar (ICS) agenda source — "next meeting in 12 min" on the overlay, and a `busy` flag
+//! automations can react to. Each configured calendar is an `.ics` file or an `http(s)://` /
+//! `webcal://` feed (a Google/Outlook "secret address"), re-read every `refresh_secs`; the agenda
+//! is recomputed every `TICK` in between so countdowns and `busy` stay current. Publishes:
+//!   `calendar.<name>.next.title`     the next timed event to start (all-day events are skipped)
+//!   `calendar.<name>.next.start`     its start, RFC 3339 in local time
+//!   `calendar.<name>.next.in_secs`   seconds until it starts (−1 when nothing is scheduled)
+//!   `calendar.<name>.next.location`  its location (empty when none)
+//!   `calendar.<name>.today`          Json array of today's events (local day), all-day included
+//!   `calendar.<name>.upcoming`       Json array of the next `UPCOMING_DAYS` days' events, for a
+//!                                    month view (capped at `MAX_UPCOMING`)
+//!   `calendar.<name>.busy`           1 while an opaque (not `TRANSP:TRANSPARENT`) event is running
+//!   `calendar.<name>.status`         `ok` / `error` / `HTTP 404`
+//! Event objects are `{ title, location, start, end, allDay }` with start/end in Unix ms.
+//!
+//! Configured in `plugins/calendar.json`:
+//! `{ "calendars": [{ "name": "work", "source": "https://calendar.example/basic.ics" },
+//!                  { "name": "family", "source": "C:\\Users\\me\\family.ics", "always": true }] }`
+//!
+//! Recurrence covers what calendar apps export: RRULE with FREQ=DAILY/WEEKLY/MONTHLY/YEARLY,
+//! INTERVAL, COUNT, UNTIL, BYDAY (with ordinals, e.g. `-1FR`; counted within the year for a YEARLY
+//! rule without BYMONTH), BYMONTHDAY, BYMONTH and BYSETPOS, plus RDATE, EXDATE, RECURRENCE-ID
+//! overrides and STATUS:CANCELLED. Weeks start on Monday (WKST is ignored). Rules with finer
+//! frequencies (HOURLY, …) are skipped with a warning. `TZID`s resolve through the feed's own
+//! VTIMEZONE blocks (which RFC 5545 requires), so no tz database is bundled; each zone's
+//! transitions are worked out once per year and cached. An unknown TZID falls back to local time.
+//! Feeds are read up to `MAX_FEED` bytes. Fetching reuses httppoll.rs' demand gating, `always`
+//! opt-out and backoff. A feed URL is treated like a token: the status reports only its host, a
+//! blank save keeps the stored one, and fetch errors are logged without it. The parser, recurrence
+//! expansion and agenda are unit-tested.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::httppoll::{http_client, read_capped};
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Refresh guardrails (seconds).
+const MIN_REFRESH: u64 = 60;
+const MAX_REFRESH: u64 = 86_400;
+/// How often the agenda is recomputed from the cached calendar.
+const TICK: Duration = Duration::from_secs(30);
+/// Re-check the demand gate this often while a calendar is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+const FETCH_TIMEOUT_SECS: u64 = 30;
+/// Largest feed read (bytes); years of a busy shared calendar stay well under it.
+const MAX_FEED: usize = 16 * 1024 * 1024;
+/// How far ahead `next` looks.
+const HORIZON_DAYS: i64 = 366;
+const UPCOMING_DAYS: i64 = 42;
+const MAX_UPCOMING: usize = 200;
+/// Recurrence periods walked per rule before giving up (a daily rule over ~270 years).
+const MAX_PERIODS: u32 = 100_000;
+const DAY_MS: i64 = 86_400_000;
+
+fn default_refresh() -> u64 {
+    900
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One calendar feed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalendarDef {
+    pub name: String,
+    /// An absolute `.ics` path, or an `http(s)://` / `webcal://` URL.
+    pub source: String,
+    #[serde(default = "default_refresh")]
+    pub refresh_secs: u64,
+    /// Accept a self-signed certificate (like `HaConfig.insecure`).
+    #[serde(default)]
+    pub insecure: bool,
+    /// Keep the agenda live while no window shows it (for automations).
+    #[serde(default)]
+    pub always: bool,
+}
+
+impl Supervised for CalendarDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// What the webview learns about one calendar. A feed URL is a secret (private iCal links carry
+/// their token in the path), so only its host is shown; local paths are shown as they are.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarDefStatus {
+    pub name: String,
+    /// The path of a local file; empty for a URL.
+    pub source: String,
+    pub has_url: bool,
+    /// `https://host/…` for a URL.
+    pub url_host: Option<String>,
+    pub refresh_secs: u64,
+    pub insecure: bool,
+    pub always: bool,
+}
+
+impl From<&CalendarDef> for CalendarDefStatus {
+    fn from(c: &CalendarDef) -> Self {
+        let url = feed_url(&c.source);
+        CalendarDefStatus {
+            name: c.name.clone(),
+            source: if url.is_some() {
+                String::new()
+            } else {
+                c.source.clone()
+            },
+            has_url: url.is_some(),
+            url_host: url.as_deref().map(redact_url),
+            refresh_secs: c.refresh_secs,
+            insecure: c.insecure,
+            always: c.always,
+        }
+    }
+}
+
+/// `scheme://host/…` — everything after the host dropped.
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(u) => format!("{}://{}/…", u.scheme(), u.host_str().unwrap_or("")),
+        Err(_) => "…".into(),
+    }
+}
+
+/// `plugins/calendar.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CalendarConfig {
+    #[serde(default)]
+    pub calendars: Vec<CalendarDef>,
+}
+
+/// Managed state: the live definitions plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct CalendarSource {
+    config: Mutex<CalendarConfig>,
+    generation: AtomicU64,
+}
+
+impl CalendarSource {
+    fn replace(&self, cfg: CalendarConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("calendar.json"))
+}
+
+pub fn load_calendar_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<CalendarConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_calendar_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("calendar", "skipping calendar")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<CalendarSource>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("calendar", "failed to read calendar.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// `webcal://` is just the calendar-app scheme for https.
+fn feed_url(source: &str) -> Option<String> {
+    if let Some(rest) = source.strip_prefix("webcal://") {
+        return Some(format!("https://{rest}"));
+    }
+    (source.starts_with("http://") || source.starts_with("https://")).then(|| source.to_string())
+}
+
+/// Slug names, clamp the refresh, reject duplicates and sources that are neither a URL nor an
+/// absolute path.
+fn normalize_config(cfg: CalendarConfig) -> (CalendarConfig, Vec<String>) {
+    let mut kept: Vec<CalendarDef> = Vec::new();
+    let mut errors = Vec::new();
+    for c in cfg.calendars {
+        let Some(name) = id_segment(&c.name) else {
+            errors.push(format!("`{}`: invalid name", c.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let source = c.source.trim().to_string();
+        if feed_url(&source).is_none() && !std::path::Path::new(&source).is_absolute() {
+            errors.push(format!(
+                "`{name}`: source must be a URL or an absolute path"
+            ));
+            continue;
+        }
+        kept.push(CalendarDef {
+            name,
+            source,
+            refresh_secs: c.refresh_secs.clamp(MIN_REFRESH, MAX_REFRESH),
+            ..c
+        });
+    }
+    (CalendarConfig { calendars: kept }, errors)
+}
+
+// ---- ICS parsing (pure) ----
+
+/// One content line: `NAME;PARAM=v:VALUE`.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Prop {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Prop {
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A `BEGIN:X` … `END:X` block.
+#[derive(Clone, Debug, Default)]
+struct Component {
+    name: String,
+    props: Vec<Prop>,
+    children: Vec<Component>,
+}
+
+impl Component {
+    fn prop(&self, name: &str) -> Option<&Prop> {
+        self.props.iter().find(|p| p.name == name)
+    }
+
+    fn props<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Prop> {
+        self.props.iter().filter(move |p| p.name == name)
+    }
+}
+
+/// Split a (unfolded) content line into name, params and value. The value starts at the first
+/// colon outside a quoted param value.
+fn parse_line(line: &str) -> Option<Prop> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| {
+            (
+                k.trim().to_ascii_uppercase(),
+                v.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some(Prop {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Undo TEXT escaping (`\n`, `\,`, `\;`, `\\`).
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Parse ICS text into its top-level components (normally one VCALENDAR).
+fn parse_ics(text: &str) -> Result<Vec<Component>, String> {
+    // Unfold: a line starting with a space or tab continues the previous one.
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match raw.strip_prefix([' ', '\t']) {
+            Some(cont) if !lines.is_empty() => lines.last_mut().unwrap().push_str(cont),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    let mut stack: Vec<Component> = Vec::new();
+    let mut top = Vec::new();
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        let Some(prop) = parse_line(line) else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: prop.value.trim().to_ascii_uppercase(),
+                ..Component::default()
+            }),
+            "END" => {
+                let done = stack.pop().ok_or("unbalanced END")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(done),
+                    None => top.push(done),
+                }
+            }
+            _ => {
+                if let Some(current) = stack.last_mut() {
+                    current.props.push(prop);
+                }
+            }
+        }
+    }
+    if top.iter().all(|c| c.name != "VCALENDAR") {
+        return Err("no VCALENDAR".into());
+    }
+    Ok(top)
+}
+
+// ---- dates & zones ----
+
+/// How a wall-clock time maps to an instant.
+#[derive(Clone, Debug, PartialEq)]
+enum Zone {
+    Utc,
+    /// Floating (no zone) and all-day values: the viewer's local time.
+    Local,
+    Named(String),
+}
+
+/// A DATE or DATE-TIME value.
+#[derive(Clone, Debug, PartialEq)]
+struct Stamp {
+    at: NaiveDateTime,
+    zone: Zone,
+    date_only: bool,
+}
+
+fn parse_stamp(value: &str, tzid: Option<&str>, date_only: bool) -> Option<Stamp> {
+    let value = value.trim();
+    if date_only || value.len() == 8 {
+        let d = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(Stamp {
+            at: d.and_time(NaiveTime::MIN),
+            zone: Zone::Local,
+            date_only: true,
+        });
+    }
+    let (raw, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(raw) => (raw, true),
+        None => (value, false),
+    };
+    let at = NaiveDateTime::parse_from_str(raw, "%Y%m%dT%H%M%S").ok()?;
+    let zone = match tzid {
+        _ if utc => Zone::Utc,
+        Some("UTC" | "Etc/UTC" | "GMT" | "Etc/GMT" | "Z") => Zone::Utc,
+        Some(id) => Zone::Named(id.to_string()),
+        None => Zone::Local,
+    };
+    Some(Stamp {
+        at,
+        zone,
+        date_only: false,
+    })
+}
+
+/// Every value of a (possibly comma-separated, possibly repeated) date property.
+fn stamps(c: &Component, name: &str) -> Vec<Stamp> {
+    c.props(name)
+        .flat_map(|p| {
+            let date_only = p.param("VALUE") == Some("DATE");
+            let tzid = p.param("TZID");
+            p.value
+                .split(',')
+                .filter_map(move |v| parse_stamp(v, tzid, date_only))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// `+0100` / `-0530` / `+053000` → seconds east of UTC.
+fn parse_offset(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let (sign, digits) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 && digits.len() != 6 {
+        return None;
+    }
+    let n = |i: usize| digits.get(i..i + 2)?.parse::<i32>().ok();
+    let secs = if digits.len() == 6 { n(4)? } else { 0 };
+    Some(sign * (n(0)? * 3600 + n(2)? * 60 + secs))
+}
+
+/// One STANDARD/DAYLIGHT block of a VTIMEZONE.
+#[derive(Clone, Debug)]
+struct Observance {
+    /// Onset, in the wall time before it takes effect.
+    start: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    rule: Option<RRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+/// One year's onsets for `offset_at`: the last one before the year (if any), then the year's own,
+/// ascending, as (wall time, offset it switches to).
+type YearOnsets = Vec<(NaiveDateTime, i32)>;
+
+/// A feed-supplied timezone definition.
+#[derive(Debug, Default)]
+struct VTimezone {
+    observances: Vec<Observance>,
+    /// `year_onsets` per year, computed on first use: the rules are expanded from their DTSTART
+    /// (often 1601), which is too much work for every `to_ms` call.
+    years: Mutex<HashMap<i32, YearOnsets>>,
+}
+
+impl VTimezone {
+    fn parse(c: &Component) -> Self {
+        let observances = c
+            .children
+            .iter()
+            .filter(|o| o.name == "STANDARD" || o.name == "DAYLIGHT")
+            .filter_map(|o| {
+                let start = stamps(o, "DTSTART").first()?.at;
+                Some(Observance {
+                    start,
+                    offset_from: parse_offset(&o.prop("TZOFFSETFROM")?.value)?,
+                    offset_to: parse_offset(&o.prop("TZOFFSETTO")?.value)?,
+                    rule: o.prop("RRULE").and_then(|p| RRule::parse(&p.value).ok()),
+                    rdates: stamps(o, "RDATE").into_iter().map(|s| s.at).collect(),
+                })
+            })
+            .collect();
+        Self {
+            observances,
+            years: Mutex::default(),
+        }
+    }
+
+    /// An observance's onsets through `limit`. A rule's UNTIL retires it, so historic rules
+    /// (pre-2007 US DST) don't keep producing onsets.
+    fn onsets(o: &Observance, limit: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let mut onsets = match &o.rule {
+            Some(rule) => {
+                // An onset is in the wall time of the offset it leaves; UNTIL is usually UTC.
+                let until_ok = |t: NaiveDateTime| match &rule.until {
+                    None => true,
+                    Some(u) if u.date_only => t.date() <= u.at.date(),
+                    Some(u) if u.zone == Zone::Utc => {
+                        t - chrono::TimeDelta::seconds(i64::from(o.offset_from)) <= u.at
+                    }
+                    Some(u) => t <= u.at,
+                };
+                rule.expand(o.start, limit, &until_ok)
+            }
+            None => vec![o.start],
+        };
+        onsets.extend(o.rdates.iter().copied());
+        onsets
+    }
+
+    /// Every observance's onsets that matter within `year` (see `YearOnsets`). On equal onsets the
+    /// first observance wins.
+    fn year_onsets(&self, year: i32) -> YearOnsets {
+        let bound = |y| NaiveDate::from_ymd_opt(y, 1, 1).map(|d| d.and_time(NaiveTime::MIN));
+        let (Some(start), Some(end)) = (bound(year), bound(year + 1)) else {
+            return Vec::new();
+        };
+        let mut before: Option<(NaiveDateTime, i32)> = None;
+        let mut within = Vec::new();
+        for o in &self.observances {
+            for t in Self::onsets(o, end) {
+                if t < start {
+                    if before.is_none_or(|(b, _)| t > b) {
+                        before = Some((t, o.offset_to));
+                    }
+                } else if t < end {
+                    within.push((t, o.offset_to));
+                }
+            }
+        }
+        within.sort_by_key(|(t, _)| *t);
+        within.dedup_by_key(|(t, _)| *t);
+        before.into_iter().chain(within).collect()
+    }
+
+    /// The UTC offset in force at wall time `local`: the observance with the latest onset at or
+    /// before it wins; before any onset, the earliest observance's `offset_from`.
+    fn offset_at(&self, local: NaiveDateTime) -> i32 {
+        let mut years = self.years.lock().unwrap_or_else(|e| e.into_inner());
+        let onsets = years
+            .entry(local.year())
+            .or_insert_with(|| self.year_onsets(local.year()));
+        onsets
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= local)
+            .map(|(_, off)| *off)
+            .unwrap_or_else(|| {
+                self.observances
+                    .iter()
+                    .min_by_key(|o| o.start)
+                    .map_or(0, |o| o.offset_from)
+            })
+    }
+}
+
+/// The feed's zones, for turning wall times into instants.
+#[derive(Default)]
+struct Zones(HashMap<String, VTimezone>);
+
+impl Zones {
+    /// Unix ms of wall time `at` in `zone`.
+    fn to_ms(&self, at: NaiveDateTime, zone: &Zone) -> i64 {
+        match zone {
+            Zone::Utc => at.and_utc().timestamp_millis(),
+            Zone::Named(id) if self.0.contains_key(id) => {
+                let offset = self.0[id].offset_at(at);
+                at.and_utc().timestamp_millis() - i64::from(offset) * 1000
+            }
+            // Unknown TZIDs read as local time rather than dropping the event.
+            Zone::Local | Zone::Named(_) => local_ms(at),
+        }
+    }
+}
+
+/// Local wall time → Unix ms; a time skipped by a DST jump resolves an hour later.
+fn local_ms(at: NaiveDateTime) -> i64 {
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .or_else(|| {
+            Local
+                .from_local_datetime(&(at + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| at.and_utc().timestamp_millis(), |t| t.timestamp_millis())
+}
+
+// ---- recurrence (pure) ----
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported subset of an RRULE.
+#[derive(Clone, Debug, PartialEq)]
+struct RRule {
+    freq: Freq,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<Stamp>,
+    /// `(ordinal, weekday)`; the ordinal only applies to MONTHLY/YEARLY.
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+    by_set_pos: Vec<i32>,
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    Some(match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (y, m) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(y, m, 1)
+        .and_then(|d| d.pred_opt())
+        .map_or(28, |d| d.day())
+}
+
+impl RRule {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut rule = RRule {
+            freq: Freq::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+        };
+        let mut freq = None;
+        let ints = |v: &str| -> Result<Vec<i32>, String> {
+            v.split(',')
+                .map(|n| n.trim().parse::<i32>().map_err(|e| e.to_string()))
+                .collect()
+        };
+        for part in s.trim().split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or("malformed RRULE")?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Freq::Daily,
+                        "WEEKLY" => Freq::Weekly,
+                        "MONTHLY" => Freq::Monthly,
+                        "YEARLY" => Freq::Yearly,
+                        other => return Err(format!("unsupported FREQ={other}")),
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse().map_err(|_| "bad INTERVAL")?,
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| "bad COUNT")?),
+                "UNTIL" => rule.until = parse_stamp(value, None, false),
+                "BYDAY" => {
+                    for d in value.split(',') {
+                        let d = d.trim().to_ascii_uppercase();
+                        let split = d.len().saturating_sub(2);
+                        let wd = weekday(&d[split..]).ok_or("bad BYDAY")?;
+                        let ord = match &d[..split] {
+                            "" => None,
+                            n => Some(n.trim_start_matches('+').parse().map_err(|_| "bad BYDAY")?),
+                        };
+                        rule.by_day.push((ord, wd));
+                    }
+                }
+                "BYMONTHDAY" => rule.by_month_day = ints(value)?,
+                "BYMONTH" => rule.by_month = ints(value)?.into_iter().map(|m| m as u32).collect(),
+                "BYSETPOS" => rule.by_set_pos = ints(value)?,
+                "WKST" => {}
+                other => return Err(format!("unsupported {other}")),
+            }
+        }
+        rule.freq = freq.ok_or("RRULE without FREQ")?;
+        if rule.interval == 0 {
+            return Err("INTERVAL must be positive".into());
+        }
+        Ok(rule)
+    }
+
+    /// Whether `date` passes the BYDAY filter, with ordinals counted within its month.
+    fn day_matches(&self, date: NaiveDate, ordinals: bool) -> bool {
+        let last = days_in_month(date.year(), date.month()) as i32;
+        let d = date.day() as i32;
+        self.by_day.iter().any(|(ord, wd)| {
+            date.weekday() == *wd
+                && match ord {
+                    Some(n) if ordinals && *n > 0 => (d - 1) / 7 + 1 == *n,
+                    Some(n) if ordinals && *n < 0 => (last - d) / 7 + 1 == -n,
+                    _ => true,
+                }
+        })
+    }
+
+    /// Candidate days of a YEARLY rule with BYDAY but neither BYMONTH nor BYMONTHDAY: the whole
+    /// year, with ordinals counted within it (`20MO` is the year's 20th Monday, RFC 5545 §3.3.10).
+    fn year_days(&self, year: i32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+            return Vec::new();
+        };
+        let len = if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+            366
+        } else {
+            365
+        };
+        first
+            .iter_days()
+            .take(len)
+            .filter(|d| {
+                let i = d.ordinal0() as i32;
+                self.by_day.iter().any(|(ord, wd)| {
+                    d.weekday() == *wd
+                        && match ord {
+                            Some(n) if *n > 0 => i / 7 + 1 == *n,
+                            Some(n) if *n < 0 => (len as i32 - 1 - i) / 7 + 1 == -n,
+                            _ => true,
+                        }
+                })
+            })
+            .collect()
+    }
+
+    /// Candidate days of one month for MONTHLY/YEARLY.
+    fn month_days(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let last = days_in_month(year, month);
+        let days: Vec<u32> = if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .filter_map(|&n| {
+                    let d = if n < 0 { last as i32 + 1 + n } else { n };
+                    (1..=last as i32).contains(&d).then_some(d as u32)
+                })
+                .collect()
+        } else if self.by_day.is_empty() {
+            // Feb 30th just doesn't happen (RFC 5545 §3.3.10).
+            (default_day <= last)
+                .then_some(default_day)
+                .into_iter()
+                .collect()
+        } else {
+            (1..=last).collect()
+        };
+        let mut out: Vec<NaiveDate> = days
+            .into_iter()
+            .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+            .filter(|d| self.by_day.is_empty() || self.day_matches(*d, true))
+            .collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    /// The first day of the period `step` periods after the one containing `anchor`.
+    fn period_start(&self, anchor: NaiveDate, step: u32) -> Option<NaiveDate> {
+        let step = i64::from(step);
+        match self.freq {
+            Freq::Daily => anchor.checked_add_signed(chrono::Duration::days(step)),
+            Freq::Weekly => {
+                let back = i64::from(anchor.weekday().num_days_from_monday());
+                anchor.checked_add_signed(chrono::Duration::days(step * 7 - back))
+            }
+            Freq::Monthly => {
+                let months = i64::from(anchor.year()) * 12 + i64::from(anchor.month0()) + step;
+                NaiveDate::from_ymd_opt(
+                    i32::try_from(months.div_euclid(12)).ok()?,
+                    months.rem_euclid(12) as u32 + 1,
+                    1,
+                )
+            }
+            Freq::Yearly => {
+                NaiveDate::from_ymd_opt(i32::try_from(i64::from(anchor.year()) + step).ok()?, 1, 1)
+            }
+        }
+    }
+
+    /// The candidate dates of the period `step` periods after the one containing `anchor`.
+    fn period(&self, anchor: NaiveDate, step: u32) -> Vec<NaiveDate> {
+        let mut dates = match self.freq {
+            Freq::Daily => {
+                let Some(d) = self.period_start(anchor, step) else {
+                    return Vec::new();
+                };
+                let ok = (self.by_day.is_empty() || self.day_matches(d, false))
+                    && (self.by_month_day.is_empty() || !self.month_days_of(d).is_empty());
+                if ok { vec![d] } else { vec![] }
+            }
+            Freq::Weekly => {
+                let Some(monday) = self.period_start(anchor, step) else {
+                    return Vec::new();
+                };
+                let mut days: Vec<NaiveDate> = if self.by_day.is_empty() {
+                    vec![anchor.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, wd)| *wd).collect()
+                }
+                .into_iter()
+                .map(|wd| monday + chrono::Duration::days(i64::from(wd.num_days_from_monday())))
+                .collect();
+                days.sort();
+                days.dedup();
+                days
+            }
+            Freq::Monthly => match self.period_start(anchor, step) {
+                Some(first) => self.month_days(first.year(), first.month(), anchor.day()),
+                None => Vec::new(),
+            },
+            Freq::Yearly => {
+                let Some(y) = self.period_start(anchor, step).map(|d| d.year()) else {
+                    return Vec::new();
+                };
+                // Without BYMONTH, BYDAY spans the whole year and BYMONTHDAY every month; only a
+                // bare rule stays on DTSTART's month.
+                if self.by_month.is_empty()
+                    && self.by_month_day.is_empty()
+                    && !self.by_day.is_empty()
+                {
+                    self.year_days(y)
+                } else {
+                    let months = if !self.by_month.is_empty() {
+                        self.by_month.clone()
+                    } else if !self.by_month_day.is_empty() {
+                        (1..=12).collect()
+                    } else {
+                        vec![anchor.month()]
+                    };
+                    months
+                        .into_iter()
+                        .flat_map(|m| self.month_days(y, m, anchor.day()))
+                        .collect()
+                }
+            }
+        };
+        if !self.by_month.is_empty() {
+            dates.retain(|d| self.by_month.contains(&d.month()));
+        }
+        if !self.by_set_pos.is_empty() {
+            let n = dates.len() as i32;
+            let mut picked: Vec<NaiveDate> = self
+                .by_set_pos
+                .iter()
+                .filter_map(|&p| {
+                    let i = if p < 0 { n + p } else { p - 1 };
+                    (0..n).contains(&i).then(|| dates[i as usize])
+                })
+                .collect();
+            picked.sort();
+            picked.dedup();
+            dates = picked;
+        }
+        dates
+    }
+
+    /// BYMONTHDAY as a filter for DAILY: `[date]` when it matches, else empty.
+    fn month_days_of(&self, date: NaiveDate) -> Vec<NaiveDate> {
+        let last = days_in_month(date.year(), date.month()) as i32;
+        let d = date.day() as i32;
+        self.by_month_day
+            .iter()
+            .any(|&n| n == d || n == d - last - 1)
+            .then_some(date)
+            .into_iter()
+            .collect()
+    }
+
+    /// Occurrence starts (wall time) from `dtstart` through `limit`, ascending. `dtstart` is always
+    /// the first occurrence; COUNT counts it. `until_ok` applies UNTIL in the event's zone.
+    fn expand(
+        &self,
+        dtstart: NaiveDateTime,
+        limit: NaiveDateTime,
+        until_ok: &dyn Fn(NaiveDateTime) -> bool,
+    ) -> Vec<NaiveDateTime> {
+        let mut out = Vec::new();
+        if dtstart > limit || !until_ok(dtstart) {
+            return out;
+        }
+        out.push(dtstart);
+        let count = self.count.unwrap_or(u32::MAX) as usize;
+        let time = dtstart.time();
+        for k in 0..MAX_PERIODS {
+            let Some(step) = k.checked_mul(self.interval) else {
+                break;
+            };
+            // Periods only move forward: stop at the first one starting past the limit.
+            match self.period_start(dtstart.date(), step) {
+                Some(first) if first.and_time(NaiveTime::MIN) <= limit => {}
+                _ => break,
+            }
+            for d in self.period(dtstart.date(), step) {
+                let t = d.and_time(time);
+                if t <= dtstart {
+                    continue;
+                }
+                if out.len() >= count || t > limit || !until_ok(t) {
+                    return out;
+                }
+                out.push(t);
+            }
+        }
+        out
+    }
+}
+
+// ---- events & agenda (pure) ----
+
+/// One occurrence, ready to publish.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub title: String,
+    pub location: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub all_day: bool,
+    /// Counts towards `busy` (not `TRANSP:TRANSPARENT`).
+    pub opaque: bool,
+}
+
+/// A parsed feed: its events and the zones they reference.
+#[derive(Default)]
+pub struct Calendar {
+    events: Vec<Component>,
+    zones: Zones,
+}
+
+impl Calendar {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut cal = Calendar::default();
+        for top in parse_ics(text)? {
+            for c in top.children {
+                match c.name.as_str() {
+                    "VEVENT" => cal.events.push(c),
+                    "VTIMEZONE" => {
+                        if let Some(id) = c.prop("TZID").map(|p| p.value.trim().to_string()) {
+                            cal.zones.0.insert(id, VTimezone::parse(&c));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(cal)
+    }
+
+    /// One occurrence of `event` starting at wall time `start` (end from DTEND/DURATION).
+    fn instance(&self, event: &Component, start: &Stamp, length: Length) -> Instance {
+        let start_ms = self.zones.to_ms(start.at, &start.zone);
+        let end_ms = match length {
+            Length::Days(days) => self
+                .zones
+                .to_ms(start.at + chrono::Duration::days(days), &start.zone),
+            Length::Wall(d) => self.zones.to_ms(start.at + d, &start.zone),
+            Length::Exact(ms) => start_ms + ms,
+        };
+        let text = |name: &str| {
+            event
+                .prop(name)
+                .map(|p| unescape(p.value.trim()))
+                .unwrap_or_default()
+        };
+        Instance {
+            title: text("SUMMARY"),
+            location: text("LOCATION"),
+            start_ms,
+            end_ms: end_ms.max(start_ms),
+            all_day: start.date_only,
+            opaque: !event
+                .prop("TRANSP")
+                .is_some_and(|p| p.value.trim().eq_ignore_ascii_case("TRANSPARENT")),
+        }
+    }
+
+    /// An event's length, from DTEND (relative to DTSTART) or DURATION.
+    fn length_of(&self, e: &Component, start: &Stamp) -> Length {
+        if let Some(end) = stamps(e, "DTEND").into_iter().next() {
+            if start.date_only {
+                return Length::Days((end.at.date() - start.at.date()).num_days().max(1));
+            }
+            if end.zone == start.zone {
+                return Length::Wall(end.at - start.at);
+            }
+            let ms = self.zones.to_ms(end.at, &end.zone) - self.zones.to_ms(start.at, &start.zone);
+            return Length::Exact(ms.max(0));
+        }
+        match e.prop("DURATION").and_then(|p| parse_duration(&p.value)) {
+            Some(ms) if start.date_only && ms % DAY_MS == 0 => Length::Days((ms / DAY_MS).max(1)),
+            Some(ms) => Length::Exact(ms.max(0)),
+            // No end: an all-day event is one day, a timed one is instantaneous.
+            None if start.date_only => Length::Days(1),
+            None => Length::Exact(0),
+        }
+    }
+
+    /// Every occurrence overlapping `[from_ms, to_ms)`, by start.
+    pub fn instances(&self, from_ms: i64, to_ms: i64) -> Vec<Instance> {
+        let cancelled = |e: &Component| {
+            e.prop("STATUS")
+                .is_some_and(|p| p.value.trim().eq_ignore_ascii_case("CANCELLED"))
+        };
+        let uid = |e: &Component| e.prop("UID").map(|p| p.value.trim().to_string());
+        // Overridden occurrences: (UID, original start ms).
+        let mut overridden: Vec<(String, i64)> = Vec::new();
+        let mut out = Vec::new();
+        for e in self.events.iter() {
+            let Some(rid) = stamps(e, "RECURRENCE-ID").into_iter().next() else {
+                continue;
+            };
+            if let Some(u) = uid(e) {
+                overridden.push((u, self.zones.to_ms(rid.at, &rid.zone)));
+            }
+            if let (false, Some(start)) = (cancelled(e), stamps(e, "DTSTART").first()) {
+                out.push(self.instance(e, start, self.length_of(e, start)));
+            }
+        }
+        // Expand a little past the window in wall time: zone offsets are under a day.
+        let limit = chrono::DateTime::from_timestamp_millis(to_ms + DAY_MS)
+            .map_or(NaiveDateTime::MAX, |d| d.naive_utc());
+        let floor = chrono::DateTime::from_timestamp_millis(from_ms - 2 * DAY_MS)
+            .map_or(NaiveDateTime::MIN, |d| d.naive_utc());
+        for e in self.events.iter() {
+            if e.prop("RECURRENCE-ID").is_some() || cancelled(e) {
+                continue;
+            }
+            let Some(start) = stamps(e, "DTSTART").into_iter().next() else {
+                continue;
+            };
+            let length = self.length_of(e, &start);
+            let mut starts = match e.prop("RRULE").map(|p| RRule::parse(&p.value)) {
+                Some(Ok(rule)) => {
+                    let until = rule.until.clone();
+                    let zone = start.zone.clone();
+                    let until_ok = |t: NaiveDateTime| match &until {
+                        None => true,
+                        Some(u) if u.date_only => t.date() <= u.at.date(),
+                        Some(u) if u.zone == Zone::Utc && zone != Zone::Utc => {
+                            self.zones.to_ms(t, &zone) <= u.at.and_utc().timestamp_millis()
+                        }
+                        Some(u) => t <= u.at,
+                    };
+                    rule.expand(start.at, limit, &until_ok)
+                }
+                Some(Err(err)) => {
+                    log::warn(
+                        "calendar",
+                        "unsupported recurrence; using the first occurrence",
+                    )
+                    .field(
+                        "summary",
+                        e.prop("SUMMARY")
+                            .map(|p| p.value.clone())
+                            .unwrap_or_default(),
+                    )
+                    .field("error", err)
+                    .emit();
+                    vec![start.at]
+                }
+                None => vec![start.at],
+            };
+            starts.extend(stamps(e, "RDATE").into_iter().map(|s| s.at));
+            starts.sort();
+            starts.dedup();
+            let excluded: Vec<i64> = stamps(e, "EXDATE")
+                .iter()
+                .map(|x| self.zones.to_ms(x.at, &x.zone))
+                .collect();
+            let id = uid(e);
+            // Rough wall-time prefilter before the (zone-resolving) exact one.
+            let span = length.rough();
+            for at in starts.into_iter().filter(|t| *t + span >= floor) {
+                let stamp = Stamp {
+                    at,
+                    ..start.clone()
+                };
+                let inst = self.instance(e, &stamp, length);
+                let original = self.zones.to_ms(at, &start.zone);
+                if excluded.contains(&original)
+                    || id
+                        .as_ref()
+                        .is_some_and(|u| overridden.contains(&(u.clone(), original)))
+                {
+                    continue;
+                }
+                out.push(inst);
+            }
+        }
+        out.retain(|i| i.start_ms < to_ms && (i.end_ms > from_ms || i.start_ms >= from_ms));
+        out.sort_by(|a, b| (a.start_ms, &a.title).cmp(&(b.start_ms, &b.title)));
+        out
+    }
+}
+
+/// How long an occurrence lasts.
+#[derive(Clone, Copy, Debug)]
+enum Length {
+    /// All-day: whole local days.
+    Days(i64),
+    /// DTEND in the same zone: wall-clock length (a 9–10 meeting stays 9–10 across DST).
+    Wall(chrono::Duration),
+    /// DURATION / cross-zone DTEND: exact elapsed time (ms).
+    Exact(i64),
+}
+
+impl Length {
+    fn rough(self) -> chrono::Duration {
+        match self {
+            Length::Days(d) => chrono::Duration::days(d),
+            Length::Wall(d) => d,
+            Length::Exact(ms) => chrono::Duration::milliseconds(ms),
+        }
+    }
+}
+
+/// `P1D`, `PT1H30M`, `P2W`, `-PT15M` → ms.
+fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let mut rest = s.strip_prefix('P')?;
+    let mut total = 0i64;
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = r;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let n: i64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?;
+        total += n * match (unit, in_time) {
+            ('W', false) => 7 * DAY_MS,
+            ('D', false) => DAY_MS,
+            ('H', true) => 3_600_000,
+            ('M', true) => 60_000,
+            ('S', true) => 1_000,
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(sign * total)
+}
+
+fn event_json(i: &Instance) -> serde_json::Value {
+    json!({
+        "title": i.title,
+        "location": i.location,
+        "start": i.start_ms,
+        "end": i.end_ms,
+        "allDay": i.all_day,
+    })
+}
+
+/// The agenda sensors for `instances` (covering today through the horizon) at `now_ms`, with
+/// today being `[day_start, day_end)`.
+pub fn agenda(
+    name: &str,
+    instances: &[Instance],
+    now_ms: i64,
+    day: (i64, i64),
+    ts: u64,
+) -> Vec<SensorSample> {
+    let base = format!("calendar.{name}");
+    let next = instances.iter().find(|i| !i.all_day && i.start_ms > now_ms);
+    let start_text = next
+        .and_then(|i| chrono::DateTime::from_timestamp_millis(i.start_ms))
+        .map(|t| t.with_timezone(&Local).to_rfc3339())
+        .unwrap_or_default();
+    let in_secs = next.map_or(-1.0, |i| ((i.start_ms - now_ms) / 1000) as f64);
+    let today: Vec<_> = instances
+        .iter()
+        .filter(|i| i.start_ms < day.1 && (i.end_ms > day.0 || i.start_ms >= day.0))
+        .map(event_json)
+        .collect();
+    let upcoming: Vec<_> = instances
+        .iter()
+        .filter(|i| i.end_ms > now_ms && i.start_ms < now_ms + UPCOMING_DAYS * DAY_MS)
+        .take(MAX_UPCOMING)
+        .map(event_json)
+        .collect();
+    let busy = instances
+        .iter()
+        .any(|i| i.opaque && i.start_ms <= now_ms && now_ms < i.end_ms);
+    vec![
+        SensorSample::text(
+            format!("{base}.next.title"),
+            ts,
+            next.map(|i| i.title.clone()).unwrap_or_default(),
+        ),
+        SensorSample::text(format!("{base}.next.start"), ts, start_text),
+        SensorSample::scalar(format!("{base}.next.in_secs"), ts, in_secs),
+        SensorSample::text(
+            format!("{base}.next.location"),
+            ts,
+            next.map(|i| i.location.clone()).unwrap_or_default(),
+        ),
+        SensorSample {
+            sensor: format!("{base}.today"),
+            ts_ms: ts,
+            value: SensorValue::Json(serde_json::Value::Array(today)),
+            stale: false,
+        },
+        SensorSample {
+            sensor: format!("{base}.upcoming"),
+            ts_ms: ts,
+            value: SensorValue::Json(serde_json::Value::Array(upcoming)),
+            stale: false,
+        },
+        SensorSample::scalar(format!("{base}.busy"), ts, f64::from(u8::from(busy))),
+    ]
+}
+
+// ---- task ----
+
+/// True while any window is consuming one of this calendar's sensors. Default OFF before the first
+/// report, like `endpoint_wanted`.
+fn calendar_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active = app.state::<ActiveSensors>();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let prefix = format!("calendar.{name}.");
+    crate::sensors::any_wanted(&guard, |id| id.starts_with(&prefix))
+}
+
+/// Read the feed: a local file, or a GET on its URL (2xx only), either capped at `MAX_FEED`.
+async fn load(client: &reqwest::Client, source: &str) -> Result<String, String> {
+    let Some(url) = feed_url(source) else {
+        let len = tokio::fs::metadata(source)
+            .await
+            .map_err(|e| e.to_string())?
+            .len();
+        if len > MAX_FEED as u64 {
+            return Err(format!("file larger than {MAX_FEED} bytes"));
+        }
+        return tokio::fs::read_to_string(source)
+            .await
+            .map_err(|e| e.to_string());
+    };
+    // reqwest errors quote the URL; it's a secret, so it never reaches the log.
+    let resp = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    let bytes = read_capped(resp, MAX_FEED).await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Today's local-day bounds (Unix ms).
+fn local_day() -> (i64, i64) {
+    let today = Local::now().date_naive();
+    let start = local_ms(today.and_time(NaiveTime::MIN));
+    let end = today
+        .succ_opt()
+        .map_or(start + DAY_MS, |d| local_ms(d.and_time(NaiveTime::MIN)));
+    (start, end)
+}
+
+/// Follow one calendar until aborted: refetch every `refresh_secs` (backing off on failure) and
+/// republish the agenda every `TICK` from the last good copy.
+async fn watch_calendar<R: Runtime>(app: AppHandle<R>, def: CalendarDef) {
+    let client = match http_client(FETCH_TIMEOUT_SECS, def.insecure) {
+        Ok(c) => c,
+        Err(err) => {
+            log::warn("calendar", "client build failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            return;
+        }
+    };
+    let status_id = format!("calendar.{}.status", def.name);
+    let refresh = Duration::from_secs(def.refresh_secs);
+    let mut cached: Option<Calendar> = None;
+    let mut next_fetch = tokio::time::Instant::now();
+    let mut fails: u32 = 0;
+    loop {
+        if !def.always && !calendar_wanted(&app, &def.name) {
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        if tokio::time::Instant::now() >= next_fetch {
+            let result = load(&client, &def.source)
+                .await
+                .and_then(|text| Calendar::parse(&text));
+            let status = match result {
+                Ok(cal) => {
+                    fails = 0;
+                    cached = Some(cal);
+                    "ok".to_string()
+                }
+                Err(err) => {
+                    fails = (fails + 1).min(5);
+                    log::warn("calendar", "calendar load failed")
+                        .field("name", def.name.clone())
+                        .field("error", err.clone())
+                        .emit();
+                    if err.starts_with("HTTP ") {
+                        err
+                    } else {
+                        "error".to_string()
+                    }
+                }
+            };
+            let _ = bus::publish(
+                &app,
+                &[SensorSample::text(status_id.clone(), now_ms(), status)],
+            );
+            let wait = if fails == 0 {
+                refresh
+            } else {
+                refresh
+                    .saturating_mul(1u32 << fails.min(4))
+                    .min(Duration::from_secs(1800))
+            };
+            next_fetch = tokio::time::Instant::now() + wait;
+        }
+        if let Some(cal) = &cached {
+            let now = now_ms();
+            let day = local_day();
+            let instances = cal.instances(day.0, now as i64 + HORIZON_DAYS * DAY_MS);
+            let batch = agenda(&def.name, &instances, now as i64, day, now);
+            let _ = bus::publish(&app, &batch);
+        }
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+/// The supervisor: one task per calendar, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_calendar<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<CalendarSource> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .calendars
+                .clone()
+        },
+        |c| tauri::async_runtime::spawn(watch_calendar(app.clone(), c)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/calendar.json` and restart the changed calendars (each refetches at once).
+/// Studio-window-guarded; any invalid entry rejects the whole save with every problem listed. A
+/// blank source keeps the stored one (the status never hands a feed URL back).
+#[tauri::command]
+pub async fn save_calendar_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, CalendarSource>,
+    calendars: Vec<CalendarDef>,
+) -> Result<Vec<CalendarDefStatus>, String> {
+    if window.label() != "studio" {
+        return Err("save_calendar_config is only allowed from the studio window".into());
+    }
+    let stored = load_calendar_config(&app)?.unwrap_or_default();
+    let calendars = keep_stored_sources(calendars, &stored.calendars);
+    let (cfg, errors) = normalize_config(CalendarConfig { calendars });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let status = cfg.calendars.iter().map(CalendarDefStatus::from).collect();
+    state.replace(cfg);
+    Ok(status)
+}
+
+/// Fill each blank `source` from the stored calendar of the same name.
+fn keep_stored_sources(calendars: Vec<CalendarDef>, stored: &[CalendarDef]) -> Vec<CalendarDef> {
+    calendars
+        .into_iter()
+        .map(|c| {
+            if !c.source.trim().is_empty() {
+                return c;
+            }
+            let slug = id_segment(&c.name);
+            match stored.iter().find(|s| Some(&s.name) == slug.as_ref()) {
+                Some(old) => CalendarDef {
+                    source: old.source.clone(),
+                    ..c
+                },
+                None => c,
+            }
+        })
+        .collect()
+}
+
+/// The configured calendars — a feed URL only as its host.
+#[tauri::command]
+pub fn calendar_config_status(state: State<'_, CalendarSource>) -> Vec<CalendarDefStatus> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .calendars
+        .iter()
+        .map(CalendarDefStatus::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> i64 {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    fn naive(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    const NEW_YORK: &str = "BEGIN:VTIMEZONE\r\nTZID:America/New_York\r\n\
+        BEGIN:DAYLIGHT\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nDTSTART:20070311T020000\r\n\
+        RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nEND:DAYLIGHT\r\n\
+        BEGIN:STANDARD\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nDTSTART:20071104T020000\r\n\
+        RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n";
+
+    fn ics(body: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{NEW_YORK}{body}END:VCALENDAR\r\n")
+    }
+
+    #[test]
+    fn parses_folded_escaped_lines_and_params() {
+        let cal = parse_ics(&ics(
+            "BEGIN:VEVENT\r\nSUMMARY:Plan\\, review\r\n  and ship\r\n\
+             LOCATION;ALTREP=\"https://x.example/a:b\":Room 1\r\nEND:VEVENT\r\n",
+        ))
+        .unwrap();
+        let ev = cal[0].children.iter().find(|c| c.name == "VEVENT").unwrap();
+        assert_eq!(
+            unescape(&ev.prop("SUMMARY").unwrap().value),
+            "Plan, review and ship"
+        );
+        let loc = ev.prop("LOCATION").unwrap();
+        assert_eq!(loc.value, "Room 1");
+        assert_eq!(loc.param("ALTREP"), Some("https://x.example/a:b"));
+        assert!(parse_ics("BEGIN:VEVENT\r\n").is_err());
+    }
+
+    #[test]
+    fn vtimezone_offsets_follow_dst() {
+        let cal = Calendar::parse(&ics("")).unwrap();
+        let ny = Zone::Named("America/New_York".into());
+        // Winter: UTC-5; summer: UTC-4.
+        assert_eq!(
+            cal.zones.to_ms(naive("2026-01-15 09:00"), &ny),
+            utc("2026-01-15 14:00")
+        );
+        assert_eq!(
+            cal.zones.to_ms(naive("2026-07-15 09:00"), &ny),
+            utc("2026-07-15 13:00")
+        );
+        // 2026's switch is Sunday March 8th.
+        assert_eq!(
+            cal.zones.to_ms(naive("2026-03-07 12:00"), &ny),
+            utc("2026-03-07 17:00")
+        );
+        assert_eq!(
+            cal.zones.to_ms(naive("2026-03-09 12:00"), &ny),
+            utc("2026-03-09 16:00")
+        );
+        // Each year's transitions are worked out once.
+        let years = cal.zones.0["America/New_York"].years.lock().unwrap();
+        assert_eq!(years.len(), 1);
+        assert_eq!(years[&2026].len(), 3);
+    }
+
+    #[test]
+    fn vtimezone_rules_stop_at_until() {
+        // Pre-2007 US rules end with UNTIL; only the current pair may apply in 2026.
+        let zone = "BEGIN:VTIMEZONE\r\nTZID:US-Eastern\r\n\
+            BEGIN:DAYLIGHT\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nDTSTART:19870405T020000\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=4;BYDAY=1SU;UNTIL=20060402T070000Z\r\nEND:DAYLIGHT\r\n\
+            BEGIN:STANDARD\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nDTSTART:19671029T020000\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU;UNTIL=20061029T060000Z\r\nEND:STANDARD\r\n\
+            BEGIN:DAYLIGHT\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\nDTSTART:20070311T020000\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nEND:DAYLIGHT\r\n\
+            BEGIN:STANDARD\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\nDTSTART:20071104T020000\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n";
+        let cal = Calendar::parse(&format!("BEGIN:VCALENDAR\r\n{zone}END:VCALENDAR\r\n")).unwrap();
+        let us = Zone::Named("US-Eastern".into());
+        // Between the old rule's last-Sunday-of-October and November's switch: still daylight.
+        assert_eq!(
+            cal.zones.to_ms(naive("2026-10-28 12:00"), &us),
+            utc("2026-10-28 16:00")
+        );
+        // The old rules still apply to their own years.
+        assert_eq!(
+            cal.zones.to_ms(naive("2005-10-31 12:00"), &us),
+            utc("2005-10-31 17:00")
+        );
+        assert_eq!(
+            cal.zones.to_ms(naive("2005-04-04 12:00"), &us),
+            utc("2005-04-04 16:00")
+        );
+    }
+
+    #[test]
+    fn rrule_expands_by_day_ordinals_and_set_pos() {
+        let rule = RRule::parse("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3").unwrap();
+        let got = rule.expand(
+            naive("2026-01-30 17:00"),
+            naive("2027-01-01 00:00"),
+            &|_| true,
+        );
+        assert_eq!(
+            got,
+            vec![
+                naive("2026-01-30 17:00"),
+                naive("2026-02-27 17:00"),
+                naive("2026-03-27 17:00")
+            ]
+        );
+        // Last weekday of the month.
+        let rule = RRule::parse("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1").unwrap();
+        let got = rule.expand(
+            naive("2026-05-29 09:00"),
+            naive("2026-07-01 00:00"),
+            &|_| true,
+        );
+        assert_eq!(
+            got,
+            vec![naive("2026-05-29 09:00"), naive("2026-06-30 09:00")]
+        );
+        // Every other week on Tue/Thu until a date.
+        let rule = RRule::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;UNTIL=20260115").unwrap();
+        let until = rule.until.clone().unwrap();
+        let got = rule.expand(naive("2026-01-01 10:00"), naive("2026-12-31 00:00"), &|t| {
+            t.date() <= until.at.date()
+        });
+        assert_eq!(
+            got,
+            vec![
+                naive("2026-01-01 10:00"),
+                naive("2026-01-13 10:00"),
+                naive("2026-01-15 10:00")
+            ]
+        );
+        // The 31st only in months that have one.
+        let rule = RRule::parse("FREQ=MONTHLY;COUNT=3").unwrap();
+        let got = rule.expand(
+            naive("2026-01-31 08:00"),
+            naive("2027-01-01 00:00"),
+            &|_| true,
+        );
+        assert_eq!(got[1], naive("2026-03-31 08:00"));
+        assert!(RRule::parse("FREQ=HOURLY").is_err());
+    }
+
+    #[test]
+    fn yearly_by_day_without_by_month_spans_the_year() {
+        let expand = |rule: &str, from: &str| {
+            RRule::parse(rule)
+                .unwrap()
+                .expand(naive(from), naive("2028-01-01 00:00"), &|_| true)
+        };
+        // Every Monday, past DTSTART's month.
+        assert_eq!(
+            expand("FREQ=YEARLY;BYDAY=MO;COUNT=3", "2026-01-26 09:00"),
+            vec![
+                naive("2026-01-26 09:00"),
+                naive("2026-02-02 09:00"),
+                naive("2026-02-09 09:00")
+            ]
+        );
+        // Ordinals count within the year: the 20th Monday, the last Friday.
+        assert_eq!(
+            expand("FREQ=YEARLY;BYDAY=20MO;COUNT=2", "2026-05-18 09:00"),
+            vec![naive("2026-05-18 09:00"), naive("2027-05-17 09:00")]
+        );
+        assert_eq!(
+            expand("FREQ=YEARLY;BYDAY=-1FR;COUNT=2", "2026-12-25 09:00"),
+            vec![naive("2026-12-25 09:00"), naive("2027-12-31 09:00")]
+        );
+    }
+
+    #[test]
+    fn instances_apply_zones_exdates_overrides_and_cancellations() {
+        let cal = Calendar::parse(&ics("BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup\r\n\
+             DTSTART;TZID=America/New_York:20260302T093000\r\n\
+             DTEND;TZID=America/New_York:20260302T094500\r\n\
+             RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR\r\n\
+             EXDATE;TZID=America/New_York:20260310T093000\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup (moved)\r\n\
+             RECURRENCE-ID;TZID=America/New_York:20260311T093000\r\n\
+             DTSTART;TZID=America/New_York:20260311T110000\r\nDURATION:PT30M\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:gone\r\nSUMMARY:Cancelled\r\nSTATUS:CANCELLED\r\n\
+             DTSTART:20260309T150000Z\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:holiday\r\nSUMMARY:Holiday\r\nTRANSP:TRANSPARENT\r\n\
+             DTSTART;VALUE=DATE:20260312\r\nEND:VEVENT\r\n"))
+        .unwrap();
+        let got = cal.instances(utc("2026-03-06 00:00"), utc("2026-03-12 00:00"));
+        let timed: Vec<(&str, i64)> = got
+            .iter()
+            .filter(|i| !i.all_day)
+            .map(|i| (i.title.as_str(), i.start_ms))
+            .collect();
+        assert_eq!(
+            timed,
+            vec![
+                // EST on Friday, EDT from Monday the 9th; the 10th is excluded, the 11th moved.
+                ("Standup", utc("2026-03-06 14:30")),
+                ("Standup", utc("2026-03-09 13:30")),
+                ("Standup (moved)", utc("2026-03-11 15:00")),
+            ]
+        );
+        assert_eq!(got[0].end_ms - got[0].start_ms, 15 * 60_000);
+        assert!(got.iter().all(|i| i.title != "Cancelled"));
+    }
+
+    #[test]
+    fn agenda_reports_next_today_and_busy() {
+        let meeting = |title: &str, start: &str, mins: i64| Instance {
+            title: title.into(),
+            location: "Room 1".into(),
+            start_ms: utc(start),
+            end_ms: utc(start) + mins * 60_000,
+            all_day: false,
+            opaque: true,
+        };
+        let instances = vec![
+            Instance {
+                all_day: true,
+                opaque: false,
+                ..meeting("Holiday", "2026-03-09 00:00", 24 * 60)
+            },
+            meeting("Standup", "2026-03-09 09:00", 15),
+            meeting("Review", "2026-03-09 10:00", 60),
+            meeting("Retro", "2026-03-10 16:00", 60),
+        ];
+        let day = (utc("2026-03-09 00:00"), utc("2026-03-10 00:00"));
+        let batch = agenda("work", &instances, utc("2026-03-09 09:10"), day, 1);
+        let get = |id: &str| &batch.iter().find(|s| s.sensor == id).unwrap().value;
+        assert!(matches!(get("calendar.work.next.title"), SensorValue::Text(s) if s == "Review"));
+        assert!(
+            matches!(get("calendar.work.next.in_secs"), SensorValue::Scalar(v) if *v == 3000.0)
+        );
+        assert!(matches!(get("calendar.work.busy"), SensorValue::Scalar(v) if *v == 1.0));
+        let today = match get("calendar.work.today") {
+            SensorValue::Json(v) => v.as_array().unwrap().len(),
+            _ => 0,
+        };
+        assert_eq!(today, 3);
+        // Between meetings; after the last one nothing is next.
+        let batch = agenda("work", &instances, utc("2026-03-10 18:00"), day, 1);
+        let get = |id: &str| &batch.iter().find(|s| s.sensor == id).unwrap().value;
+        assert!(matches!(get("calendar.work.busy"), SensorValue::Scalar(v) if *v == 0.0));
+        assert!(matches!(get("calendar.work.next.in_secs"), SensorValue::Scalar(v) if *v == -1.0));
+    }
+
+    #[test]
+    fn normalize_accepts_urls_and_absolute_paths() {
+        let def = |name: &str, source: &str| CalendarDef {
+            name: name.into(),
+            source: source.into(),
+            refresh_secs: 1,
+            insecure: false,
+            always: false,
+        };
+        let abs = std::env::temp_dir()
+            .join("cal.ics")
+            .to_string_lossy()
+            .into_owned();
+        let (cfg, errors) = normalize_config(CalendarConfig {
+            calendars: vec![
+                def("Work", "webcal://calendar.example/basic.ics"),
+                def("home", &abs),
+                def("rel", "cal.ics"),
+            ],
+        });
+        assert_eq!(errors.len(), 1);
+        assert_eq!(cfg.calendars[0].name, "work");
+        assert_eq!(cfg.calendars[0].refresh_secs, MIN_REFRESH);
+        assert_eq!(
+            feed_url(&cfg.calendars[0].source).as_deref(),
+            Some("https://calendar.example/basic.ics")
+        );
+
+        // The status shows a URL's host only, and a blank source keeps the stored one.
+        let status = CalendarDefStatus::from(&cfg.calendars[0]);
+        assert!(status.has_url && status.source.is_empty());
+        assert_eq!(
+            status.url_host.as_deref(),
+            Some("https://calendar.example/…")
+        );
+        assert_eq!(CalendarDefStatus::from(&cfg.calendars[1]).source, abs);
+        let kept = keep_stored_sources(vec![def("Work", " ")], &cfg.calendars);
+        assert_eq!(kept[0].source, "webcal://calendar.example/basic.ics");
+    }
+}
@@ -0,0 +1,656 @@
+//! The backend sensor catalog: one authoritative description of every sensor the backend can
+//! produce — kind, unit, expected range, source, demand-gating group and a friendly label.
+//!
+//! Two halves:
+//! - a static rule table (`RULES`) covering the system feed's stable ids and the shapes of the
+//!   dynamic ones (`cpu.core.{}`, `disk.{}.read`, `proc.{}.cpu`, `ha.{}`, …). Exact rules double as
+//!   the "always offered" list, like the client's `KNOWN_SENSORS`;
+//! - the ids actually SEEN, recorded by `bus::publish` as batches go out, with their observed kind.
+//!
+//! `list_sensors` joins them (static system ids + everything seen, each described by the first
+//! matching rule) for the studio inspector. The same list is mirrored to
+//! `<app_config_dir>/mcp/sensors.json` whenever it grows, for the out-of-process MCP server — which
+//! reads files, like `mcp/state.json`. Pure seams (`describe`, `catalog_entries`) are unit-tested.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::sensors::SensorSample;
+
+/// What unit a rule's value is in — drives both the `unit` string and the expected range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Unit {
+    /// 0..100.
+    Pct,
+    /// A signed percentage (e.g. a price change) — no fixed range.
+    PctSigned,
+    Bytes,
+    BytesPerSec,
+    Secs,
+    Millis,
+    Mhz,
+    Celsius,
+    Watts,
+    /// Battery charge/discharge rate: negative while discharging.
+    WattsSigned,
+    Wh,
+    Kwh,
+    Count,
+    /// 0 or 1.
+    Flag,
+    /// A non-negative amount in the user's own currency (no fixed unit string).
+    Money,
+    None,
+}
+
+impl Unit {
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Unit::Pct | Unit::PctSigned => Some("%"),
+            Unit::Bytes => Some("bytes"),
+            Unit::BytesPerSec => Some("bytes/s"),
+            Unit::Secs => Some("s"),
+            Unit::Millis => Some("ms"),
+            Unit::Mhz => Some("MHz"),
+            Unit::Celsius => Some("°C"),
+            Unit::Watts | Unit::WattsSigned => Some("W"),
+            Unit::Wh => Some("Wh"),
+            Unit::Kwh => Some("kWh"),
+            Unit::Count | Unit::Flag | Unit::Money | Unit::None => None,
+        }
+    }
+
+    fn range(self) -> (Option<f64>, Option<f64>) {
+        match self {
+            Unit::Pct => (Some(0.0), Some(100.0)),
+            Unit::Flag => (Some(0.0), Some(1.0)),
+            Unit::Bytes
+            | Unit::BytesPerSec
+            | Unit::Secs
+            | Unit::Millis
+            | Unit::Mhz
+            | Unit::Watts
+            | Unit::Wh
+            | Unit::Kwh
+            | Unit::Count
+            | Unit::Money => (Some(0.0), None),
+            Unit::PctSigned | Unit::Celsius | Unit::WattsSigned | Unit::None => (None, None),
+        }
+    }
+}
+
+/// One catalog rule. `pattern` is either an exact id or contains a single `{}` standing for one
+/// dynamic part (a core index, a drive letter, an alias, an entity id…); `label` may repeat the
+/// `{}` to interpolate it. `kind` is what the id is expected to carry until it has been seen.
+struct Rule {
+    pattern: &'static str,
+    kind: &'static str,
+    unit: Unit,
+    group: &'static str,
+    label: &'static str,
+}
+
+const fn rule(
+    pattern: &'static str,
+    kind: &'static str,
+    unit: Unit,
+    group: &'static str,
+    label: &'static str,
+) -> Rule {
+    Rule {
+        pattern,
+        kind,
+        unit,
+        group,
+        label,
+    }
+}
+
+const S: &str = "scalar";
+const T: &str = "text";
+const SERIES: &str = "series";
+const J: &str = "json";
+
+/// First match wins, so a specific shape (`cpu.core.{}.freq`) must precede a broader one
+/// (`cpu.core.{}`). Gating groups name the demand gate in `run_system_sensors` that guards the id
+/// (`always` = emitted every tick; `battery` = presence-gated) or the proxy source that owns it.
+/// Mirrors the ids emitted by sensors.rs / energy.rs / procwatch.rs / ha.rs / mqtt.rs / stocks.rs /
+/// httppoll.rs / cmdsource.rs / prom.rs / netprobe.rs / certwatch.rs / folder.rs / tail.rs /
+/// calendar.rs / feed.rs / weather.rs / astro.rs / gitrepo.rs / tasks.rs.
+#[rustfmt::skip]
+const RULES: &[Rule] = &[
+    // CPU
+    rule("cpu.total", S, Unit::Pct, "always", "CPU usage"),
+    rule("cpu.freq", S, Unit::Mhz, "cpu.freq", "CPU base clock"),
+    rule("cpu.freq.current", S, Unit::Mhz, "cpufreq", "CPU clock (live)"),
+    rule("cpu.freq.max", S, Unit::Mhz, "cpufreq", "CPU rated max clock"),
+    rule("cpu.brand", T, Unit::None, "always", "CPU model"),
+    rule("cpu.cores.logical", S, Unit::Count, "always", "Logical cores"),
+    rule("cpu.cores.physical", S, Unit::Count, "always", "Physical cores"),
+    rule("cpu.core.{}.freq", S, Unit::Mhz, "cpufreq", "Core {} clock"),
+    rule("cpu.core.{}", S, Unit::Pct, "always", "Core {} usage"),
+    // Memory + swap
+    rule("mem.used", S, Unit::Pct, "always", "Memory used"),
+    rule("mem.total", S, Unit::Bytes, "always", "Memory total"),
+    rule("mem.used.bytes", S, Unit::Bytes, "always", "Memory used (bytes)"),
+    rule("mem.available", S, Unit::Bytes, "always", "Memory available"),
+    rule("mem.free", S, Unit::Bytes, "always", "Memory free"),
+    rule("mem.commit.used", S, Unit::Bytes, "perf", "Commit charge"),
+    rule("mem.commit.limit", S, Unit::Bytes, "perf", "Commit limit"),
+    rule("mem.commit.peak", S, Unit::Bytes, "perf", "Commit peak"),
+    rule("mem.cached", S, Unit::Bytes, "perf", "System cache"),
+    rule("mem.kernel.paged", S, Unit::Bytes, "perf", "Kernel paged pool"),
+    rule("mem.kernel.nonpaged", S, Unit::Bytes, "perf", "Kernel non-paged pool"),
+    rule("swap.used", S, Unit::Pct, "always", "Swap used"),
+    rule("swap.total", S, Unit::Bytes, "always", "Swap total"),
+    rule("swap.used.bytes", S, Unit::Bytes, "always", "Swap used (bytes)"),
+    rule("swap.free", S, Unit::Bytes, "always", "Swap free"),
+    // Network
+    rule("net.down", S, Unit::BytesPerSec, "always", "Download"),
+    rule("net.up", S, Unit::BytesPerSec, "always", "Upload"),
+    rule("net.total", S, Unit::BytesPerSec, "always", "Network total"),
+    rule("net.down.total", S, Unit::Bytes, "always", "Downloaded"),
+    rule("net.up.total", S, Unit::Bytes, "always", "Uploaded"),
+    rule("net.linkspeed.rx", S, Unit::BytesPerSec, "netlink", "Link speed (rx)"),
+    rule("net.linkspeed.tx", S, Unit::BytesPerSec, "netlink", "Link speed (tx)"),
+    rule("net.adapter", T, Unit::None, "netlink", "Network adapter"),
+    rule("net.state", T, Unit::None, "netlink", "Network state"),
+    // Latency probes (netprobe.rs) — under `net.latency.` so a target name can't shadow `net.up.*`.
+    rule("net.latency.{}.jitter", S, Unit::Millis, "netprobe", "{} jitter"),
+    rule("net.latency.{}.loss", S, Unit::Pct, "netprobe", "{} packet loss"),
+    rule("net.latency.{}.up", S, Unit::Flag, "netprobe", "{} reachable"),
+    rule("net.latency.{}", S, Unit::Millis, "netprobe", "{} latency"),
+    // Disks (dynamic per drive letter)
+    rule("disk.{}.busy.pct", S, Unit::Pct, "disk.io", "Disk {} active time"),
+    rule("disk.{}.used.pct", S, Unit::Pct, "disks", "Disk {} used"),
+    rule("disk.{}.read", S, Unit::BytesPerSec, "disk.io", "Disk {} read"),
+    rule("disk.{}.write", S, Unit::BytesPerSec, "disk.io", "Disk {} write"),
+    rule("disk.{}.total", S, Unit::Bytes, "disks", "Disk {} size"),
+    rule("disk.{}.free", S, Unit::Bytes, "disks", "Disk {} free"),
+    rule("disk.{}.used", S, Unit::Bytes, "disks", "Disk {} used (bytes)"),
+    // Host
+    rule("host.uptime", S, Unit::Secs, "always", "Uptime"),
+    rule("host.procs", S, Unit::Count, "procs", "Processes"),
+    rule("host.idle", S, Unit::Secs, "always", "Idle time"),
+    rule("host.handles", S, Unit::Count, "perf", "Handles"),
+    rule("host.threads", S, Unit::Count, "perf", "Threads"),
+    // Processes (+ the procwatch.rs watch list, dynamic per alias)
+    rule("proc.cpu.top.name", T, Unit::None, "proc", "Top process (CPU)"),
+    rule("proc.cpu.top.pct", S, Unit::Pct, "proc", "Top process CPU"),
+    rule("proc.mem.top.name", T, Unit::None, "proc", "Top process (memory)"),
+    rule("proc.mem.top.bytes", S, Unit::Bytes, "proc", "Top process memory"),
+    rule("proc.top", J, Unit::None, "proc.top", "Top processes"),
+    rule("proc.{}.cpu", S, Unit::Pct, "proc", "{} CPU"),
+    rule("proc.{}.mem", S, Unit::Bytes, "proc", "{} memory"),
+    rule("proc.{}.count", S, Unit::Count, "proc", "{} processes"),
+    rule("proc.{}.running", S, Unit::Flag, "proc", "{} running"),
+    rule("proc.{}.io.read", S, Unit::BytesPerSec, "proc", "{} disk read"),
+    rule("proc.{}.io.write", S, Unit::BytesPerSec, "proc", "{} disk write"),
+    // GPU (NVIDIA / NVML)
+    rule("gpu.util", S, Unit::Pct, "gpu", "GPU usage"),
+    rule("gpu.mem.util", S, Unit::Pct, "gpu", "GPU memory controller"),
+    rule("gpu.vram", S, Unit::Pct, "gpu", "VRAM used"),
+    rule("gpu.vram.total", S, Unit::Bytes, "gpu", "VRAM total"),
+    rule("gpu.vram.used", S, Unit::Bytes, "gpu", "VRAM used (bytes)"),
+    rule("gpu.vram.free", S, Unit::Bytes, "gpu", "VRAM free"),
+    rule("gpu.temp", S, Unit::Celsius, "gpu", "GPU temperature"),
+    rule("gpu.clock.core", S, Unit::Mhz, "gpu", "GPU core clock"),
+    rule("gpu.clock.mem", S, Unit::Mhz, "gpu", "GPU memory clock"),
+    rule("gpu.power", S, Unit::Watts, "gpu", "GPU power"),
+    rule("gpu.power.limit", S, Unit::Watts, "gpu", "GPU power limit"),
+    rule("gpu.fan", S, Unit::Pct, "gpu", "GPU fan"),
+    rule("gpu.name", T, Unit::None, "gpu", "GPU model"),
+    // Battery
+    rule("battery.percent", S, Unit::Pct, "battery", "Battery"),
+    rule("battery.state", T, Unit::None, "battery", "Battery state"),
+    rule("battery.time", S, Unit::Secs, "battery", "Battery time left"),
+    rule("battery.rate", S, Unit::WattsSigned, "battery", "Battery rate"),
+    rule("battery.capacity.full", S, Unit::Wh, "battery", "Battery full capacity"),
+    rule("battery.capacity.remaining", S, Unit::Wh, "battery", "Battery remaining"),
+    // Power / energy (energy.rs)
+    rule("power.cpu", S, Unit::Watts, "always", "CPU package power"),
+    rule("power.gpu", S, Unit::Watts, "always", "GPU power"),
+    rule("power.system.estimate", S, Unit::Watts, "always", "System power (estimate)"),
+    rule("energy.today.kwh", S, Unit::Kwh, "always", "Energy today"),
+    rule("energy.today.cost", S, Unit::Money, "always", "Energy cost today"),
+    // Derived sensors (derived.rs) — computed in the backend from other sensors.
+    rule("derived.{}", S, Unit::None, "derived", "{}"),
+    // Synthetic generators (synthetic.rs); an `as` override publishes under the real id instead.
+    rule("synthetic.{}", S, Unit::None, "synthetic", "{} (synthetic)"),
+    // Alerts (alerts.rs): 1 while the rule is firing.
+    rule("alert.{}.active", S, Unit::Flag, "alerts", "Alert {} active"),
+    // Automations (automations.rs): the last `llm_prompt` action's reply.
+    rule("automation.{}.llm", T, Unit::None, "automations", "{} LLM reply"),
+    // Astronomy (astro.rs) — computed locally from the configured location.
+    rule("astro.{}.in_secs", S, Unit::Secs, "astro", "Until {}"),
+    rule("astro.day_length", S, Unit::Secs, "astro", "Day length"),
+    rule("astro.sun.elevation", S, Unit::None, "astro", "Sun elevation"),
+    rule("astro.sun.azimuth", S, Unit::None, "astro", "Sun azimuth"),
+    rule("astro.sun.up", S, Unit::Flag, "astro", "Sun up"),
+    rule("astro.sun.phase", T, Unit::None, "astro", "Sun phase"),
+    rule("astro.moon.phase", S, Unit::None, "astro", "Moon phase"),
+    rule("astro.moon.illumination", S, Unit::Pct, "astro", "Moon illumination"),
+    rule("astro.moon.name", T, Unit::None, "astro", "Moon phase name"),
+    rule("astro.{}", T, Unit::None, "astro", "{}"),
+    // Home Assistant (ha.rs)
+    rule("ha.status", T, Unit::None, "ha", "Home Assistant status"),
+    rule("ha.{}.state", S, Unit::None, "ha", "{} (numeric)"),
+    rule("ha.{}", J, Unit::None, "ha", "{}"),
+    // MQTT (mqtt.rs) — a topic's kind depends on its payload, so the observed kind is what counts.
+    rule("mqtt.status", T, Unit::None, "mqtt", "MQTT status"),
+    rule("mqtt.{}", T, Unit::None, "mqtt", "{}"),
+    // HTTP poller (httppoll.rs) — an extracted field's kind follows its value.
+    rule("http.{}.status", T, Unit::None, "http", "{} status"),
+    rule("http.{}", S, Unit::None, "http", "{}"),
+    // Command source (cmdsource.rs) — a parsed value's kind follows the `parse` mode.
+    rule("cmd.{}.exit", S, Unit::None, "cmd", "{} exit code"),
+    rule("cmd.{}.stderr", T, Unit::None, "cmd", "{} stderr"),
+    rule("cmd.{}.status", T, Unit::None, "cmd", "{} status"),
+    rule("cmd.{}", S, Unit::None, "cmd", "{}"),
+    // Prometheus scraper (prom.rs) — aliases are single segments, so `.status` can't collide.
+    rule("prom.{}.status", T, Unit::None, "prom", "{} scrape status"),
+    rule("prom.{}", S, Unit::None, "prom", "{}"),
+    // Certificate expiry (certwatch.rs) — `days` goes negative once the cert has expired.
+    rule("cert.{}.days", S, Unit::None, "cert", "{} cert days left"),
+    rule("cert.{}.issuer", T, Unit::None, "cert", "{} cert issuer"),
+    rule("cert.{}.subject", T, Unit::None, "cert", "{} cert subject"),
+    rule("cert.{}.hostname", S, Unit::Flag, "cert", "{} cert hostname valid"),
+    rule("cert.{}.trusted", S, Unit::Flag, "cert", "{} cert trusted"),
+    rule("cert.{}.status", T, Unit::None, "cert", "{} cert status"),
+    // Folder watches (folder.rs) — `mtime` is Unix ms.
+    rule("folder.{}.count", S, Unit::Count, "folder", "{} files"),
+    rule("folder.{}.size", S, Unit::Bytes, "folder", "{} size"),
+    rule("folder.{}.newest", T, Unit::None, "folder", "{} newest file"),
+    rule("folder.{}.mtime", S, Unit::None, "folder", "{} newest file time"),
+    rule("folder.{}.matched", S, Unit::Count, "folder", "{} matching files"),
+    rule("folder.{}.status", T, Unit::None, "folder", "{} folder status"),
+    // Log tail (tail.rs) — a capture's kind follows what it matched.
+    rule("tail.{}.line", T, Unit::None, "tail", "{} last line"),
+    rule("tail.{}.status", T, Unit::None, "tail", "{} tail status"),
+    rule("tail.{}.count", S, Unit::Count, "tail", "{} matches"),
+    rule("tail.{}", S, Unit::None, "tail", "{}"),
+    // Calendars (calendar.rs)
+    rule("calendar.{}.next.title", T, Unit::None, "calendar", "{} next event"),
+    rule("calendar.{}.next.start", T, Unit::None, "calendar", "{} next event start"),
+    rule("calendar.{}.next.in_secs", S, Unit::Secs, "calendar", "{} next event in"),
+    rule("calendar.{}.next.location", T, Unit::None, "calendar", "{} next event location"),
+    rule("calendar.{}.today", J, Unit::None, "calendar", "{} today's events"),
+    rule("calendar.{}.upcoming", J, Unit::None, "calendar", "{} upcoming events"),
+    rule("calendar.{}.busy", S, Unit::Flag, "calendar", "{} busy"),
+    rule("calendar.{}.status", T, Unit::None, "calendar", "{} calendar status"),
+    // Feeds (feed.rs)
+    rule("feed.{}.items", J, Unit::None, "feed", "{} entries"),
+    rule("feed.{}.latest.title", T, Unit::None, "feed", "{} latest title"),
+    rule("feed.{}.latest.link", T, Unit::None, "feed", "{} latest link"),
+    rule("feed.{}.latest.published", T, Unit::None, "feed", "{} latest published"),
+    rule("feed.{}.unread", S, Unit::Count, "feed", "{} unread"),
+    rule("feed.{}.status", T, Unit::None, "feed", "{} feed status"),
+    // Git repos (gitrepo.rs)
+    rule("git.{}.branch", T, Unit::None, "git", "{} branch"),
+    rule("git.{}.upstream", T, Unit::None, "git", "{} upstream"),
+    rule("git.{}.ahead", S, Unit::Count, "git", "{} ahead"),
+    rule("git.{}.behind", S, Unit::Count, "git", "{} behind"),
+    rule("git.{}.staged", S, Unit::Count, "git", "{} staged"),
+    rule("git.{}.dirty", S, Unit::Count, "git", "{} modified"),
+    rule("git.{}.untracked", S, Unit::Count, "git", "{} untracked"),
+    rule("git.{}.conflicts", S, Unit::Count, "git", "{} conflicts"),
+    rule("git.{}.stash", S, Unit::Count, "git", "{} stashes"),
+    rule("git.{}.commit.subject", T, Unit::None, "git", "{} last commit"),
+    rule("git.{}.commit.hash", T, Unit::None, "git", "{} HEAD"),
+    rule("git.{}.commit.age", S, Unit::Secs, "git", "{} last commit age"),
+    rule("git.{}.status", T, Unit::None, "git", "{} repo status"),
+    // Task lists (tasks.rs)
+    rule("tasks.{}.open", S, Unit::Count, "tasks", "{} open tasks"),
+    rule("tasks.{}.overdue", S, Unit::Count, "tasks", "{} overdue tasks"),
+    rule("tasks.{}.due_today", S, Unit::Count, "tasks", "{} tasks due today"),
+    rule("tasks.{}.done", S, Unit::Count, "tasks", "{} done tasks"),
+    rule("tasks.{}.items", J, Unit::None, "tasks", "{} next tasks"),
+    rule("tasks.{}.next", T, Unit::None, "tasks", "{} next task"),
+    rule("tasks.{}.status", T, Unit::None, "tasks", "{} task file status"),
+    // Weather (weather.rs); temperature/wind units follow the configured unit system.
+    rule("weather.status", T, Unit::None, "weather", "Weather status"),
+    rule("weather.temp", S, Unit::None, "weather", "Temperature"),
+    rule("weather.feels_like", S, Unit::None, "weather", "Feels like"),
+    rule("weather.condition", T, Unit::None, "weather", "Condition"),
+    rule("weather.code", S, Unit::None, "weather", "WMO weather code"),
+    rule("weather.wind", S, Unit::None, "weather", "Wind speed"),
+    rule("weather.wind.dir", S, Unit::None, "weather", "Wind direction"),
+    rule("weather.humidity", S, Unit::Pct, "weather", "Humidity"),
+    rule("weather.precip.prob", S, Unit::Pct, "weather", "Precipitation chance"),
+    rule("weather.is_day", S, Unit::Flag, "weather", "Daytime"),
+    rule("weather.temp.unit", T, Unit::None, "weather", "Temperature unit"),
+    rule("weather.wind.unit", T, Unit::None, "weather", "Wind unit"),
+    rule("weather.hourly.temp", SERIES, Unit::None, "weather", "Hourly temperature"),
+    rule("weather.hourly.precip.prob", SERIES, Unit::Pct, "weather", "Hourly precipitation chance"),
+    rule("weather.daily.max", SERIES, Unit::None, "weather", "Daily high"),
+    rule("weather.daily.min", SERIES, Unit::None, "weather", "Daily low"),
+    rule("weather.hourly", J, Unit::None, "weather", "Hourly forecast"),
+    rule("weather.daily", J, Unit::None, "weather", "Daily forecast"),
+    // Stocks (stocks.rs)
+    rule("stocks.status", T, Unit::None, "stocks", "Stocks status"),
+    rule("stocks.{}.price", S, Unit::None, "stocks", "{} price"),
+    rule("stocks.{}.change", S, Unit::PctSigned, "stocks", "{} change"),
+    rule("stocks.{}.changeAbs", S, Unit::None, "stocks", "{} change (abs)"),
+    rule("stocks.{}.prevClose", S, Unit::None, "stocks", "{} prev close"),
+    rule("stocks.{}.currency", T, Unit::None, "stocks", "{} currency"),
+    rule("stocks.{}.state", T, Unit::None, "stocks", "{} market state"),
+    rule("stocks.{}.series", SERIES, Unit::None, "stocks", "{} intraday"),
+];
+
+/// One catalog row, as returned by `list_sensors` and mirrored to `mcp/sensors.json`. Mirrors
+/// `SensorInfo` in `client/src/lib/core/sensors.ts`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SensorInfo {
+    pub id: String,
+    /// `scalar` / `text` / `series` / `json` — the observed kind once seen, else the expected one.
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// The producing source: `system`, `ha`, `mqtt`, `stocks`, … (the id's prefix for proxies).
+    pub source: String,
+    /// The demand gate (or owning source) that decides whether the id is produced this tick.
+    pub group: String,
+    pub label: String,
+    /// True once the id has actually been emitted this session.
+    pub live: bool,
+}
+
+/// Managed state: every id published this session with its kind, plus a dirty flag for the
+/// `mcp/sensors.json` mirror. Plain std locks — `observe` runs on every batch, briefly.
+#[derive(Default)]
+pub struct SensorCatalog {
+    seen: Mutex<HashMap<String, &'static str>>,
+    dirty: AtomicBool,
+}
+
+/// Record a published batch's ids. Only a NEW id takes the write path (and marks the mirror dirty),
+/// so the steady state is one lock + a lookup per sample. No-op if the catalog isn't managed.
+pub fn observe<R: Runtime>(app: &AppHandle<R>, batch: &[SensorSample]) {
+    let Some(state) = app.try_state::<SensorCatalog>() else {
+        return;
+    };
+    let mut seen = state.seen.lock().unwrap_or_else(|e| e.into_inner());
+    for s in batch {
+        if !seen.contains_key(&s.sensor) {
+            seen.insert(s.sensor.clone(), s.value.kind());
+            state.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// ---- pure seams (unit-tested) ----
+
+/// Match `id` against a rule pattern: the exact id, or `prefix{}suffix` with a non-empty middle.
+/// Returns the interpolated part (empty for an exact rule).
+fn match_pattern<'a>(pattern: &str, id: &'a str) -> Option<&'a str> {
+    match pattern.split_once("{}") {
+        None => (pattern == id).then_some(""),
+        Some((prefix, suffix)) => {
+            let middle = id.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            (!middle.is_empty()).then_some(middle)
+        }
+    }
+}
+
+/// Id prefix → owning source, for every prefix the backend publishes under. First match wins, so
+/// `net.latency.` (netprobe.rs) precedes the system loop's `net.`. Sources are named like the
+/// rules' groups: proxy sources after their prefix, in-process producers after their module.
+#[rustfmt::skip]
+const SOURCES: &[(&str, &str)] = &[
+    // The system loop (sensors.rs and the modules it drives: energy.rs, procwatch.rs).
+    ("cpu.", "system"), ("mem.", "system"), ("swap.", "system"), ("net.latency.", "netprobe"),
+    ("net.", "system"), ("disk.", "system"), ("host.", "system"), ("proc.", "system"),
+    ("gpu.", "system"), ("battery.", "system"), ("power.", "system"), ("energy.", "system"),
+    // Computed in-process.
+    ("derived.", "derived"), ("synthetic.", "synthetic"), ("alert.", "alerts"),
+    ("automation.", "automations"), ("astro.", "astro"),
+    // Proxy sources.
+    ("ha.", "ha"), ("mqtt.", "mqtt"), ("stocks.", "stocks"), ("http.", "http"), ("cmd.", "cmd"),
+    ("prom.", "prom"), ("cert.", "cert"), ("folder.", "folder"), ("tail.", "tail"),
+    ("calendar.", "calendar"), ("feed.", "feed"), ("weather.", "weather"), ("git.", "git"),
+    ("tasks.", "tasks"),
+];
+
+/// The source an id belongs to (see `SOURCES`). An id no prefix covers is counted as the system
+/// loop's.
+pub(crate) fn source_of(id: &str) -> &'static str {
+    SOURCES
+        .iter()
+        .find(|(prefix, _)| id.starts_with(prefix))
+        .map_or("system", |&(_, source)| source)
+}
+
+/// Whether a source computes its values in-process (the system loop and what is derived from it or
+/// from local state): they refill on their own within a tick or two, unlike the proxy sources.
+pub(crate) fn is_local_source(source: &str) -> bool {
+    matches!(
+        source,
+        "system" | "netprobe" | "derived" | "synthetic" | "alerts" | "automations" | "astro"
+    )
+}
+
+/// Whether `id` is a source's own status id (`ha.status`, `http.<name>.status`, …), per the
+/// `*.status` rules. The name part is a single segment, so a nested field that happens to be
+/// called `status` (`http.<name>.<path>.status`) isn't one.
+pub(crate) fn is_source_status(id: &str) -> bool {
+    RULES
+        .iter()
+        .filter(|r| r.pattern.ends_with(".status"))
+        .any(|r| match_pattern(r.pattern, id).is_some_and(|part| !part.contains('.')))
+}
+
+/// Describe one id: the first matching rule supplies unit/range/group/label; `observed_kind`
+/// (when the id has been seen) overrides the rule's expected kind. An id no rule covers still gets
+/// a row — its own id as the label, the source from its prefix, no unit.
+pub fn describe(id: &str, observed_kind: Option<&str>) -> SensorInfo {
+    let source = source_of(id);
+    let hit = RULES
+        .iter()
+        .find_map(|r| match_pattern(r.pattern, id).map(|part| (r, part)));
+    match hit {
+        Some((r, part)) => {
+            let (min, max) = r.unit.range();
+            SensorInfo {
+                id: id.to_string(),
+                kind: observed_kind.unwrap_or(r.kind).to_string(),
+                unit: r.unit.label().map(str::to_string),
+                min,
+                max,
+                source: source.to_string(),
+                group: r.group.to_string(),
+                label: r.label.replace("{}", part),
+                live: observed_kind.is_some(),
+            }
+        }
+        None => SensorInfo {
+            id: id.to_string(),
+            kind: observed_kind.unwrap_or(S).to_string(),
+            unit: None,
+            min: None,
+            max: None,
+            source: source.to_string(),
+            group: if source == "system" { "always" } else { source }.to_string(),
+            label: id.to_string(),
+            live: observed_kind.is_some(),
+        },
+    }
+}
+
+/// The full catalog: every exact rule of a local source (offered even before it has been emitted,
+/// like the client's curated list) plus every seen id, sorted by id.
+pub fn catalog_entries(seen: &HashMap<String, &'static str>) -> Vec<SensorInfo> {
+    let mut ids: Vec<&str> = RULES
+        .iter()
+        .filter(|r| !r.pattern.contains("{}") && is_local_source(source_of(r.pattern)))
+        .map(|r| r.pattern)
+        .collect();
+    ids.extend(seen.keys().map(String::as_str));
+    ids.sort_unstable();
+    ids.dedup();
+    ids.into_iter()
+        .map(|id| describe(id, seen.get(id).copied()))
+        .collect()
+}
+
+fn snapshot<R: Runtime>(app: &AppHandle<R>) -> Vec<SensorInfo> {
+    let Some(state) = app.try_state::<SensorCatalog>() else {
+        return catalog_entries(&HashMap::new());
+    };
+    let seen = state.seen.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    catalog_entries(&seen)
+}
+
+/// Mirror the catalog to `<app_config_dir>/mcp/sensors.json` if it grew since the last write. Called
+/// on the system loop's state-snapshot cadence; best-effort (the MCP server treats a missing file as
+/// "app not running"). The `mcp/` subdir keeps it out of the NonRecursive config watchers.
+pub fn write_catalog_snapshot<R: Runtime>(app: &AppHandle<R>) {
+    let Some(state) = app.try_state::<SensorCatalog>() else {
+        return;
+    };
+    if !state.dirty.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let Ok(dir) = app.path().app_config_dir() else {
+        return;
+    };
+    let mcp_dir = dir.join("mcp");
+    if std::fs::create_dir_all(&mcp_dir).is_err() {
+        return;
+    }
+    if let Ok(txt) = serde_json::to_string(&snapshot(app)) {
+        let _ = std::fs::write(mcp_dir.join("sensors.json"), txt);
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Every sensor the backend can currently produce, described (see module docs). Read-only.
+#[tauri::command]
+pub fn list_sensors(state: State<'_, SensorCatalog>) -> Vec<SensorInfo> {
+    let seen = state.seen.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    catalog_entries(&seen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_pattern_exact_and_dynamic() {
+        assert_eq!(match_pattern("cpu.total", "cpu.total"), Some(""));
+        assert_eq!(match_pattern("cpu.total", "cpu.total.x"), None);
+        assert_eq!(match_pattern("disk.{}.read", "disk.c.read"), Some("c"));
+        assert_eq!(match_pattern("disk.{}.read", "disk..read"), None); // empty middle
+        assert_eq!(
+            match_pattern("ha.{}", "ha.sensor.kitchen_temp"),
+            Some("sensor.kitchen_temp")
+        );
+    }
+
+    #[test]
+    fn specific_rules_win_over_broad_ones() {
+        let freq = describe("cpu.core.3.freq", None);
+        assert_eq!(freq.unit.as_deref(), Some("MHz"));
+        assert_eq!(freq.group, "cpufreq");
+        assert_eq!(freq.label, "Core 3 clock");
+        let usage = describe("cpu.core.3", None);
+        assert_eq!(usage.unit.as_deref(), Some("%"));
+        assert_eq!((usage.min, usage.max), (Some(0.0), Some(100.0)));
+        // The fixed proc.cpu.top.* ids aren't mistaken for a watch alias called "cpu".
+        assert_eq!(describe("proc.cpu.top.pct", None).label, "Top process CPU");
+        assert_eq!(describe("proc.obs.running", None).label, "obs running");
+    }
+
+    #[test]
+    fn source_status_ids_come_from_the_status_rules() {
+        assert!(is_source_status("ha.status"));
+        assert!(is_source_status("weather.status"));
+        assert!(is_source_status("http.pihole.status"));
+        assert!(is_source_status("tasks.work.status"));
+        assert!(!is_source_status("http.pihole.api.status"));
+        assert!(!is_source_status("http.pihole.blocked"));
+        assert!(!is_source_status("ha.sensor.status"));
+        assert!(!is_source_status("cpu.total"));
+    }
+
+    #[test]
+    fn observed_kind_overrides_expected_kind() {
+        // An MQTT topic is expected to be text, but this one carried JSON.
+        let info = describe("mqtt.zigbee/plug", Some("json"));
+        assert_eq!(info.kind, "json");
+        assert_eq!(info.source, "mqtt");
+        assert!(info.live);
+        let unseen = describe("gpu.temp", None);
+        assert_eq!(unseen.kind, "scalar");
+        assert_eq!(unseen.unit.as_deref(), Some("°C"));
+        assert!(!unseen.live);
+    }
+
+    #[test]
+    fn every_prefix_has_its_own_source() {
+        assert_eq!(source_of("cpu.total"), "system");
+        assert_eq!(source_of("net.down"), "system");
+        assert_eq!(source_of("net.latency.router"), "netprobe");
+        assert_eq!(source_of("net.latency.router.loss"), "netprobe");
+        assert_eq!(source_of("energy.today.kwh"), "system");
+        assert_eq!(source_of("derived.cpu_avg"), "derived");
+        assert_eq!(source_of("synthetic.sine"), "synthetic");
+        assert_eq!(source_of("alert.hot.active"), "alerts");
+        assert_eq!(source_of("automation.morning.llm"), "automations");
+        assert_eq!(source_of("astro.sun.up"), "astro");
+        assert_eq!(source_of("ha.light.desk"), "ha");
+        assert_eq!(source_of("hardware.thing"), "system");
+        // Every rule's id shape resolves to a listed prefix.
+        for r in RULES {
+            let id = r.pattern.replace("{}", "x");
+            assert!(
+                SOURCES.iter().any(|(p, _)| id.starts_with(p)),
+                "no source for {id}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_ids_still_get_a_row() {
+        let info = describe("something.new", Some("scalar"));
+        assert_eq!(info.label, "something.new");
+        assert_eq!(info.source, "system");
+        assert_eq!(info.unit, None);
+    }
+
+    #[test]
+    fn catalog_lists_static_system_ids_plus_seen() {
+        let mut seen = HashMap::new();
+        seen.insert("disk.c.read".to_string(), "scalar");
+        seen.insert("ha.light.kitchen".to_string(), "json");
+        let entries = catalog_entries(&seen);
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        assert!(ids.contains(&"cpu.total")); // static, not yet seen
+        assert!(ids.contains(&"disk.c.read")); // dynamic, seen
+        assert!(ids.contains(&"ha.light.kitchen"));
+        assert!(!ids.contains(&"ha.status")); // plugin ids appear only once produced
+        assert!(ids.windows(2).all(|w| w[0] < w[1])); // sorted, deduped
+        let kitchen = entries.iter().find(|e| e.id == "ha.light.kitchen").unwrap();
+        assert_eq!(
+            (kitchen.source.as_str(), kitchen.group.as_str()),
+            ("ha", "ha")
+        );
+    }
+
+    #[test]
+    fn sensor_info_serializes_without_empty_fields() {
+        let json = serde_json::to_value(describe("cpu.brand", None)).unwrap();
+        assert_eq!(json["kind"], "text");
+        assert!(json.get("unit").is_none());
+        assert!(json.get("min").is_none());
+        assert_eq!(json["label"], "CPU model");
+    }
+}
@@ -0,0 +1,703 @@
+//! TLS certificate expiry monitor — for the homelab HA instance (and its `HaConfig.insecure`
+//! self-signed cert) that nobody remembers to renew. Each configured `host:port` gets a TLS
+//! handshake through the same native-tls stack ha.rs / mqtt.rs use; the peer certificate is read
+//! and published as `cert.<name>.*` sensors:
+//!   `cert.<name>.days`      days until `notAfter` (fractional; negative once expired)
+//!   `cert.<name>.issuer`    issuer CN (or O)
+//!   `cert.<name>.subject`   subject CN (or O)
+//!   `cert.<name>.hostname`  1 when the cert names the host (SAN dNSName / iPAddress, `*.`
+//!                           wildcards; the subject CN only when there is no SAN), else 0
+//!   `cert.<name>.trusted`   1 when the chain verifies against the OS trust store, else 0
+//!   `cert.<name>.status`    `ok` / `warning` / `critical` / `expired` / `error`
+//!
+//! Configured in `plugins/certs.json`:
+//! `{ "targets": [{ "name": "ha", "host": "ha.home.arpa", "port": 8123, "warn_days": 21,
+//!    "critical_days": 7 }] }`
+//! The thresholds drive `status`; moving to a worse status raises a desktop notification (once per
+//! session per level, `notify: false` opts out) and a `warn` log record, and `cert.<name>.days`
+//! works in alerts.rs rules like any sensor. Checks are hours apart and one handshake each, so they
+//! run regardless of demand — a notification shouldn't need a window open. Certificates are read
+//! with a small DER walker (`parse_cert`) rather than a full X.509 crate; it and the hostname
+//! matcher are unit-tested against a fixture certificate.
+
+use std::io::Write;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::bus;
+use crate::log;
+use crate::sensors::{SensorSample, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Check cadence guardrails (seconds).
+const MIN_INTERVAL: u64 = 60;
+const MAX_INTERVAL: u64 = 7 * 86_400;
+/// Connect + handshake budget per attempt.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Retry a failed check this soon rather than waiting out a multi-hour interval.
+const RETRY_AFTER: Duration = Duration::from_secs(300);
+const SECS_PER_DAY: f64 = 86_400.0;
+
+fn default_port() -> u16 {
+    443
+}
+fn default_interval() -> u64 {
+    6 * 3_600
+}
+fn default_warn_days() -> u32 {
+    21
+}
+fn default_critical_days() -> u32 {
+    7
+}
+fn default_notify() -> bool {
+    true
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One watched endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CertTarget {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// SNI / hostname to check when it differs from `host` (e.g. connecting by IP).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_warn_days")]
+    pub warn_days: u32,
+    #[serde(default = "default_critical_days")]
+    pub critical_days: u32,
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+}
+
+impl Supervised for CertTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl CertTarget {
+    /// The name the certificate should carry.
+    fn expected_name(&self) -> &str {
+        self.server_name.as_deref().unwrap_or(&self.host)
+    }
+}
+
+/// `plugins/certs.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CertConfig {
+    #[serde(default)]
+    pub targets: Vec<CertTarget>,
+}
+
+/// Managed state: the live targets plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct CertWatch {
+    config: Mutex<CertConfig>,
+    generation: AtomicU64,
+}
+
+impl CertWatch {
+    fn replace(&self, cfg: CertConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("certs.json"))
+}
+
+pub fn load_cert_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<CertConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed targets from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_cert_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("certs", "skipping certificate target")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<CertWatch>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("certs", "failed to read certs.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, clamp the interval, reject duplicates, blank hosts and inverted thresholds.
+fn normalize_config(cfg: CertConfig) -> (CertConfig, Vec<String>) {
+    let mut kept: Vec<CertTarget> = Vec::new();
+    let mut errors = Vec::new();
+    for t in cfg.targets {
+        let Some(name) = id_segment(&t.name) else {
+            errors.push(format!("`{}`: invalid name", t.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let host = t.host.trim().to_string();
+        if host.is_empty() || host.contains(['/', ' ']) {
+            errors.push(format!("`{name}`: host must be a bare hostname or IP"));
+            continue;
+        }
+        if t.port == 0 {
+            errors.push(format!("`{name}`: bad port"));
+            continue;
+        }
+        if t.critical_days > t.warn_days {
+            errors.push(format!("`{name}`: critical_days must not exceed warn_days"));
+            continue;
+        }
+        kept.push(CertTarget {
+            name,
+            host,
+            server_name: t
+                .server_name
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            interval_secs: t.interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+            ..t
+        });
+    }
+    (CertConfig { targets: kept }, errors)
+}
+
+// ---- DER (pure) ----
+
+/// What the monitor needs from a certificate.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CertInfo {
+    /// Unix seconds.
+    pub not_before: i64,
+    pub not_after: i64,
+    pub issuer: String,
+    pub subject: String,
+    pub dns_names: Vec<String>,
+    pub ips: Vec<IpAddr>,
+}
+
+/// One DER tag-length-value: `(tag, contents, rest)`.
+fn tlv(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = buf.split_first()?;
+    let (&len0, rest) = rest.split_first()?;
+    let (len, rest) = if len0 < 0x80 {
+        (len0 as usize, rest)
+    } else {
+        let n = (len0 & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None;
+        }
+        let len = rest[..n].iter().fold(0usize, |l, b| (l << 8) | *b as usize);
+        (len, &rest[n..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Every TLV in a constructed value's contents.
+fn children(mut buf: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut out = Vec::new();
+    while let Some((tag, body, rest)) = tlv(buf) {
+        out.push((tag, body));
+        buf = rest;
+    }
+    out
+}
+
+/// UTCTime (0x17, `YYMMDDHHMMSSZ`) or GeneralizedTime (0x18, `YYYYMMDDHHMMSSZ`) → Unix seconds.
+fn parse_time(tag: u8, body: &[u8]) -> Option<i64> {
+    let s = std::str::from_utf8(body).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        0x17 if s.len() == 12 => {
+            let yy: i32 = s[..2].parse().ok()?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, &s[2..])
+        }
+        0x18 if s.len() == 14 => (s[..4].parse().ok()?, &s[4..]),
+        _ => return None,
+    };
+    let n = |i: usize| rest.get(i..i + 2)?.parse::<u32>().ok();
+    Some(
+        chrono::NaiveDate::from_ymd_opt(year, n(0)?, n(2)?)?
+            .and_hms_opt(n(4)?, n(6)?, n(8)?)?
+            .and_utc()
+            .timestamp(),
+    )
+}
+
+const OID_CN: &[u8] = &[0x55, 0x04, 0x03];
+const OID_O: &[u8] = &[0x55, 0x04, 0x0a];
+const OID_SAN: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// A Name's CN, else its O, else empty.
+fn display_name(name: &[u8]) -> String {
+    let attr = |oid: &[u8]| {
+        children(name)
+            .into_iter()
+            .flat_map(|(_, set)| children(set))
+            .find_map(|(_, atv)| match children(atv).as_slice() {
+                [(0x06, o), (_, v)] if *o == oid => Some(String::from_utf8_lossy(v).into_owned()),
+                _ => None,
+            })
+    };
+    attr(OID_CN).or_else(|| attr(OID_O)).unwrap_or_default()
+}
+
+/// SAN dNSNames and iPAddresses from an extensions block (`[3]` contents).
+fn subject_alt_names(extensions: &[u8]) -> (Vec<String>, Vec<IpAddr>) {
+    let mut dns = Vec::new();
+    let mut ips = Vec::new();
+    let Some((_, list, _)) = tlv(extensions) else {
+        return (dns, ips);
+    };
+    for (_, ext) in children(list) {
+        let parts = children(ext);
+        let Some((0x06, oid)) = parts.first().copied() else {
+            continue;
+        };
+        let Some((0x04, value)) = parts.last().copied() else {
+            continue;
+        };
+        if oid != OID_SAN {
+            continue;
+        }
+        let Some((_, names, _)) = tlv(value) else {
+            continue;
+        };
+        for (tag, body) in children(names) {
+            match (tag, body.len()) {
+                (0x82, _) => dns.push(String::from_utf8_lossy(body).to_ascii_lowercase()),
+                (0x87, 4) => ips.push(IpAddr::from(<[u8; 4]>::try_from(body).unwrap())),
+                (0x87, 16) => ips.push(IpAddr::from(<[u8; 16]>::try_from(body).unwrap())),
+                _ => {}
+            }
+        }
+    }
+    (dns, ips)
+}
+
+/// Read validity, issuer, subject and SANs out of a DER certificate.
+pub fn parse_cert(der: &[u8]) -> Result<CertInfo, String> {
+    let bad = || "malformed certificate".to_string();
+    let (_, cert, _) = tlv(der).ok_or_else(bad)?;
+    let (_, tbs, _) = tlv(cert).ok_or_else(bad)?;
+    let mut fields = children(tbs).into_iter().peekable();
+    // Optional explicit version [0].
+    fields.next_if(|(tag, _)| *tag == 0xa0);
+    let _serial = fields.next().ok_or_else(bad)?;
+    let _signature = fields.next().ok_or_else(bad)?;
+    let (_, issuer) = fields.next().ok_or_else(bad)?;
+    let (_, validity) = fields.next().ok_or_else(bad)?;
+    let (_, subject) = fields.next().ok_or_else(bad)?;
+    let times = children(validity);
+    let [(t0, nb), (t1, na)] = times.as_slice() else {
+        return Err(bad());
+    };
+    let not_before = parse_time(*t0, nb).ok_or_else(bad)?;
+    let not_after = parse_time(*t1, na).ok_or_else(bad)?;
+    let (dns_names, ips) = fields
+        .find(|(tag, _)| *tag == 0xa3)
+        .map(|(_, ext)| subject_alt_names(ext))
+        .unwrap_or_default();
+    Ok(CertInfo {
+        not_before,
+        not_after,
+        issuer: display_name(issuer),
+        subject: display_name(subject),
+        dns_names,
+        ips,
+    })
+}
+
+/// Whether the certificate names `host`: an IP against the iPAddress SANs, a hostname against the
+/// dNSName SANs (a leading `*.` matches exactly one label), the subject CN only without SANs.
+pub fn hostname_matches(host: &str, info: &CertInfo) -> bool {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    if let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        return info.ips.contains(&ip);
+    }
+    let cn = [info.subject.to_ascii_lowercase()];
+    let names: &[String] = if info.dns_names.is_empty() && info.ips.is_empty() {
+        &cn
+    } else {
+        &info.dns_names
+    };
+    names
+        .iter()
+        .any(|pattern| match pattern.strip_prefix("*.") {
+            Some(suffix) => host
+                .split_once('.')
+                .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+            None => *pattern == host,
+        })
+}
+
+/// The status for `days` left under a target's thresholds.
+pub fn level(days: f64, warn_days: u32, critical_days: u32) -> &'static str {
+    if days <= 0.0 {
+        "expired"
+    } else if days <= critical_days as f64 {
+        "critical"
+    } else if days <= warn_days as f64 {
+        "warning"
+    } else {
+        "ok"
+    }
+}
+
+/// Severity order of a status (`error` ranks with `ok`: it is a check failure, not an expiry).
+fn severity(status: &str) -> u8 {
+    match status {
+        "warning" => 1,
+        "critical" => 2,
+        "expired" => 3,
+        _ => 0,
+    }
+}
+
+/// One check's sensors.
+pub fn samples(
+    target: &CertTarget,
+    info: &CertInfo,
+    trusted: bool,
+    now_secs: i64,
+    ts: u64,
+) -> Vec<SensorSample> {
+    let base = format!("cert.{}", target.name);
+    let days = (info.not_after - now_secs) as f64 / SECS_PER_DAY;
+    let flag = |b: bool| f64::from(u8::from(b));
+    vec![
+        SensorSample::scalar(format!("{base}.days"), ts, (days * 100.0).round() / 100.0),
+        SensorSample::text(format!("{base}.issuer"), ts, info.issuer.clone()),
+        SensorSample::text(format!("{base}.subject"), ts, info.subject.clone()),
+        SensorSample::scalar(
+            format!("{base}.hostname"),
+            ts,
+            flag(hostname_matches(target.expected_name(), info)),
+        ),
+        SensorSample::scalar(format!("{base}.trusted"), ts, flag(trusted)),
+        SensorSample::text(
+            format!("{base}.status"),
+            ts,
+            level(days, target.warn_days, target.critical_days),
+        ),
+    ]
+}
+
+// ---- checking ----
+
+/// One blocking handshake. `verify_chain` checks the chain against the OS store (hostnames are
+/// checked separately by `hostname_matches`); otherwise anything is accepted so the certificate
+/// can always be read. Returns the peer certificate's DER.
+fn handshake(target: &CertTarget, verify_chain: bool) -> Result<Vec<u8>, String> {
+    let addr = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("`{}` did not resolve", target.host))?;
+    let tcp = TcpStream::connect_timeout(&addr, HANDSHAKE_TIMEOUT).map_err(|e| e.to_string())?;
+    let _ = tcp.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+    let _ = tcp.set_write_timeout(Some(HANDSHAKE_TIMEOUT));
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(!verify_chain)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut tls = connector
+        .connect(target.expected_name(), tcp)
+        .map_err(|e| e.to_string())?;
+    let der = tls
+        .peer_certificate()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "no peer certificate".to_string())?
+        .to_der()
+        .map_err(|e| e.to_string())?;
+    let _ = tls.shutdown();
+    let _ = tls.flush();
+    Ok(der)
+}
+
+/// Read the certificate, then see whether its chain verifies. Blocking; run off the async pool.
+fn check(target: &CertTarget) -> Result<(CertInfo, bool), String> {
+    let info = parse_cert(&handshake(target, false)?)?;
+    let trusted = handshake(target, true).is_ok();
+    Ok((info, trusted))
+}
+
+fn notify<R: Runtime>(app: &AppHandle<R>, target: &CertTarget, status: &str, info: &CertInfo) {
+    let when = chrono::DateTime::from_timestamp(info.not_after, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let body = if status == "expired" {
+        format!("{}:{} expired on {when}", target.host, target.port)
+    } else {
+        format!("{}:{} expires on {when}", target.host, target.port)
+    };
+    if let Err(err) = app
+        .notification()
+        .builder()
+        .title(format!("Certificate {status}: {}", target.name))
+        .body(body)
+        .show()
+    {
+        log::warn("certs", "failed to show desktop notification")
+            .field("error", err.to_string())
+            .emit();
+    }
+}
+
+/// Check one target until aborted: on its interval, or `RETRY_AFTER` after a failed check.
+async fn watch_target<R: Runtime>(app: AppHandle<R>, target: CertTarget) {
+    // The worst level already reported this session, so a level is notified once.
+    let mut reported = 0u8;
+    loop {
+        let t = target.clone();
+        let result = tokio::task::spawn_blocking(move || check(&t))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+        let wait = match result {
+            Ok((info, trusted)) => {
+                let now = now_ms();
+                let batch = samples(&target, &info, trusted, (now / 1000) as i64, now);
+                let status = match batch.last().map(|s| &s.value) {
+                    Some(crate::sensors::SensorValue::Text(s)) => s.clone(),
+                    _ => String::new(),
+                };
+                let _ = bus::publish(&app, &batch);
+                let sev = severity(&status);
+                if sev > reported {
+                    log::warn("certs", "certificate nearing expiry")
+                        .field("name", target.name.clone())
+                        .field("status", status.clone())
+                        .emit();
+                    if target.notify {
+                        notify(&app, &target, &status, &info);
+                    }
+                }
+                // A renewal resets it, so the next expiry is reported again.
+                reported = if sev == 0 { 0 } else { reported.max(sev) };
+                Duration::from_secs(target.interval_secs)
+            }
+            Err(err) => {
+                log::warn("certs", "certificate check failed")
+                    .field("name", target.name.clone())
+                    .field("error", err)
+                    .emit();
+                let status = format!("cert.{}.status", target.name);
+                let _ = bus::publish(&app, &[SensorSample::text(status, now_ms(), "error")]);
+                RETRY_AFTER.min(Duration::from_secs(target.interval_secs))
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// The supervisor: one watch task per target, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_certs<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<CertWatch> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .targets
+                .clone()
+        },
+        |t| tauri::async_runtime::spawn(watch_target(app.clone(), t)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/certs.json` and restart the changed watchers (each checks at once).
+/// Studio-window-guarded; any invalid target rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_cert_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, CertWatch>,
+    targets: Vec<CertTarget>,
+) -> Result<Vec<CertTarget>, String> {
+    if window.label() != "studio" {
+        return Err("save_cert_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(CertConfig { targets });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.targets.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured certificate targets.
+#[tauri::command]
+pub fn cert_config_status(state: State<'_, CertWatch>) -> Vec<CertTarget> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .targets
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    /// Self-signed P-256 cert: O=Homelab, CN=ha.home.arpa, valid 2025-01-01 → 2030-01-01,
+    /// SAN DNS:ha.home.arpa, DNS:*.lab.home.arpa, IP:192.168.1.10.
+    const FIXTURE_HEX: &str = concat!(
+        "308201c53082016ca003020102020101300a06082a8648ce3d04030230293110300e060355040a0c07486f6d",
+        "656c61623115301306035504030c0c68612e686f6d652e61727061301e170d3235303130313030303030305a",
+        "170d3330303130313030303030305a30293110300e060355040a0c07486f6d656c6162311530130603550403",
+        "0c0c68612e686f6d652e617270613059301306072a8648ce3d020106082a8648ce3d03010703420004074898",
+        "66053fd83dbe4ed649ad897418691233feb74e3de0bf3873d123e476cd581c2f745e110dc28f5d2b80f9c3d4",
+        "547db11dd3b56ae6ce860faa3630784f1da38184308181301d0603551d0e041604146fb8fb7798d4e13e1171",
+        "8e4d2a68d100ab0c3ddb301f0603551d230418301680146fb8fb7798d4e13e11718e4d2a68d100ab0c3ddb30",
+        "0f0603551d130101ff040530030101ff302e0603551d1104273025820c68612e686f6d652e61727061820f2a",
+        "2e6c61622e686f6d652e617270618704c0a8010a300a06082a8648ce3d040302034700304402206b208711d3",
+        "4456fc73ac3693e9b14a4d98e6a8ec952f226fa035eaaa5d53440102207ff6aef4db602a85400eccb09deea2",
+        "de1da7f3e339d0bebe6f92ec691ca17a61",
+    );
+
+    fn fixture() -> Vec<u8> {
+        (0..FIXTURE_HEX.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&FIXTURE_HEX[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn target(host: &str) -> CertTarget {
+        serde_json::from_value(serde_json::json!({ "name": "ha", "host": host })).unwrap()
+    }
+
+    #[test]
+    fn parses_validity_names_and_sans() {
+        let info = parse_cert(&fixture()).unwrap();
+        assert_eq!(info.not_before, 1_735_689_600);
+        assert_eq!(info.not_after, 1_893_456_000);
+        assert_eq!(info.issuer, "ha.home.arpa");
+        assert_eq!(info.subject, "ha.home.arpa");
+        assert_eq!(info.dns_names, vec!["ha.home.arpa", "*.lab.home.arpa"]);
+        assert_eq!(info.ips, vec!["192.168.1.10".parse::<IpAddr>().unwrap()]);
+        assert!(parse_cert(&fixture()[..100]).is_err());
+        assert!(parse_cert(&[]).is_err());
+    }
+
+    #[test]
+    fn matches_hostnames_wildcards_and_ips() {
+        let info = parse_cert(&fixture()).unwrap();
+        assert!(hostname_matches("HA.home.arpa.", &info));
+        assert!(hostname_matches("nas.lab.home.arpa", &info));
+        assert!(!hostname_matches("a.b.lab.home.arpa", &info));
+        assert!(!hostname_matches("lab.home.arpa", &info));
+        assert!(hostname_matches("192.168.1.10", &info));
+        assert!(!hostname_matches("192.168.1.11", &info));
+        // Without SANs the subject CN stands in.
+        let bare = CertInfo {
+            subject: "printer.local".into(),
+            ..CertInfo::default()
+        };
+        assert!(hostname_matches("printer.local", &bare));
+    }
+
+    #[test]
+    fn thresholds_pick_the_status() {
+        assert_eq!(level(30.0, 21, 7), "ok");
+        assert_eq!(level(21.0, 21, 7), "warning");
+        assert_eq!(level(3.5, 21, 7), "critical");
+        assert_eq!(level(-1.0, 21, 7), "expired");
+    }
+
+    #[test]
+    fn samples_cover_days_names_and_checks() {
+        let info = parse_cert(&fixture()).unwrap();
+        let t = CertTarget {
+            server_name: Some("ha.home.arpa".into()),
+            ..target("192.168.1.10")
+        };
+        // Ten days before expiry.
+        let batch = samples(&t, &info, false, 1_893_456_000 - 864_000, 1);
+        let get = |id: &str| &batch.iter().find(|s| s.sensor == id).unwrap().value;
+        assert!(matches!(get("cert.ha.days"), SensorValue::Scalar(v) if *v == 10.0));
+        assert!(matches!(get("cert.ha.issuer"), SensorValue::Text(s) if s == "ha.home.arpa"));
+        assert!(matches!(get("cert.ha.hostname"), SensorValue::Scalar(v) if *v == 1.0));
+        assert!(matches!(get("cert.ha.trusted"), SensorValue::Scalar(v) if *v == 0.0));
+        assert!(matches!(get("cert.ha.status"), SensorValue::Text(s) if s == "warning"));
+    }
+
+    #[test]
+    fn normalize_rejects_inverted_thresholds() {
+        let (cfg, errors) = normalize_config(CertConfig {
+            targets: vec![
+                CertTarget {
+                    warn_days: 5,
+                    critical_days: 10,
+                    ..target("a.example")
+                },
+                CertTarget {
+                    name: "Home Assistant".into(),
+                    interval_secs: 1,
+                    ..target(" ha.home.arpa ")
+                },
+            ],
+        });
+        assert_eq!(errors.len(), 1);
+        assert_eq!(cfg.targets[0].name, "home_assistant");
+        assert_eq!(cfg.targets[0].host, "ha.home.arpa");
+        assert_eq!(cfg.targets[0].interval_secs, MIN_INTERVAL);
+    }
+}
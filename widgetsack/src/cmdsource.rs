@@ -0,0 +1,733 @@
+//! Command source — the Rainmeter RunCommand equivalent: run a configured executable on an interval
+//! (or on demand) and publish its stdout as `cmd.<name>.*` sensors, for `nvidia-smi` queries,
+//! custom scripts, `git` counts and the like.
+//!
+//! Configured in `plugins/commands.json`:
+//! `{ "commands": [{ "name": "gpu_temp", "program": "nvidia-smi",
+//!    "args": ["--query-gpu=temperature.gpu", "--format=csv,noheader"], "cwd": null,
+//!    "interval_secs": 10, "timeout_secs": 5, "parse": "number" }] }`
+//! The program is spawned DIRECTLY (no shell, so arguments are never re-parsed) unless `shell` is
+//! set, in which case `program` is the whole command line for `cmd /C` (Windows) or `sh -c` and
+//! `args` must be empty (joining them unquoted would re-parse them). No console window is opened.
+//! `interval_secs: 0` means on demand only (`run_command_source`). A command never runs twice at
+//! once: an on-demand run while one is in flight is refused, and an interval run is skipped.
+//!
+//! stdout is parsed per `parse`:
+//!   `number`  the first whitespace-separated token → `cmd.<name>.value` (Scalar)
+//!   `text`    the trimmed output → `cmd.<name>.value` (Text; the default)
+//!   `json`    the parsed document → `cmd.<name>.value` (Json)
+//!   `kv`      `key=value` lines → `cmd.<name>.<key>` each (Scalar when numeric, else Text)
+//! Every run also publishes `cmd.<name>.exit` (the exit code; -1 on timeout/spawn failure),
+//! `cmd.<name>.stderr` (trimmed, capped) and `cmd.<name>.status` (`ok` / `exit 2` / `timeout` /
+//! `error`). Output is read as it arrives and cut at `MAX_STDOUT` / `MAX_STDERR_BYTES` (the rest is
+//! drained and discarded), so a noisy command can't grow memory. Interval runs are demand-gated
+//! like the HTTP poller unless `always` is set.
+//!
+//! Config writes are studio-window-guarded. Pure seams (`parse_output`, `outcome_samples`, `argv`,
+//! `normalize_config`, `read_capped`, the in-flight guard) are unit-tested.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::bus;
+use crate::httppoll::field_id;
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Interval guardrails (seconds); 0 (on demand only) is allowed separately.
+const MIN_INTERVAL: u64 = 1;
+const MAX_INTERVAL: u64 = 86_400;
+/// Re-check the demand gate this often while a command is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+/// Most stdout bytes parsed and stderr characters published.
+const MAX_STDOUT: usize = 1024 * 1024;
+const MAX_STDERR: usize = 1024;
+/// Most stderr bytes kept (room for `MAX_STDERR` multi-byte characters).
+const MAX_STDERR_BYTES: usize = 4 * MAX_STDERR;
+/// Ids every run publishes itself, so a `kv` key can't shadow them.
+const RESERVED: [&str; 3] = ["exit", "stderr", "status"];
+
+fn default_interval() -> u64 {
+    60
+}
+fn default_timeout() -> u64 {
+    10
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// How stdout becomes sensors (see module docs).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parse {
+    Number,
+    #[default]
+    Text,
+    Json,
+    Kv,
+}
+
+/// One configured command.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommandDef {
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Run `program` as a shell command line instead of spawning it directly.
+    #[serde(default)]
+    pub shell: bool,
+    /// 0 = on demand only.
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub parse: Parse,
+    /// Run on the interval even when no window shows this command's sensors.
+    #[serde(default)]
+    pub always: bool,
+}
+
+impl Supervised for CommandDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/commands.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommandsConfig {
+    #[serde(default)]
+    pub commands: Vec<CommandDef>,
+}
+
+/// Managed state: the live definitions, a generation counter the supervisor polls, and the names
+/// of the commands running right now.
+#[derive(Default)]
+pub struct CmdSource {
+    config: Mutex<CommandsConfig>,
+    generation: AtomicU64,
+    running: Mutex<HashSet<String>>,
+}
+
+impl CmdSource {
+    fn replace(&self, cfg: CommandsConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Mark `name` as running until the guard drops; `None` while a run is already in flight.
+    fn begin(&self, name: &str) -> Option<InFlight<'_>> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        running.insert(name.to_string()).then(|| InFlight {
+            running: &self.running,
+            name: name.to_string(),
+        })
+    }
+}
+
+/// One command's run in progress (see `CmdSource::begin`).
+struct InFlight<'a> {
+    running: &'a Mutex<HashSet<String>>,
+    name: String,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.name);
+    }
+}
+
+fn commands_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("commands.json"))
+}
+
+pub fn load_commands_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<CommandsConfig>, String> {
+    let path = commands_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk; each rejected entry is logged (the studio save path
+/// rejects them outright instead).
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_commands_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("cmd", "skipping command source")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<CmdSource>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("cmd", "failed to read commands.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, clamp intervals/timeouts, and reject duplicates, blank programs, `args` in shell
+/// mode and missing working directories. Returns the kept definitions plus one message per
+/// rejected one.
+fn normalize_config(cfg: CommandsConfig) -> (CommandsConfig, Vec<String>) {
+    let mut kept: Vec<CommandDef> = Vec::new();
+    let mut errors = Vec::new();
+    for def in cfg.commands {
+        let Some(name) = id_segment(&def.name) else {
+            errors.push(format!("`{}`: invalid name", def.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let program = def.program.trim().to_string();
+        if program.is_empty() {
+            errors.push(format!("`{name}`: no program"));
+            continue;
+        }
+        if def.shell && !def.args.is_empty() {
+            errors.push(format!(
+                "`{name}`: shell commands take no `args` — put them in the command line"
+            ));
+            continue;
+        }
+        let cwd = def
+            .cwd
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty());
+        if let Some(dir) = &cwd
+            && !std::path::Path::new(dir).is_dir()
+        {
+            errors.push(format!(
+                "`{name}`: working directory `{dir}` does not exist"
+            ));
+            continue;
+        }
+        kept.push(CommandDef {
+            name,
+            program,
+            cwd,
+            interval_secs: match def.interval_secs {
+                0 => 0,
+                n => n.clamp(MIN_INTERVAL, MAX_INTERVAL),
+            },
+            timeout_secs: def.timeout_secs.clamp(1, 600),
+            ..def
+        });
+    }
+    (CommandsConfig { commands: kept }, errors)
+}
+
+// ---- parsing (pure) ----
+
+/// The program and arguments actually spawned: the definition as-is, or its command line handed to
+/// the platform shell when `shell` is set (`normalize_config` rejects `args` there).
+pub fn argv(def: &CommandDef) -> (String, Vec<String>) {
+    if !def.shell {
+        return (def.program.clone(), def.args.clone());
+    }
+    let line = def.program.clone();
+    if cfg!(windows) {
+        ("cmd".to_string(), vec!["/C".to_string(), line])
+    } else {
+        ("sh".to_string(), vec!["-c".to_string(), line])
+    }
+}
+
+/// A string → Scalar when it parses as a finite number, else Text.
+fn scalar_or_text(s: &str) -> SensorValue {
+    match s.parse::<f64>() {
+        Ok(n) if n.is_finite() => SensorValue::Scalar(n),
+        _ => SensorValue::Text(s.to_string()),
+    }
+}
+
+/// Map stdout to `cmd.<name>.*` samples per `parse`. Unparseable output yields nothing (the hub
+/// keeps the last value; the status sensors say what happened).
+pub fn parse_output(name: &str, parse: Parse, stdout: &str, ts_ms: u64) -> Vec<SensorSample> {
+    let sample = |field: &str, value: SensorValue| SensorSample {
+        sensor: format!("cmd.{name}.{field}"),
+        ts_ms,
+        value,
+        stale: false,
+    };
+    let out = stdout.trim();
+    match parse {
+        Parse::Number => out
+            .split_whitespace()
+            .next()
+            .and_then(|tok| tok.trim_end_matches([',', ';', '%']).parse::<f64>().ok())
+            .filter(|n| n.is_finite())
+            .map(|n| vec![sample("value", SensorValue::Scalar(n))])
+            .unwrap_or_default(),
+        Parse::Text => vec![sample("value", SensorValue::Text(out.to_string()))],
+        Parse::Json => serde_json::from_str::<Value>(out)
+            .map(|v| vec![sample("value", SensorValue::Json(v))])
+            .unwrap_or_default(),
+        Parse::Kv => out
+            .lines()
+            .filter_map(|line| {
+                let (k, v) = line.split_once('=')?;
+                let key = field_id(k)?;
+                (!RESERVED.contains(&key.as_str())).then(|| sample(&key, scalar_or_text(v.trim())))
+            })
+            .collect(),
+    }
+}
+
+/// How one run ended.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Exited {
+        code: i32,
+        stdout: String,
+        stderr: String,
+    },
+    TimedOut,
+    Failed(String),
+}
+
+/// Every sample one run publishes: the parsed stdout plus `exit` / `stderr` / `status`.
+pub fn outcome_samples(def: &CommandDef, outcome: &Outcome, ts_ms: u64) -> Vec<SensorSample> {
+    let name = &def.name;
+    let (mut out, code, stderr, status) = match outcome {
+        Outcome::Exited {
+            code,
+            stdout,
+            stderr,
+        } => (
+            parse_output(name, def.parse, stdout, ts_ms),
+            *code,
+            stderr.trim().chars().take(MAX_STDERR).collect::<String>(),
+            if *code == 0 {
+                "ok".to_string()
+            } else {
+                format!("exit {code}")
+            },
+        ),
+        Outcome::TimedOut => (Vec::new(), -1, String::new(), "timeout".to_string()),
+        Outcome::Failed(err) => (Vec::new(), -1, err.clone(), "error".to_string()),
+    };
+    out.push(SensorSample::scalar(
+        format!("cmd.{name}.exit"),
+        ts_ms,
+        code as f64,
+    ));
+    out.push(SensorSample::text(
+        format!("cmd.{name}.stderr"),
+        ts_ms,
+        stderr,
+    ));
+    out.push(SensorSample::text(
+        format!("cmd.{name}.status"),
+        ts_ms,
+        status,
+    ));
+    out
+}
+
+// ---- execution ----
+
+/// Read a pipe to the end, keeping the first `cap` bytes. The rest is drained and discarded, so the
+/// child never blocks on a full pipe and memory stays bounded.
+pub async fn read_capped(mut pipe: impl AsyncRead + Unpin, cap: usize) -> std::io::Result<Vec<u8>> {
+    let mut kept = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = pipe.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(kept);
+        }
+        let room = cap.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+    }
+}
+
+/// Spawn the command (no console window), wait up to its timeout, and collect its output. The child
+/// is killed if the timeout fires.
+async fn execute(def: &CommandDef) -> Outcome {
+    let (program, args) = argv(def);
+    let mut cmd = tokio::process::Command::new(&program);
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = &def.cwd {
+        cmd.current_dir(dir);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // CREATE_NO_WINDOW: a console program must not flash a window over the desktop.
+        cmd.creation_flags(0x0800_0000);
+    }
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(err) => return Outcome::Failed(format!("{program}: {err}")),
+    };
+    let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+        return Outcome::Failed("output pipes unavailable".into());
+    };
+    let run = async {
+        let (stdout, stderr, status) = tokio::join!(
+            read_capped(stdout, MAX_STDOUT),
+            read_capped(stderr, MAX_STDERR_BYTES),
+            child.wait()
+        );
+        Ok::<_, std::io::Error>((stdout?, stderr?, status?))
+    };
+    let timeout = Duration::from_secs(def.timeout_secs);
+    match tokio::time::timeout(timeout, run).await {
+        Err(_) => Outcome::TimedOut,
+        Ok(Err(err)) => Outcome::Failed(err.to_string()),
+        Ok(Ok((stdout, stderr, status))) => Outcome::Exited {
+            code: status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        },
+    }
+}
+
+/// Run once and publish the result, unless a run of this command is already in flight.
+async fn run_once<R: Runtime>(
+    app: &AppHandle<R>,
+    def: &CommandDef,
+) -> Result<Vec<SensorSample>, String> {
+    let state: State<CmdSource> = app.state();
+    let Some(_running) = state.begin(&def.name) else {
+        return Err(format!("command source `{}` is already running", def.name));
+    };
+    let outcome = execute(def).await;
+    if !matches!(outcome, Outcome::Exited { code: 0, .. }) {
+        log::warn("cmd", "command source run failed")
+            .field("name", def.name.clone())
+            .field("outcome", format!("{outcome:?}"))
+            .emit();
+    }
+    let batch = outcome_samples(def, &outcome, now_ms());
+    let _ = bus::publish(app, &batch);
+    Ok(batch)
+}
+
+/// True while any window is consuming one of this command's sensors (default OFF before the first
+/// report, like the HTTP poller).
+fn command_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active: State<ActiveSensors> = app.state();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let prefix = format!("cmd.{name}.");
+    crate::sensors::any_wanted(&guard, |id| id.starts_with(&prefix))
+}
+
+/// Run one command on its interval until aborted.
+async fn run_interval<R: Runtime>(app: AppHandle<R>, def: CommandDef) {
+    let interval = Duration::from_secs(def.interval_secs);
+    loop {
+        if !def.always && !command_wanted(&app, &def.name) {
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        // Already running on demand → that run's result stands in for this one.
+        let _ = run_once(&app, &def).await;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// The supervisor: one interval task per scheduled command, restarted whenever a save changes it.
+/// Runs for the app's lifetime.
+pub async fn run_commands<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<CmdSource> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .commands
+                .iter()
+                .filter(|d| d.interval_secs > 0)
+                .cloned()
+                .collect()
+        },
+        |def| tauri::async_runtime::spawn(run_interval(app.clone(), def)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/commands.json` and apply it. Studio-window-guarded: this config decides what
+/// the app executes. Any invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_commands_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, CmdSource>,
+    commands: Vec<CommandDef>,
+) -> Result<CommandsConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_commands_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(CommandsConfig { commands });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = commands_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    state.replace(cfg.clone());
+    Ok(cfg)
+}
+
+/// The configured commands.
+#[tauri::command]
+pub fn commands_config_status(state: State<'_, CmdSource>) -> CommandsConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Run a configured command now (a widget's refresh button, or an on-demand-only command) and
+/// return what it published. Only SAVED commands can run, so any window may call this; a call
+/// while that command is still running is refused rather than starting another process.
+#[tauri::command]
+pub async fn run_command_source(
+    app: AppHandle,
+    state: State<'_, CmdSource>,
+    name: String,
+) -> Result<Vec<SensorSample>, String> {
+    let def = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .commands
+        .iter()
+        .find(|d| d.name == name)
+        .cloned()
+        .ok_or_else(|| format!("no command source named `{name}`"))?;
+    run_once(&app, &def).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn def(name: &str, parse: Parse) -> CommandDef {
+        CommandDef {
+            name: name.into(),
+            program: "tool".into(),
+            args: vec!["--flag".into(), "a b".into()],
+            cwd: None,
+            shell: false,
+            interval_secs: 10,
+            timeout_secs: 5,
+            parse,
+            always: false,
+        }
+    }
+
+    fn by_id(samples: &[SensorSample]) -> HashMap<&str, &SensorValue> {
+        samples
+            .iter()
+            .map(|s| (s.sensor.as_str(), &s.value))
+            .collect()
+    }
+
+    #[test]
+    fn parses_each_output_mode() {
+        let n = parse_output("gpu", Parse::Number, " 61 C\n", 1);
+        assert!(matches!(n[0].value, SensorValue::Scalar(v) if v == 61.0));
+        assert_eq!(n[0].sensor, "cmd.gpu.value");
+        assert!(
+            matches!(parse_output("p", Parse::Number, "42%", 1)[0].value, SensorValue::Scalar(v) if v == 42.0)
+        );
+        assert!(parse_output("gpu", Parse::Number, "N/A", 1).is_empty());
+
+        let t = parse_output("branch", Parse::Text, "main\n", 1);
+        assert!(matches!(&t[0].value, SensorValue::Text(s) if s == "main"));
+
+        let j = parse_output("j", Parse::Json, r#"{"a": 1}"#, 1);
+        assert!(matches!(&j[0].value, SensorValue::Json(v) if v["a"] == 1));
+        assert!(parse_output("j", Parse::Json, "not json", 1).is_empty());
+    }
+
+    #[test]
+    fn kv_lines_become_one_sensor_each() {
+        let out = parse_output(
+            "git",
+            Parse::Kv,
+            "Ahead=2\nbranch = main\nnoise line\nexit=5\nDirty Files=3\n",
+            1,
+        );
+        let by = by_id(&out);
+        assert_eq!(out.len(), 3);
+        assert!(matches!(by["cmd.git.ahead"], SensorValue::Scalar(v) if *v == 2.0));
+        assert!(matches!(by["cmd.git.branch"], SensorValue::Text(s) if s == "main"));
+        assert!(matches!(by["cmd.git.dirty_files"], SensorValue::Scalar(v) if *v == 3.0));
+    }
+
+    #[test]
+    fn every_outcome_reports_exit_stderr_and_status() {
+        let d = def("gpu", Parse::Number);
+        let ok = outcome_samples(
+            &d,
+            &Outcome::Exited {
+                code: 0,
+                stdout: "55".into(),
+                stderr: String::new(),
+            },
+            1,
+        );
+        let by = by_id(&ok);
+        assert!(matches!(by["cmd.gpu.value"], SensorValue::Scalar(v) if *v == 55.0));
+        assert!(matches!(by["cmd.gpu.exit"], SensorValue::Scalar(v) if *v == 0.0));
+        assert!(matches!(by["cmd.gpu.status"], SensorValue::Text(s) if s == "ok"));
+
+        let failed = outcome_samples(
+            &d,
+            &Outcome::Exited {
+                code: 9,
+                stdout: String::new(),
+                stderr: "  NVML not found \n".into(),
+            },
+            1,
+        );
+        let by = by_id(&failed);
+        assert!(matches!(by["cmd.gpu.status"], SensorValue::Text(s) if s == "exit 9"));
+        assert!(matches!(by["cmd.gpu.stderr"], SensorValue::Text(s) if s == "NVML not found"));
+
+        let timeout = outcome_samples(&d, &Outcome::TimedOut, 1);
+        assert_eq!(timeout.len(), 3);
+        assert!(matches!(by_id(&timeout)["cmd.gpu.exit"], SensorValue::Scalar(v) if *v == -1.0));
+        assert!(
+            matches!(by_id(&outcome_samples(&d, &Outcome::Failed("no such file".into()), 1))["cmd.gpu.status"], SensorValue::Text(s) if s == "error")
+        );
+    }
+
+    #[test]
+    fn argv_spawns_directly_unless_shell_is_set() {
+        let d = def("x", Parse::Text);
+        assert_eq!(
+            argv(&d),
+            (
+                "tool".to_string(),
+                vec!["--flag".to_string(), "a b".to_string()]
+            )
+        );
+        let (prog, args) = argv(&CommandDef {
+            shell: true,
+            program: "tool --flag 'a b' | wc -l".into(),
+            args: Vec::new(),
+            ..d
+        });
+        assert_eq!(args.last().unwrap(), "tool --flag 'a b' | wc -l");
+        assert!(prog == "cmd" || prog == "sh");
+    }
+
+    #[tokio::test]
+    async fn output_is_cut_at_the_cap_but_read_to_the_end() {
+        let big = vec![b'x'; 100_000];
+        let mut pipe: &[u8] = &big;
+        let kept = read_capped(&mut pipe, 10).await.unwrap();
+        assert_eq!(kept, b"xxxxxxxxxx");
+        assert!(pipe.is_empty());
+        let short: &[u8] = b"42\n";
+        assert_eq!(read_capped(short, 10).await.unwrap(), b"42\n");
+    }
+
+    #[test]
+    fn a_command_runs_once_at_a_time() {
+        let state = CmdSource::default();
+        let first = state.begin("gpu").unwrap();
+        assert!(state.begin("gpu").is_none());
+        assert!(state.begin("other").is_some());
+        drop(first);
+        assert!(state.begin("gpu").is_some());
+    }
+
+    #[test]
+    fn normalize_config_reports_each_bad_entry() {
+        let (cfg, errors) = normalize_config(CommandsConfig {
+            commands: vec![
+                CommandDef {
+                    interval_secs: 0,
+                    timeout_secs: 0,
+                    ..def("Git Count", Parse::Kv)
+                },
+                def("git_count", Parse::Kv),
+                CommandDef {
+                    program: "  ".into(),
+                    ..def("blank", Parse::Text)
+                },
+                CommandDef {
+                    cwd: Some("/definitely/not/here".into()),
+                    ..def("nowhere", Parse::Text)
+                },
+                CommandDef {
+                    shell: true,
+                    ..def("shell_args", Parse::Text)
+                },
+            ],
+        });
+        assert_eq!(cfg.commands.len(), 1);
+        assert_eq!(cfg.commands[0].name, "git_count");
+        assert_eq!(cfg.commands[0].interval_secs, 0);
+        assert_eq!(cfg.commands[0].timeout_secs, 1);
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].contains("duplicate"));
+        assert!(errors[2].contains("does not exist"));
+        assert!(errors[3].contains("no `args`"));
+    }
+
+    #[test]
+    fn config_defaults_to_text_and_direct_spawn() {
+        let d: CommandDef = serde_json::from_str(r#"{ "name": "n", "program": "p" }"#).unwrap();
+        assert_eq!(d.parse, Parse::Text);
+        assert!(!d.shell && d.args.is_empty());
+        assert_eq!((d.interval_secs, d.timeout_secs), (60, 10));
+    }
+}
@@ -6,7 +6,9 @@ use notify::Watcher;
 use serde::Serialize;
 use tauri::{Emitter, Manager};
 
-use crate::bridge::{CONTROLS_CHANGED_EVENT, LAYOUT_CHANGED_EVENT, THEMES_CHANGED_EVENT};
+use crate::bridge::{
+    AUTOMATIONS_CHANGED_EVENT, CONTROLS_CHANGED_EVENT, LAYOUT_CHANGED_EVENT, THEMES_CHANGED_EVENT,
+};
 use crate::{log, AppState, SessionRecord};
 
 #[derive(Serialize)]
@@ -231,7 +233,7 @@ fn themes_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
 /// sees a truncated/partial file. The temp name keeps the original and appends `.tmp`, so its
 /// extension is `tmp` (not `css`/`json`) and the directory watchers, which filter by extension,
 /// ignore it. Best-effort cleanup of the temp file on failure.
-fn atomic_write(path: &Path, contents: &str) -> Result<(), String> {
+pub(crate) fn atomic_write(path: &Path, contents: &str) -> Result<(), String> {
     let file_name = path
         .file_name()
         .and_then(|s| s.to_str())
@@ -396,13 +398,13 @@ fn sacks_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     Ok(dir.join("sacks"))
 }
 
-/// Shared filename allowlist for user-named config files (themes, sacks). The name becomes a
-/// path segment, so it must be a safe, bounded token: 1–64 chars of `[A-Za-z0-9 _-]` only. This
-/// rejects control chars, path separators, `..`, and Windows-reserved characters by construction;
-/// the explicit empty/`..`/separator checks below are kept as a defensive backstop. Leading/trailing
-/// spaces are rejected too — Windows silently trims trailing spaces/dots from filenames, so `"a "`
-/// and `"a"` would collide on disk and a delete-by-name could miss.
-fn valid_name(name: &str) -> bool {
+/// Shared filename allowlist for user-named config files (themes, sacks, recordings). The name
+/// becomes a path segment, so it must be a safe, bounded token: 1–64 chars of `[A-Za-z0-9 _-]`
+/// only. This rejects control chars, path separators, `..`, and Windows-reserved characters by
+/// construction; the explicit empty/`..`/separator checks below are kept as a defensive backstop.
+/// Leading/trailing spaces are rejected too — Windows silently trims trailing spaces/dots from
+/// filenames, so `"a "` and `"a"` would collide on disk and a delete-by-name could miss.
+pub(crate) fn valid_name(name: &str) -> bool {
     !name.is_empty()
         && name == name.trim()
         && !name.contains('/')
@@ -534,6 +536,55 @@ pub fn delete_layout(app: tauri::AppHandle, name: String) -> Result<(), String>
     }
 }
 
+/// Put saved layout `name` onto `monitor` (`default` = the primary) in widgets.json — the backend
+/// automations' "switch layout" action. Written atomically; the layout watcher then emits
+/// `layout_changed` and the overlays reload it exactly as they do for an external edit.
+pub(crate) fn activate_saved_layout(
+    app: &tauri::AppHandle,
+    name: &str,
+    monitor: &str,
+) -> Result<(), String> {
+    if !valid_name(name) {
+        return Err("invalid layout name".to_string());
+    }
+    let saved = fs::read_to_string(layouts_dir(app)?.join(format!("{name}.layout.json")))
+        .map_err(|e| format!("saved layout `{name}`: {e}"))?;
+    let path = layout_path(app)?;
+    let current = match fs::read_to_string(&path) {
+        Ok(contents) => Some(contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.to_string()),
+    };
+    let next = merge_saved_layout(current.as_deref(), &saved, monitor)?;
+    atomic_write(&path, &next)
+}
+
+/// `widgets.json` with `monitor`'s entry replaced by the saved layout's monitor. A missing file
+/// starts a fresh v2 envelope; a present-but-unparseable or pre-v2 one is an error rather than
+/// something to overwrite (the studio migrates v1 on load).
+fn merge_saved_layout(current: Option<&str>, saved: &str, monitor: &str) -> Result<String, String> {
+    let saved: serde_json::Value = serde_json::from_str(saved).map_err(|e| e.to_string())?;
+    if saved.get("kind").and_then(|k| k.as_str()) != Some("widgetsack/layout") {
+        return Err("not a saved layout".to_string());
+    }
+    let mon = saved
+        .get("monitor")
+        .filter(|m| m.is_object())
+        .cloned()
+        .ok_or_else(|| "saved layout has no monitor".to_string())?;
+    let mut layout = match current {
+        Some(txt) => serde_json::from_str::<serde_json::Value>(txt).map_err(|e| e.to_string())?,
+        None => serde_json::json!({ "version": 2, "monitors": {} }),
+    };
+    if layout.get("version").and_then(|v| v.as_u64()).unwrap_or(0) < 2
+        || !layout.get("monitors").is_some_and(|m| m.is_object())
+    {
+        return Err("widgets.json is not a v2 layout (open the studio once to migrate it)".to_string());
+    }
+    layout["monitors"][monitor] = mon;
+    serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())
+}
+
 // ---- plugin packages: declarative third-party bundles (`plugins/<id>/plugin.json`) ----
 // The app-config `plugins/` dir already holds first-party config FILES (ha.json, llm.json, …);
 // a third-party package is a SUBDIRECTORY containing a `plugin.json`, so the two coexist
@@ -1214,6 +1265,26 @@ pub fn watch_controls(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Watch the config dir for changes to automations.json: reload the backend rules (automations.rs)
+/// and emit `automations_changed` so an open studio editor refreshes. Mirrors `watch_controls`.
+pub fn watch_automations(app: tauri::AppHandle) -> Result<(), String> {
+    let path = crate::automations::automations_path(&app)?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| "automations path has no parent".to_string())?
+        .to_path_buf();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    watch_and_emit(
+        app,
+        dir,
+        "automations",
+        AUTOMATIONS_CHANGED_EVENT,
+        move |event| event.paths.iter().any(|p| p.file_name() == path.file_name()),
+        crate::automations::load_into_state,
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::valid_name;
@@ -1399,4 +1470,23 @@ mod tests {
         assert!(!super::valid_wallpaper_name("a\\b.png")); // separator
         assert!(!super::valid_wallpaper_name("")); // empty
     }
+
+    #[test]
+    fn merge_saved_layout_replaces_only_the_target_monitor() {
+        let saved = r#"{"kind":"widgetsack/layout","version":2,"monitor":{"floating":[1]}}"#;
+        let current = r#"{"version":2,"monitors":{"default":{"floating":[]},"DISPLAY2":{"x":1}}}"#;
+        let out: serde_json::Value =
+            serde_json::from_str(&super::merge_saved_layout(Some(current), saved, "default").unwrap())
+                .unwrap();
+        assert_eq!(out["monitors"]["default"]["floating"][0], 1);
+        assert_eq!(out["monitors"]["DISPLAY2"]["x"], 1); // other monitors untouched
+        // No widgets.json yet: a fresh v2 envelope.
+        let fresh: serde_json::Value =
+            serde_json::from_str(&super::merge_saved_layout(None, saved, "default").unwrap()).unwrap();
+        assert_eq!(fresh["version"], 2);
+        // Never overwrite something we can't read, or a v1 layout.
+        assert!(super::merge_saved_layout(Some("{oops"), saved, "default").is_err());
+        assert!(super::merge_saved_layout(Some(r#"{"version":1,"monitors":{}}"#), saved, "d").is_err());
+        assert!(super::merge_saved_layout(None, r#"{"monitor":{}}"#, "default").is_err());
+    }
 }
@@ -32,9 +32,9 @@ const MAX_HEAD: usize = 16 * 1024;
 const MAX_BODY: usize = 256 * 1024;
 /// A whole request must arrive within this window — defends against slow-loris connections that open a
 /// socket and then stall, parking a task forever.
-const READ_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_secs(10);
 /// Cap concurrent in-flight connections so a flood of (even slow) connections can't exhaust tasks/FDs.
-const MAX_CONNS: usize = 64;
+pub(crate) const MAX_CONNS: usize = 64;
 
 /// Managed state: the running server task (so a toggle can stop/restart it).
 #[derive(Default)]
@@ -61,11 +61,11 @@ fn gen_token() -> String {
 // ---- pure seams (unit-tested) ----
 
 #[derive(Debug)]
-struct Head {
-    method: String,
+pub(crate) struct Head {
+    pub(crate) method: String,
     /// Path WITHOUT the query string.
-    path: String,
-    headers: HashMap<String, String>,
+    pub(crate) path: String,
+    pub(crate) headers: HashMap<String, String>,
 }
 
 /// Parse an HTTP request head (everything before the blank line). Header names are lowercased.
@@ -104,7 +104,7 @@ fn ct_eq(a: &[u8], b: &[u8]) -> bool {
 }
 
 /// Whether the request carries the exact `Bearer <token>` authorization (constant-time compare).
-fn bearer_ok(headers: &HashMap<String, String>, token: &str) -> bool {
+pub(crate) fn bearer_ok(headers: &HashMap<String, String>, token: &str) -> bool {
     let Some(h) = headers.get("authorization") else {
         return false;
     };
@@ -191,8 +191,9 @@ fn find_marker(buf: &[u8], marker: &[u8]) -> Option<usize> {
     buf.windows(marker.len()).position(|w| w == marker)
 }
 
-/// Read one request (head + body) from the connection. Caps head/body sizes.
-async fn read_request(stream: &mut TcpStream) -> Option<(Head, Vec<u8>)> {
+/// Read one request (head + body) from the connection. Caps head/body sizes. Shared with the
+/// metrics exporter (exporter.rs).
+pub(crate) async fn read_request(stream: &mut TcpStream) -> Option<(Head, Vec<u8>)> {
     let mut buf = Vec::new();
     let mut tmp = [0u8; 2048];
     let head_end = loop {
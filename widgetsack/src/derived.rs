@@ -0,0 +1,881 @@
+//! Derived sensors: user-defined rolling statistics computed server-side from ANY source's samples
+//! and published back onto the telemetry bus as `derived.<name>` (Scalar), so every window, the MCP
+//! snapshot and later backend consumers see one consistent value instead of each widget formula
+//! keeping its own buffer.
+//!
+//! Declared in `plugins/derived.json` as
+//! `{ "sensors": [{ "name": "cpu_1m", "expr": "avg(cpu.total, 60s)" }] }`.
+//! Expression grammar — `func(input[, span])`, where `input` is a sensor id (or a `*`/`?` glob where
+//! noted; each `*` stays within one dot-separated segment, and `derived.*` ids never match — an
+//! expression can't read its own or another derived output) and `span` a duration (`500ms`,
+//! `30s`, `10m`, `2h`, `1d`) or `midnight`:
+//!   `avg|min|max(glob)`      instant aggregate over every matching id's latest value (an id drops
+//!                            out when health.rs flags it stale, or after `LATEST_TTL_MS` silent)
+//!   `avg|min|max(id, 60s)`   rolling over the window
+//!   `sum(glob)`              instant sum over matching ids (e.g. `sum(cpu.core.*)`)
+//!   `rate(id[, 60s])`        per-second rate of a counter (last two samples, or across the window);
+//!                            a counter reset (value went down anywhere in the window) yields
+//!                            nothing rather than a negative or an understated rate
+//!   `ewma(id, 30s)`          exponentially-weighted moving average with that time constant
+//!   `delta(id, 1h)`          change across the window
+//!   `delta(id, midnight)`    change since local midnight (e.g. an HA energy meter's daily usage);
+//!                            the baseline is persisted to `<app_config_dir>/derived/baselines.json`
+//!
+//! The engine subscribes to `bus.rs`, ingests every Scalar sample, and publishes its results once a
+//! second. Pure seams (`parse_expr`, `Runtime`, `Engine`) are unit-tested without the app.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime as TauriRuntime, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus;
+use crate::log;
+use crate::procwatch::glob_match;
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+
+/// Evaluation/publish cadence — matches the system loop's 1 Hz.
+const TICK_MS: u64 = 1000;
+
+/// How long a glob aggregate keeps counting an id that has stopped reporting (an unplugged disk, an
+/// exited process) when nothing flagged it stale first.
+const LATEST_TTL_MS: u64 = 86_400_000;
+
+/// Most points one windowed sensor keeps (a day at 1 Hz). A longer window over a faster source is
+/// truncated to its most recent points rather than growing without bound.
+const MAX_POINTS: usize = 86_400;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The local calendar date as `YYYY-MM-DD` — `delta(.., midnight)` rolls over at local midnight.
+fn local_date() -> String {
+    chrono::Local::now().date_naive().to_string()
+}
+
+// ---- config ----
+
+/// One declared derived sensor: published as `derived.<name>`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DerivedDef {
+    pub name: String,
+    pub expr: String,
+}
+
+/// `plugins/derived.json`. `#[serde(default)]` so a partial file parses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DerivedConfig {
+    #[serde(default)]
+    pub sensors: Vec<DerivedDef>,
+}
+
+/// Managed state: the live definitions plus a generation counter the engine polls, so a save swaps
+/// the rule set on the next tick without restarting the task.
+#[derive(Default)]
+pub struct Derived {
+    config: Mutex<DerivedConfig>,
+    generation: AtomicU64,
+}
+
+impl Derived {
+    fn replace(&self, cfg: DerivedConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn derived_config_path<R: TauriRuntime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("derived.json"))
+}
+
+fn baselines_path<R: TauriRuntime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("derived").join("baselines.json"))
+}
+
+pub fn load_derived_config<R: TauriRuntime>(
+    app: &AppHandle<R>,
+) -> Result<Option<DerivedConfig>, String> {
+    let path = derived_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk. A hand-edited file with a bad expression keeps the good
+/// entries; each rejected one is logged (the studio save path rejects them outright instead).
+fn load_into_state<R: TauriRuntime>(app: &AppHandle<R>) {
+    match load_derived_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("derived", "skipping derived sensor")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<Derived>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("derived", "failed to read derived.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// A persisted `delta(.., midnight)` baseline: `input`'s value at the start of local `date`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub input: String,
+    pub date: String,
+    pub value: f64,
+}
+
+fn load_baselines<R: TauriRuntime>(app: &AppHandle<R>) -> HashMap<String, Baseline> {
+    baselines_path(app)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+fn save_baselines<R: TauriRuntime>(app: &AppHandle<R>, baselines: &HashMap<String, Baseline>) {
+    let result = baselines_path(app).and_then(|path| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let txt = serde_json::to_string(baselines).map_err(|e| e.to_string())?;
+        std::fs::write(&path, txt).map_err(|e| e.to_string())
+    });
+    if let Err(err) = result {
+        log::warn("derived", "failed to persist midnight baselines")
+            .field("error", err)
+            .emit();
+    }
+}
+
+// ---- expressions (pure) ----
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Func {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Rate,
+    Ewma,
+    Delta,
+}
+
+/// The optional second argument: nothing, a duration, or local midnight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Span {
+    None,
+    Window(u64),
+    Midnight,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub func: Func,
+    pub input: String,
+    pub span: Span,
+}
+
+/// Glob-match `id` one dot-separated segment at a time, so `cpu.core.*` takes `cpu.core.3` but
+/// not `cpu.core.3.freq`.
+fn segment_glob(pattern: &str, id: &str) -> bool {
+    pattern.split('.').count() == id.split('.').count()
+        && pattern
+            .split('.')
+            .zip(id.split('.'))
+            .all(|(p, s)| glob_match(p, s))
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains('*') || s.contains('?')
+}
+
+/// `500ms` / `30s` / `10m` / `2h` / `1d` → milliseconds. `None` for anything else (or zero).
+pub(crate) fn parse_duration(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().ok()?;
+    let scale = match unit.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    (n > 0).then(|| n.saturating_mul(scale))
+}
+
+/// Parse and validate one expression (see module docs). Errors name what is wrong, for the
+/// settings form.
+pub fn parse_expr(src: &str) -> Result<Expr, String> {
+    let src = src.trim();
+    let (name, rest) = src
+        .split_once('(')
+        .ok_or_else(|| format!("expected func(sensor, ...), got `{src}`"))?;
+    let args = rest
+        .strip_suffix(')')
+        .ok_or_else(|| format!("missing `)` in `{src}`"))?;
+    let func = match name.trim().to_lowercase().as_str() {
+        "avg" => Func::Avg,
+        "min" => Func::Min,
+        "max" => Func::Max,
+        "sum" => Func::Sum,
+        "rate" => Func::Rate,
+        "ewma" => Func::Ewma,
+        "delta" => Func::Delta,
+        other => return Err(format!("unknown function `{other}`")),
+    };
+    let mut parts = args.split(',').map(str::trim);
+    let input = parts.next().unwrap_or_default().to_string();
+    if input.is_empty() {
+        return Err("missing sensor id".into());
+    }
+    if input.starts_with("derived.") {
+        return Err("a derived sensor can't read derived.* ids".into());
+    }
+    let span = match parts.next() {
+        None => Span::None,
+        Some(s) if s.eq_ignore_ascii_case("midnight") => Span::Midnight,
+        Some(s) => Span::Window(parse_duration(s).ok_or_else(|| format!("bad duration `{s}`"))?),
+    };
+    if parts.next().is_some() {
+        return Err("too many arguments".into());
+    }
+
+    let glob = is_glob(&input);
+    match (func, span) {
+        (Func::Avg | Func::Min | Func::Max, Span::None) | (Func::Sum, Span::None) => {}
+        (Func::Avg | Func::Min | Func::Max, Span::Window(_))
+        | (Func::Rate, Span::None | Span::Window(_))
+            if !glob => {}
+        (Func::Ewma, Span::Window(_)) | (Func::Delta, Span::Window(_) | Span::Midnight)
+            if !glob => {}
+        (Func::Sum, _) => return Err("sum takes only a sensor glob, e.g. sum(cpu.core.*)".into()),
+        (Func::Ewma, Span::None) => {
+            return Err("ewma needs a time constant, e.g. ewma(id, 30s)".into());
+        }
+        (Func::Delta, Span::None) => {
+            return Err("delta needs a window or `midnight`, e.g. delta(id, midnight)".into());
+        }
+        (_, Span::Midnight) => return Err("only delta supports `midnight`".into()),
+        _ => return Err("a time window needs a single sensor id, not a glob".into()),
+    }
+    Ok(Expr { func, input, span })
+}
+
+/// Sanitize names, compile expressions and drop duplicates. Returns the kept config plus one
+/// message per rejected entry.
+fn normalize_config(cfg: DerivedConfig) -> (DerivedConfig, Vec<String>) {
+    let mut kept: Vec<DerivedDef> = Vec::new();
+    let mut errors = Vec::new();
+    for def in cfg.sensors {
+        let Some(name) = id_segment(&def.name) else {
+            errors.push(format!("`{}`: invalid name", def.name));
+            continue;
+        };
+        if kept.iter().any(|d| d.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        if let Err(err) = parse_expr(&def.expr) {
+            errors.push(format!("`{name}`: {err}"));
+            continue;
+        }
+        kept.push(DerivedDef {
+            name,
+            expr: def.expr.trim().to_string(),
+        });
+    }
+    (DerivedConfig { sensors: kept }, errors)
+}
+
+// ---- per-sensor runtime state (pure) ----
+
+/// One derived sensor's accumulated state.
+#[derive(Debug)]
+pub struct Runtime {
+    expr: Expr,
+    /// Windowed points (time stats / rate / delta over a window); for `rate` without a window only
+    /// the last two are kept.
+    points: VecDeque<(u64, f64)>,
+    /// Latest `(ts, value)` per matching id, for the instant (glob) aggregates.
+    latest: HashMap<String, (u64, f64)>,
+    /// `(ts, value)` of the running EWMA.
+    ewma: Option<(u64, f64)>,
+    /// Latest input value, for `delta(.., midnight)`.
+    last: Option<f64>,
+    baseline: Option<Baseline>,
+}
+
+impl Runtime {
+    pub fn new(expr: Expr) -> Self {
+        Runtime {
+            expr,
+            points: VecDeque::new(),
+            latest: HashMap::new(),
+            ewma: None,
+            last: None,
+            baseline: None,
+        }
+    }
+
+    fn wants(&self, id: &str) -> bool {
+        // Our own output (or another expression's) would feed back into the aggregate.
+        if id.starts_with("derived.") {
+            return false;
+        }
+        if matches!(self.expr.span, Span::None)
+            && matches!(
+                self.expr.func,
+                Func::Avg | Func::Min | Func::Max | Func::Sum
+            )
+        {
+            id == self.expr.input || segment_glob(&self.expr.input, id)
+        } else {
+            id == self.expr.input
+        }
+    }
+
+    /// Feed one Scalar sample (already known to match). `today` is the local date at ingest. Returns
+    /// true when a new midnight baseline was taken (the caller persists it).
+    pub fn ingest(&mut self, id: &str, ts: u64, v: f64, today: &str) -> bool {
+        if !v.is_finite() {
+            return false;
+        }
+        let mut rebased = false;
+        match (self.expr.func, self.expr.span) {
+            (Func::Avg | Func::Min | Func::Max | Func::Sum, Span::None) => {
+                self.latest.insert(id.to_string(), (ts, v));
+            }
+            (Func::Ewma, Span::Window(tau)) => {
+                let next = match self.ewma {
+                    Some((prev_ts, prev)) if ts > prev_ts => {
+                        let alpha = 1.0 - (-((ts - prev_ts) as f64) / tau as f64).exp();
+                        prev + alpha * (v - prev)
+                    }
+                    Some((_, prev)) => prev,
+                    None => v,
+                };
+                self.ewma = Some((ts, next));
+            }
+            (Func::Delta, Span::Midnight) => {
+                if self.baseline.as_ref().is_none_or(|b| b.date != today) {
+                    // The last value seen before the rollover is the best estimate of the reading at
+                    // midnight; with none (first sample of a fresh install), start from this one.
+                    self.baseline = Some(Baseline {
+                        input: self.expr.input.clone(),
+                        date: today.to_string(),
+                        value: self.last.unwrap_or(v),
+                    });
+                    rebased = true;
+                }
+                self.last = Some(v);
+            }
+            (Func::Rate, Span::None) => {
+                self.points.push_back((ts, v));
+                while self.points.len() > 2 {
+                    self.points.pop_front();
+                }
+            }
+            _ => {
+                self.points.push_back((ts, v));
+                while self.points.len() > MAX_POINTS {
+                    self.points.pop_front();
+                }
+            }
+        }
+        rebased
+    }
+
+    /// `id` went stale (its source is down, or it missed its update interval): a glob aggregate
+    /// stops counting its frozen value.
+    pub fn forget(&mut self, id: &str) {
+        self.latest.remove(id);
+    }
+
+    /// The current value at `now`, or `None` when there isn't enough data (no samples yet, a single
+    /// rate sample, a counter reset).
+    pub fn value(&mut self, now: u64) -> Option<f64> {
+        self.latest
+            .retain(|_, (ts, _)| now.saturating_sub(*ts) <= LATEST_TTL_MS);
+        if let Span::Window(w) = self.expr.span
+            && self.expr.func != Func::Ewma
+        {
+            let cutoff = now.saturating_sub(w);
+            while self.points.front().is_some_and(|(ts, _)| *ts < cutoff) {
+                self.points.pop_front();
+            }
+        }
+        let window_values = || self.points.iter().map(|(_, v)| *v);
+        let latest = || self.latest.values().map(|(_, v)| *v);
+        match (self.expr.func, self.expr.span) {
+            (Func::Avg, Span::None) => mean(latest()),
+            (Func::Min, Span::None) => latest().reduce(f64::min),
+            (Func::Max, Span::None) => latest().reduce(f64::max),
+            (Func::Sum, _) => (!self.latest.is_empty()).then(|| latest().sum()),
+            (Func::Avg, _) => mean(window_values()),
+            (Func::Min, _) => window_values().reduce(f64::min),
+            (Func::Max, _) => window_values().reduce(f64::max),
+            (Func::Rate, _) => {
+                let (t0, v0) = *self.points.front()?;
+                let (t1, v1) = *self.points.back()?;
+                // A reset and a climb back past the start inside the window would read as a small
+                // positive rate from the endpoints alone.
+                let reset = self
+                    .points
+                    .iter()
+                    .zip(self.points.iter().skip(1))
+                    .any(|((_, a), (_, b))| b < a);
+                (t1 > t0 && !reset).then(|| (v1 - v0) * 1000.0 / (t1 - t0) as f64)
+            }
+            (Func::Ewma, _) => self.ewma.map(|(_, v)| v),
+            (Func::Delta, Span::Midnight) => Some(self.last? - self.baseline.as_ref()?.value),
+            (Func::Delta, _) => {
+                let (_, v0) = *self.points.front()?;
+                let (_, v1) = *self.points.back()?;
+                Some(v1 - v0)
+            }
+        }
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, n) = values.fold((0.0, 0usize), |(s, n), v| (s + v, n + 1));
+    (n > 0).then(|| sum / n as f64)
+}
+
+// ---- the engine (pure; the async loop below drives it) ----
+
+/// Every configured derived sensor's runtime, plus the midnight baselines to persist.
+#[derive(Default)]
+pub struct Engine {
+    sensors: Vec<(DerivedDef, Runtime)>,
+    baselines: HashMap<String, Baseline>,
+    baselines_dirty: bool,
+}
+
+impl Engine {
+    /// Swap in a new definition list. A sensor whose name AND expression are unchanged keeps its
+    /// accumulated state (a save elsewhere in the file doesn't reset every window); a persisted
+    /// midnight baseline is restored when it still refers to the same input.
+    pub fn configure(&mut self, defs: &[DerivedDef]) {
+        let mut old: Vec<(DerivedDef, Runtime)> = std::mem::take(&mut self.sensors);
+        for def in defs {
+            if let Some(pos) = old.iter().position(|(d, _)| d == def) {
+                self.sensors.push(old.swap_remove(pos));
+                continue;
+            }
+            let Ok(expr) = parse_expr(&def.expr) else {
+                continue;
+            };
+            let mut rt = Runtime::new(expr);
+            if let Some(b) = self.baselines.get(&def.name)
+                && b.input == rt.expr.input
+            {
+                rt.baseline = Some(b.clone());
+            }
+            self.sensors.push((def.clone(), rt));
+        }
+    }
+
+    /// Feed one published batch (only live Scalar samples count; a stale one retires its id from
+    /// the glob aggregates).
+    pub fn ingest(&mut self, batch: &[SensorSample], today: &str) {
+        for s in batch.iter().filter(|s| s.stale) {
+            for (_, rt) in &mut self.sensors {
+                if rt.wants(&s.sensor) {
+                    rt.forget(&s.sensor);
+                }
+            }
+        }
+        for s in batch.iter().filter(|s| !s.stale) {
+            let SensorValue::Scalar(v) = s.value else {
+                continue;
+            };
+            for (def, rt) in &mut self.sensors {
+                if rt.wants(&s.sensor) && rt.ingest(&s.sensor, s.ts_ms, v, today) {
+                    if let Some(b) = &rt.baseline {
+                        self.baselines.insert(def.name.clone(), b.clone());
+                    }
+                    self.baselines_dirty = true;
+                }
+            }
+        }
+    }
+
+    /// The `derived.<name>` samples that have a value at `now`.
+    pub fn evaluate(&mut self, now: u64) -> Vec<SensorSample> {
+        self.sensors
+            .iter_mut()
+            .filter_map(|(def, rt)| {
+                rt.value(now)
+                    .map(|v| SensorSample::scalar(format!("derived.{}", def.name), now, v))
+            })
+            .collect()
+    }
+
+    /// The baselines to persist, if any changed since the last call.
+    fn take_dirty_baselines(&mut self) -> Option<&HashMap<String, Baseline>> {
+        std::mem::take(&mut self.baselines_dirty).then_some(&self.baselines)
+    }
+}
+
+/// The engine task: subscribe to the bus, ingest every batch, publish `derived.*` once a second.
+/// Picks up saved definitions by polling the state's generation counter each tick. Runs until the
+/// bus closes (app exit).
+pub async fn run_derived<R: TauriRuntime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let mut engine = Engine {
+        baselines: load_baselines(&app),
+        ..Engine::default()
+    };
+    let mut generation = u64::MAX;
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => engine.ingest(&batch, &local_date()),
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("derived", "derived engine fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticker.tick() => {
+                let state: State<Derived> = app.state();
+                let current = state.generation.load(Ordering::Relaxed);
+                if current != generation {
+                    let defs = state.config.lock().unwrap_or_else(|e| e.into_inner()).sensors.clone();
+                    engine.configure(&defs);
+                    generation = current;
+                }
+                let batch = engine.evaluate(now_ms());
+                if !batch.is_empty() {
+                    let _ = bus::publish(&app, &batch);
+                }
+                if let Some(baselines) = engine.take_dirty_baselines() {
+                    save_baselines(&app, baselines);
+                }
+            }
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/derived.json` and swap the live definitions (the engine picks them up within a
+/// second). Studio-window-guarded like the other plugin configs. Any invalid entry rejects the whole
+/// save with every problem listed, so the form never silently loses a sensor.
+#[tauri::command]
+pub async fn save_derived_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Derived>,
+    sensors: Vec<DerivedDef>,
+) -> Result<DerivedConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_derived_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(DerivedConfig { sensors });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = derived_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    state.replace(cfg.clone());
+    Ok(cfg)
+}
+
+/// The live derived-sensor definitions (nothing secret in them).
+#[tauri::command]
+pub fn derived_config_status(state: State<'_, Derived>) -> DerivedConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rt(src: &str) -> Runtime {
+        Runtime::new(parse_expr(src).unwrap())
+    }
+
+    #[test]
+    fn parses_the_documented_forms() {
+        assert_eq!(
+            parse_expr("avg(cpu.total, 60s)").unwrap(),
+            Expr {
+                func: Func::Avg,
+                input: "cpu.total".into(),
+                span: Span::Window(60_000)
+            }
+        );
+        assert_eq!(
+            parse_expr("max(gpu.temp, 10m)").unwrap().span,
+            Span::Window(600_000)
+        );
+        assert_eq!(parse_expr("rate(net.down.total)").unwrap().span, Span::None);
+        assert_eq!(parse_expr(" SUM( cpu.core.* ) ").unwrap().func, Func::Sum);
+        assert_eq!(
+            parse_expr("delta(ha.sensor.energy.state, midnight)")
+                .unwrap()
+                .span,
+            Span::Midnight
+        );
+        assert_eq!(
+            parse_expr("ewma(cpu.total, 500ms)").unwrap().span,
+            Span::Window(500)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_meaningless_expressions() {
+        for bad in [
+            "cpu.total",
+            "avg(cpu.total",
+            "median(cpu.total)",
+            "avg()",
+            "avg(cpu.total, 5 parsecs)",
+            "avg(cpu.total, 0s)",
+            "avg(cpu.core.*, 60s)",
+            "sum(cpu.total, 60s)",
+            "ewma(cpu.total)",
+            "delta(x)",
+            "max(x, midnight)",
+            "rate(x, 1s, 2s)",
+        ] {
+            assert!(parse_expr(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn windowed_stats_evict_old_points() {
+        let mut r = rt("avg(cpu.total, 10s)");
+        r.ingest("cpu.total", 1_000, 10.0, "d");
+        r.ingest("cpu.total", 5_000, 20.0, "d");
+        r.ingest("cpu.total", 9_000, 30.0, "d");
+        assert_eq!(r.value(9_000), Some(20.0));
+        // At t=14s the window is [4s, 14s]: the t=1s point is gone.
+        assert_eq!(r.value(14_000), Some(25.0));
+        let mut m = rt("max(gpu.temp, 10s)");
+        m.ingest("gpu.temp", 0, 90.0, "d");
+        m.ingest("gpu.temp", 5_000, 70.0, "d");
+        assert_eq!(m.value(5_000), Some(90.0));
+        assert_eq!(m.value(12_000), Some(70.0));
+        assert_eq!(m.value(20_000), None); // everything aged out
+    }
+
+    #[test]
+    fn a_glob_star_matches_one_segment() {
+        let r = rt("sum(cpu.core.*)");
+        assert!(r.wants("cpu.core.3"));
+        assert!(!r.wants("cpu.core.3.freq") && !r.wants("cpu.core.3.temp"));
+        let t = rt("max(*.temp)");
+        assert!(t.wants("gpu.temp") && !t.wants("disk.c.temp"));
+    }
+
+    #[test]
+    fn derived_outputs_never_feed_back() {
+        let r = rt("max(*.temp)");
+        assert!(!r.wants("derived.temp"));
+        let all = rt("avg(*.*)");
+        assert!(all.wants("gpu.temp") && !all.wants("derived.cpu_1m"));
+        assert!(parse_expr("ewma(derived.cpu_1m, 30s)").is_err());
+    }
+
+    #[test]
+    fn glob_aggregates_use_each_ids_latest_value() {
+        let mut r = rt("sum(cpu.core.*)");
+        assert!(r.wants("cpu.core.0") && r.wants("cpu.core.11") && !r.wants("cpu.total"));
+        r.ingest("cpu.core.0", 1, 10.0, "d");
+        r.ingest("cpu.core.1", 1, 20.0, "d");
+        r.ingest("cpu.core.0", 2, 15.0, "d"); // replaces core 0's reading
+        assert_eq!(r.value(2), Some(35.0));
+        let mut a = rt("avg(cpu.core.*)");
+        a.ingest("cpu.core.0", 1, 10.0, "d");
+        a.ingest("cpu.core.1", 1, 30.0, "d");
+        assert_eq!(a.value(1), Some(20.0));
+    }
+
+    #[test]
+    fn glob_aggregates_drop_ids_that_stop_reporting() {
+        let mut engine = Engine::default();
+        engine.configure(&[DerivedDef {
+            name: "disks".into(),
+            expr: "sum(disk.*.used)".into(),
+        }]);
+        engine.ingest(
+            &[
+                SensorSample::scalar("disk.C.used", 0, 10.0),
+                SensorSample::scalar("disk.D.used", 0, 5.0),
+                SensorSample::scalar("disk.E.used", 0, 1.0),
+            ],
+            "d",
+        );
+        let sum = |engine: &mut Engine, now| {
+            let out = engine.evaluate(now);
+            serde_json::to_value(&out[0]).unwrap()["value"]["value"].as_f64()
+        };
+        assert_eq!(sum(&mut engine, 1), Some(16.0));
+        // health.rs flags D stale: it stops counting at once.
+        let stale = SensorSample {
+            stale: true,
+            ..SensorSample::scalar("disk.D.used", 5_000, 5.0)
+        };
+        engine.ingest(&[stale], "d");
+        assert_eq!(sum(&mut engine, 5_000), Some(11.0));
+        // E never reports again: it ages out after LATEST_TTL_MS; C keeps reporting.
+        engine.ingest(
+            &[SensorSample::scalar("disk.C.used", LATEST_TTL_MS, 12.0)],
+            "d",
+        );
+        assert_eq!(sum(&mut engine, LATEST_TTL_MS + 1), Some(12.0));
+    }
+
+    #[test]
+    fn rate_of_a_counter_skips_resets() {
+        let mut r = rt("rate(net.down.total)");
+        r.ingest("net.down.total", 1_000, 1_000.0, "d");
+        assert_eq!(r.value(1_000), None); // one sample isn't a rate
+        r.ingest("net.down.total", 3_000, 5_000.0, "d");
+        assert_eq!(r.value(3_000), Some(2_000.0));
+        r.ingest("net.down.total", 4_000, 100.0, "d"); // restart: counter went down
+        assert_eq!(r.value(4_000), None);
+        let mut w = rt("rate(net.down.total, 10s)");
+        for (t, v) in [(0, 0.0), (1_000, 50.0), (4_000, 400.0)] {
+            w.ingest("net.down.total", t, v, "d");
+        }
+        assert_eq!(w.value(4_000), Some(100.0));
+        // A reset mid-window, even one the counter has climbed back past, yields nothing.
+        let mut m = rt("rate(net.down.total, 10s)");
+        for (t, v) in [(0, 100.0), (1_000, 900.0), (2_000, 10.0), (4_000, 300.0)] {
+            m.ingest("net.down.total", t, v, "d");
+        }
+        assert_eq!(m.value(4_000), None);
+        // Once the reset has left the window, the rate is back.
+        m.ingest("net.down.total", 12_000, 1_010.0, "d");
+        assert_eq!(m.value(12_000), Some(100.0));
+    }
+
+    #[test]
+    fn ewma_moves_toward_new_values_by_elapsed_time() {
+        let mut r = rt("ewma(cpu.total, 10s)");
+        r.ingest("cpu.total", 0, 0.0, "d");
+        assert_eq!(r.value(0), Some(0.0));
+        r.ingest("cpu.total", 10_000, 100.0, "d");
+        // One time constant later: 1 - 1/e of the way there.
+        let v = r.value(10_000).unwrap();
+        assert!((v - 100.0 * (1.0 - (-1.0f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn delta_since_midnight_rebases_on_the_last_value_before_rollover() {
+        let mut r = rt("delta(ha.sensor.energy.state, midnight)");
+        assert!(r.ingest("ha.sensor.energy.state", 1, 100.0, "2026-01-01"));
+        assert!(!r.ingest("ha.sensor.energy.state", 2, 104.5, "2026-01-01"));
+        assert_eq!(r.value(2), Some(4.5));
+        // First reading after midnight: usage since the last pre-midnight reading counts today.
+        assert!(r.ingest("ha.sensor.energy.state", 3, 106.0, "2026-01-02"));
+        assert_eq!(r.value(3), Some(1.5));
+        let mut w = rt("delta(x, 1h)");
+        w.ingest("x", 0, 5.0, "d");
+        w.ingest("x", 1_000, 8.0, "d");
+        assert_eq!(w.value(1_000), Some(3.0));
+    }
+
+    #[test]
+    fn engine_keeps_state_for_unchanged_defs_and_restores_baselines() {
+        let def = |name: &str, expr: &str| DerivedDef {
+            name: name.into(),
+            expr: expr.into(),
+        };
+        let mut engine = Engine::default();
+        engine.baselines.insert(
+            "energy".into(),
+            Baseline {
+                input: "meter".into(),
+                date: "2026-01-01".into(),
+                value: 10.0,
+            },
+        );
+        engine.configure(&[
+            def("cpu", "avg(cpu.total, 60s)"),
+            def("energy", "delta(meter, midnight)"),
+        ]);
+        engine.ingest(
+            &[
+                SensorSample::scalar("cpu.total", 1_000, 40.0),
+                SensorSample::scalar("meter", 1_000, 12.0),
+                SensorSample::text("cpu.brand", 1_000, "ignored"),
+            ],
+            "2026-01-01",
+        );
+        let out = engine.evaluate(1_000);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].sensor, "derived.cpu");
+        let energy = serde_json::to_value(&out[1]).unwrap();
+        assert_eq!(energy["value"]["value"], 2.0); // restored baseline, not "since start"
+        assert!(engine.take_dirty_baselines().is_none());
+
+        // Re-configure with cpu unchanged: its window survives.
+        engine.configure(&[def("cpu", "avg(cpu.total, 60s)")]);
+        assert_eq!(engine.evaluate(2_000).len(), 1);
+    }
+
+    #[test]
+    fn normalize_config_reports_each_bad_entry() {
+        let (cfg, errors) = normalize_config(DerivedConfig {
+            sensors: vec![
+                DerivedDef {
+                    name: "CPU 1m".into(),
+                    expr: "avg(cpu.total, 1m)".into(),
+                },
+                DerivedDef {
+                    name: "cpu_1m".into(),
+                    expr: "max(cpu.total, 1m)".into(),
+                },
+                DerivedDef {
+                    name: "bad".into(),
+                    expr: "nope(x)".into(),
+                },
+            ],
+        });
+        assert_eq!(cfg.sensors.len(), 1);
+        assert_eq!(cfg.sensors[0].name, "cpu_1m");
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].contains("unknown function"));
+    }
+}
@@ -0,0 +1,623 @@
+//! Power + energy accounting: CPU package power from RAPL, combined with the GPU and battery
+//! readings into a whole-system estimate, integrated into a persisted "today" energy total.
+//!
+//! Emitted ids (every tick, from `run_system_sensors`):
+//!   `power.cpu`              Scalar  CPU package watts (RAPL; absent where powercap isn't exposed)
+//!   `power.gpu`              Scalar  GPU board watts (NVML `gpu.power`, mirrored here)
+//!   `power.system.estimate`  Scalar  whole-system watts — the battery drain when discharging, else
+//!                                    `power.cpu + power.gpu + baseline_watts`
+//!   `energy.today.kwh`       Scalar  integral of the estimate since local midnight (persisted)
+//!   `energy.today.cost`      Scalar  `energy.today.kwh × tariff_per_kwh`
+//!
+//! RAPL is read from `/sys/class/powercap` (`intel-rapl:N/energy_uj` — AMD packages appear under
+//! the same driver), which only exists on Linux: the meter only reads it there (`cfg!(target_os =
+//! "linux")`). On Windows there is no unprivileged RAPL API, so `power.cpu` is simply not emitted
+//! and the estimate falls back to GPU + baseline (or the battery). The root is configurable so the
+//! reader can be pointed at a fixture tree in tests.
+//!
+//! Accounting is NOT demand-gated — an integral with holes is worthless — but each tick is only a
+//! handful of small file reads. The GPU board power is the exception: when no `gpu.*` meter already
+//! read it and no `power.*` / `energy.*` sensor is on screen, NVML is queried only every
+//! `GPU_IDLE_EVERY` ticks and the last reading held in between. The running total is written to
+//! `<app_config_dir>/energy/today.json` (a subdir, so the NonRecursive config watchers never see
+//! it) about once a minute, on rollover and on exit (`persist`, from the exit hook in main.rs).
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::log;
+use crate::sensors::SensorSample;
+
+/// Where Linux exposes the powercap (RAPL) zones.
+const DEFAULT_POWERCAP_ROOT: &str = "/sys/class/powercap";
+
+/// Ticks between writes of the running total (~1 min at the 1 s sensor interval). A crash loses at
+/// most this much of the day's integral.
+const PERSIST_EVERY: u32 = 60;
+
+/// Ticks between NVML board-power reads while nothing on screen shows power or energy.
+const GPU_IDLE_EVERY: u32 = 10;
+
+/// Longest gap integrated as one step. A tick that arrives after a sleep/resume (or a stalled loop)
+/// must not multiply the current draw by hours the machine spent suspended.
+const MAX_STEP_MS: u64 = 5_000;
+
+fn default_powercap_root() -> String {
+    DEFAULT_POWERCAP_ROOT.to_string()
+}
+
+/// `plugins/energy.json`. `#[serde(default)]` everywhere so a partial file parses.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnergyConfig {
+    /// Price of one kWh in the user's currency; 0 leaves `energy.today.cost` at 0.
+    #[serde(default)]
+    pub tariff_per_kwh: f64,
+    /// Watts added to CPU + GPU for everything RAPL/NVML can't see (board, RAM, drives, fans).
+    #[serde(default)]
+    pub baseline_watts: f64,
+    #[serde(default = "default_powercap_root")]
+    pub powercap_root: String,
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        EnergyConfig {
+            tariff_per_kwh: 0.0,
+            baseline_watts: 0.0,
+            powercap_root: default_powercap_root(),
+        }
+    }
+}
+
+/// Managed state: the live config, read by the sensors loop each tick and replaced by
+/// `save_energy_config`, and the meter's latest day total for the exit hook to write. Plain std
+/// Mutexes (brief, synchronous locks — never held across I/O).
+#[derive(Default)]
+pub struct Energy {
+    config: Mutex<EnergyConfig>,
+    /// `None` until the meter has restored the persisted total, so an early exit can't clobber it.
+    day: Mutex<Option<EnergyDay>>,
+}
+
+/// One RAPL zone's cumulative counter and the value it wraps at (both µJ). The range is `None`
+/// when the zone doesn't expose it, which leaves a wrap unmeasurable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaplZone {
+    pub energy_uj: u64,
+    pub max_range_uj: Option<u64>,
+}
+
+/// The persisted running total: watt-hours accrued on `date` (local `YYYY-MM-DD`).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnergyDay {
+    pub date: String,
+    pub wh: f64,
+}
+
+// ---- config + state I/O ----
+
+fn energy_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("energy.json"))
+}
+
+fn energy_day_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("energy").join("today.json"))
+}
+
+pub fn load_energy_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<EnergyConfig>, String> {
+    let path = energy_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed config from disk. Called once when the sensors loop starts; a missing or
+/// unreadable file leaves the default (no tariff, no baseline) in place.
+pub fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    if let Ok(Some(cfg)) = load_energy_config(app) {
+        let state: State<Energy> = app.state();
+        *state.config.lock().unwrap_or_else(|e| e.into_inner()) = normalize_config(cfg);
+    }
+}
+
+fn load_energy_day<R: Runtime>(app: &AppHandle<R>) -> Option<EnergyDay> {
+    let txt = std::fs::read_to_string(energy_day_path(app).ok()?).ok()?;
+    serde_json::from_str(&txt).ok()
+}
+
+/// Best-effort write of the running total; a failure is logged and retried at the next persist.
+fn save_energy_day<R: Runtime>(app: &AppHandle<R>, day: &EnergyDay) {
+    let result = energy_day_path(app).and_then(|path| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let txt = serde_json::to_string(day).map_err(|e| e.to_string())?;
+        std::fs::write(&path, txt).map_err(|e| e.to_string())
+    });
+    if let Err(err) = result {
+        log::warn("energy", "failed to persist today's energy total")
+            .field("error", err)
+            .emit();
+    }
+}
+
+/// Write the meter's running total now. Called from the exit hook in main.rs so the integral since
+/// the last periodic write isn't lost on a clean quit.
+pub fn persist<R: Runtime>(app: &AppHandle<R>) {
+    let Some(state) = app.try_state::<Energy>() else {
+        return;
+    };
+    let day = state.day.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(day) = day {
+        save_energy_day(app, &day);
+    }
+}
+
+/// The local calendar date as `YYYY-MM-DD` — "today" rolls over at local midnight, not UTC.
+fn local_date() -> String {
+    chrono::Local::now().date_naive().to_string()
+}
+
+// ---- pure seams (unit-tested, no live hardware) ----
+
+/// A top-level package zone directory (`intel-rapl:0`, `amd-rapl:1`) — not a subzone
+/// (`intel-rapl:0:0` = core/uncore/dram, already counted in the package) and not the MMIO mirror
+/// (`intel-rapl-mmio:0`, a second view of the same package that would double-count).
+fn is_package_dir(name: &str) -> bool {
+    let Some((prefix, index)) = name.split_once(':') else {
+        return false;
+    };
+    matches!(prefix, "intel-rapl" | "amd-rapl")
+        && !index.is_empty()
+        && index.chars().all(|c| c.is_ascii_digit())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Every readable CPU package zone under `root`, in directory-name order so successive reads line
+/// up zone-for-zone. Empty when the root is missing (Windows, containers) or `energy_uj` is
+/// root-only (recent kernels restrict it) — both simply mean "no `power.cpu`".
+pub fn read_rapl(root: &Path) -> Vec<RaplZone> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|e| is_package_dir(&e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter()
+        .filter(|dir| {
+            std::fs::read_to_string(dir.join("name"))
+                .map(|n| n.trim().starts_with("package"))
+                .unwrap_or(false)
+        })
+        .filter_map(|dir| {
+            Some(RaplZone {
+                energy_uj: read_u64(&dir.join("energy_uj"))?,
+                max_range_uj: read_u64(&dir.join("max_energy_range_uj")),
+            })
+        })
+        .collect()
+}
+
+/// µJ consumed between two counter reads, across at most one wrap at `max_range_uj`. `None` for a
+/// wrap without a known range: guessing one would report an absurd spike.
+fn rapl_delta_uj(prev: u64, cur: u64, max_range_uj: Option<u64>) -> Option<u64> {
+    if cur >= prev {
+        Some(cur - prev)
+    } else {
+        Some(max_range_uj?.saturating_sub(prev).saturating_add(cur))
+    }
+}
+
+/// Summed package watts over `elapsed_ms` from two zone snapshots. `None` when there is nothing to
+/// compare (first read, no zones, a zone appeared/vanished), no time has passed, or a zone wrapped
+/// without a known range (that one sample is dropped).
+pub fn package_watts(prev: &[RaplZone], cur: &[RaplZone], elapsed_ms: u64) -> Option<f64> {
+    if cur.is_empty() || prev.len() != cur.len() || elapsed_ms == 0 {
+        return None;
+    }
+    let uj: u64 = prev
+        .iter()
+        .zip(cur)
+        .map(|(p, c)| rapl_delta_uj(p.energy_uj, c.energy_uj, c.max_range_uj))
+        .sum::<Option<u64>>()?;
+    // µJ / ms = mW; ÷ 1000 → W.
+    Some(uj as f64 / elapsed_ms as f64 / 1000.0)
+}
+
+/// The whole-system draw. While discharging, the battery rate (negative W) IS the machine's draw,
+/// measured at the source — better than any sum of parts. Otherwise sum what we can see plus the
+/// configured baseline; `None` when neither CPU nor GPU power is known (nothing to estimate from).
+pub fn system_estimate(
+    cpu: Option<f64>,
+    gpu: Option<f64>,
+    battery_rate: Option<f64>,
+    baseline_watts: f64,
+) -> Option<f64> {
+    if let Some(rate) = battery_rate
+        && rate < 0.0
+    {
+        return Some(-rate);
+    }
+    if cpu.is_none() && gpu.is_none() {
+        return None;
+    }
+    Some(cpu.unwrap_or(0.0) + gpu.unwrap_or(0.0) + baseline_watts.max(0.0))
+}
+
+/// Add `watts` held for `elapsed_ms` to `day`, first resetting it when the local date has moved on.
+/// Returns true on a rollover (the caller persists immediately). The step is capped at `MAX_STEP_MS`.
+fn accumulate(day: &mut EnergyDay, today: &str, watts: f64, elapsed_ms: u64) -> bool {
+    let rolled = day.date != today;
+    if rolled {
+        *day = EnergyDay {
+            date: today.to_string(),
+            wh: 0.0,
+        };
+    }
+    if watts.is_finite() && watts > 0.0 {
+        day.wh += watts * elapsed_ms.min(MAX_STEP_MS) as f64 / 3_600_000.0;
+    }
+    rolled
+}
+
+/// Clamp nonsense out of a config: negative/non-finite tariff or baseline → 0, blank root → default.
+fn normalize_config(cfg: EnergyConfig) -> EnergyConfig {
+    let non_negative = |v: f64| if v.is_finite() && v > 0.0 { v } else { 0.0 };
+    let root = cfg.powercap_root.trim();
+    EnergyConfig {
+        tariff_per_kwh: non_negative(cfg.tariff_per_kwh),
+        baseline_watts: non_negative(cfg.baseline_watts),
+        powercap_root: if root.is_empty() {
+            default_powercap_root()
+        } else {
+            root.to_string()
+        },
+    }
+}
+
+/// The `power.*` / `energy.*` samples for one tick. Pure.
+fn energy_samples(
+    ts: u64,
+    cpu: Option<f64>,
+    gpu: Option<f64>,
+    estimate: Option<f64>,
+    day: &EnergyDay,
+    tariff_per_kwh: f64,
+) -> Vec<SensorSample> {
+    let mut out = Vec::with_capacity(5);
+    if let Some(w) = cpu {
+        out.push(SensorSample::scalar("power.cpu", ts, w));
+    }
+    if let Some(w) = gpu {
+        out.push(SensorSample::scalar("power.gpu", ts, w));
+    }
+    if let Some(w) = estimate {
+        out.push(SensorSample::scalar("power.system.estimate", ts, w));
+    }
+    let kwh = day.wh / 1000.0;
+    out.push(SensorSample::scalar("energy.today.kwh", ts, kwh));
+    out.push(SensorSample::scalar(
+        "energy.today.cost",
+        ts,
+        kwh * tariff_per_kwh,
+    ));
+    out
+}
+
+// ---- the per-tick meter (owned by the sensors loop) ----
+
+/// Carries the previous RAPL snapshot and the running day total between sensor ticks.
+pub struct EnergyMeter {
+    rapl_prev: Vec<RaplZone>,
+    last_ts: Option<u64>,
+    day: EnergyDay,
+    ticks: u32,
+    /// The last NVML board-power reading, held between gated reads.
+    gpu_held: Option<f64>,
+}
+
+impl EnergyMeter {
+    /// Restore today's total from disk (a file from an earlier day is discarded by the first
+    /// `accumulate`) and seed the managed config.
+    pub fn new<R: Runtime>(app: &AppHandle<R>) -> Self {
+        load_into_state(app);
+        EnergyMeter {
+            rapl_prev: Vec::new(),
+            last_ts: None,
+            day: load_energy_day(app).unwrap_or_default(),
+            ticks: 0,
+            gpu_held: None,
+        }
+    }
+
+    /// The GPU board power for the estimate when no `gpu.*` meter read it this tick: `read` (the
+    /// NVML query) runs every tick while `wanted` (a `power.*` / `energy.*` sensor is on screen),
+    /// otherwise every `GPU_IDLE_EVERY` ticks with the last reading held in between.
+    pub fn gpu_watts(&mut self, wanted: bool, read: impl FnOnce() -> Option<f64>) -> Option<f64> {
+        if wanted || self.ticks.is_multiple_of(GPU_IDLE_EVERY) {
+            self.gpu_held = read();
+        }
+        self.gpu_held
+    }
+
+    /// One accounting step. `gpu_watts` / `battery_rate` are this tick's `gpu.power` /
+    /// `battery.rate` readings when the sensors loop had them.
+    pub fn tick<R: Runtime>(
+        &mut self,
+        app: &AppHandle<R>,
+        ts: u64,
+        gpu_watts: Option<f64>,
+        battery_rate: Option<f64>,
+    ) -> Vec<SensorSample> {
+        let cfg = {
+            let state: State<Energy> = app.state();
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        };
+        let elapsed_ms = self.last_ts.map_or(0, |prev| ts.saturating_sub(prev));
+        self.last_ts = Some(ts);
+
+        // powercap is a Linux sysfs interface; elsewhere there is nothing to read.
+        let rapl = if cfg!(target_os = "linux") {
+            read_rapl(Path::new(&cfg.powercap_root))
+        } else {
+            Vec::new()
+        };
+        let cpu_watts = package_watts(&self.rapl_prev, &rapl, elapsed_ms);
+        self.rapl_prev = rapl;
+
+        let estimate = system_estimate(cpu_watts, gpu_watts, battery_rate, cfg.baseline_watts);
+        let rolled = accumulate(
+            &mut self.day,
+            &local_date(),
+            estimate.unwrap_or(0.0),
+            elapsed_ms,
+        );
+        self.ticks = self.ticks.wrapping_add(1);
+        if rolled || self.ticks.is_multiple_of(PERSIST_EVERY) {
+            save_energy_day(app, &self.day);
+        }
+        let state: State<Energy> = app.state();
+        *state.day.lock().unwrap_or_else(|e| e.into_inner()) = Some(self.day.clone());
+        energy_samples(
+            ts,
+            cpu_watts,
+            gpu_watts,
+            estimate,
+            &self.day,
+            cfg.tariff_per_kwh,
+        )
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/energy.json` and swap the live config (the next tick picks it up). Studio-window
+/// guarded like the other plugin configs; returns the normalized config that was actually kept.
+#[tauri::command]
+pub async fn save_energy_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Energy>,
+    config: EnergyConfig,
+) -> Result<EnergyConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_energy_config is only allowed from the studio window".into());
+    }
+    let path = energy_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let cfg = normalize_config(config);
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    *state.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg.clone();
+    Ok(cfg)
+}
+
+/// The live energy config (nothing secret in it).
+#[tauri::command]
+pub fn energy_config_status(state: State<'_, Energy>) -> EnergyConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A throwaway powercap tree under the OS temp dir: `zones` are `(dir, name, energy_uj)`.
+    fn fixture(tag: &str, zones: &[(&str, &str, u64)]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("widgetsack-rapl-{}-{tag}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (dir, name, uj) in zones {
+            let zone = root.join(dir);
+            std::fs::create_dir_all(&zone).unwrap();
+            std::fs::write(zone.join("name"), format!("{name}\n")).unwrap();
+            std::fs::write(zone.join("energy_uj"), format!("{uj}\n")).unwrap();
+            std::fs::write(zone.join("max_energy_range_uj"), "262143328850\n").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn read_rapl_keeps_top_level_package_zones_only() {
+        let root = fixture(
+            "pkg",
+            &[
+                ("intel-rapl:0", "package-0", 1_000),
+                ("intel-rapl:0:0", "core", 500),
+                ("intel-rapl:0:2", "dram", 200),
+                ("intel-rapl-mmio:0", "package-0", 1_000),
+                ("intel-rapl:1", "psys", 9_999),
+                ("intel-rapl:2", "package-1", 2_000),
+            ],
+        );
+        let zones = read_rapl(&root);
+        let _ = std::fs::remove_dir_all(&root);
+        assert_eq!(
+            zones.iter().map(|z| z.energy_uj).collect::<Vec<_>>(),
+            vec![1_000, 2_000]
+        );
+        assert_eq!(zones[0].max_range_uj, Some(262_143_328_850));
+    }
+
+    #[test]
+    fn read_rapl_is_empty_without_powercap() {
+        assert!(read_rapl(Path::new("/definitely/not/a/powercap/root")).is_empty());
+    }
+
+    #[test]
+    fn package_watts_from_counter_delta() {
+        let zone = |uj| RaplZone {
+            energy_uj: uj,
+            max_range_uj: Some(1_000_000_000),
+        };
+        // 45 J over 1 s = 45 W; two packages sum.
+        assert_eq!(
+            package_watts(&[zone(0)], &[zone(45_000_000)], 1_000),
+            Some(45.0)
+        );
+        assert_eq!(
+            package_watts(
+                &[zone(0), zone(0)],
+                &[zone(10_000_000), zone(20_000_000)],
+                2_000
+            ),
+            Some(15.0)
+        );
+        // First read / zone-count change / no elapsed time → nothing to report.
+        assert_eq!(package_watts(&[], &[zone(5)], 1_000), None);
+        assert_eq!(package_watts(&[zone(0)], &[zone(5), zone(5)], 1_000), None);
+        assert_eq!(package_watts(&[zone(0)], &[zone(5)], 0), None);
+    }
+
+    #[test]
+    fn rapl_counter_wrap_is_handled() {
+        assert_eq!(rapl_delta_uj(100, 250, Some(1_000)), Some(150));
+        assert_eq!(rapl_delta_uj(900, 100, Some(1_000)), Some(200));
+        // No range exposed: a rising counter still measures, a wrap drops the sample.
+        assert_eq!(rapl_delta_uj(100, 250, None), Some(150));
+        assert_eq!(rapl_delta_uj(900, 100, None), None);
+        let zone = |uj| RaplZone {
+            energy_uj: uj,
+            max_range_uj: None,
+        };
+        assert_eq!(package_watts(&[zone(900)], &[zone(100)], 1_000), None);
+    }
+
+    #[test]
+    fn system_estimate_prefers_battery_drain() {
+        assert_eq!(
+            system_estimate(Some(20.0), Some(30.0), Some(-42.0), 10.0),
+            Some(42.0)
+        );
+        // Charging / on AC: sum of parts + baseline.
+        assert_eq!(
+            system_estimate(Some(20.0), Some(30.0), Some(15.0), 10.0),
+            Some(60.0)
+        );
+        assert_eq!(system_estimate(None, Some(30.0), None, 0.0), Some(30.0));
+        assert_eq!(system_estimate(None, None, None, 50.0), None);
+    }
+
+    #[test]
+    fn accumulate_integrates_and_rolls_over_at_midnight() {
+        let mut day = EnergyDay {
+            date: "2026-01-01".into(),
+            wh: 0.0,
+        };
+        // 360 W for 1 s = 0.1 Wh.
+        assert!(!accumulate(&mut day, "2026-01-01", 360.0, 1_000));
+        assert!((day.wh - 0.1).abs() < 1e-9);
+        // A long gap (sleep/resume) integrates at most MAX_STEP_MS.
+        accumulate(&mut day, "2026-01-01", 360.0, 3_600_000);
+        assert!((day.wh - 0.6).abs() < 1e-9);
+        // New local date: reset, then add this tick.
+        assert!(accumulate(&mut day, "2026-01-02", 3_600.0, 1_000));
+        assert_eq!(day.date, "2026-01-02");
+        assert!((day.wh - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gpu_power_is_read_every_tick_only_on_demand() {
+        let mut meter = EnergyMeter {
+            rapl_prev: Vec::new(),
+            last_ts: None,
+            day: EnergyDay::default(),
+            ticks: 0,
+            gpu_held: None,
+        };
+        let mut reads = 0;
+        for tick in 0..2 * GPU_IDLE_EVERY {
+            meter.ticks = tick;
+            let w = meter.gpu_watts(false, || {
+                reads += 1;
+                Some(f64::from(tick))
+            });
+            // Between gated reads the last value is held.
+            assert_eq!(w, Some(f64::from(tick - tick % GPU_IDLE_EVERY)));
+        }
+        assert_eq!(reads, 2);
+        meter.gpu_watts(true, || {
+            reads += 1;
+            None
+        });
+        assert_eq!(reads, 3);
+    }
+
+    #[test]
+    fn energy_samples_price_the_day() {
+        let day = EnergyDay {
+            date: "2026-01-01".into(),
+            wh: 2_500.0,
+        };
+        let out = energy_samples(1, None, Some(80.0), Some(80.0), &day, 0.3);
+        let ids: Vec<&str> = out.iter().map(|s| s.sensor.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "power.gpu",
+                "power.system.estimate",
+                "energy.today.kwh",
+                "energy.today.cost"
+            ]
+        );
+        let json = serde_json::to_value(&out[3]).unwrap();
+        assert!((json["value"]["value"].as_f64().unwrap() - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normalize_config_clamps_bad_values() {
+        let cfg = normalize_config(EnergyConfig {
+            tariff_per_kwh: -1.0,
+            baseline_watts: f64::NAN,
+            powercap_root: "  ".into(),
+        });
+        assert_eq!(cfg.tariff_per_kwh, 0.0);
+        assert_eq!(cfg.baseline_watts, 0.0);
+        assert_eq!(cfg.powercap_root, DEFAULT_POWERCAP_ROOT);
+    }
+}
@@ -0,0 +1,523 @@
+//! Prometheus `/metrics` exporter (OPT-IN) — the reverse of prom.rs: every current telemetry value
+//! (the system feed, HA/MQTT passthrough, derived, any proxy source) served in the text exposition
+//! format, so Grafana can graph the same numbers the overlay shows without a second agent.
+//!
+//! Configured in `plugins/exporter.json`:
+//! `{ "enabled": true, "port": 9464, "lan": false, "token": "…", "info_prefixes": ["ha.light."] }`
+//! Security posture follows control.rs:
+//!   - OFF by default.
+//!   - Binds 127.0.0.1 unless `lan` is set; a LAN bind (0.0.0.0) REQUIRES a token of at least
+//!     `MIN_TOKEN_LEN` characters. A token, when set, is checked as `Authorization: Bearer <token>`
+//!     (Prometheus' `authorization.credentials`). The token is a secret like `HaConfig.token`:
+//!     `exporter_status` reports only whether one is set and a blank save keeps the stored one.
+//!   - Read-only: `GET /metrics` is the only route.
+//!
+//! Naming: `cpu.total` → `widgetsack_cpu_total_ratio{id="cpu.total",source="system"}`. The catalog
+//! rule's unit picks the Prometheus base-unit suffix and scale (`%` → `_ratio` ÷100, `MHz` →
+//! `_hertz`, `ms` → `_seconds`, `Wh`/`kWh` → `_joules`, …); the `id` label keeps two ids that slug
+//! alike apart. Text values become `…_info{…,value="on"} 1`, but only for ids under one of the
+//! opt-in `info_prefixes`: every distinct text is a new series, so free-form text
+//! (`tail.*.line`, feed titles, MQTT payloads) would give Prometheus unbounded label cardinality.
+//! Series/JSON values are skipped. A sensor that goes stale (health.rs, or a lastknown.rs
+//! restore) is absent until its next live sample rather than frozen, as Prometheus expects. Pure
+//! seams (`metric_name`, `base_unit`, `Snapshot`, `render`) are unit-tested.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::{JoinHandle, Mutex};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus;
+use crate::catalog;
+use crate::control::{MAX_CONNS, READ_TIMEOUT, bearer_ok, read_request};
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue};
+
+/// Every metric name starts with this.
+const PREFIX: &str = "widgetsack";
+/// Shortest token accepted for a LAN bind.
+const MIN_TOKEN_LEN: usize = 16;
+/// Longest text value exported as an `_info` label (longer ones are cut).
+const MAX_TEXT: usize = 200;
+
+fn default_port() -> u16 {
+    9464
+}
+
+// ---- config ----
+
+/// `plugins/exporter.json`. The token is secret (see module docs).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExporterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Bind every interface instead of 127.0.0.1 (requires `token`).
+    #[serde(default)]
+    pub lan: bool,
+    #[serde(default)]
+    pub token: String,
+    /// Sensor-id prefixes whose Text values are exported as `_info` series; empty exports none.
+    #[serde(default)]
+    pub info_prefixes: Vec<String>,
+}
+
+impl Default for ExporterConfig {
+    fn default() -> Self {
+        ExporterConfig {
+            enabled: false,
+            port: default_port(),
+            lan: false,
+            token: String::new(),
+            info_prefixes: Vec::new(),
+        }
+    }
+}
+
+/// What the webview learns: everything but the token, plus where the server is listening.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExporterStatus {
+    pub enabled: bool,
+    pub port: u16,
+    pub lan: bool,
+    pub has_token: bool,
+    pub info_prefixes: Vec<String>,
+    /// The scrape URL while the server runs.
+    pub url: Option<String>,
+}
+
+/// Managed state: the running server task and the URL it serves (so a save can restart it).
+#[derive(Default)]
+pub struct ExporterState {
+    handle: Mutex<Option<(JoinHandle<()>, String)>>,
+}
+
+fn exporter_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("exporter.json"))
+}
+
+pub fn load_exporter_config<R: Runtime>(app: &AppHandle<R>) -> Result<ExporterConfig, String> {
+    let path = exporter_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt).map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ExporterConfig::default()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Reject a config that would expose the endpoint unauthenticated (or on port 0).
+fn validate(cfg: &ExporterConfig) -> Result<(), String> {
+    if cfg.port == 0 {
+        return Err("port must be 1-65535".into());
+    }
+    if cfg.lan && cfg.token.trim().len() < MIN_TOKEN_LEN {
+        return Err(format!(
+            "a LAN bind needs a token of at least {MIN_TOKEN_LEN} characters"
+        ));
+    }
+    Ok(())
+}
+
+// ---- naming + rendering (pure) ----
+
+/// `cpu.core.3`, `mqtt.home/temp` → `cpu_core_3`, `mqtt_home_temp`: anything outside
+/// `[a-zA-Z0-9_]` becomes `_`, runs collapse, and the result is prefixed with `PREFIX`.
+pub fn metric_name(id: &str) -> String {
+    let mut out = format!("{PREFIX}_");
+    for c in id.chars() {
+        let c = if c.is_ascii_alphanumeric() {
+            c.to_ascii_lowercase()
+        } else {
+            '_'
+        };
+        if !(c == '_' && out.ends_with('_')) {
+            out.push(c);
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// A catalog unit label → the Prometheus base-unit suffix and the factor into that unit.
+pub fn base_unit(unit: Option<&str>) -> (Option<&'static str>, f64) {
+    match unit {
+        Some("%") => (Some("ratio"), 0.01),
+        Some("bytes") => (Some("bytes"), 1.0),
+        Some("bytes/s") => (Some("bytes_per_second"), 1.0),
+        Some("s") => (Some("seconds"), 1.0),
+        Some("ms") => (Some("seconds"), 0.001),
+        Some("MHz") => (Some("hertz"), 1e6),
+        Some("°C") => (Some("celsius"), 1.0),
+        Some("W") => (Some("watts"), 1.0),
+        Some("Wh") => (Some("joules"), 3_600.0),
+        Some("kWh") => (Some("joules"), 3_600_000.0),
+        _ => (None, 1.0),
+    }
+}
+
+/// The current live value of every sensor, minus the ones flagged stale since.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    values: BTreeMap<String, SensorValue>,
+}
+
+impl Snapshot {
+    pub fn observe(&mut self, batch: &[SensorSample]) {
+        for s in batch {
+            if s.stale {
+                self.values.remove(&s.sensor);
+            } else {
+                self.values.insert(s.sensor.clone(), s.value.clone());
+            }
+        }
+    }
+}
+
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_help(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// The text exposition (format 0.0.4) of a snapshot: one gauge family per metric name, sorted,
+/// each with a single `# HELP` (the catalog label) and `# TYPE`. Text values are rendered only for
+/// ids starting with one of `info_prefixes`.
+pub fn render(snapshot: &Snapshot, info_prefixes: &[String]) -> String {
+    // name → (help, lines)
+    let mut families: BTreeMap<String, (String, Vec<String>)> = BTreeMap::new();
+    for (id, value) in &snapshot.values {
+        let info = catalog::describe(id, Some(value.kind()));
+        let labels = format!(
+            "id=\"{}\",source=\"{}\"",
+            escape_label(id),
+            escape_label(&info.source)
+        );
+        let (name, line) = match value {
+            SensorValue::Scalar(v) if v.is_finite() => {
+                let (suffix, scale) = base_unit(info.unit.as_deref());
+                let base = metric_name(id);
+                let name = match suffix {
+                    Some(sfx) if !base.ends_with(&format!("_{sfx}")) => format!("{base}_{sfx}"),
+                    _ => base,
+                };
+                let line = format!("{name}{{{labels}}} {}", v * scale);
+                (name, line)
+            }
+            SensorValue::Text(t) if info_prefixes.iter().any(|p| id.starts_with(p.as_str())) => {
+                let name = format!("{}_info", metric_name(id));
+                let text: String = t.chars().take(MAX_TEXT).collect();
+                let line = format!("{name}{{{labels},value=\"{}\"}} 1", escape_label(&text));
+                (name, line)
+            }
+            _ => continue,
+        };
+        families
+            .entry(name)
+            .or_insert_with(|| (info.label.clone(), Vec::new()))
+            .1
+            .push(line);
+    }
+    let mut out = String::new();
+    for (name, (help, lines)) in families {
+        out.push_str(&format!("# HELP {name} {}\n", escape_help(&help)));
+        out.push_str(&format!("# TYPE {name} gauge\n"));
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// ---- server ----
+
+async fn write_text(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) {
+    let resp = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(resp.as_bytes()).await;
+    let _ = stream.flush().await;
+}
+
+async fn handle_conn(
+    snapshot: Arc<std::sync::Mutex<Snapshot>>,
+    token: Option<String>,
+    info_prefixes: Arc<[String]>,
+    mut stream: TcpStream,
+) {
+    let Ok(Some((head, _))) = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await
+    else {
+        return;
+    };
+    if token.as_ref().is_some_and(|t| !bearer_ok(&head.headers, t)) {
+        write_text(
+            &mut stream,
+            "401 Unauthorized",
+            "text/plain",
+            "unauthorized\n",
+        )
+        .await;
+        return;
+    }
+    if head.method != "GET" || head.path != "/metrics" {
+        write_text(&mut stream, "404 Not Found", "text/plain", "not found\n").await;
+        return;
+    }
+    let body = render(
+        &snapshot.lock().unwrap_or_else(|e| e.into_inner()),
+        &info_prefixes,
+    );
+    write_text(
+        &mut stream,
+        "200 OK",
+        "text/plain; version=0.0.4; charset=utf-8",
+        &body,
+    )
+    .await;
+}
+
+/// Serve until aborted: keep the snapshot current from the bus and answer scrapes, each connection
+/// in its own task behind control.rs' concurrency cap.
+async fn run_exporter<R: Runtime>(
+    app: AppHandle<R>,
+    listener: TcpListener,
+    token: Option<String>,
+    info_prefixes: Arc<[String]>,
+) {
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let snapshot = Arc::new(std::sync::Mutex::new(Snapshot::default()));
+    // Seed with what's already known so the first scrape isn't empty — minus stale values, which
+    // `observe` leaves out just as it does on the live stream.
+    let seed = bus::latest_samples(&app);
+    snapshot
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .observe(bus::actionable(&seed, bus::replaying(&app)));
+    let sem = Arc::new(Semaphore::new(MAX_CONNS));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => snapshot
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .observe(bus::actionable(&batch, bus::replaying(&app))),
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("exporter", "metrics exporter fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                }
+                Err(RecvError::Closed) => return,
+            },
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let Ok(permit) = sem.clone().acquire_owned().await else {
+                        continue;
+                    };
+                    let snapshot = snapshot.clone();
+                    let token = token.clone();
+                    let info_prefixes = info_prefixes.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _permit = permit;
+                        handle_conn(snapshot, token, info_prefixes, stream).await;
+                    });
+                }
+                Err(e) => {
+                    log::warn("exporter", "accept failed")
+                        .field("error", e.to_string())
+                        .emit();
+                }
+            },
+        }
+    }
+}
+
+// ---- start/stop (managed) ----
+
+/// (Re)start the exporter from `exporter.json`: stop any running server, then bind and serve if
+/// enabled. A bind failure is returned (and logged at startup) rather than left as a dead handle.
+pub async fn restart<R: Runtime>(app: AppHandle<R>, state: &ExporterState) -> Result<(), String> {
+    let mut guard = state.handle.lock().await;
+    if let Some((handle, _)) = guard.take() {
+        handle.abort();
+    }
+    let cfg = load_exporter_config(&app)?;
+    if !cfg.enabled {
+        return Ok(());
+    }
+    validate(&cfg)?;
+    let host = if cfg.lan { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = TcpListener::bind((host, cfg.port))
+        .await
+        .map_err(|e| format!("bind {host}:{} failed: {e}", cfg.port))?;
+    let url = format!("http://{host}:{}/metrics", cfg.port);
+    log::info("exporter", "metrics exporter listening")
+        .field("url", &url)
+        .emit();
+    let token = Some(cfg.token.trim().to_string()).filter(|t| !t.is_empty());
+    let info_prefixes: Arc<[String]> = cfg.info_prefixes.into();
+    let handle =
+        tauri::async_runtime::spawn(run_exporter(app.clone(), listener, token, info_prefixes));
+    *guard = Some((handle, url));
+    Ok(())
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/exporter.json` and restart the server. Studio-window-guarded; a blank token
+/// keeps the stored one.
+#[tauri::command]
+pub async fn save_exporter_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, ExporterState>,
+    config: ExporterConfig,
+) -> Result<ExporterStatus, String> {
+    if window.label() != "studio" {
+        return Err("save_exporter_config is only allowed from the studio window".into());
+    }
+    let stored = load_exporter_config(&app)?;
+    let token = match config.token.trim() {
+        "" => stored.token,
+        t => t.to_string(),
+    };
+    let info_prefixes = config
+        .info_prefixes
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    let cfg = ExporterConfig {
+        token,
+        info_prefixes,
+        ..config
+    };
+    validate(&cfg)?;
+    let path = exporter_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    restart(app.clone(), &state).await?;
+    exporter_status(app, state).await
+}
+
+/// The exporter settings (never the token) and its scrape URL while running.
+#[tauri::command]
+pub async fn exporter_status(
+    app: AppHandle,
+    state: State<'_, ExporterState>,
+) -> Result<ExporterStatus, String> {
+    let cfg = load_exporter_config(&app)?;
+    let url = state
+        .handle
+        .lock()
+        .await
+        .as_ref()
+        .map(|(_, url)| url.clone());
+    Ok(ExporterStatus {
+        enabled: cfg.enabled,
+        port: cfg.port,
+        lan: cfg.lan,
+        has_token: !cfg.token.trim().is_empty(),
+        info_prefixes: cfg.info_prefixes,
+        url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_names_are_slugged_ids() {
+        assert_eq!(metric_name("cpu.total"), "widgetsack_cpu_total");
+        assert_eq!(metric_name("mqtt.home/Temp"), "widgetsack_mqtt_home_temp");
+        assert_eq!(metric_name("ha.sensor.a--b."), "widgetsack_ha_sensor_a_b");
+    }
+
+    #[test]
+    fn units_map_to_prometheus_base_units() {
+        assert_eq!(base_unit(Some("%")), (Some("ratio"), 0.01));
+        assert_eq!(base_unit(Some("MHz")), (Some("hertz"), 1e6));
+        assert_eq!(base_unit(Some("kWh")), (Some("joules"), 3_600_000.0));
+        assert_eq!(base_unit(None), (None, 1.0));
+    }
+
+    #[test]
+    fn renders_gauges_and_text_info_and_drops_stale_values() {
+        let mut snap = Snapshot::default();
+        snap.observe(&[
+            SensorSample::scalar("cpu.total", 1, 42.0),
+            SensorSample::text("ha.light.desk", 1, "on \"bright\""),
+            SensorSample::scalar("mqtt.home/temp", 1, 21.5),
+            SensorSample::scalar("derived.nan", 1, f64::NAN),
+        ]);
+        let out = render(&snap, &["ha.light.".to_string()]);
+        assert!(out.contains("# TYPE widgetsack_cpu_total_ratio gauge\n"));
+        assert!(
+            out.contains("widgetsack_cpu_total_ratio{id=\"cpu.total\",source=\"system\"} 0.42\n")
+        );
+        assert!(out.contains(
+            "widgetsack_ha_light_desk_info{id=\"ha.light.desk\",source=\"ha\",\
+             value=\"on \\\"bright\\\"\"} 1\n"
+        ));
+        assert!(
+            out.contains("widgetsack_mqtt_home_temp{id=\"mqtt.home/temp\",source=\"mqtt\"} 21.5\n")
+        );
+        assert!(!out.contains("derived_nan"));
+
+        snap.observe(&[SensorSample {
+            stale: true,
+            ..SensorSample::scalar("mqtt.home/temp", 2, 21.5)
+        }]);
+        assert!(!render(&snap, &[]).contains("mqtt_home_temp"));
+    }
+
+    #[test]
+    fn text_values_export_only_under_an_info_prefix() {
+        let mut snap = Snapshot::default();
+        snap.observe(&[
+            SensorSample::text("ha.light.desk", 1, "on"),
+            SensorSample::text("tail.app.line", 1, "GET /index 200"),
+        ]);
+        assert!(!render(&snap, &[]).contains("_info"));
+        let out = render(&snap, &["ha.light.".to_string()]);
+        assert!(out.contains("widgetsack_ha_light_desk_info"));
+        assert!(!out.contains("tail_app_line"));
+    }
+
+    #[test]
+    fn lan_bind_requires_a_token() {
+        let lan = ExporterConfig {
+            enabled: true,
+            lan: true,
+            ..ExporterConfig::default()
+        };
+        assert!(validate(&lan).is_err());
+        assert!(
+            validate(&ExporterConfig {
+                token: "x".repeat(16),
+                ..lan.clone()
+            })
+            .is_ok()
+        );
+        assert!(validate(&ExporterConfig { lan: false, ..lan }).is_ok());
+    }
+}
@@ -0,0 +1,788 @@
+//! RSS/Atom feed reader source — release notes, a news ticker, a status page. Each configured feed
+//! is polled with conditional requests (`If-None-Match` / `If-Modified-Since` from the last
+//! response's ETag / Last-Modified, so an unchanged feed costs a 304) and a streamed size cap like
+//! the plugin installer's. Items are deduplicated by guid/id (else link, else title) into a small
+//! per-feed store with read flags, and published as:
+//!   `feed.<name>.items`             Json array of the newest `items` entries
+//!                                   (`{ id, title, link, published, read }`, `published` in
+//!                                   Unix ms or null)
+//!   `feed.<name>.latest.title`      the newest entry's title
+//!   `feed.<name>.latest.link`       its link
+//!   `feed.<name>.latest.published`  its date, RFC 3339 in local time (empty when undated)
+//!   `feed.<name>.unread`            stored entries not yet marked read
+//!   `feed.<name>.status`            `ok` / `error` / `HTTP 404`
+//! Entries present on a feed's first successful fetch start out read, so adding a feed doesn't
+//! light up a backlog; only what arrives afterwards counts as unread. `feed_mark_read` clears one
+//! entry (by id) or the whole feed. The store (ETag, entries, read flags) persists in
+//! `feeds/<name>.json` next to the config.
+//!
+//! Configured in `plugins/feeds.json`:
+//! `{ "feeds": [{ "name": "releases", "url": "https://github.com/o/r/releases.atom",
+//!               "items": 5 }] }`
+//! RSS 2.0, RSS 1.0 (RDF) and Atom are read by element name, ignoring namespaces. Polling reuses
+//! stocks.rs' backoff and httppoll.rs' demand gating with the `always` opt-out. The parser and the
+//! store merge are unit-tested.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::command::atomic_write;
+use crate::httppoll::{http_client, read_capped};
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Poll cadence guardrails (seconds).
+const MIN_INTERVAL: u64 = 60;
+const MAX_INTERVAL: u64 = 86_400;
+const FETCH_TIMEOUT_SECS: u64 = 20;
+/// Largest feed body accepted, enforced while streaming.
+const FEED_CAP: usize = 2 * 1024 * 1024;
+/// Entries kept per feed (newest first) for dedupe and read state.
+const MAX_KEPT: usize = 200;
+const MAX_ITEMS: usize = 100;
+/// Re-check the demand gate this often while a feed is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+
+fn default_interval() -> u64 {
+    900
+}
+fn default_items() -> usize {
+    10
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One polled feed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeedDef {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    /// How many entries `feed.<name>.items` carries.
+    #[serde(default = "default_items")]
+    pub items: usize,
+    #[serde(default)]
+    pub insecure: bool,
+    #[serde(default)]
+    pub always: bool,
+}
+
+impl Supervised for FeedDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/feeds.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FeedConfig {
+    #[serde(default)]
+    pub feeds: Vec<FeedDef>,
+}
+
+/// Managed state: the live definitions, the generation counter the supervisor polls, and each
+/// feed's loaded store (shared with `feed_mark_read`).
+#[derive(Default)]
+pub struct FeedReader {
+    config: Mutex<FeedConfig>,
+    generation: AtomicU64,
+    stores: Mutex<HashMap<String, FeedStore>>,
+}
+
+impl FeedReader {
+    fn replace(&self, cfg: FeedConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn def(&self, name: &str) -> Option<FeedDef> {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .feeds
+            .iter()
+            .find(|f| f.name == name)
+            .cloned()
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("feeds.json"))
+}
+
+fn store_path<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("feeds").join(format!("{name}.json")))
+}
+
+pub fn load_feed_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<FeedConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_feed_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("feed", "skipping feed")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<FeedReader>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("feed", "failed to read feeds.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, clamp the interval and item count, reject duplicates and non-http(s) URLs.
+fn normalize_config(cfg: FeedConfig) -> (FeedConfig, Vec<String>) {
+    let mut kept: Vec<FeedDef> = Vec::new();
+    let mut errors = Vec::new();
+    for f in cfg.feeds {
+        let Some(name) = id_segment(&f.name) else {
+            errors.push(format!("`{}`: invalid name", f.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let url = f.url.trim().to_string();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            errors.push(format!("`{name}`: url must be http(s)"));
+            continue;
+        }
+        kept.push(FeedDef {
+            name,
+            url,
+            interval_secs: f.interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+            items: f.items.clamp(1, MAX_ITEMS),
+            ..f
+        });
+    }
+    (FeedConfig { feeds: kept }, errors)
+}
+
+// ---- parsing (pure) ----
+
+/// One feed entry as parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub published: Option<i64>,
+}
+
+/// Collapse runs of whitespace (titles often carry the feed's indentation).
+fn squash(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// RFC 2822 (RSS) or RFC 3339 (Atom, `dc:date`) → Unix ms.
+fn parse_date(s: &str) -> Option<i64> {
+    let s = s.trim();
+    chrono::DateTime::parse_from_rfc2822(s)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(s))
+        .ok()
+        .map(|d| d.timestamp_millis())
+}
+
+/// Parse an RSS 2.0, RSS 1.0 or Atom document into its entries, in document order.
+pub fn parse_feed(xml: &str) -> Result<Vec<Entry>, String> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    let is_atom = root.tag_name().name() == "feed";
+    let entry_tag = if is_atom { "entry" } else { "item" };
+    if !matches!(root.tag_name().name(), "rss" | "RDF" | "feed") {
+        return Err(format!("not a feed (<{}>)", root.tag_name().name()));
+    }
+    let entries = root
+        .descendants()
+        .filter(|n| n.is_element() && n.tag_name().name() == entry_tag)
+        .map(|item| {
+            let child = |name: &str| {
+                item.children()
+                    .find(|c| c.is_element() && c.tag_name().name() == name)
+            };
+            let text = |name: &str| {
+                child(name)
+                    .map(|c| {
+                        squash(
+                            &c.descendants()
+                                .filter(|d| d.is_text())
+                                .filter_map(|d| d.text())
+                                .collect::<String>(),
+                        )
+                    })
+                    .unwrap_or_default()
+            };
+            let link = if is_atom {
+                // The alternate link (an absent rel means alternate).
+                item.children()
+                    .filter(|c| c.is_element() && c.tag_name().name() == "link")
+                    .find(|c| c.attribute("rel").is_none_or(|r| r == "alternate"))
+                    .and_then(|c| c.attribute("href"))
+                    .unwrap_or_default()
+                    .to_string()
+            } else {
+                text("link")
+            };
+            let published = ["pubDate", "published", "updated", "date"]
+                .iter()
+                .find_map(|name| parse_date(&text(name)));
+            let title = text("title");
+            let id = [text("guid"), text("id"), link.clone(), title.clone()]
+                .into_iter()
+                .find(|s| !s.is_empty())
+                .unwrap_or_default();
+            Entry {
+                id,
+                title,
+                link,
+                published,
+            }
+        })
+        .filter(|e| !e.id.is_empty())
+        .collect();
+    Ok(entries)
+}
+
+// ---- store (pure) ----
+
+/// A kept entry with its read flag.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredEntry {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub published: Option<i64>,
+    /// When this reader first saw it (orders undated entries).
+    pub seen: i64,
+    pub read: bool,
+}
+
+impl StoredEntry {
+    fn sort_key(&self) -> i64 {
+        self.published.unwrap_or(self.seen)
+    }
+}
+
+/// Per-feed persisted state: conditional-request validators plus the kept entries, newest first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FeedStore {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    /// Set after the first successful fetch; until then new entries start read.
+    #[serde(default)]
+    pub primed: bool,
+    #[serde(default)]
+    pub entries: Vec<StoredEntry>,
+}
+
+impl FeedStore {
+    /// Fold a fetch in: unseen ids are added (read if this is the first fetch), known ones keep
+    /// their read flag but take updated titles/links. Returns how many were new.
+    pub fn merge(&mut self, fetched: Vec<Entry>, now: i64) -> usize {
+        let mut added = 0;
+        for e in fetched {
+            match self.entries.iter_mut().find(|s| s.id == e.id) {
+                Some(s) => {
+                    s.title = e.title;
+                    s.link = e.link;
+                    s.published = e.published.or(s.published);
+                }
+                None => {
+                    added += 1;
+                    self.entries.push(StoredEntry {
+                        id: e.id,
+                        title: e.title,
+                        link: e.link,
+                        published: e.published,
+                        seen: now,
+                        read: !self.primed,
+                    });
+                }
+            }
+        }
+        self.primed = true;
+        // Stable: undated entries from one fetch keep the feed's order.
+        self.entries
+            .sort_by_key(|s| std::cmp::Reverse(s.sort_key()));
+        self.entries.truncate(MAX_KEPT);
+        added
+    }
+
+    /// Mark one entry (or, with `None`, every entry) read. False when the id isn't kept.
+    pub fn mark_read(&mut self, id: Option<&str>) -> bool {
+        let mut hit = false;
+        for s in &mut self.entries {
+            if id.is_none_or(|id| s.id == id) {
+                s.read = true;
+                hit = true;
+            }
+        }
+        hit || id.is_none()
+    }
+
+    pub fn unread(&self) -> usize {
+        self.entries.iter().filter(|s| !s.read).count()
+    }
+
+    /// The feed's sensors, with `items` entries in the Json list.
+    pub fn samples(&self, name: &str, items: usize, ts: u64) -> Vec<SensorSample> {
+        let base = format!("feed.{name}");
+        let list: Vec<_> = self
+            .entries
+            .iter()
+            .take(items)
+            .map(|s| {
+                json!({
+                    "id": s.id,
+                    "title": s.title,
+                    "link": s.link,
+                    "published": s.published,
+                    "read": s.read,
+                })
+            })
+            .collect();
+        let mut out = vec![SensorSample {
+            sensor: format!("{base}.items"),
+            ts_ms: ts,
+            value: SensorValue::Json(serde_json::Value::Array(list)),
+            stale: false,
+        }];
+        if let Some(latest) = self.entries.first() {
+            let published = latest
+                .published
+                .and_then(chrono::DateTime::from_timestamp_millis)
+                .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+                .unwrap_or_default();
+            out.push(SensorSample::text(
+                format!("{base}.latest.title"),
+                ts,
+                latest.title.clone(),
+            ));
+            out.push(SensorSample::text(
+                format!("{base}.latest.link"),
+                ts,
+                latest.link.clone(),
+            ));
+            out.push(SensorSample::text(
+                format!("{base}.latest.published"),
+                ts,
+                published,
+            ));
+        }
+        out.push(SensorSample::scalar(
+            format!("{base}.unread"),
+            ts,
+            self.unread() as f64,
+        ));
+        out
+    }
+}
+
+fn load_store<R: Runtime>(app: &AppHandle<R>, name: &str) -> FeedStore {
+    store_path(app, name)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|txt| serde_json::from_str(&txt).ok())
+        .unwrap_or_default()
+}
+
+fn save_store<R: Runtime>(app: &AppHandle<R>, name: &str, store: &FeedStore) -> Result<(), String> {
+    let path = store_path(app, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string(store).map_err(|e| e.to_string())?;
+    atomic_write(&path, &txt)
+}
+
+// ---- task ----
+
+/// True while any window is consuming one of this feed's sensors. Default OFF before the first
+/// report, like `endpoint_wanted`.
+fn feed_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active = app.state::<ActiveSensors>();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let prefix = format!("feed.{name}.");
+    crate::sensors::any_wanted(&guard, |id| id.starts_with(&prefix))
+}
+
+/// One fetch's outcome.
+enum Fetched {
+    NotModified,
+    Body {
+        text: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+/// A conditional GET, reading the body under `FEED_CAP`.
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<Fetched, String> {
+    use reqwest::header;
+    let mut req = client.get(url);
+    if let Some(etag) = etag {
+        req = req.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(lm) = last_modified {
+        req = req.header(header::IF_MODIFIED_SINCE, lm);
+    }
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    let value = |name: header::HeaderName| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let (etag, last_modified) = (value(header::ETAG), value(header::LAST_MODIFIED));
+    let buf = read_capped(resp, FEED_CAP).await?;
+    Ok(Fetched::Body {
+        text: String::from_utf8_lossy(&buf).into_owned(),
+        etag,
+        last_modified,
+    })
+}
+
+/// Publish a feed's current sensors from the shared store.
+fn publish_store<R: Runtime>(app: &AppHandle<R>, def: &FeedDef) {
+    let state = app.state::<FeedReader>();
+    let stores = state.stores.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(store) = stores.get(&def.name) {
+        let _ = bus::publish(app, &store.samples(&def.name, def.items, now_ms()));
+    }
+}
+
+/// Poll one feed until aborted: demand-gated, with stocks' backoff on failures.
+async fn poll_feed<R: Runtime>(app: AppHandle<R>, def: FeedDef) {
+    let client = match http_client(FETCH_TIMEOUT_SECS, def.insecure) {
+        Ok(c) => c,
+        Err(err) => {
+            log::warn("feed", "client build failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            return;
+        }
+    };
+    {
+        let state = app.state::<FeedReader>();
+        let mut stores = state.stores.lock().unwrap_or_else(|e| e.into_inner());
+        if !stores.contains_key(&def.name) {
+            stores.insert(def.name.clone(), load_store(&app, &def.name));
+        }
+    }
+    publish_store(&app, &def);
+    let status_id = format!("feed.{}.status", def.name);
+    let interval = Duration::from_secs(def.interval_secs);
+    let mut fails: u32 = 0;
+    loop {
+        if !def.always && !feed_wanted(&app, &def.name) {
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        let (etag, last_modified) = {
+            let state = app.state::<FeedReader>();
+            let stores = state.stores.lock().unwrap_or_else(|e| e.into_inner());
+            let store = stores.get(&def.name);
+            (
+                store.and_then(|s| s.etag.clone()),
+                store.and_then(|s| s.last_modified.clone()),
+            )
+        };
+        let result = match fetch(&client, &def.url, etag.as_deref(), last_modified.as_deref()).await
+        {
+            Ok(Fetched::NotModified) => Ok(None),
+            Ok(Fetched::Body {
+                text,
+                etag,
+                last_modified,
+            }) => parse_feed(&text).map(|entries| Some((entries, etag, last_modified))),
+            Err(err) => Err(err),
+        };
+        let status = match result {
+            Ok(update) => {
+                fails = 0;
+                if let Some((entries, etag, last_modified)) = update {
+                    let state = app.state::<FeedReader>();
+                    let mut stores = state.stores.lock().unwrap_or_else(|e| e.into_inner());
+                    let store = stores.entry(def.name.clone()).or_default();
+                    store.merge(entries, now_ms() as i64);
+                    store.etag = etag;
+                    store.last_modified = last_modified;
+                    if let Err(err) = save_store(&app, &def.name, store) {
+                        log::warn("feed", "failed to save feed store")
+                            .field("name", def.name.clone())
+                            .field("error", err)
+                            .emit();
+                    }
+                }
+                publish_store(&app, &def);
+                "ok".to_string()
+            }
+            Err(err) => {
+                fails = (fails + 1).min(5);
+                log::warn("feed", "feed fetch failed")
+                    .field("name", def.name.clone())
+                    .field("error", err.clone())
+                    .emit();
+                if err.starts_with("HTTP ") {
+                    err
+                } else {
+                    "error".to_string()
+                }
+            }
+        };
+        let _ = bus::publish(
+            &app,
+            &[SensorSample::text(status_id.clone(), now_ms(), status)],
+        );
+        let wait = if fails == 0 {
+            interval
+        } else {
+            interval
+                .saturating_mul(1u32 << fails.min(4))
+                .min(Duration::from_secs(1800))
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// The supervisor: one poller per feed, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_feeds<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<FeedReader> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .feeds
+                .clone()
+        },
+        |f| tauri::async_runtime::spawn(poll_feed(app.clone(), f)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/feeds.json` and restart the changed pollers. Studio-window-guarded; any
+/// invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_feed_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, FeedReader>,
+    feeds: Vec<FeedDef>,
+) -> Result<Vec<FeedDef>, String> {
+    if window.label() != "studio" {
+        return Err("save_feed_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(FeedConfig { feeds });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.feeds.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured feeds.
+#[tauri::command]
+pub fn feed_config_status(state: State<'_, FeedReader>) -> Vec<FeedDef> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .feeds
+        .clone()
+}
+
+/// Mark one entry (`id` from `feed.<name>.items`) or, without an id, the whole feed as read.
+/// Persists the store, republishes the feed's sensors, and returns the new unread count. Callable
+/// from any window, so a widget can clear its own badge.
+#[tauri::command]
+pub fn feed_mark_read(
+    app: AppHandle,
+    state: State<'_, FeedReader>,
+    name: String,
+    id: Option<String>,
+) -> Result<usize, String> {
+    let def = state
+        .def(&name)
+        .ok_or_else(|| format!("no feed named `{name}`"))?;
+    let unread = {
+        let mut stores = state.stores.lock().unwrap_or_else(|e| e.into_inner());
+        let store = stores
+            .get_mut(&name)
+            .ok_or_else(|| format!("feed `{name}` hasn't loaded yet"))?;
+        if !store.mark_read(id.as_deref()) {
+            return Err(format!(
+                "feed `{name}` has no entry `{}`",
+                id.unwrap_or_default()
+            ));
+        }
+        save_store(&app, &name, store)?;
+        store.unread()
+    };
+    publish_store(&app, &def);
+    Ok(unread)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Blog</title>
+  <item><title>  Second
+      post </title><link>https://blog.example/2</link><guid>post-2</guid>
+    <pubDate>Tue, 10 Mar 2026 09:00:00 GMT</pubDate></item>
+  <item><title>First &amp; best</title><link>https://blog.example/1</link>
+    <pubDate>Mon, 09 Mar 2026 09:00:00 +0000</pubDate></item>
+</channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>Releases</title>
+  <entry><id>tag:github.com,2008:v1.2.0</id><title type="html">v1.2.0</title>
+    <link rel="self" href="https://x.example/self"/>
+    <link rel="alternate" type="text/html" href="https://x.example/v1.2.0"/>
+    <updated>2026-03-10T12:00:00+01:00</updated></entry>
+  <entry><id>tag:github.com,2008:v1.1.0</id><title>v1.1.0</title>
+    <link href="https://x.example/v1.1.0"/></entry>
+</feed>"#;
+
+    fn entry(id: &str, published: Option<i64>) -> Entry {
+        Entry {
+            id: id.into(),
+            title: id.to_uppercase(),
+            link: format!("https://x.example/{id}"),
+            published,
+        }
+    }
+
+    #[test]
+    fn parses_rss_and_atom() {
+        let rss = parse_feed(RSS).unwrap();
+        assert_eq!(rss.len(), 2);
+        assert_eq!(rss[0].id, "post-2");
+        assert_eq!(rss[0].title, "Second post");
+        assert_eq!(rss[0].published, Some(1_773_133_200_000));
+        // No guid: the link stands in.
+        assert_eq!(rss[1].id, "https://blog.example/1");
+        assert_eq!(rss[1].title, "First & best");
+
+        let atom = parse_feed(ATOM).unwrap();
+        assert_eq!(atom[0].link, "https://x.example/v1.2.0");
+        assert_eq!(atom[0].published, Some(1_773_140_400_000));
+        assert_eq!(atom[1].link, "https://x.example/v1.1.0");
+        assert_eq!(atom[1].published, None);
+
+        assert!(parse_feed("<html><body/></html>").is_err());
+        assert!(parse_feed("not xml").is_err());
+    }
+
+    #[test]
+    fn merge_dedupes_and_only_later_entries_are_unread() {
+        let mut store = FeedStore::default();
+        assert_eq!(
+            store.merge(vec![entry("a", Some(1)), entry("b", Some(2))], 10),
+            2
+        );
+        assert_eq!(store.unread(), 0);
+        // A refetch repeats `b`, adds `c`; newest first.
+        assert_eq!(
+            store.merge(vec![entry("c", Some(3)), entry("b", Some(2))], 20),
+            1
+        );
+        let ids: Vec<&str> = store.entries.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["c", "b", "a"]);
+        assert_eq!(store.unread(), 1);
+        // An undated entry sorts by when it was seen.
+        store.merge(vec![entry("d", None)], 30);
+        assert_eq!(store.entries[0].id, "d");
+        assert_eq!(store.unread(), 2);
+
+        assert!(store.mark_read(Some("c")));
+        assert!(!store.mark_read(Some("zzz")));
+        assert_eq!(store.unread(), 1);
+        assert!(store.mark_read(None));
+        assert_eq!(store.unread(), 0);
+    }
+
+    #[test]
+    fn samples_carry_latest_items_and_unread() {
+        let mut store = FeedStore::default();
+        store.merge(vec![entry("a", Some(1))], 1);
+        store.merge(vec![entry("b", Some(2)), entry("c", Some(3))], 2);
+        let batch = store.samples("blog", 2, 5);
+        let get = |id: &str| &batch.iter().find(|s| s.sensor == id).unwrap().value;
+        assert!(matches!(get("feed.blog.latest.title"), SensorValue::Text(s) if s == "C"));
+        assert!(matches!(get("feed.blog.unread"), SensorValue::Scalar(v) if *v == 2.0));
+        let items = match get("feed.blog.items") {
+            SensorValue::Json(v) => v.as_array().unwrap().clone(),
+            _ => Vec::new(),
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["id"], "c");
+        assert_eq!(items[0]["read"], false);
+    }
+}
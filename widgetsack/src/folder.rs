@@ -0,0 +1,502 @@
+//! Folder watch source — "how full is Downloads", "did the render land", "what's in the print
+//! queue". Each configured directory is watched with the same `notify` watcher command.rs'
+//! `watch_and_emit` uses for themes/layout, and rescanned when it changes — no polling. Publishes:
+//!   `folder.<name>.count`    files (directories aren't counted)
+//!   `folder.<name>.size`     their total size (bytes)
+//!   `folder.<name>.newest`   the most recently modified file's name (relative when recursive;
+//!                            `""` while the folder is empty)
+//!   `folder.<name>.mtime`    its modification time (Unix ms; 0 while empty)
+//!   `folder.<name>.matched`  files whose name matches `pattern` — only when a pattern is set
+//!   `folder.<name>.status`   `ok`, or `missing` while the directory can't be read
+//!
+//! Configured in `plugins/folders.json`:
+//! `{ "folders": [{ "name": "downloads", "path": "C:\\Users\\me\\Downloads", "pattern": "*.part" },
+//!                { "name": "renders", "path": "D:\\renders", "recursive": true }] }`
+//! `pattern` is procwatch.rs' case-insensitive `*`/`?` glob. Dotfiles are skipped unless `hidden`.
+//! A burst of events (a copy, an extraction) is coalesced into one rescan after `SETTLE` of quiet,
+//! but never waits longer than `MAX_SETTLE`, so a folder written to non-stop still rescans.
+//! The watch is armed before each scan, so a change landing mid-scan still triggers a rescan.
+//! A missing directory is retried every `MISSING_RETRY` until it appears. The scan is capped at
+//! `MAX_ENTRIES` entries so a mistaken `C:\` recursive watch can't stall. Watching is idle until
+//! something changes, so it isn't demand-gated. The pure seam (`Summary`) and a scan of a temp dir
+//! are unit-tested.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use notify::Watcher;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::log;
+use crate::procwatch::glob_match;
+use crate::sensors::{SensorSample, id_segment};
+use crate::supervisor::{Supervised, settle, supervise};
+
+/// Quiet period after the last event before rescanning.
+const SETTLE: Duration = Duration::from_millis(500);
+/// Longest a burst is coalesced before rescanning anyway.
+const MAX_SETTLE: Duration = Duration::from_millis(2_500);
+/// How often a missing (or unwatchable) directory is looked for again.
+const MISSING_RETRY: Duration = Duration::from_secs(30);
+/// Entries visited per scan before giving up on the rest.
+const MAX_ENTRIES: usize = 100_000;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One watched directory.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FolderDef {
+    pub name: String,
+    pub path: String,
+    /// Count files matching this glob as `folder.<name>.matched`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Include subdirectories.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Include dotfiles (and files in dot-directories).
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl Supervised for FolderDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/folders.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FolderConfig {
+    #[serde(default)]
+    pub folders: Vec<FolderDef>,
+}
+
+/// Managed state: the live definitions plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct FolderWatch {
+    config: Mutex<FolderConfig>,
+    generation: AtomicU64,
+}
+
+impl FolderWatch {
+    fn replace(&self, cfg: FolderConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("folders.json"))
+}
+
+pub fn load_folder_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<FolderConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_folder_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("folder", "skipping folder watch")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<FolderWatch>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("folder", "failed to read folders.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, reject duplicates and relative paths, drop blank patterns.
+fn normalize_config(cfg: FolderConfig) -> (FolderConfig, Vec<String>) {
+    let mut kept: Vec<FolderDef> = Vec::new();
+    let mut errors = Vec::new();
+    for f in cfg.folders {
+        let Some(name) = id_segment(&f.name) else {
+            errors.push(format!("`{}`: invalid name", f.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let path = f.path.trim().to_string();
+        if !Path::new(&path).is_absolute() {
+            errors.push(format!("`{name}`: path must be absolute"));
+            continue;
+        }
+        kept.push(FolderDef {
+            name,
+            path,
+            pattern: f
+                .pattern
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty()),
+            ..f
+        });
+    }
+    (FolderConfig { folders: kept }, errors)
+}
+
+// ---- scanning ----
+
+/// One scan's totals.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub count: u64,
+    pub size: u64,
+    /// `(name, mtime ms)` of the most recently modified file.
+    pub newest: Option<(String, u64)>,
+    pub matched: u64,
+}
+
+impl Summary {
+    /// Fold in one file. `name` is its path relative to the watched root.
+    fn add(&mut self, name: &str, size: u64, mtime_ms: u64, pattern: Option<&str>) {
+        self.count += 1;
+        self.size += size;
+        if self.newest.as_ref().is_none_or(|(_, t)| mtime_ms > *t) {
+            self.newest = Some((name.to_string(), mtime_ms));
+        }
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        if pattern.is_some_and(|p| glob_match(p, file_name)) {
+            self.matched += 1;
+        }
+    }
+
+    /// The sensors for `def`.
+    fn samples(&self, def: &FolderDef, ts: u64) -> Vec<SensorSample> {
+        let base = format!("folder.{}", def.name);
+        let mut out = vec![
+            SensorSample::scalar(format!("{base}.count"), ts, self.count as f64),
+            SensorSample::scalar(format!("{base}.size"), ts, self.size as f64),
+        ];
+        // An emptied folder still publishes both, so the last file's name doesn't linger.
+        let (name, mtime) = self.newest.clone().unwrap_or_default();
+        out.push(SensorSample::text(format!("{base}.newest"), ts, name));
+        out.push(SensorSample::scalar(
+            format!("{base}.mtime"),
+            ts,
+            mtime as f64,
+        ));
+        if def.pattern.is_some() {
+            out.push(SensorSample::scalar(
+                format!("{base}.matched"),
+                ts,
+                self.matched as f64,
+            ));
+        }
+        out.push(SensorSample::text(format!("{base}.status"), ts, "ok"));
+        out
+    }
+}
+
+/// Walk `def.path` (one level, or the whole tree when recursive). Symlinks are skipped.
+/// Blocking; run off the async pool.
+fn scan(def: &FolderDef) -> std::io::Result<Summary> {
+    let root = PathBuf::from(&def.path);
+    let mut summary = Summary::default();
+    let mut pending = vec![(root, String::new())];
+    let mut visited = 0usize;
+    while let Some((dir, prefix)) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if prefix.is_empty() => return Err(err),
+            // A subdirectory vanishing mid-scan isn't an error for the folder as a whole.
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            visited += 1;
+            if visited > MAX_ENTRIES {
+                return Ok(summary);
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !def.hidden && name.starts_with('.') {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let rel = format!("{prefix}{name}");
+            if meta.is_dir() {
+                if def.recursive {
+                    pending.push((entry.path(), format!("{rel}/")));
+                }
+            } else if meta.is_file() {
+                let mtime = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0);
+                summary.add(&rel, meta.len(), mtime, def.pattern.as_deref());
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Scan and publish; `false` when the directory couldn't be read (`status` is then `missing`).
+async fn scan_and_publish<R: Runtime>(app: &AppHandle<R>, def: &FolderDef) -> bool {
+    let d = def.clone();
+    let result = tokio::task::spawn_blocking(move || scan(&d))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()));
+    match result {
+        Ok(summary) => {
+            let _ = bus::publish(app, &summary.samples(def, now_ms()));
+            true
+        }
+        Err(err) => {
+            log::warn("folder", "folder scan failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            let status = format!("folder.{}.status", def.name);
+            let _ = bus::publish(app, &[SensorSample::text(status, now_ms(), "missing")]);
+            false
+        }
+    }
+}
+
+/// Watch one directory until aborted: arm the watch, scan once, then rescan after each settled
+/// burst of events. Arming first means nothing written during the initial scan goes unnoticed.
+/// The watcher lives in this task, so aborting it stops the OS watch.
+async fn watch_folder<R: Runtime>(app: AppHandle<R>, def: FolderDef) {
+    let mode = if def.recursive {
+        notify::RecursiveMode::Recursive
+    } else {
+        notify::RecursiveMode::NonRecursive
+    };
+    loop {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            // Reads (including the rescan's own) would otherwise loop back as changes.
+            if res.as_ref().is_ok_and(|e| !e.kind.is_access()) {
+                let _ = tx.send(());
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                log::error("folder", "folder watcher init failed")
+                    .field("error", err)
+                    .emit();
+                return;
+            }
+        };
+        let armed = watcher.watch(Path::new(&def.path), mode);
+        // Scanned even when the watch failed, so a missing directory reports `missing`.
+        if !scan_and_publish(&app, &def).await {
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        }
+        if let Err(err) = armed {
+            log::warn("folder", "folder watch failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        }
+        while rx.recv().await.is_some() {
+            settle(&mut rx, SETTLE, MAX_SETTLE).await;
+            if !scan_and_publish(&app, &def).await {
+                // The directory itself went away; drop the watch and wait for it to return.
+                break;
+            }
+        }
+        drop(watcher);
+        tokio::time::sleep(MISSING_RETRY).await;
+    }
+}
+
+/// The supervisor: one watch task per folder, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_folders<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<FolderWatch> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .folders
+                .clone()
+        },
+        |f| tauri::async_runtime::spawn(watch_folder(app.clone(), f)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/folders.json` and restart the changed watches. Studio-window-guarded; any
+/// invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_folder_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, FolderWatch>,
+    folders: Vec<FolderDef>,
+) -> Result<Vec<FolderDef>, String> {
+    if window.label() != "studio" {
+        return Err("save_folder_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(FolderConfig { folders });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.folders.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured folder watches.
+#[tauri::command]
+pub fn folder_config_status(state: State<'_, FolderWatch>) -> Vec<FolderDef> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .folders
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    fn def(path: &Path) -> FolderDef {
+        FolderDef {
+            name: "dl".into(),
+            path: path.to_string_lossy().into_owned(),
+            pattern: Some("*.PART".into()),
+            recursive: false,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn summary_tracks_totals_newest_and_matches() {
+        let mut s = Summary::default();
+        s.add("a.iso", 100, 5, Some("*.part"));
+        s.add("sub/b.part", 20, 9, Some("*.part"));
+        s.add("c.PART", 1, 7, Some("*.part"));
+        assert_eq!(s.count, 3);
+        assert_eq!(s.size, 121);
+        assert_eq!(s.newest, Some(("sub/b.part".into(), 9)));
+        assert_eq!(s.matched, 2);
+        // No pattern: nothing matches, and the sensor isn't published.
+        let ids: Vec<String> = s
+            .samples(
+                &FolderDef {
+                    pattern: None,
+                    ..def(Path::new("/x"))
+                },
+                1,
+            )
+            .into_iter()
+            .map(|s| s.sensor)
+            .collect();
+        assert_eq!(
+            ids,
+            ["count", "size", "newest", "mtime", "status"].map(|k| format!("folder.dl.{k}"))
+        );
+    }
+
+    #[test]
+    fn an_empty_folder_clears_newest_and_mtime() {
+        let out = Summary::default().samples(&def(Path::new("/x")), 1);
+        let newest = out.iter().find(|s| s.sensor == "folder.dl.newest").unwrap();
+        assert!(matches!(&newest.value, SensorValue::Text(t) if t.is_empty()));
+        let mtime = out.iter().find(|s| s.sensor == "folder.dl.mtime").unwrap();
+        assert!(matches!(mtime.value, SensorValue::Scalar(v) if v == 0.0));
+    }
+
+    #[test]
+    fn scan_counts_files_respecting_recursion_and_hidden() {
+        let dir = std::env::temp_dir().join(format!("widgetsack-folder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("movie.mkv.part"), [0u8; 10]).unwrap();
+        std::fs::write(dir.join(".DS_Store"), [0u8; 3]).unwrap();
+        std::fs::write(dir.join("sub").join("nested.part"), [0u8; 5]).unwrap();
+
+        let flat = scan(&def(&dir)).unwrap();
+        assert_eq!((flat.count, flat.size, flat.matched), (1, 10, 1));
+        assert_eq!(flat.newest.unwrap().0, "movie.mkv.part");
+
+        let deep = scan(&FolderDef {
+            recursive: true,
+            hidden: true,
+            ..def(&dir)
+        })
+        .unwrap();
+        assert_eq!((deep.count, deep.size, deep.matched), (3, 18, 2));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(scan(&def(&dir)).is_err());
+    }
+
+    #[test]
+    fn normalize_requires_absolute_paths() {
+        let abs = std::env::temp_dir();
+        let (cfg, errors) = normalize_config(FolderConfig {
+            folders: vec![
+                FolderDef {
+                    name: "My Downloads".into(),
+                    pattern: Some(" ".into()),
+                    ..def(&abs)
+                },
+                FolderDef {
+                    name: "rel".into(),
+                    path: "downloads".into(),
+                    ..def(&abs)
+                },
+                FolderDef {
+                    name: "my downloads".into(),
+                    ..def(&abs)
+                },
+            ],
+        });
+        assert_eq!(errors.len(), 2);
+        assert_eq!(cfg.folders[0].name, "my_downloads");
+        assert_eq!(cfg.folders[0].pattern, None);
+    }
+}
@@ -0,0 +1,670 @@
+//! Git repository status source — the branch and working-tree state of local repos for a
+//! developer's overlay. Each configured repo's git directory is watched with the same `notify`
+//! watcher folder.rs uses, and `git` is re-run when it changes (a commit, checkout, `add`, fetch,
+//! stash, or the index refresh editors do constantly) — no polling. Publishes:
+//!   `git.<name>.branch`          the checked-out branch (the short commit hash when detached)
+//!   `git.<name>.upstream`        its upstream (`origin/main`; empty when none)
+//!   `git.<name>.ahead` / `.behind`  commits ahead of / behind the upstream, as of the last fetch
+//!   `git.<name>.staged`          paths with staged changes
+//!   `git.<name>.dirty`           tracked paths with unstaged changes
+//!   `git.<name>.untracked`       untracked paths (respecting .gitignore)
+//!   `git.<name>.conflicts`       unmerged paths
+//!   `git.<name>.stash`           stash entries
+//!   `git.<name>.commit.subject`  HEAD's subject line
+//!   `git.<name>.commit.hash`     HEAD's short hash
+//!   `git.<name>.commit.age`      seconds since HEAD was committed (re-sent every `AGE_TICK`)
+//!   `git.<name>.status`          `ok`, or `missing` while the path isn't a readable repo
+//!
+//! Configured in `plugins/git.json`:
+//! `{ "repos": [{ "name": "widget", "path": "C:\\code\\nowplaying-widget" },
+//!              { "name": "site", "path": "D:\\site", "worktree": true }] }`
+//! Edits to tracked files surface with the next index write; `worktree` also watches the working
+//! tree so they show up immediately (at the cost of a `git status` per settled burst of writes);
+//! writes under the usual build/dependency directories (`target/`, `node_modules/`, … — see
+//! `BUILD_DIRS`) are ignored, so a build doesn't keep re-running it. Runs the `git` on PATH with
+//! `--no-optional-locks`, so our own `status` never writes the index and re-triggers the watch.
+//! Bursts are coalesced into one refresh after `SETTLE` (at most `MAX_SETTLE`, so a long burst
+//! still refreshes); a missing repo, or a watcher the OS won't create, is retried every
+//! `MISSING_RETRY`. Not demand-gated — it's idle until something changes. The porcelain
+//! parser and the samples are unit-tested.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use notify::Watcher;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::log;
+use crate::sensors::{SensorSample, id_segment};
+use crate::supervisor::{Supervised, settle, supervise};
+
+/// Quiet period after the last event before refreshing.
+const SETTLE: Duration = Duration::from_millis(500);
+/// Longest a burst is coalesced before re-reading anyway.
+const MAX_SETTLE: Duration = Duration::from_millis(2_500);
+/// How often a missing (or unwatchable) repo is looked for again.
+const MISSING_RETRY: Duration = Duration::from_secs(30);
+/// How often `commit.age` is re-sent between refreshes.
+const AGE_TICK: Duration = Duration::from_secs(60);
+/// A hung `git` (a network filesystem, a credential prompt) is killed after this.
+const GIT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Worktree directories whose churn is build output or dependencies, nearly always git-ignored.
+/// Checking `git check-ignore` per event would cost a process spawn for each write of a build.
+const BUILD_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    ".next",
+    ".svelte-kit",
+    "__pycache__",
+    ".venv",
+    ".gradle",
+];
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One watched repository.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GitRepoDef {
+    pub name: String,
+    /// The working tree (or any directory inside it).
+    pub path: String,
+    /// Also watch the working tree, so unstaged edits show up without an index write.
+    #[serde(default)]
+    pub worktree: bool,
+}
+
+impl Supervised for GitRepoDef {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/git.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GitConfig {
+    #[serde(default)]
+    pub repos: Vec<GitRepoDef>,
+}
+
+/// Managed state: the live definitions plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct GitWatch {
+    config: Mutex<GitConfig>,
+    generation: AtomicU64,
+}
+
+impl GitWatch {
+    fn replace(&self, cfg: GitConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("git.json"))
+}
+
+pub fn load_git_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<GitConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_git_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("git", "skipping git repo")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<GitWatch>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("git", "failed to read git.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, reject duplicates and relative paths.
+fn normalize_config(cfg: GitConfig) -> (GitConfig, Vec<String>) {
+    let mut kept: Vec<GitRepoDef> = Vec::new();
+    let mut errors = Vec::new();
+    for r in cfg.repos {
+        let Some(name) = id_segment(&r.name) else {
+            errors.push(format!("`{}`: invalid name", r.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let path = r.path.trim().to_string();
+        if !Path::new(&path).is_absolute() {
+            errors.push(format!("`{name}`: path must be absolute"));
+            continue;
+        }
+        kept.push(GitRepoDef { name, path, ..r });
+    }
+    (GitConfig { repos: kept }, errors)
+}
+
+// ---- parsing (pure) ----
+
+/// `git status --porcelain=v2 --branch`, summarized.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepoStatus {
+    pub branch: String,
+    pub upstream: String,
+    pub ahead: u64,
+    pub behind: u64,
+    pub staged: u64,
+    pub dirty: u64,
+    pub untracked: u64,
+    pub conflicts: u64,
+}
+
+/// Parse porcelain v2 output. Ordinary (`1`) and rename/copy (`2`) lines carry an `XY` pair —
+/// index then worktree, `.` meaning unchanged; `u` lines are conflicts, `?` untracked.
+fn parse_status(out: &str) -> RepoStatus {
+    let mut s = RepoStatus::default();
+    let mut oid = "";
+    for line in out.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" => oid = value,
+                "branch.head" => s.branch = value.to_string(),
+                "branch.upstream" => s.upstream = value.to_string(),
+                "branch.ab" => {
+                    for part in value.split_whitespace() {
+                        if let Some(n) = part.strip_prefix('+') {
+                            s.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            s.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let mut fields = line.splitn(3, ' ');
+        match (fields.next(), fields.next()) {
+            (Some("1" | "2"), Some(xy)) => {
+                let mut xy = xy.chars();
+                if xy.next().is_some_and(|c| c != '.') {
+                    s.staged += 1;
+                }
+                if xy.next().is_some_and(|c| c != '.') {
+                    s.dirty += 1;
+                }
+            }
+            (Some("u"), _) => s.conflicts += 1,
+            (Some("?"), _) => s.untracked += 1,
+            _ => {}
+        }
+    }
+    if s.branch == "(detached)" && oid.len() >= 7 {
+        s.branch = oid[..7].to_string();
+    }
+    s
+}
+
+/// HEAD as `git log -1 --format=%h%x00%ct%x00%s`: short hash, commit time (Unix s), subject.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub time_ms: i64,
+    pub subject: String,
+}
+
+fn parse_commit(out: &str) -> Option<Commit> {
+    let mut parts = out.trim_end_matches(['\r', '\n']).splitn(3, '\0');
+    let hash = parts.next()?.to_string();
+    let time: i64 = parts.next()?.trim().parse().ok()?;
+    Some(Commit {
+        hash,
+        time_ms: time * 1000,
+        subject: parts.next().unwrap_or("").to_string(),
+    })
+}
+
+fn age_sample(name: &str, commit: &Commit, ts: u64) -> SensorSample {
+    let age = (ts as i64 - commit.time_ms).max(0) / 1000;
+    SensorSample::scalar(format!("git.{name}.commit.age"), ts, age as f64)
+}
+
+/// The sensors for one refresh. A repo with no commits yet has no `commit.*`.
+fn samples(
+    name: &str,
+    status: &RepoStatus,
+    commit: Option<&Commit>,
+    stash: u64,
+    ts: u64,
+) -> Vec<SensorSample> {
+    let base = format!("git.{name}");
+    let mut out = vec![
+        SensorSample::text(format!("{base}.branch"), ts, status.branch.clone()),
+        SensorSample::text(format!("{base}.upstream"), ts, status.upstream.clone()),
+    ];
+    for (key, n) in [
+        ("ahead", status.ahead),
+        ("behind", status.behind),
+        ("staged", status.staged),
+        ("dirty", status.dirty),
+        ("untracked", status.untracked),
+        ("conflicts", status.conflicts),
+        ("stash", stash),
+    ] {
+        out.push(SensorSample::scalar(format!("{base}.{key}"), ts, n as f64));
+    }
+    if let Some(c) = commit {
+        out.push(SensorSample::text(
+            format!("{base}.commit.subject"),
+            ts,
+            c.subject.clone(),
+        ));
+        out.push(SensorSample::text(
+            format!("{base}.commit.hash"),
+            ts,
+            c.hash.clone(),
+        ));
+        out.push(age_sample(name, c, ts));
+    }
+    out.push(SensorSample::text(format!("{base}.status"), ts, "ok"));
+    out
+}
+
+/// Whether a watch event can change what we report: object-store writes and lock files are the
+/// noise around a commit or checkout, whose ref/index renames follow anyway; with a `worktree`
+/// root, so are writes under one of its `BUILD_DIRS` (outside `.git`, where `refs/heads/build`
+/// is a branch).
+fn relevant(paths: &[PathBuf], worktree: Option<&Path>) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|p| {
+            let lock = p.extension().is_some_and(|e| e == "lock");
+            // `objects/3f/…`, `objects/pack/…`
+            let object = p
+                .ancestors()
+                .skip(1)
+                .take(2)
+                .any(|a| a.file_name().is_some_and(|n| n == "objects"));
+            let build = worktree
+                .and_then(|root| p.strip_prefix(root).ok())
+                .is_some_and(|rel| {
+                    !rel.starts_with(".git")
+                        && rel
+                            .components()
+                            .any(|c| BUILD_DIRS.iter().any(|d| c.as_os_str() == *d))
+                });
+            !lock && !object && !build
+        })
+}
+
+// ---- git ----
+
+/// Run `git --no-optional-locks -C <dir> <args>`; stdout on success, else the first stderr line.
+async fn git(dir: &str, args: &[&str]) -> Result<String, String> {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    {
+        // CREATE_NO_WINDOW, as in cmdsource.rs.
+        cmd.creation_flags(0x0800_0000);
+    }
+    let child = cmd.spawn().map_err(|e| format!("git: {e}"))?;
+    let output = tokio::time::timeout(GIT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| "git timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or("git failed").to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The directories to watch: the git dir, plus the common dir when it differs (a linked
+/// worktree keeps its refs and stash there).
+async fn git_dirs(path: &str) -> Result<Vec<PathBuf>, String> {
+    let out = git(
+        path,
+        &["rev-parse", "--absolute-git-dir", "--git-common-dir"],
+    )
+    .await?;
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for line in out.lines().filter(|l| !l.is_empty()) {
+        let dir = Path::new(path).join(line);
+        let dir = dir.canonicalize().unwrap_or(dir);
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    Ok(dirs)
+}
+
+/// Re-run `git` and publish. Returns HEAD (for the age ticks), or `Err` when the repo can't be
+/// read (`status` is then `missing`).
+async fn refresh<R: Runtime>(app: &AppHandle<R>, def: &GitRepoDef) -> Result<Option<Commit>, ()> {
+    let status = match git(&def.path, &["status", "--porcelain=v2", "--branch"]).await {
+        Ok(out) => parse_status(&out),
+        Err(err) => {
+            log::warn("git", "git status failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            let status = format!("git.{}.status", def.name);
+            let _ = bus::publish(app, &[SensorSample::text(status, now_ms(), "missing")]);
+            return Err(());
+        }
+    };
+    // Both fail harmlessly on an empty repo / one that has never stashed.
+    let commit = git(&def.path, &["log", "-1", "--format=%h%x00%ct%x00%s"])
+        .await
+        .ok()
+        .and_then(|out| parse_commit(&out));
+    let stash = git(
+        &def.path,
+        &["rev-list", "--walk-reflogs", "--count", "refs/stash"],
+    )
+    .await
+    .ok()
+    .and_then(|out| out.trim().parse().ok())
+    .unwrap_or(0);
+    let batch = samples(&def.name, &status, commit.as_ref(), stash, now_ms());
+    let _ = bus::publish(app, &batch);
+    Ok(commit)
+}
+
+/// Watch one repo until aborted: refresh once, then after each settled burst of relevant events,
+/// re-sending `commit.age` every `AGE_TICK` in between. The watcher lives in this task, so
+/// aborting it stops the OS watch.
+async fn watch_repo<R: Runtime>(app: AppHandle<R>, def: GitRepoDef) {
+    loop {
+        let mut targets = match git_dirs(&def.path).await {
+            Ok(dirs) => dirs,
+            Err(err) => {
+                log::warn("git", "not a git repository")
+                    .field("name", def.name.clone())
+                    .field("error", err)
+                    .emit();
+                let status = format!("git.{}.status", def.name);
+                let _ = bus::publish(&app, &[SensorSample::text(status, now_ms(), "missing")]);
+                tokio::time::sleep(MISSING_RETRY).await;
+                continue;
+            }
+        };
+        if def.worktree {
+            // The worktree watch covers a `.git` inside it.
+            let root = PathBuf::from(&def.path);
+            targets.retain(|d| !d.starts_with(&root));
+            targets.push(root);
+        }
+        let Ok(mut commit) = refresh(&app, &def).await else {
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let worktree = def.worktree.then(|| PathBuf::from(&def.path));
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(e) = res
+                && !e.kind.is_access()
+                && relevant(&e.paths, worktree.as_deref())
+            {
+                let _ = tx.send(());
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                // Out of OS watch handles, say; the values published above stay, and this retries.
+                log::error("git", "git watcher init failed")
+                    .field("name", def.name.clone())
+                    .field("error", err)
+                    .emit();
+                tokio::time::sleep(MISSING_RETRY).await;
+                continue;
+            }
+        };
+        let watched = targets.iter().try_for_each(|dir| {
+            watcher
+                .watch(dir, notify::RecursiveMode::Recursive)
+                .map_err(|e| format!("{}: {e}", dir.display()))
+        });
+        if let Err(err) = watched {
+            log::warn("git", "git watch failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        }
+        loop {
+            tokio::select! {
+                ev = rx.recv() => {
+                    if ev.is_none() {
+                        break;
+                    }
+                    settle(&mut rx, SETTLE, MAX_SETTLE).await;
+                    match refresh(&app, &def).await {
+                        Ok(c) => commit = c,
+                        // The repo went away; drop the watch and wait for it to return.
+                        Err(()) => break,
+                    }
+                }
+                _ = tokio::time::sleep(AGE_TICK) => {
+                    if let Some(c) = &commit {
+                        let _ = bus::publish(&app, &[age_sample(&def.name, c, now_ms())]);
+                    }
+                }
+            }
+        }
+        drop(watcher);
+        tokio::time::sleep(MISSING_RETRY).await;
+    }
+}
+
+/// The supervisor: one watch task per repo, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_git<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<GitWatch> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .repos
+                .clone()
+        },
+        |r| tauri::async_runtime::spawn(watch_repo(app.clone(), r)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/git.json` and restart the changed watches. Studio-window-guarded; any
+/// invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_git_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, GitWatch>,
+    repos: Vec<GitRepoDef>,
+) -> Result<Vec<GitRepoDef>, String> {
+    if window.label() != "studio" {
+        return Err("save_git_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(GitConfig { repos });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.repos.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured repos.
+#[tauri::command]
+pub fn git_config_status(state: State<'_, GitWatch>) -> Vec<GitRepoDef> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .repos
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+# branch.oid 3f2a9c1d0b7e6f5a4c3b2a1908f7e6d5c4b3a291
+# branch.head main
+# branch.upstream origin/main
+# branch.ab +2 -5
+1 M. N... 100644 100644 100644 aaaa bbbb src/main.rs
+1 .M N... 100644 100644 100644 aaaa bbbb README.md
+1 MM N... 100644 100644 100644 aaaa bbbb Cargo.toml
+2 R. N... 100644 100644 100644 aaaa bbbb R100 new.rs\told.rs
+u UU N... 100644 100644 100644 100644 aaaa bbbb cccc conflict.rs
+? notes.txt
+? scratch/
+! target/
+";
+
+    #[test]
+    fn porcelain_counts_branch_and_changes() {
+        let s = parse_status(PORCELAIN);
+        assert_eq!(
+            s,
+            RepoStatus {
+                branch: "main".into(),
+                upstream: "origin/main".into(),
+                ahead: 2,
+                behind: 5,
+                staged: 3,
+                dirty: 2,
+                untracked: 2,
+                conflicts: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn detached_and_fresh_repos() {
+        let detached = parse_status(
+            "# branch.oid 3f2a9c1d0b7e6f5a4c3b2a1908f7e6d5c4b3a291\n# branch.head (detached)\n",
+        );
+        assert_eq!(detached.branch, "3f2a9c1");
+        assert_eq!((detached.upstream.as_str(), detached.ahead), ("", 0));
+        let fresh = parse_status("# branch.oid (initial)\n# branch.head main\n? a.txt\n");
+        assert_eq!((fresh.branch.as_str(), fresh.untracked), ("main", 1));
+    }
+
+    #[test]
+    fn commit_line_and_samples() {
+        let c = parse_commit("3f2a9c1\u{0}1760000000\u{0}Fix the thing: really\n").unwrap();
+        assert_eq!(c.hash, "3f2a9c1");
+        assert_eq!(c.time_ms, 1_760_000_000_000);
+        assert_eq!(c.subject, "Fix the thing: really");
+        assert!(parse_commit("").is_none());
+
+        let s = samples(
+            "w",
+            &parse_status(PORCELAIN),
+            Some(&c),
+            3,
+            1_760_000_090_000,
+        );
+        let get = |id: &str| {
+            let v = serde_json::to_value(s.iter().find(|x| x.sensor == id).unwrap()).unwrap();
+            v["value"]["value"].clone()
+        };
+        assert_eq!(get("git.w.branch"), "main");
+        assert_eq!(get("git.w.behind"), 5.0);
+        assert_eq!(get("git.w.stash"), 3.0);
+        assert_eq!(get("git.w.commit.age"), 90.0);
+        assert_eq!(get("git.w.status"), "ok");
+        // No commits yet: no `commit.*` at all.
+        let empty = samples("w", &RepoStatus::default(), None, 0, 0);
+        assert!(!empty.iter().any(|x| x.sensor.starts_with("git.w.commit.")));
+    }
+
+    #[test]
+    fn object_writes_and_locks_are_noise() {
+        let git = Path::new("/r/.git");
+        assert!(relevant(&[git.join("index")], None));
+        assert!(relevant(
+            &[git.join("refs").join("heads").join("main")],
+            None
+        ));
+        assert!(!relevant(&[git.join("index.lock")], None));
+        assert!(!relevant(
+            &[git.join("objects").join("3f").join("2a9c1d")],
+            None
+        ));
+        // A rename reports both ends; one relevant path is enough.
+        assert!(relevant(&[git.join("HEAD.lock"), git.join("HEAD")], None));
+    }
+
+    #[test]
+    fn build_output_in_the_worktree_is_noise() {
+        let root = Path::new("/r");
+        assert!(relevant(&[root.join("src").join("main.rs")], Some(root)));
+        assert!(!relevant(
+            &[root.join("target").join("debug").join("app")],
+            Some(root)
+        ));
+        assert!(!relevant(
+            &[root.join("web").join("node_modules").join("x.js")],
+            Some(root)
+        ));
+        // A branch called `build` is a ref, not build output.
+        let branch = root.join(".git").join("refs").join("heads").join("build");
+        assert!(relevant(std::slice::from_ref(&branch), Some(root)));
+        // Without a worktree watch only the git dir is watched, and nothing is filtered by name.
+        assert!(relevant(&[root.join("target").join("x")], None));
+    }
+}
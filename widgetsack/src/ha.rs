@@ -22,12 +22,14 @@ use futures_util::{SinkExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tauri::async_runtime::{JoinHandle, Mutex};
-use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tauri::{AppHandle, Manager, Runtime, State};
 use tokio_tungstenite::tungstenite::{Error as WsError, Message};
 use tokio_tungstenite::{connect_async, connect_async_tls_with_config, Connector};
 
+use crate::bus;
+use crate::health;
 use crate::log;
-use crate::sensors::{SensorSample, SensorValue, TELEMETRY_EVENT};
+use crate::sensors::{SensorSample, SensorValue};
 
 type BoxErr = Box<dyn std::error::Error + Send + Sync>;
 
@@ -213,6 +215,7 @@ fn state_to_samples(entity_id: &str, new_state: &Value, ts_ms: u64) -> Option<Ve
         sensor: base.clone(),
         ts_ms,
         value: SensorValue::Json(new_state.clone()),
+        stale: false,
     }];
     if let Some(s) = new_state["state"].as_str()
         && let Ok(n) = s.parse::<f64>()
@@ -293,12 +296,14 @@ fn entity_reg_from(v: &Value) -> Option<HaEntityReg> {
 /// telemetry event (a Text meter bound to `ha.status` shows it). Single status transport —
 /// no separate bridge event.
 fn emit_status<R: Runtime>(app: &AppHandle<R>, status: &str) {
+    health::report_status(app, "ha", status);
     let batch = vec![SensorSample {
         sensor: "ha.status".to_string(),
         ts_ms: now_ms(),
         value: SensorValue::Text(status.to_string()),
+        stale: false,
     }];
-    let _ = app.emit(TELEMETRY_EVENT, &batch);
+    let _ = bus::publish(app, &batch);
 }
 
 /// Prime every entity from a `get_states` snapshot so widgets render immediately.
@@ -316,7 +321,7 @@ fn emit_snapshot<R: Runtime>(app: &AppHandle<R>, states: &Value) {
         }
     }
     if !batch.is_empty() {
-        let _ = app.emit(TELEMETRY_EVENT, &batch);
+        let _ = bus::publish(app, &batch);
     }
 }
 
@@ -485,7 +490,7 @@ async fn connect_and_stream<R: Runtime>(
                         if let Some(eid) = data["entity_id"].as_str()
                             && let Some(batch) = state_to_samples(eid, &data["new_state"], now_ms())
                         {
-                            let _ = app.emit(TELEMETRY_EVENT, &batch);
+                            let _ = bus::publish(app, &batch);
                         }
                     }
                     _ => {}
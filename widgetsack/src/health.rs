@@ -0,0 +1,328 @@
+//! Source health and per-sensor staleness. Without it, a disconnected Home Assistant leaves every
+//! `ha.*` value frozen on screen with only `ha.status` hinting that anything is wrong.
+//!
+//! Two signals mark a sensor stale:
+//! - its SOURCE declares itself down: ha.rs / mqtt.rs / stocks.rs / weather.rs call
+//!   `report_status` from their status emitters, and every sensor owned by that source
+//!   (`catalog::source_of`) goes stale — `connecting` changes nothing, since a stocks remount or a
+//!   reconnect attempt isn't an outage;
+//! - a PERIODIC sensor (the system feed, stocks, derived, synthetic — not the event-driven
+//!   HA/MQTT/folder/tail/git/tasks/automation ids, which can sit unchanged for hours) misses its
+//!   expected interval: the interval is learned from its own update gaps, and the sensor is stale
+//!   once `STALE_FACTOR` gaps (at least `MIN_STALE_MS`) pass without an update.
+//!
+//! On the transition the tracker re-publishes the sensor's last value with `stale: true` (the same
+//! flag lastknown.rs uses), so the client greys it out; its next live sample clears it. Backend
+//! consumers ignore stale samples. `source_health` reports each source for the studio. The pure
+//! seam (`Tracker`) is unit-tested.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus;
+use crate::catalog::source_of;
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue};
+
+/// How often staleness is evaluated.
+const TICK_MS: u64 = 1000;
+
+/// A periodic sensor is stale after this many expected intervals without an update…
+const STALE_FACTOR: f64 = 3.0;
+
+/// …and never sooner than this, so a 1 Hz sensor survives a briefly busy system loop.
+const MIN_STALE_MS: u64 = 5_000;
+
+/// Weight of the newest gap in the learned interval.
+const INTERVAL_ALPHA: f64 = 0.3;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Managed state: each source's declared liveness (absent = never declared = live) and the
+/// latest per-source summary for `source_health`.
+#[derive(Default)]
+pub struct Health {
+    live: Mutex<HashMap<String, bool>>,
+    summary: Mutex<Vec<SourceHealth>>,
+}
+
+/// One source's health, as the studio shows it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceHealth {
+    pub source: String,
+    pub live: bool,
+    pub sensors: usize,
+    pub stale: usize,
+    pub last_update_ms: Option<u64>,
+}
+
+/// Record a source's status (called from each source's status emitter): `connected` is up, an
+/// error or disconnect is down, and `connecting` leaves the previous verdict in place.
+pub fn report_status<R: Runtime>(app: &AppHandle<R>, source: &str, status: &str) {
+    let Some(live) = liveness(status) else {
+        return;
+    };
+    if let Some(health) = app.try_state::<Health>() {
+        health
+            .live
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(source.to_string(), live);
+    }
+}
+
+/// What a status says about liveness; `None` for a transitional one.
+fn liveness(status: &str) -> Option<bool> {
+    match status {
+        "connecting" => None,
+        s => Some(s == "connected"),
+    }
+}
+
+/// Whether a source's sensors are expected to update on a cadence. HA, MQTT, folder watches, log
+/// tails, git repos, task lists and automation LLM replies push on change only.
+fn periodic(source: &str) -> bool {
+    !matches!(
+        source,
+        "ha" | "mqtt" | "folder" | "tail" | "git" | "tasks" | "automations"
+    )
+}
+
+// ---- tracker (pure) ----
+
+#[derive(Debug)]
+struct Entry {
+    source: &'static str,
+    value: SensorValue,
+    last_ms: u64,
+    /// Learned update interval; `None` until a second update arrives.
+    interval_ms: Option<f64>,
+    stale: bool,
+}
+
+/// Per-sensor freshness across every source.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    sensors: HashMap<String, Entry>,
+}
+
+impl Tracker {
+    /// Record a published batch. Stale samples (restored or re-sent) don't count as updates.
+    pub fn observe(&mut self, batch: &[SensorSample]) {
+        for s in batch.iter().filter(|s| !s.stale) {
+            match self.sensors.get_mut(&s.sensor) {
+                Some(e) => {
+                    let gap = s.ts_ms.saturating_sub(e.last_ms) as f64;
+                    if gap > 0.0 {
+                        e.interval_ms = Some(match e.interval_ms {
+                            None => gap,
+                            Some(i) => i + INTERVAL_ALPHA * (gap - i),
+                        });
+                    }
+                    e.value = s.value.clone();
+                    e.last_ms = s.ts_ms;
+                    e.stale = false;
+                }
+                None => {
+                    self.sensors.insert(
+                        s.sensor.clone(),
+                        Entry {
+                            source: source_of(&s.sensor),
+                            value: s.value.clone(),
+                            last_ms: s.ts_ms,
+                            interval_ms: None,
+                            stale: false,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Mark what has gone stale at `now` given each source's liveness (`live(source)`), returning
+    /// the samples to re-publish with `stale: true` — only sensors that just turned stale.
+    pub fn sweep(&mut self, now: u64, live: impl Fn(&str) -> bool) -> Vec<SensorSample> {
+        let mut out = Vec::new();
+        for (id, e) in &mut self.sensors {
+            if e.stale {
+                continue;
+            }
+            let overdue = periodic(e.source)
+                && e.interval_ms.is_some_and(|i| {
+                    let limit = (i * STALE_FACTOR).max(MIN_STALE_MS as f64);
+                    now.saturating_sub(e.last_ms) as f64 > limit
+                });
+            // A source's own status id stays live: it is how the disconnect is reported.
+            let source_down = !live(e.source) && *id != format!("{}.status", e.source);
+            if overdue || source_down {
+                e.stale = true;
+                out.push(SensorSample {
+                    sensor: id.clone(),
+                    ts_ms: e.last_ms,
+                    value: e.value.clone(),
+                    stale: true,
+                });
+            }
+        }
+        out.sort_by(|a, b| a.sensor.cmp(&b.sensor));
+        out
+    }
+
+    /// Per-source counts, sorted by source.
+    pub fn summary(&self, live: impl Fn(&str) -> bool) -> Vec<SourceHealth> {
+        let mut by_source: HashMap<&str, SourceHealth> = HashMap::new();
+        for e in self.sensors.values() {
+            let h = by_source.entry(e.source).or_insert_with(|| SourceHealth {
+                source: e.source.to_string(),
+                live: live(e.source),
+                sensors: 0,
+                stale: 0,
+                last_update_ms: None,
+            });
+            h.sensors += 1;
+            h.stale += usize::from(e.stale);
+            h.last_update_ms = h.last_update_ms.max(Some(e.last_ms));
+        }
+        let mut out: Vec<SourceHealth> = by_source.into_values().collect();
+        out.sort_by(|a, b| a.source.cmp(&b.source));
+        out
+    }
+}
+
+// ---- task ----
+
+/// The health task: track every batch, sweep once a second, re-publish what just went stale.
+/// Paused while a recording replays (its timing isn't the live sources'). Runs until the bus
+/// closes (app exit).
+pub async fn run_health<R: Runtime>(app: AppHandle<R>) {
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let mut tracker = Tracker::default();
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => tracker.observe(&batch),
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("health", "health tracker fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticker.tick() => {
+                if bus::replaying(&app) {
+                    continue;
+                }
+                let state: State<Health> = app.state();
+                let live = state.live.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                let is_live = |source: &str| live.get(source).copied().unwrap_or(true);
+                let stale = tracker.sweep(now_ms(), is_live);
+                *state.summary.lock().unwrap_or_else(|e| e.into_inner()) = tracker.summary(is_live);
+                if !stale.is_empty() {
+                    let _ = bus::publish(&app, &stale);
+                }
+            }
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Each source's liveness and how many of its sensors are stale.
+#[tauri::command]
+pub fn source_health(state: State<'_, Health>) -> Vec<SourceHealth> {
+    state
+        .summary
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_live(_: &str) -> bool {
+        true
+    }
+
+    #[test]
+    fn connecting_keeps_the_previous_liveness() {
+        assert_eq!(liveness("connected"), Some(true));
+        assert_eq!(liveness("error"), Some(false));
+        assert_eq!(liveness("disconnected"), Some(false));
+        assert_eq!(liveness("connecting"), None);
+    }
+
+    #[test]
+    fn periodic_sensor_goes_stale_after_missing_its_interval() {
+        let mut t = Tracker::default();
+        for ts in [0, 1_000, 2_000, 3_000] {
+            t.observe(&[SensorSample::scalar("cpu.total", ts, 5.0)]);
+        }
+        assert!(t.sweep(6_000, all_live).is_empty());
+        let stale = t.sweep(8_001, all_live);
+        assert_eq!(stale.len(), 1);
+        assert!(stale[0].stale);
+        assert_eq!(stale[0].ts_ms, 3_000);
+        // Reported once, not every tick.
+        assert!(t.sweep(9_000, all_live).is_empty());
+        // A live sample revives it.
+        t.observe(&[SensorSample::scalar("cpu.total", 9_500, 6.0)]);
+        assert_eq!(t.summary(all_live)[0].stale, 0);
+    }
+
+    #[test]
+    fn slow_pollers_get_a_proportionally_longer_leash() {
+        let mut t = Tracker::default();
+        for ts in [0, 60_000, 120_000] {
+            t.observe(&[SensorSample::scalar("stocks.AAPL.price", ts, 1.0)]);
+        }
+        assert!(t.sweep(250_000, all_live).is_empty());
+        assert_eq!(t.sweep(300_001, all_live).len(), 1);
+    }
+
+    #[test]
+    fn event_driven_sources_only_go_stale_on_disconnect() {
+        let mut t = Tracker::default();
+        t.observe(&[
+            SensorSample::text("ha.light.desk", 0, "on"),
+            SensorSample::text("ha.status", 0, "connected"),
+        ]);
+        t.observe(&[SensorSample::text("ha.light.desk", 1_000, "off")]);
+        assert!(t.sweep(10_000_000, all_live).is_empty());
+
+        let down = |s: &str| s != "ha";
+        let stale = t.sweep(10_000_000, down);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].sensor, "ha.light.desk");
+        let summary = t.summary(down);
+        assert_eq!(summary.len(), 1);
+        assert!(!summary[0].live);
+        assert_eq!((summary[0].sensors, summary[0].stale), (2, 1));
+        assert_eq!(summary[0].last_update_ms, Some(1_000));
+    }
+
+    #[test]
+    fn stale_samples_are_not_updates() {
+        let mut t = Tracker::default();
+        t.observe(&[SensorSample {
+            stale: true,
+            ..SensorSample::scalar("mqtt.home/temp", 0, 20.0)
+        }]);
+        assert!(t.summary(all_live).is_empty());
+    }
+}
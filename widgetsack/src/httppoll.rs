@@ -0,0 +1,904 @@
+//! Generic HTTP/JSON poller — a declarative peer to stocks.rs / mqtt.rs / ha.rs. Each configured
+//! endpoint is fetched server-side on its interval and mapped, via JSONPath or regex extractors, to
+//! `http.<name>.<field>` samples on the telemetry bus, so any REST API on the LAN (router stats, a
+//! build server, Pi-hole) becomes widget data without writing a plugin package.
+//!
+//! Configured in `plugins/http.json`:
+//! `{ "endpoints": [{ "name": "pihole", "url": "http://pi.hole/admin/api.php?summaryRaw",
+//!    "headers": { "Authorization": "Bearer …" }, "interval_secs": 60,
+//!    "extract": { "blocked": "$.ads_blocked_today", "top": "$.top_ads[0].domain",
+//!                 "version": "regex:v(\\d+\\.\\d+)" } }] }`
+//! Extractor specs:
+//!   `$.a.b[0]['c d']`  a JSONPath subset — dot keys, quoted bracket keys, array indices (negative
+//!                      counts from the end); numbers → Scalar, booleans → 1/0, strings → Text,
+//!                      objects/arrays → Json
+//!   `regex:<pattern>`  matched against the raw body; the first capture group (or the whole match)
+//!                      → Scalar when it parses as a number, else Text
+//! Each endpoint also publishes `http.<name>.status` (`ok` / `error` / `HTTP 503`).
+//!
+//! Header VALUES are secrets like `HaConfig.token`: they stay in `http.json`, `http_config_status`
+//! returns only their names, and saving a header with a blank value keeps the stored one. The
+//! request body is treated the same way: the status says only whether there is one, a blank body
+//! on save keeps the stored one, and `clear_body` removes it. Stored values only carry over while
+//! the URL keeps its scheme, host and port; pointing an endpoint elsewhere needs them re-entered,
+//! so a renamed host never receives another host's `Authorization` header (the same applies to
+//! `http_test_endpoint`).
+//! Polling reuses stocks' demand gating (an endpoint idles until a window mounts one of its ids,
+//! unless `always` is set for backend consumers) and its exponential backoff on failures.
+//! Pure seams (`parse_path`, `Extractor`, `extract`, `merge_headers`, `merge_body`,
+//! `with_stored_secrets`) are unit-tested.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Poll cadence guardrails (seconds), clamped server-side like stocks'.
+const MIN_INTERVAL: u64 = 5;
+const MAX_INTERVAL: u64 = 86_400;
+/// Re-check the demand gate this often while an endpoint is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+/// Largest response body read (bytes) — a misconfigured URL pointing at a file download stops here.
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+fn default_interval() -> u64 {
+    60
+}
+fn default_timeout() -> u64 {
+    10
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One polled endpoint. Header values are secret (see module docs).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HttpEndpoint {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Request body (sent as-is; set a `Content-Type` header to match).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Set by the settings form to remove the stored body (a blank one keeps it). Never stored.
+    #[serde(default, skip_serializing)]
+    pub clear_body: bool,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+    /// Accept self-signed certificates (a LAN box), like `HaConfig.insecure`.
+    #[serde(default)]
+    pub insecure: bool,
+    /// Poll even when no window shows this endpoint's sensors (for derived/alerts/automations).
+    #[serde(default)]
+    pub always: bool,
+    /// Field → extractor spec; each field publishes `http.<name>.<field>`.
+    #[serde(default)]
+    pub extract: BTreeMap<String, String>,
+}
+
+impl Supervised for HttpEndpoint {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/http.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub endpoints: Vec<HttpEndpoint>,
+}
+
+/// What the webview learns about one endpoint: everything but the header values.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpEndpointStatus {
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    pub header_names: Vec<String>,
+    pub has_body: bool,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    pub insecure: bool,
+    pub always: bool,
+    pub extract: BTreeMap<String, String>,
+}
+
+impl From<&HttpEndpoint> for HttpEndpointStatus {
+    fn from(ep: &HttpEndpoint) -> Self {
+        HttpEndpointStatus {
+            name: ep.name.clone(),
+            url: ep.url.clone(),
+            method: ep.method.clone(),
+            header_names: ep.headers.keys().cloned().collect(),
+            has_body: ep.body.is_some(),
+            interval_secs: ep.interval_secs,
+            timeout_secs: ep.timeout_secs,
+            insecure: ep.insecure,
+            always: ep.always,
+            extract: ep.extract.clone(),
+        }
+    }
+}
+
+/// Managed state: the live endpoints plus a generation counter the supervisor polls, so a save
+/// restarts the pollers without restarting the app.
+#[derive(Default)]
+pub struct HttpPoll {
+    config: Mutex<HttpConfig>,
+    generation: AtomicU64,
+}
+
+impl HttpPoll {
+    fn replace(&self, cfg: HttpConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn http_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("http.json"))
+}
+
+pub fn load_http_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<HttpConfig>, String> {
+    let path = http_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed endpoints from disk; each rejected entry is logged (the studio save path
+/// rejects them outright instead).
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_http_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("http", "skipping http endpoint")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<HttpPoll>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("http", "failed to read http.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// A field name as a (possibly dotted) sensor-id tail: each segment slugged like a name.
+pub(crate) fn field_id(field: &str) -> Option<String> {
+    field
+        .split('.')
+        .map(id_segment)
+        .collect::<Option<Vec<_>>>()
+        .map(|segs| segs.join("."))
+}
+
+/// Slug names and fields, clamp the interval, and reject duplicates, bad URLs/methods and bad
+/// extractors. Returns the kept endpoints plus one message per rejected one.
+fn normalize_config(cfg: HttpConfig) -> (HttpConfig, Vec<String>) {
+    let mut kept: Vec<HttpEndpoint> = Vec::new();
+    let mut errors = Vec::new();
+    for ep in cfg.endpoints {
+        let Some(name) = id_segment(&ep.name) else {
+            errors.push(format!("`{}`: invalid name", ep.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        match normalize_endpoint(HttpEndpoint { name, ..ep }) {
+            Ok(ep) => kept.push(ep),
+            Err(err) => errors.push(err),
+        }
+    }
+    (HttpConfig { endpoints: kept }, errors)
+}
+
+fn normalize_endpoint(ep: HttpEndpoint) -> Result<HttpEndpoint, String> {
+    let name = ep.name.clone();
+    let url = ep.url.trim().to_string();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("`{name}`: url must be http:// or https://"));
+    }
+    let method = ep.method.trim().to_uppercase();
+    reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("`{name}`: bad method `{method}`"))?;
+    let mut extract = BTreeMap::new();
+    for (field, spec) in &ep.extract {
+        let id = field_id(field).ok_or_else(|| format!("`{name}`: invalid field `{field}`"))?;
+        if id == "status" {
+            return Err(format!("`{name}`: `status` is reserved"));
+        }
+        Extractor::parse(spec).map_err(|e| format!("`{name}.{id}`: {e}"))?;
+        extract.insert(id, spec.trim().to_string());
+    }
+    if extract.is_empty() {
+        return Err(format!("`{name}`: no extractors"));
+    }
+    Ok(HttpEndpoint {
+        url,
+        method,
+        interval_secs: ep.interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+        timeout_secs: ep.timeout_secs.clamp(1, 120),
+        extract,
+        ..ep
+    })
+}
+
+/// Carry stored header values over to a save that left them blank (the form never reads them
+/// back), matching header names case-insensitively. Blank headers with nothing stored are dropped.
+pub fn merge_headers(
+    new: BTreeMap<String, String>,
+    stored: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    new.into_iter()
+        .filter_map(|(k, v)| {
+            if !v.is_empty() {
+                return Some((k, v));
+            }
+            stored
+                .iter()
+                .find(|(sk, _)| sk.eq_ignore_ascii_case(&k))
+                .map(|(_, sv)| (k, sv.clone()))
+        })
+        .collect()
+}
+
+/// The body a save should store: none when `clear` is set, else the submitted one, or — when
+/// it's blank (the form never reads the body back) — the stored one.
+pub fn merge_body(new: Option<String>, stored: Option<&String>, clear: bool) -> Option<String> {
+    match new {
+        _ if clear => None,
+        Some(body) if !body.trim().is_empty() => Some(body),
+        _ => stored.cloned(),
+    }
+}
+
+/// Whether two URLs share scheme, host and port — the only case where stored secrets may follow
+/// an endpoint whose URL was edited.
+pub fn same_origin(a: &str, b: &str) -> bool {
+    match (reqwest::Url::parse(a.trim()), reqwest::Url::parse(b.trim())) {
+        (Ok(a), Ok(b)) => {
+            a.scheme() == b.scheme()
+                && a.host_str() == b.host_str()
+                && a.port_or_known_default() == b.port_or_known_default()
+        }
+        _ => false,
+    }
+}
+
+/// Fill `ep`'s blank header values and body from the stored endpoint of the same name
+/// (`merge_headers` / `merge_body`). Secrets only follow an unchanged origin: when the URL now
+/// points elsewhere, a blank value that would have been filled in is an error asking for it again.
+pub fn with_stored_secrets(
+    ep: HttpEndpoint,
+    stored: Option<&HttpEndpoint>,
+) -> Result<HttpEndpoint, String> {
+    let stored = match stored {
+        Some(old) if !same_origin(&old.url, &ep.url) => {
+            let blank_header = ep.headers.iter().any(|(k, v)| {
+                v.is_empty() && old.headers.keys().any(|sk| sk.eq_ignore_ascii_case(k))
+            });
+            let blank_body = !ep.clear_body
+                && old.body.is_some()
+                && ep.body.as_deref().is_none_or(|b| b.trim().is_empty());
+            if blank_header || blank_body {
+                return Err(format!(
+                    "`{}`: the URL now points at a different host — re-enter its header values \
+                     and body",
+                    ep.name
+                ));
+            }
+            None
+        }
+        other => other,
+    };
+    let empty = BTreeMap::new();
+    let headers = merge_headers(ep.headers, stored.map_or(&empty, |o| &o.headers));
+    let body = merge_body(ep.body, stored.and_then(|o| o.body.as_ref()), ep.clear_body);
+    Ok(HttpEndpoint {
+        headers,
+        body,
+        clear_body: false,
+        ..ep
+    })
+}
+
+// ---- extraction (pure) ----
+
+/// One step of a parsed JSONPath.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathStep {
+    Key(String),
+    Index(i64),
+}
+
+/// Parse the supported JSONPath subset: `$`, `.key`, `['key']` / `["key"]`, `[n]`.
+pub fn parse_path(src: &str) -> Result<Vec<PathStep>, String> {
+    let rest = src
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| format!("path must start with `$`: `{src}`"))?;
+    let mut steps = Vec::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                if key.is_empty() {
+                    return Err(format!("empty key in `{src}`"));
+                }
+                steps.push(PathStep::Key(key));
+            }
+            '[' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(format!("missing `]` in `{src}`")),
+                    }
+                }
+                let inner = inner.trim();
+                let quoted = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+                match quoted {
+                    Some(key) => steps.push(PathStep::Key(key.to_string())),
+                    None => steps.push(PathStep::Index(
+                        inner
+                            .parse()
+                            .map_err(|_| format!("bad index `[{inner}]` in `{src}`"))?,
+                    )),
+                }
+            }
+            other => return Err(format!("unexpected `{other}` in `{src}`")),
+        }
+    }
+    Ok(steps)
+}
+
+/// Walk `steps` into `root`. `None` when a key/index is missing.
+pub fn select<'a>(root: &'a Value, steps: &[PathStep]) -> Option<&'a Value> {
+    steps.iter().try_fold(root, |v, step| match step {
+        PathStep::Key(k) => v.get(k),
+        PathStep::Index(i) => {
+            let arr = v.as_array()?;
+            let idx = if *i < 0 { arr.len() as i64 + i } else { *i };
+            usize::try_from(idx).ok().and_then(|i| arr.get(i))
+        }
+    })
+}
+
+/// A parsed extractor spec (see module docs).
+#[derive(Debug)]
+pub enum Extractor {
+    Path(Vec<PathStep>),
+    Regex(Regex),
+}
+
+impl Extractor {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        match spec.strip_prefix("regex:") {
+            Some(pattern) => Regex::new(pattern)
+                .map(Extractor::Regex)
+                .map_err(|e| e.to_string()),
+            None => parse_path(spec).map(Extractor::Path),
+        }
+    }
+
+    /// The value this extractor pulls from a response (`json` is the body parsed, if it was JSON).
+    pub fn apply(&self, body: &str, json: Option<&Value>) -> Option<SensorValue> {
+        match self {
+            Extractor::Path(steps) => match select(json?, steps)? {
+                Value::Null => None,
+                Value::Number(n) => n.as_f64().map(SensorValue::Scalar),
+                Value::Bool(b) => Some(SensorValue::Scalar(if *b { 1.0 } else { 0.0 })),
+                Value::String(s) => Some(SensorValue::Text(s.clone())),
+                other => Some(SensorValue::Json(other.clone())),
+            },
+            Extractor::Regex(re) => {
+                let caps = re.captures(body)?;
+                let text = caps.get(1).or_else(|| caps.get(0))?.as_str().trim();
+                Some(match text.parse::<f64>() {
+                    Ok(n) if n.is_finite() => SensorValue::Scalar(n),
+                    _ => SensorValue::Text(text.to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// Map one response body to `http.<name>.<field>` samples. A field whose extractor finds nothing
+/// is skipped (the hub keeps its last value). Invalid specs were rejected at save/load.
+pub fn extract(
+    name: &str,
+    extractors: &[(String, Extractor)],
+    body: &str,
+    ts_ms: u64,
+) -> Vec<SensorSample> {
+    let json: Option<Value> = serde_json::from_str(body).ok();
+    extractors
+        .iter()
+        .filter_map(|(field, ex)| {
+            ex.apply(body, json.as_ref()).map(|value| SensorSample {
+                sensor: format!("http.{name}.{field}"),
+                ts_ms,
+                value,
+                stale: false,
+            })
+        })
+        .collect()
+}
+
+fn compile(ep: &HttpEndpoint) -> Vec<(String, Extractor)> {
+    ep.extract
+        .iter()
+        .filter_map(|(field, spec)| Extractor::parse(spec).ok().map(|ex| (field.clone(), ex)))
+        .collect()
+}
+
+// ---- polling ----
+
+fn status_sample(name: &str, status: &str) -> SensorSample {
+    SensorSample::text(format!("http.{name}.status"), now_ms(), status)
+}
+
+/// True while any window is consuming one of this endpoint's sensors. Default OFF before the first
+/// report, like `stocks_wanted`, so nothing hits the network before a widget exists.
+fn endpoint_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active: State<ActiveSensors> = app.state();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let prefix = format!("http.{name}.");
+    crate::sensors::any_wanted(&guard, |id| id.starts_with(&prefix))
+}
+
+/// A reqwest client with a per-request timeout, honouring the `insecure` self-signed opt-in the same
+/// way ha.rs does (cert AND hostname checks off). Shared with the Prometheus scraper.
+pub(crate) fn http_client(timeout_secs: u64, insecure: bool) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(timeout_secs));
+    if insecure {
+        builder = builder
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// One request: the body text on a 2xx, else an error naming the status.
+async fn fetch(client: &reqwest::Client, ep: &HttpEndpoint) -> Result<String, String> {
+    let method = reqwest::Method::from_bytes(ep.method.as_bytes()).map_err(|e| e.to_string())?;
+    let mut req = client.request(method, &ep.url);
+    for (k, v) in &ep.headers {
+        req = req.header(k.as_str(), v.as_str());
+    }
+    if let Some(body) = &ep.body {
+        req = req.body(body.clone());
+    }
+    let resp = req.send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    let bytes = read_capped(resp, MAX_BODY).await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Read a response body, giving up as soon as it passes `cap` bytes (or up front, when the
+/// declared length already does) — so an endpoint that turns out to be a large download is never
+/// buffered whole. Shared with prom.rs, calendar.rs and feed.rs; errors leave out the URL, which
+/// is a secret for a calendar feed.
+pub(crate) async fn read_capped(resp: reqwest::Response, cap: usize) -> Result<Vec<u8>, String> {
+    use futures_util::StreamExt;
+    if resp.content_length().is_some_and(|len| len > cap as u64) {
+        return Err(format!("response larger than {cap} bytes"));
+    }
+    let mut buf: Vec<u8> = Vec::new();
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let bytes = chunk.map_err(|e| e.without_url().to_string())?;
+        if buf.len() + bytes.len() > cap {
+            return Err(format!("response larger than {cap} bytes"));
+        }
+        buf.extend_from_slice(&bytes);
+    }
+    Ok(buf)
+}
+
+/// Poll one endpoint until aborted: demand-gated, with stocks' backoff (2× … 16× the interval on
+/// a run of failures, capped at 30 min).
+async fn poll_endpoint<R: Runtime>(app: AppHandle<R>, ep: HttpEndpoint) {
+    let client = match http_client(ep.timeout_secs, ep.insecure) {
+        Ok(c) => c,
+        Err(err) => {
+            log::warn("http", "client build failed")
+                .field("name", ep.name.clone())
+                .field("error", err)
+                .emit();
+            let _ = bus::publish(&app, &[status_sample(&ep.name, "error")]);
+            return;
+        }
+    };
+    let extractors = compile(&ep);
+    let interval = Duration::from_secs(ep.interval_secs);
+    let mut fails: u32 = 0;
+    loop {
+        if !ep.always && !endpoint_wanted(&app, &ep.name) {
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        match fetch(&client, &ep).await {
+            Ok(body) => {
+                fails = 0;
+                let mut batch = extract(&ep.name, &extractors, &body, now_ms());
+                batch.push(status_sample(&ep.name, "ok"));
+                let _ = bus::publish(&app, &batch);
+            }
+            Err(err) => {
+                fails = (fails + 1).min(5);
+                let status = if err.starts_with("HTTP ") {
+                    err.as_str()
+                } else {
+                    "error"
+                };
+                let _ = bus::publish(&app, &[status_sample(&ep.name, status)]);
+                log::warn("http", "fetch failed")
+                    .field("name", ep.name.clone())
+                    .field("error", err.clone())
+                    .emit();
+            }
+        }
+        let wait = if fails == 0 {
+            interval
+        } else {
+            interval
+                .saturating_mul(1u32 << fails.min(4))
+                .min(Duration::from_secs(1800))
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// The supervisor: one poll task per endpoint, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_http<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<HttpPoll> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .endpoints
+                .clone()
+        },
+        |ep| tauri::async_runtime::spawn(poll_endpoint(app.clone(), ep)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/http.json` and restart the changed pollers. Studio-window-guarded like the
+/// other plugin configs. Blank header values and a blank body keep the stored ones while the URL's
+/// origin is unchanged (`with_stored_secrets`); any invalid endpoint rejects the whole save with
+/// every problem listed.
+#[tauri::command]
+pub async fn save_http_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, HttpPoll>,
+    endpoints: Vec<HttpEndpoint>,
+) -> Result<Vec<HttpEndpointStatus>, String> {
+    if window.label() != "studio" {
+        return Err("save_http_config is only allowed from the studio window".into());
+    }
+    let stored = load_http_config(&app)?.unwrap_or_default();
+    let mut errors = Vec::new();
+    let endpoints = endpoints
+        .into_iter()
+        .filter_map(|ep| {
+            let slug = id_segment(&ep.name);
+            let old = stored
+                .endpoints
+                .iter()
+                .find(|s| Some(&s.name) == slug.as_ref());
+            with_stored_secrets(ep, old)
+                .map_err(|e| errors.push(e))
+                .ok()
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let (cfg, errors) = normalize_config(HttpConfig { endpoints });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = http_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let status = cfg.endpoints.iter().map(HttpEndpointStatus::from).collect();
+    state.replace(cfg);
+    Ok(status)
+}
+
+/// The configured endpoints — header NAMES only, never their values.
+#[tauri::command]
+pub fn http_config_status(state: State<'_, HttpPoll>) -> Vec<HttpEndpointStatus> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .endpoints
+        .iter()
+        .map(HttpEndpointStatus::from)
+        .collect()
+}
+
+/// Fetch an UNSAVED endpoint once and return what its extractors produce, so the settings form can
+/// check a URL and its paths before saving. Blank header values and a blank body use the stored
+/// ones, but only for the stored URL's origin.
+#[tauri::command]
+pub async fn http_test_endpoint(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    endpoint: HttpEndpoint,
+) -> Result<Vec<SensorSample>, String> {
+    if window.label() != "studio" {
+        return Err("http_test_endpoint is only allowed from the studio window".into());
+    }
+    let name = id_segment(&endpoint.name).unwrap_or_else(|| "test".to_string());
+    let stored = load_http_config(&app)?.unwrap_or_default();
+    let old = stored.endpoints.iter().find(|s| s.name == name);
+    let ep = normalize_endpoint(with_stored_secrets(HttpEndpoint { name, ..endpoint }, old)?)?;
+    let body = fetch(&http_client(ep.timeout_secs, ep.insecure)?, &ep).await?;
+    Ok(extract(&ep.name, &compile(&ep), &body, now_ms()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn endpoint(name: &str, extract: &[(&str, &str)]) -> HttpEndpoint {
+        serde_json::from_value(json!({
+            "name": name,
+            "url": "http://pi.hole/api",
+            "extract": extract.iter().cloned().collect::<BTreeMap<_, _>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_the_jsonpath_subset() {
+        assert_eq!(
+            parse_path("$.a.b[0]['c d'][-1]").unwrap(),
+            vec![
+                PathStep::Key("a".into()),
+                PathStep::Key("b".into()),
+                PathStep::Index(0),
+                PathStep::Key("c d".into()),
+                PathStep::Index(-1),
+            ]
+        );
+        assert_eq!(parse_path("$").unwrap(), vec![]);
+        for bad in ["a.b", "$..a", "$[x]", "$[0", "$a"] {
+            assert!(parse_path(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn select_walks_keys_and_indices() {
+        let v = json!({ "a": { "list": [1, 2, 3] } });
+        let get = |p: &str| select(&v, &parse_path(p).unwrap()).cloned();
+        assert_eq!(get("$.a.list[0]"), Some(json!(1)));
+        assert_eq!(get("$.a.list[-1]"), Some(json!(3)));
+        assert_eq!(get("$.a.list[9]"), None);
+        assert_eq!(get("$.a.missing"), None);
+        assert_eq!(get("$['a']['list'][1]"), Some(json!(2)));
+    }
+
+    #[test]
+    fn extract_maps_json_types_and_regex_captures() {
+        let ep = endpoint(
+            "pihole",
+            &[
+                ("blocked", "$.ads_blocked_today"),
+                ("enabled", "$.enabled"),
+                ("top", "$.top[0].domain"),
+                ("clients", "$.clients"),
+                ("version", r"regex:v(\d+\.\d+)"),
+                ("missing", "$.nope"),
+            ],
+        );
+        let body = r#"{"ads_blocked_today": 1234, "enabled": true,
+            "top": [{"domain": "ads.example"}], "clients": ["a", "b"], "build": "v5.18"}"#;
+        let out = extract("pihole", &compile(&ep), body, 7);
+        let by: HashMap<&str, &SensorValue> =
+            out.iter().map(|s| (s.sensor.as_str(), &s.value)).collect();
+        assert_eq!(out.len(), 5);
+        assert!(matches!(by["http.pihole.blocked"], SensorValue::Scalar(v) if *v == 1234.0));
+        assert!(matches!(by["http.pihole.enabled"], SensorValue::Scalar(v) if *v == 1.0));
+        assert!(matches!(by["http.pihole.top"], SensorValue::Text(s) if s == "ads.example"));
+        assert!(matches!(by["http.pihole.clients"], SensorValue::Json(_)));
+        assert!(matches!(by["http.pihole.version"], SensorValue::Scalar(v) if *v == 5.18));
+        assert!(out.iter().all(|s| s.ts_ms == 7));
+    }
+
+    #[test]
+    fn regex_extractors_work_on_non_json_bodies() {
+        let ep = endpoint(
+            "router",
+            &[("state", r"regex:State:\s*(\w+)"), ("raw", "regex:up")],
+        );
+        let out = extract("router", &compile(&ep), "WAN State: connected, up 3d", 1);
+        assert!(matches!(&out[0].value, SensorValue::Text(s) if s == "up"));
+        assert!(matches!(&out[1].value, SensorValue::Text(s) if s == "connected"));
+    }
+
+    #[test]
+    fn normalize_config_reports_each_bad_entry() {
+        let mut bad_url = endpoint("bad url", &[("x", "$.x")]);
+        bad_url.url = "ftp://nope".into();
+        let mut slow = endpoint("Build Server", &[("Last.Result", "$.result")]);
+        slow.interval_secs = 1;
+        let (cfg, errors) = normalize_config(HttpConfig {
+            endpoints: vec![
+                slow,
+                endpoint("build_server", &[("x", "$.x")]),
+                bad_url,
+                endpoint("noextract", &[]),
+                endpoint("reserved", &[("status", "$.s")]),
+                endpoint("badregex", &[("x", "regex:(")]),
+            ],
+        });
+        assert_eq!(cfg.endpoints.len(), 1);
+        let ep = &cfg.endpoints[0];
+        assert_eq!(ep.name, "build_server");
+        assert_eq!(ep.interval_secs, MIN_INTERVAL);
+        assert_eq!(ep.method, "GET");
+        assert!(ep.extract.contains_key("last.result"));
+        assert_eq!(errors.len(), 5);
+        assert!(errors[0].contains("duplicate"));
+        assert!(errors[3].contains("reserved"));
+    }
+
+    #[test]
+    fn blank_header_values_keep_the_stored_secret() {
+        let stored: BTreeMap<String, String> =
+            [("Authorization".to_string(), "Bearer s3cret".to_string())]
+                .into_iter()
+                .collect();
+        let new: BTreeMap<String, String> = [
+            ("authorization".to_string(), String::new()),
+            ("X-New".to_string(), "1".to_string()),
+            ("X-Blank".to_string(), String::new()),
+        ]
+        .into_iter()
+        .collect();
+        let merged = merge_headers(new, &stored);
+        assert_eq!(
+            merged.get("authorization").map(String::as_str),
+            Some("Bearer s3cret")
+        );
+        assert_eq!(merged.get("X-New").map(String::as_str), Some("1"));
+        assert!(!merged.contains_key("X-Blank"));
+    }
+
+    #[test]
+    fn a_blank_body_keeps_the_stored_one_unless_cleared() {
+        let stored = "{\"query\": 1}".to_string();
+        assert_eq!(merge_body(None, Some(&stored), false), Some(stored.clone()));
+        assert_eq!(
+            merge_body(Some("  ".into()), Some(&stored), false),
+            Some(stored.clone())
+        );
+        assert_eq!(
+            merge_body(Some("new".into()), Some(&stored), false).as_deref(),
+            Some("new")
+        );
+        assert_eq!(merge_body(Some(String::new()), None, false), None);
+        assert_eq!(merge_body(None, Some(&stored), true), None);
+    }
+
+    #[test]
+    fn stored_secrets_only_follow_the_same_origin() {
+        let mut old = endpoint("pihole", &[("x", "$.x")]);
+        old.headers
+            .insert("Authorization".into(), "Bearer s3cret".into());
+        old.body = Some("q".into());
+        let mut edit = old.clone();
+        edit.headers.insert("Authorization".into(), String::new());
+        edit.body = None;
+
+        // Same scheme/host/port (default port spelled out, different path): carried over.
+        edit.url = "http://PI.hole:80/other".into();
+        let ep = with_stored_secrets(edit.clone(), Some(&old)).unwrap();
+        assert_eq!(ep.headers["Authorization"], "Bearer s3cret");
+        assert_eq!(ep.body.as_deref(), Some("q"));
+
+        // Another host (or port, or scheme): the blank values must be entered again.
+        for url in [
+            "http://evil.example/api",
+            "http://pi.hole:8080/api",
+            "https://pi.hole/api",
+        ] {
+            edit.url = url.into();
+            assert!(
+                with_stored_secrets(edit.clone(), Some(&old)).is_err(),
+                "{url}"
+            );
+        }
+        edit.url = "http://evil.example/api".into();
+        edit.headers
+            .insert("Authorization".into(), "Bearer other".into());
+        edit.clear_body = true;
+        let ep = with_stored_secrets(edit, Some(&old)).unwrap();
+        assert_eq!(ep.headers["Authorization"], "Bearer other");
+        assert_eq!(ep.body, None);
+        assert!(!ep.clear_body);
+    }
+
+    #[test]
+    fn status_never_serializes_header_values() {
+        let mut ep = endpoint("pihole", &[("x", "$.x")]);
+        ep.headers
+            .insert("Authorization".into(), "Bearer s3cret".into());
+        let v = serde_json::to_value(HttpEndpointStatus::from(&ep)).unwrap();
+        assert_eq!(v["headerNames"], json!(["Authorization"]));
+        assert!(!v.to_string().contains("s3cret"));
+    }
+}
@@ -0,0 +1,799 @@
+//! InfluxDB line-protocol sink (OPT-IN): batches the telemetry stream — the same samples
+//! `run_system_sensors` and every proxy source publish on the bus — and writes them to an InfluxDB
+//! v2 `/api/v2/write` endpoint, or appends them to a local `.lp` file for a later import.
+//!
+//! Configured in `plugins/influx.json`:
+//! `{ "enabled": true, "url": "http://nas:8086", "org": "home", "bucket": "desk", "token": "…",
+//!    "prefixes": ["cpu.", "gpu.", "ha.sensor."], "tags": { "room": "office" },
+//!    "interval": "10s" }`
+//! or `"file": "D:/telemetry/desk.lp"` instead of url/org/bucket/token.
+//! Every point is `<measurement>,host=…,sensor=<id>,source=<src>[,<tags>] value=<f64> <ms>`
+//! (`text="…"` for text sensors; series/JSON are skipped). `host` defaults to the machine name and
+//! `source` is the owning source from `catalog::source_of`; `prefixes` is an id-prefix allowlist
+//! (empty = everything). Stale samples (lastknown.rs / health.rs) are not new readings and are
+//! never written, nor is anything while a recording replays.
+//!
+//! When a write fails the batch is appended to `<app_config_dir>/influx/buffer.lp` (capped at
+//! `MAX_BUFFER` bytes — beyond that new points are dropped and counted) and the buffer is re-sent,
+//! oldest first, before the next batch. A batch the server refuses outright (a 4xx) is logged and
+//! dropped instead, so one bad point can't block every later write. Writes (and the buffer file
+//! I/O, on the blocking pool) run in their own task, so a slow server or a long buffer drain never
+//! stops the bus from being read; while the writer is busy points keep accumulating, up to
+//! `MAX_PENDING`. The token is a secret like
+//! `HaConfig.token`: the status reports only whether one is set and a blank save keeps the stored
+//! one. Pure seams (`allowed`, `line`, the buffer file helpers) are unit-tested.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::bus;
+use crate::catalog::source_of;
+use crate::command::atomic_write;
+use crate::derived::parse_duration;
+use crate::httppoll::http_client;
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue};
+
+/// How often the task checks whether a flush is due.
+const TICK_MS: u64 = 1000;
+/// Flush early once this many points are pending; also the chunk size when draining the buffer.
+const MAX_BATCH: usize = 5_000;
+/// Largest on-disk buffer (bytes) — about a day of a busy system feed.
+const MAX_BUFFER: u64 = 64 * 1024 * 1024;
+/// Per-request timeout for the write endpoint.
+const WRITE_TIMEOUT_SECS: u64 = 15;
+/// Flushes queued for the writer task; when it's still busy the collector keeps the points.
+const WRITER_QUEUE: usize = 2;
+/// Most points held in memory while the writer is busy; beyond that new points are dropped and
+/// counted, like a full buffer.
+const MAX_PENDING: usize = 20 * MAX_BATCH;
+
+fn default_measurement() -> String {
+    "widgetsack".to_string()
+}
+fn default_interval() -> String {
+    "10s".to_string()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The machine name for the `host` tag when none is configured.
+fn machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+// ---- config ----
+
+/// `plugins/influx.json`. The token is secret (see module docs).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InfluxConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Server base URL, e.g. `http://nas:8086` (ignored when `file` is set).
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub org: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub token: String,
+    /// Accept a self-signed certificate, like `HttpEndpoint.insecure`.
+    #[serde(default)]
+    pub insecure: bool,
+    /// Append line protocol to this file instead of writing to a server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default = "default_measurement")]
+    pub measurement: String,
+    /// Sensor-id prefix allowlist; empty writes every sensor.
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    /// `host` tag value; blank = the machine name.
+    #[serde(default)]
+    pub host: String,
+    /// Extra static tags on every point.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// How often pending points are written.
+    #[serde(default = "default_interval")]
+    pub interval: String,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        InfluxConfig {
+            enabled: false,
+            url: String::new(),
+            org: String::new(),
+            bucket: String::new(),
+            token: String::new(),
+            insecure: false,
+            file: None,
+            measurement: default_measurement(),
+            prefixes: Vec::new(),
+            host: String::new(),
+            tags: BTreeMap::new(),
+            interval: default_interval(),
+        }
+    }
+}
+
+/// What the webview learns: everything but the token, plus how the sink is doing.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfluxStatus {
+    pub enabled: bool,
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    pub has_token: bool,
+    pub insecure: bool,
+    pub file: Option<String>,
+    pub measurement: String,
+    pub prefixes: Vec<String>,
+    pub host: String,
+    pub tags: BTreeMap<String, String>,
+    pub interval: String,
+    /// Bytes waiting in the failure buffer.
+    pub buffered_bytes: u64,
+    /// Points dropped (buffer or memory full, or rejected by the server), this session.
+    pub dropped: u64,
+    pub last_error: Option<String>,
+}
+
+/// Runtime counters surfaced by `influx_status`.
+#[derive(Debug, Default)]
+struct Stats {
+    dropped: u64,
+    last_error: Option<String>,
+}
+
+/// Managed state: the config (re-read by the task every tick) and its counters.
+#[derive(Default)]
+pub struct Influx {
+    config: Mutex<InfluxConfig>,
+    stats: Mutex<Stats>,
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("influx.json"))
+}
+
+fn buffer_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("influx").join("buffer.lp"))
+}
+
+pub fn load_influx_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<InfluxConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Trim fields and check an enabled sink has somewhere to write.
+fn normalize(cfg: InfluxConfig) -> Result<InfluxConfig, String> {
+    let cfg = InfluxConfig {
+        url: cfg.url.trim().trim_end_matches('/').to_string(),
+        org: cfg.org.trim().to_string(),
+        bucket: cfg.bucket.trim().to_string(),
+        token: cfg.token.trim().to_string(),
+        file: cfg
+            .file
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty()),
+        measurement: cfg.measurement.trim().to_string(),
+        prefixes: cfg
+            .prefixes
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect(),
+        host: cfg.host.trim().to_string(),
+        interval: cfg.interval.trim().to_string(),
+        ..cfg
+    };
+    parse_duration(&cfg.interval).ok_or_else(|| format!("bad interval `{}`", cfg.interval))?;
+    if cfg.measurement.is_empty() {
+        return Err("measurement is required".into());
+    }
+    if cfg
+        .tags
+        .keys()
+        .any(|k| matches!(k.as_str(), "host" | "sensor" | "source"))
+    {
+        return Err("`host`, `sensor` and `source` are reserved tag names".into());
+    }
+    if cfg.enabled && cfg.file.is_none() {
+        if !(cfg.url.starts_with("http://") || cfg.url.starts_with("https://")) {
+            return Err("url must be http:// or https:// (or set `file`)".into());
+        }
+        if cfg.org.is_empty() || cfg.bucket.is_empty() {
+            return Err("org and bucket are required".into());
+        }
+    }
+    Ok(cfg)
+}
+
+// ---- line protocol (pure) ----
+
+/// Whether `id` passes the prefix allowlist (empty = everything).
+pub fn allowed(id: &str, prefixes: &[String]) -> bool {
+    prefixes.is_empty() || prefixes.iter().any(|p| id.starts_with(p.as_str()))
+}
+
+/// Escape a measurement (commas, spaces) or a tag key/value (also `=`).
+fn escape(s: &str, tag: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ',' | ' ' | '\\' => out.push('\\'),
+            '=' if tag => out.push('\\'),
+            '\n' | '\r' => {
+                out.push(' ');
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// One sample as a line-protocol point with millisecond precision. `None` for kinds Influx can't
+/// hold as one field (series, JSON) and for non-finite numbers.
+pub fn line(
+    measurement: &str,
+    host: &str,
+    tags: &BTreeMap<String, String>,
+    s: &SensorSample,
+) -> Option<String> {
+    let field = match &s.value {
+        SensorValue::Scalar(v) if v.is_finite() => format!("value={v}"),
+        SensorValue::Text(t) => {
+            // A raw newline ends the point, so line breaks (log lines, command output) become
+            // spaces as they do in tags.
+            let t = t
+                .replace("\r\n", " ")
+                .replace(['\n', '\r'], " ")
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            format!("text=\"{t}\"")
+        }
+        _ => return None,
+    };
+    let mut all: BTreeMap<&str, &str> = tags
+        .iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    all.insert("host", host);
+    all.insert("sensor", &s.sensor);
+    all.insert("source", source_of(&s.sensor));
+    let tag_set: String = all
+        .iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| format!(",{}={}", escape(k, true), escape(v, true)))
+        .collect();
+    Some(format!(
+        "{}{tag_set} {field} {}",
+        escape(measurement, false),
+        s.ts_ms
+    ))
+}
+
+// ---- failure buffer ----
+
+/// Append `text` to `path`, creating the file and its folder as needed.
+fn append_file(path: &Path, text: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| f.write_all(text.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+/// Append points to the buffer file unless that would pass `MAX_BUFFER`. Returns how many points
+/// were dropped instead.
+pub fn buffer_append(path: &Path, lines: &[String]) -> Result<usize, String> {
+    if lines.is_empty() {
+        return Ok(0);
+    }
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let text: String = lines.iter().map(|l| format!("{l}\n")).collect();
+    if size + text.len() as u64 > MAX_BUFFER {
+        return Ok(lines.len());
+    }
+    append_file(path, &text)?;
+    Ok(0)
+}
+
+/// The buffered points, oldest first (none when the file is missing).
+pub fn buffer_read(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .map(|t| {
+            t.lines()
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Replace the buffer with what is still unsent (removing it when nothing is).
+pub fn buffer_replace(path: &Path, rest: &[String]) -> Result<(), String> {
+    if rest.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.to_string()),
+            _ => Ok(()),
+        };
+    }
+    let text: String = rest.iter().map(|l| format!("{l}\n")).collect();
+    atomic_write(path, &text)
+}
+
+// ---- writing ----
+
+/// Run file I/O (the buffer, a `file` destination) on the blocking pool rather than an async
+/// worker.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Why a write failed.
+enum WriteError {
+    /// Network, file or server trouble — the points are kept and retried.
+    Retry(String),
+    /// The server refused the points themselves (a 4xx other than 408/429); resending the same
+    /// batch can only fail again, so it is dropped.
+    Rejected(String),
+}
+
+/// Write one chunk of points to the configured destination.
+async fn write_points(
+    client: &reqwest::Client,
+    cfg: &InfluxConfig,
+    lines: &[String],
+) -> Result<(), WriteError> {
+    let body: String = lines.iter().map(|l| format!("{l}\n")).collect();
+    if let Some(file) = &cfg.file {
+        let path = PathBuf::from(file);
+        return blocking(move || append_file(&path, &body))
+            .await
+            .and_then(|r| r)
+            .map_err(WriteError::Retry);
+    }
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/api/v2/write", cfg.url),
+        [
+            ("org", cfg.org.as_str()),
+            ("bucket", cfg.bucket.as_str()),
+            ("precision", "ms"),
+        ],
+    )
+    .map_err(|e| WriteError::Retry(e.to_string()))?;
+    let mut req = client
+        .post(url)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(body);
+    if !cfg.token.is_empty() {
+        req = req.header("Authorization", format!("Token {}", cfg.token));
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| WriteError::Retry(e.to_string()))?;
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let detail = resp.text().await.unwrap_or_default();
+        let err = format!("HTTP {status} {}", detail.trim())
+            .trim()
+            .to_string();
+        return Err(match status {
+            400..=499 if status != 408 && status != 429 => WriteError::Rejected(err),
+            _ => WriteError::Retry(err),
+        });
+    }
+    Ok(())
+}
+
+/// Send the failure buffer (oldest first), then `pending`; whatever can't be sent goes (back) to
+/// the buffer. A chunk the server rejects is logged and dropped rather than blocking every later
+/// write. Returns how many points were rejected; on failure, the error and how many points were
+/// dropped (buffer full or unwritable).
+async fn flush<R: Runtime>(
+    app: &AppHandle<R>,
+    client: &reqwest::Client,
+    cfg: &InfluxConfig,
+    mut pending: Vec<String>,
+) -> Result<usize, (String, usize)> {
+    let path = buffer_path(app).map_err(|e| (e, pending.len()))?;
+    let mut rejected = 0;
+    let mut reject = |err: String, points: usize| {
+        log::error("influx", "influx rejected points; dropping them")
+            .field("error", err)
+            .field("points", points)
+            .emit();
+        rejected += points;
+    };
+    let mut buffered = {
+        let path = path.clone();
+        blocking(move || buffer_read(&path))
+            .await
+            .unwrap_or_default()
+    };
+    // On a retryable failure: the error, how much of the buffer went out, and where in `pending`
+    // sending stopped.
+    let failure = 'send: {
+        let mut sent = 0;
+        for chunk in buffered.chunks(MAX_BATCH) {
+            match write_points(client, cfg, chunk).await {
+                Ok(()) => {}
+                Err(WriteError::Rejected(err)) => reject(err, chunk.len()),
+                Err(WriteError::Retry(err)) => break 'send Some((err, sent, 0)),
+            }
+            sent += chunk.len();
+        }
+        for (i, chunk) in pending.chunks(MAX_BATCH).enumerate() {
+            match write_points(client, cfg, chunk).await {
+                Ok(()) => {}
+                Err(WriteError::Rejected(err)) => reject(err, chunk.len()),
+                Err(WriteError::Retry(err)) => break 'send Some((err, sent, i * MAX_BATCH)),
+            }
+        }
+        None
+    };
+    let Some((err, sent, from)) = failure else {
+        if !buffered.is_empty() {
+            blocking(move || buffer_replace(&path, &[]))
+                .await
+                .and_then(|r| r)
+                .map_err(|e| (e, 0))?;
+        }
+        return Ok(rejected);
+    };
+    let unsent = buffered.split_off(sent);
+    let rest = pending.split_off(from);
+    let points = rest.len();
+    let kept =
+        blocking(move || buffer_replace(&path, &unsent).and_then(|_| buffer_append(&path, &rest)))
+            .await
+            .and_then(|r| r);
+    Err(match kept {
+        Ok(dropped) => (err, dropped),
+        Err(io) => (format!("{err}; buffer: {io}"), points),
+    })
+}
+
+/// The writer task: flush each batch the collector hands over and record how it went. Kept apart
+/// from the collector so the bus receiver is read while a write (up to `WRITE_TIMEOUT_SECS` per
+/// chunk) or a long buffer drain is in progress.
+async fn write_batches<R: Runtime>(
+    app: AppHandle<R>,
+    mut rx: mpsc::Receiver<(InfluxConfig, reqwest::Client, Vec<String>)>,
+) {
+    while let Some((cfg, client, pending)) = rx.recv().await {
+        let result = flush(&app, &client, &cfg, pending).await;
+        let state = app.state::<Influx>();
+        let mut stats = state.stats.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(rejected) => {
+                stats.dropped += rejected as u64;
+                stats.last_error = None;
+            }
+            Err((err, dropped)) => {
+                if stats.last_error.as_deref() != Some(err.as_str()) {
+                    log::warn("influx", "influx write failed; buffering")
+                        .field("error", err.clone())
+                        .field("dropped", dropped)
+                        .emit();
+                }
+                stats.dropped += dropped as u64;
+                stats.last_error = Some(err);
+            }
+        }
+    }
+}
+
+/// The sink task: collect allowed, non-stale samples as points and hand them to the writer task on
+/// the configured interval (or early once `MAX_BATCH` are pending). Idles while disabled. Runs
+/// until the bus closes (app exit).
+pub async fn run_influx<R: Runtime>(app: AppHandle<R>) {
+    match load_influx_config(&app).and_then(|c| c.map(normalize).transpose()) {
+        Ok(Some(cfg)) => {
+            *app.state::<Influx>()
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = cfg
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("influx", "ignoring invalid influx.json")
+            .field("error", err)
+            .emit(),
+    }
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let config = || -> InfluxConfig {
+        app.state::<Influx>()
+            .config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    };
+    let (tx, writer_rx) = mpsc::channel(WRITER_QUEUE);
+    tauri::async_runtime::spawn(write_batches(app.clone(), writer_rx));
+    let mut pending: Vec<String> = Vec::new();
+    let mut last_flush = now_ms();
+    let mut client: Option<(bool, reqwest::Client)> = None;
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => {
+                    let cfg = config();
+                    if !cfg.enabled || bus::replaying(&app) {
+                        continue;
+                    }
+                    let host = if cfg.host.is_empty() { machine_name() } else { cfg.host.clone() };
+                    pending.extend(
+                        batch
+                            .iter()
+                            .filter(|s| !s.stale && allowed(&s.sensor, &cfg.prefixes))
+                            .filter_map(|s| line(&cfg.measurement, &host, &cfg.tags, s)),
+                    );
+                    if pending.len() > MAX_PENDING {
+                        let over = pending.len() - MAX_PENDING;
+                        pending.truncate(MAX_PENDING);
+                        let state = app.state::<Influx>();
+                        let mut stats = state.stats.lock().unwrap_or_else(|e| e.into_inner());
+                        stats.dropped += over as u64;
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("influx", "influx sink fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticker.tick() => {
+                let cfg = config();
+                if !cfg.enabled {
+                    pending.clear();
+                    continue;
+                }
+                let now = now_ms();
+                let interval = parse_duration(&cfg.interval).unwrap_or(10_000);
+                if now.saturating_sub(last_flush) < interval && pending.len() < MAX_BATCH {
+                    continue;
+                }
+                last_flush = now;
+                if client.as_ref().is_none_or(|(insecure, _)| *insecure != cfg.insecure) {
+                    match http_client(WRITE_TIMEOUT_SECS, cfg.insecure) {
+                        Ok(c) => client = Some((cfg.insecure, c)),
+                        Err(err) => {
+                            log::warn("influx", "client build failed").field("error", err).emit();
+                            continue;
+                        }
+                    }
+                }
+                let Some((_, http)) = &client else {
+                    continue;
+                };
+                match tx.try_send((cfg, http.clone(), std::mem::take(&mut pending))) {
+                    Ok(()) => {}
+                    // The writer is still busy: keep the points for the next flush.
+                    Err(TrySendError::Full((_, _, points))) => pending = points,
+                    Err(TrySendError::Closed(_)) => return,
+                }
+            }
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/influx.json` and apply it on the task's next tick. Studio-window-guarded; a
+/// blank token keeps the stored one.
+#[tauri::command]
+pub async fn save_influx_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Influx>,
+    config: InfluxConfig,
+) -> Result<InfluxStatus, String> {
+    if window.label() != "studio" {
+        return Err("save_influx_config is only allowed from the studio window".into());
+    }
+    let stored = load_influx_config(&app)?.unwrap_or_default();
+    let token = match config.token.trim() {
+        "" => stored.token,
+        t => t.to_string(),
+    };
+    let cfg = normalize(InfluxConfig { token, ..config })?;
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    *state.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+    Ok(influx_status(app, state))
+}
+
+/// The sink settings (never the token), its buffer size and the last write error.
+#[tauri::command]
+pub fn influx_status(app: AppHandle, state: State<'_, Influx>) -> InfluxStatus {
+    let cfg = state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let stats = state.stats.lock().unwrap_or_else(|e| e.into_inner());
+    let buffered_bytes = buffer_path(&app)
+        .ok()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .unwrap_or(0);
+    InfluxStatus {
+        enabled: cfg.enabled,
+        url: cfg.url,
+        org: cfg.org,
+        bucket: cfg.bucket,
+        has_token: !cfg.token.is_empty(),
+        insecure: cfg.insecure,
+        file: cfg.file,
+        measurement: cfg.measurement,
+        prefixes: cfg.prefixes,
+        host: cfg.host,
+        tags: cfg.tags,
+        interval: cfg.interval,
+        buffered_bytes,
+        dropped: stats.dropped,
+        last_error: stats.last_error.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_matches_prefixes_or_everything() {
+        let prefixes = vec!["cpu.".to_string(), "ha.sensor.".to_string()];
+        assert!(allowed("cpu.total", &prefixes));
+        assert!(allowed("ha.sensor.office_temp", &prefixes));
+        assert!(!allowed("gpu.temp", &prefixes));
+        assert!(allowed("gpu.temp", &[]));
+    }
+
+    #[test]
+    fn formats_points_with_sorted_escaped_tags() {
+        let tags = BTreeMap::from([("room".to_string(), "home office".to_string())]);
+        assert_eq!(
+            line(
+                "widgetsack",
+                "desk-pc",
+                &tags,
+                &SensorSample::scalar("cpu.total", 1_700, 42.0)
+            ),
+            Some(
+                "widgetsack,host=desk-pc,room=home\\ office,sensor=cpu.total,source=system \
+                 value=42 1700"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            line(
+                "m",
+                "h",
+                &BTreeMap::new(),
+                &SensorSample::text("ha.light.desk", 5, "on \"x\"")
+            ),
+            Some("m,host=h,sensor=ha.light.desk,source=ha text=\"on \\\"x\\\"\" 5".to_string())
+        );
+        assert_eq!(
+            line(
+                "m",
+                "h",
+                &BTreeMap::new(),
+                &SensorSample::text("tail.app.last", 5, "a\r\nb\nc")
+            ),
+            Some("m,host=h,sensor=tail.app.last,source=tail text=\"a b c\" 5".to_string())
+        );
+        assert_eq!(
+            line(
+                "m",
+                "h",
+                &BTreeMap::new(),
+                &SensorSample::scalar("mqtt.a=b,c", 5, 0.5)
+            ),
+            Some("m,host=h,sensor=mqtt.a\\=b\\,c,source=mqtt value=0.5 5".to_string())
+        );
+        assert_eq!(
+            line(
+                "m",
+                "h",
+                &BTreeMap::new(),
+                &SensorSample::scalar("cpu.total", 5, f64::NAN)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn buffer_round_trips_and_caps_its_size() {
+        let dir = std::env::temp_dir().join(format!("widgetsack-influx-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("buffer.lp");
+        assert!(buffer_read(&path).is_empty());
+        let lines = vec!["m value=1 1".to_string(), "m value=2 2".to_string()];
+        assert_eq!(buffer_append(&path, &lines).unwrap(), 0);
+        assert_eq!(buffer_append(&path, &lines[..1]).unwrap(), 0);
+        assert_eq!(buffer_read(&path).len(), 3);
+        buffer_replace(&path, &lines[1..]).unwrap();
+        assert_eq!(buffer_read(&path), vec!["m value=2 2".to_string()]);
+        buffer_replace(&path, &[]).unwrap();
+        assert!(!path.exists());
+
+        let huge = vec!["x".repeat(MAX_BUFFER as usize)];
+        assert_eq!(buffer_append(&path, &huge).unwrap(), 1);
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn normalize_requires_a_destination_when_enabled() {
+        let on = InfluxConfig {
+            enabled: true,
+            ..InfluxConfig::default()
+        };
+        assert!(normalize(on.clone()).is_err());
+        let file = InfluxConfig {
+            file: Some(" D:/t.lp ".into()),
+            ..on.clone()
+        };
+        assert_eq!(normalize(file).unwrap().file.as_deref(), Some("D:/t.lp"));
+        let server = InfluxConfig {
+            url: "http://nas:8086/".into(),
+            org: "home".into(),
+            bucket: "desk".into(),
+            ..on.clone()
+        };
+        assert_eq!(normalize(server).unwrap().url, "http://nas:8086");
+        let reserved = InfluxConfig {
+            tags: BTreeMap::from([("host".to_string(), "x".to_string())]),
+            ..InfluxConfig::default()
+        };
+        assert!(normalize(reserved).is_err());
+    }
+}
@@ -0,0 +1,529 @@
+//! Last-known-value cache: the latest sample of every proxied sensor (HA, MQTT, stocks, … —
+//! optionally the in-process ones too: the system feed, derived, astro, …) persisted to
+//! `<app_config_dir>/lastknown/values.json` periodically and on exit, and replayed at startup
+//! flagged `stale`, so overlays render the moment they open instead of sitting blank until the
+//! first HA message or stocks poll.
+//!
+//! Configured in `plugins/lastknown.json` as
+//! `{ "enabled": true, "include_system": false, "interval": "60s", "expire_after": "7d" }` (all
+//! optional; on by default). Source status ids (`ha.status`, `http.<name>.status`, … — the
+//! catalog's `*.status` rules) are never cached — a restored "connected" would lie. An id with no
+//! live sample for `expire_after` (a removed endpoint, a renamed entity) is dropped from the cache.
+//!
+//! A restored value is re-published every few seconds (so windows that open late still get it)
+//! until the id's first live sample arrives or `RESTORE_FOR_MS` passes. Backend consumers (derived,
+//! alerts, automations) ignore stale samples. Pure seams (`persistable`, `Cache`) are unit-tested.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus;
+use crate::catalog::{is_local_source, is_source_status, source_of};
+use crate::command::atomic_write;
+use crate::derived::parse_duration;
+use crate::log;
+use crate::sensors::SensorSample;
+
+/// How often restored values are re-published while they wait for live data.
+const REPUBLISH_MS: u64 = 5_000;
+
+/// How long after startup restored values keep being re-published.
+const RESTORE_FOR_MS: u64 = 120_000;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval() -> String {
+    "60s".to_string()
+}
+
+fn default_expire_after() -> String {
+    "7d".to_string()
+}
+
+/// `plugins/lastknown.json`. Every field defaults, so a partial (or absent) file means "on".
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LastKnownConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Also cache the system feed (cpu, gpu, …) — off by default: it refills within a second.
+    #[serde(default)]
+    pub include_system: bool,
+    /// How often the cache is written while running (it is also written on exit).
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    /// How long an id may go without a live sample before it is dropped from the cache.
+    #[serde(default = "default_expire_after")]
+    pub expire_after: String,
+}
+
+impl Default for LastKnownConfig {
+    fn default() -> Self {
+        LastKnownConfig {
+            enabled: default_enabled(),
+            include_system: false,
+            interval: default_interval(),
+            expire_after: default_expire_after(),
+        }
+    }
+}
+
+/// Managed state: the config plus the cache itself, so the exit hook can flush it.
+#[derive(Default)]
+pub struct LastKnown {
+    config: Mutex<LastKnownConfig>,
+    cache: Mutex<Cache>,
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("lastknown.json"))
+}
+
+fn values_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("lastknown").join("values.json"))
+}
+
+pub fn load_last_known_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<LastKnownConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn validate(cfg: &LastKnownConfig) -> Result<(), String> {
+    parse_duration(&cfg.interval).ok_or_else(|| format!("bad interval `{}`", cfg.interval))?;
+    parse_duration(&cfg.expire_after)
+        .map(|_| ())
+        .ok_or_else(|| format!("bad expire_after `{}`", cfg.expire_after))
+}
+
+fn expire_after_ms(cfg: &LastKnownConfig) -> u64 {
+    parse_duration(&cfg.expire_after).unwrap_or(7 * 86_400_000)
+}
+
+/// The persisted samples, flagged stale. Missing or unreadable → nothing to restore.
+fn load_values<R: Runtime>(app: &AppHandle<R>) -> Vec<SensorSample> {
+    let Ok(path) = values_path(app) else {
+        return Vec::new();
+    };
+    let Ok(txt) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<SensorSample>>(&txt) {
+        Ok(samples) => samples
+            .into_iter()
+            .map(|s| SensorSample { stale: true, ..s })
+            .collect(),
+        Err(err) => {
+            log::warn("lastknown", "ignoring unreadable last-known cache")
+                .field("error", err.to_string())
+                .emit();
+            Vec::new()
+        }
+    }
+}
+
+/// Write the cache if it changed since the last write. Called by the task on its interval and by
+/// the exit hook in main.rs.
+pub fn persist<R: Runtime>(app: &AppHandle<R>) {
+    let Some(state) = app.try_state::<LastKnown>() else {
+        return;
+    };
+    let Some(samples) = state
+        .cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take_dirty()
+    else {
+        return;
+    };
+    let result = values_path(app).and_then(|path| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let txt = serde_json::to_string(&samples).map_err(|e| e.to_string())?;
+        atomic_write(&path, &txt)
+    });
+    if let Err(err) = result {
+        log::warn("lastknown", "failed to persist last-known values")
+            .field("error", err)
+            .emit();
+    }
+}
+
+// ---- cache (pure) ----
+
+/// Whether `id` belongs in the cache: the proxy sources always, the system feed only when asked,
+/// a source's own status id never.
+pub fn persistable(id: &str, include_system: bool) -> bool {
+    !is_source_status(id) && (include_system || !is_local_source(source_of(id)))
+}
+
+/// The latest live sample per cacheable id, plus the restored (stale) samples still waiting for
+/// their first live update.
+#[derive(Debug, Default)]
+pub struct Cache {
+    values: HashMap<String, SensorSample>,
+    dirty: bool,
+    restored: HashMap<String, SensorSample>,
+}
+
+impl Cache {
+    /// Seed from disk: the samples become both the cache (so an untouched id survives the next
+    /// write) and the pending restore set.
+    pub fn restore(&mut self, samples: Vec<SensorSample>) {
+        for s in samples {
+            self.values.insert(s.sensor.clone(), s.clone());
+            self.restored.insert(s.sensor.clone(), s);
+        }
+    }
+
+    /// Record a published batch. Stale samples (our own re-publishes) are skipped; a live sample
+    /// retires its id from the restore set.
+    pub fn observe(&mut self, batch: &[SensorSample], include_system: bool) {
+        for s in batch.iter().filter(|s| !s.stale) {
+            self.restored.remove(&s.sensor);
+            if persistable(&s.sensor, include_system) {
+                self.values.insert(s.sensor.clone(), s.clone());
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// The restored samples still without live data, sorted by id.
+    pub fn pending_restore(&self) -> Vec<SensorSample> {
+        let mut out: Vec<SensorSample> = self.restored.values().cloned().collect();
+        out.sort_by(|a, b| a.sensor.cmp(&b.sensor));
+        out
+    }
+
+    /// Drop every id whose newest sample is older than `max_age_ms` (restored ones included).
+    pub fn evict(&mut self, now: u64, max_age_ms: u64) {
+        let before = self.values.len();
+        let fresh = |s: &SensorSample| now.saturating_sub(s.ts_ms) <= max_age_ms;
+        self.values.retain(|_, s| fresh(s));
+        self.restored.retain(|_, s| fresh(s));
+        if self.values.len() != before {
+            self.dirty = true;
+        }
+    }
+
+    pub fn stop_restoring(&mut self) {
+        self.restored.clear();
+    }
+
+    /// The samples to write (live-flagged, sorted by id) if anything changed since the last call.
+    pub fn take_dirty(&mut self) -> Option<Vec<SensorSample>> {
+        if !std::mem::take(&mut self.dirty) {
+            return None;
+        }
+        let mut out: Vec<SensorSample> = self
+            .values
+            .values()
+            .map(|s| SensorSample {
+                stale: false,
+                ..s.clone()
+            })
+            .collect();
+        out.sort_by(|a, b| a.sensor.cmp(&b.sensor));
+        Some(out)
+    }
+}
+
+// ---- task ----
+
+/// Restore the cache, then keep it current from the bus and write it on the configured interval.
+/// Idles while disabled. Runs until the bus closes (app exit).
+pub async fn run_last_known<R: Runtime>(app: AppHandle<R>) {
+    match load_last_known_config(&app) {
+        Ok(Some(cfg)) if validate(&cfg).is_ok() => {
+            *app.state::<LastKnown>()
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) = cfg;
+        }
+        Ok(Some(cfg)) => log::warn("lastknown", "ignoring invalid lastknown.json")
+            .field("interval", cfg.interval)
+            .emit(),
+        Ok(None) => {}
+        Err(err) => log::warn("lastknown", "failed to read lastknown.json")
+            .field("error", err)
+            .emit(),
+    }
+    let Some(mut rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let config = || -> LastKnownConfig {
+        app.state::<LastKnown>()
+            .config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    };
+    let started = now_ms();
+    if config().enabled {
+        let samples = load_values(&app);
+        if !samples.is_empty() {
+            let pending = {
+                let state: State<LastKnown> = app.state();
+                let mut cache = state.cache.lock().unwrap_or_else(|e| e.into_inner());
+                cache.restore(samples);
+                cache.evict(started, expire_after_ms(&config()));
+                cache.pending_restore()
+            };
+            let _ = bus::publish(&app, &pending);
+        }
+    }
+    let mut last_write = started;
+    let mut ticker = tokio::time::interval(Duration::from_millis(REPUBLISH_MS));
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(batch) => {
+                    let cfg = config();
+                    if cfg.enabled && !bus::replaying(&app) {
+                        app.state::<LastKnown>()
+                            .cache
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .observe(&batch, cfg.include_system);
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("lastknown", "last-known cache fell behind the telemetry bus")
+                        .field("skipped_batches", n)
+                        .emit();
+                }
+                Err(RecvError::Closed) => return,
+            },
+            _ = ticker.tick() => {
+                let now = now_ms();
+                let cfg = config();
+                let pending = {
+                    let state: State<LastKnown> = app.state();
+                    let mut cache = state.cache.lock().unwrap_or_else(|e| e.into_inner());
+                    if now.saturating_sub(started) > RESTORE_FOR_MS || !cfg.enabled {
+                        cache.stop_restoring();
+                    }
+                    cache.evict(now, expire_after_ms(&cfg));
+                    cache.pending_restore()
+                };
+                if !pending.is_empty() {
+                    let _ = bus::publish(&app, &pending);
+                }
+                let interval = parse_duration(&cfg.interval).unwrap_or(60_000);
+                if cfg.enabled && now.saturating_sub(last_write) >= interval {
+                    persist(&app);
+                    last_write = now;
+                }
+            }
+        }
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/lastknown.json` and apply it (the task reads it on its next batch/tick).
+/// Studio-window-guarded like the other plugin configs.
+#[tauri::command]
+pub async fn save_last_known_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, LastKnown>,
+    config: LastKnownConfig,
+) -> Result<LastKnownConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_last_known_config is only allowed from the studio window".into());
+    }
+    let config = LastKnownConfig {
+        interval: config.interval.trim().to_string(),
+        expire_after: config.expire_after.trim().to_string(),
+        ..config
+    };
+    validate(&config)?;
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    *state.config.lock().unwrap_or_else(|e| e.into_inner()) = config.clone();
+    Ok(config)
+}
+
+/// The live last-known-cache settings.
+#[tauri::command]
+pub fn last_known_config_status(state: State<'_, LastKnown>) -> LastKnownConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    fn stale(s: SensorSample) -> SensorSample {
+        SensorSample { stale: true, ..s }
+    }
+
+    #[test]
+    fn config_defaults_to_on_without_system() {
+        let cfg: LastKnownConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(cfg, LastKnownConfig::default());
+        assert!(cfg.enabled && !cfg.include_system);
+        assert!(validate(&cfg).is_ok());
+        assert!(
+            validate(&LastKnownConfig {
+                interval: "soon".into(),
+                ..cfg.clone()
+            })
+            .is_err()
+        );
+        assert!(
+            validate(&LastKnownConfig {
+                expire_after: "forever".into(),
+                ..cfg
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn persistable_covers_proxy_sources_but_not_status() {
+        assert!(persistable("ha.sensor.temp", false));
+        assert!(persistable("mqtt.home/door", false));
+        assert!(persistable("stocks.AAPL.price", false));
+        assert!(!persistable("ha.status", true));
+        assert!(!persistable("http.pihole.status", false));
+        assert!(persistable("http.pihole.blocked", false));
+        assert!(!persistable("cmd.git.status", false));
+        assert!(!persistable("prom.nas.status", false));
+        assert!(!persistable("cert.ha.status", false));
+        assert!(persistable("cert.ha.days", false));
+        assert!(!persistable("folder.downloads.status", false));
+        assert!(!persistable("cpu.total", false));
+        assert!(persistable("cpu.total", true));
+        assert!(!persistable("derived.cpu_avg", false));
+        assert!(!persistable("net.latency.router", false));
+        assert!(persistable("net.latency.router", true));
+    }
+
+    #[test]
+    fn observe_tracks_latest_and_marks_dirty_once() {
+        let mut cache = Cache::default();
+        assert!(cache.take_dirty().is_none());
+        cache.observe(
+            &[
+                SensorSample::scalar("stocks.AAPL.price", 1, 100.0),
+                SensorSample::scalar("cpu.total", 1, 5.0),
+            ],
+            false,
+        );
+        cache.observe(
+            &[SensorSample::scalar("stocks.AAPL.price", 2, 101.0)],
+            false,
+        );
+        let out = cache.take_dirty().unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].ts_ms, 2);
+        assert!(cache.take_dirty().is_none());
+    }
+
+    #[test]
+    fn restored_values_wait_for_live_data() {
+        let mut cache = Cache::default();
+        cache.restore(vec![
+            stale(SensorSample::text("ha.light.desk", 1, "on")),
+            stale(SensorSample::scalar("stocks.AAPL.price", 1, 99.0)),
+        ]);
+        assert_eq!(cache.pending_restore().len(), 2);
+        assert!(cache.pending_restore().iter().all(|s| s.stale));
+
+        // Our own stale re-publish doesn't count as live.
+        cache.observe(&cache.pending_restore(), false);
+        assert_eq!(cache.pending_restore().len(), 2);
+        assert!(cache.take_dirty().is_none());
+
+        cache.observe(
+            &[SensorSample::scalar("stocks.AAPL.price", 9, 100.0)],
+            false,
+        );
+        let pending = cache.pending_restore();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].sensor, "ha.light.desk");
+
+        // The untouched restored id is still written back — live-flagged, at its original time.
+        let out = cache.take_dirty().unwrap();
+        assert_eq!(out.len(), 2);
+        assert!(out.iter().all(|s| !s.stale));
+        assert!(matches!(&out[0].value, SensorValue::Text(s) if s == "on"));
+        assert_eq!(out[0].ts_ms, 1);
+
+        cache.stop_restoring();
+        assert!(cache.pending_restore().is_empty());
+    }
+
+    #[test]
+    fn ids_without_live_data_expire() {
+        let day = 86_400_000;
+        let mut cache = Cache::default();
+        cache.restore(vec![
+            stale(SensorSample::scalar("http.old.value", 0, 1.0)),
+            stale(SensorSample::scalar("stocks.AAPL.price", 6 * day, 99.0)),
+        ]);
+        cache.observe(
+            &[SensorSample::scalar("ha.sensor.temp", 7 * day, 20.0)],
+            false,
+        );
+        cache.take_dirty();
+
+        cache.evict(8 * day, 7 * day);
+        let out = cache.take_dirty().unwrap();
+        let ids: Vec<&str> = out.iter().map(|s| s.sensor.as_str()).collect();
+        assert_eq!(ids, ["ha.sensor.temp", "stocks.AAPL.price"]);
+        assert_eq!(cache.pending_restore().len(), 1);
+        // Nothing expired: nothing to write.
+        cache.evict(8 * day, 7 * day);
+        assert!(cache.take_dirty().is_none());
+    }
+
+    #[test]
+    fn stale_flag_is_omitted_on_the_wire_when_false() {
+        let live = serde_json::to_value(SensorSample::scalar("cpu.total", 1, 2.0)).unwrap();
+        assert!(live.get("stale").is_none());
+        let old = serde_json::to_value(stale(SensorSample::scalar("cpu.total", 1, 2.0))).unwrap();
+        assert_eq!(old["stale"], true);
+        let back: SensorSample =
+            serde_json::from_str(r#"{"sensor":"a","ts_ms":1,"value":{"kind":"scalar","value":1}}"#)
+                .unwrap();
+        assert!(!back.stale);
+    }
+}
@@ -17,23 +17,50 @@ use crate::command::get_initial_sessions;
 use crate::event::emit_to_bridge;
 use crate::state::updater;
 
+pub mod alerts;
+pub mod astro;
 pub mod audio;
+pub mod automations;
 pub mod bridge;
+pub mod bus;
+pub mod calendar;
+pub mod catalog;
+pub mod certwatch;
 pub mod clickthrough;
+pub mod cmdsource;
 pub mod command;
 pub mod control;
+pub mod derived;
 pub mod display;
+pub mod energy;
 pub mod event;
+pub mod exporter;
+pub mod feed;
+pub mod folder;
+pub mod gitrepo;
 pub mod ha;
+pub mod health;
+pub mod httppoll;
+pub mod influx;
+pub mod lastknown;
 pub mod listener;
 pub mod llm;
 pub mod log;
 pub mod media;
 pub mod mqtt;
+pub mod netprobe;
 pub mod process_diag;
+pub mod procwatch;
+pub mod prom;
+pub mod recorder;
 pub mod sensors;
-pub mod stocks;
 pub mod state;
+pub mod stocks;
+pub mod supervisor;
+pub mod synthetic;
+pub mod tail;
+pub mod tasks;
+pub mod weather;
 pub mod windowmgr;
 
 pub struct AppState {
@@ -147,6 +174,7 @@ async fn main() -> Result<(), ()> {
             }
         })
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .manage(AppState {
             sessions: Default::default(),
         })
@@ -159,6 +187,32 @@ async fn main() -> Result<(), ()> {
         .manage(sensors::ActiveSensors::default())
         .manage(audio::SpectrumState::default())
         .manage(process_diag::ProcDiag::default())
+        .manage(procwatch::ProcWatch::default())
+        .manage(energy::Energy::default())
+        .manage(catalog::SensorCatalog::default())
+        .manage(bus::Bus::default())
+        .manage(derived::Derived::default())
+        .manage(alerts::Alerts::default())
+        .manage(automations::Automations::default())
+        .manage(recorder::Recorder::default())
+        .manage(synthetic::Synthetic::default())
+        .manage(lastknown::LastKnown::default())
+        .manage(health::Health::default())
+        .manage(httppoll::HttpPoll::default())
+        .manage(cmdsource::CmdSource::default())
+        .manage(prom::PromScrape::default())
+        .manage(exporter::ExporterState::default())
+        .manage(influx::Influx::default())
+        .manage(netprobe::NetProbe::default())
+        .manage(certwatch::CertWatch::default())
+        .manage(folder::FolderWatch::default())
+        .manage(tail::TailSource::default())
+        .manage(calendar::CalendarSource::default())
+        .manage(feed::FeedReader::default())
+        .manage(weather::WeatherState::default())
+        .manage(astro::AstroSource::default())
+        .manage(gitrepo::GitWatch::default())
+        .manage(tasks::TaskLists::default())
         .invoke_handler(tauri::generate_handler![
             get_initial_sessions,
             command::load_layout,
@@ -201,6 +255,67 @@ async fn main() -> Result<(), ()> {
             clickthrough::current_work_area,
             clickthrough::set_overlay_wallpaper,
             sensors::set_active_sensors,
+            catalog::list_sensors,
+            procwatch::save_procwatch_config,
+            procwatch::procwatch_config_status,
+            energy::save_energy_config,
+            energy::energy_config_status,
+            derived::save_derived_config,
+            derived::derived_config_status,
+            alerts::save_alerts_config,
+            alerts::alerts_config_status,
+            alerts::active_alerts,
+            automations::automations_status,
+            automations::save_automations,
+            automations::test_automation,
+            recorder::start_recording,
+            recorder::stop_recording,
+            recorder::list_recordings,
+            recorder::start_replay,
+            recorder::stop_replay,
+            recorder::recorder_status,
+            synthetic::save_synthetic_config,
+            synthetic::synthetic_config_status,
+            lastknown::save_last_known_config,
+            lastknown::last_known_config_status,
+            health::source_health,
+            httppoll::save_http_config,
+            httppoll::http_config_status,
+            httppoll::http_test_endpoint,
+            cmdsource::save_commands_config,
+            cmdsource::commands_config_status,
+            cmdsource::run_command_source,
+            prom::save_prom_config,
+            prom::prom_config_status,
+            prom::prom_test_target,
+            exporter::save_exporter_config,
+            exporter::exporter_status,
+            influx::save_influx_config,
+            influx::influx_status,
+            netprobe::save_netprobe_config,
+            netprobe::netprobe_config_status,
+            certwatch::save_cert_config,
+            certwatch::cert_config_status,
+            folder::save_folder_config,
+            folder::folder_config_status,
+            tail::save_tail_config,
+            tail::tail_config_status,
+            calendar::save_calendar_config,
+            calendar::calendar_config_status,
+            feed::save_feed_config,
+            feed::feed_config_status,
+            feed::feed_mark_read,
+            weather::save_weather_config,
+            weather::weather_config_status,
+            weather::weather_connect,
+            weather::weather_disconnect,
+            astro::save_astro_config,
+            astro::astro_config_status,
+            gitrepo::save_git_config,
+            gitrepo::git_config_status,
+            tasks::save_tasks_config,
+            tasks::tasks_config_status,
+            tasks::task_toggle,
             audio::start_spectrum,
             audio::stop_spectrum,
             audio::list_audio_outputs,
@@ -253,7 +368,26 @@ async fn main() -> Result<(), ()> {
                         let state: State<AppState> = app_handle.state();
                         let mut sessions = state.sessions.lock().await;
                         let delta = updater(&mut sessions, event);
-                        emit_to_bridge(&app_handle.clone(), delta);
+                        // A media (track) update feeds the automations' `media` trigger.
+                        if let Some(record) = &delta.1
+                            && let Some(SessionUpdateEventWrapper::Media(model, _)) =
+                                &record.last_media_update
+                            && let Some(media) = &model.media
+                        {
+                            automations::media_changed(
+                                &app_handle,
+                                record.source.as_deref().unwrap_or_default(),
+                                &media.title,
+                                &media.artist,
+                            );
+                        }
+                        if let Some(record) = &delta.1 {
+                            recorder::record_media(&app_handle, delta.0, record);
+                        }
+                        // A replay stands in for the live sessions until it ends.
+                        if !bus::replaying(&app_handle) {
+                            emit_to_bridge(&app_handle.clone(), delta);
+                        }
                     }
                 }
             });
@@ -263,6 +397,126 @@ async fn main() -> Result<(), ()> {
                 sensors::run_system_sensors(sensors_handle).await;
             });
 
+            // HTTP/JSON poller (http.json): one demand-gated poll task per endpoint, `http.<name>.*`.
+            let http_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                httppoll::run_http(http_handle).await;
+            });
+
+            // Command source (commands.json): scheduled executables parsed into `cmd.<name>.*`.
+            let commands_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                cmdsource::run_commands(commands_handle).await;
+            });
+
+            // Prometheus scraper (prom.json): selected series from exposition endpoints as `prom.<alias>`.
+            let prom_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                prom::run_prom(prom_handle).await;
+            });
+
+            // Latency probes (netprobe.json): TCP-connect / HTTP HEAD round-trips as `net.latency.<name>`.
+            let netprobe_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                netprobe::run_netprobe(netprobe_handle).await;
+            });
+
+            // Certificate expiry (certs.json): peer-certificate days left / issuer / hostname as `cert.<name>.*`.
+            let certs_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                certwatch::run_certs(certs_handle).await;
+            });
+
+            // Folder watches (folders.json): event-driven file count / size / newest as `folder.<name>.*`.
+            let folder_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                folder::run_folders(folder_handle).await;
+            });
+
+            // Log tail (tail.json): follows files across rotation, regex counts/captures as `tail.<name>.*`.
+            let tail_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tail::run_tail(tail_handle).await;
+            });
+
+            // Calendars (calendar.json): ICS files/feeds → next event, today's agenda, busy as `calendar.<name>.*`.
+            let calendar_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                calendar::run_calendar(calendar_handle).await;
+            });
+
+            // Feeds (feeds.json): RSS/Atom polling with conditional GETs, items + unread as `feed.<name>.*`.
+            let feed_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                feed::run_feeds(feed_handle).await;
+            });
+
+            // Weather (weather.json): Open-Meteo current conditions + forecasts as `weather.*`, when configured.
+            let weather_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                weather::connect_on_startup(weather_handle).await;
+            });
+
+            // Astronomy (astro.json): offline sun times / position and moon phase as `astro.*`.
+            let astro_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                astro::run_astro(astro_handle).await;
+            });
+
+            // Git repos (git.json): branch / ahead-behind / dirty counts as `git.<name>.*`, refreshed on `.git` changes.
+            let git_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                gitrepo::run_git(git_handle).await;
+            });
+
+            // Task lists (tasks.json): todo.txt / Markdown checklist counts and next items as `tasks.<name>.*`.
+            let tasks_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tasks::run_tasks(tasks_handle).await;
+            });
+
+            // InfluxDB sink (influx.json): OPT-IN batched line-protocol writes of the telemetry stream.
+            let influx_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                influx::run_influx(influx_handle).await;
+            });
+
+            // Source health: re-sends a sensor flagged `stale` when its source drops or it stops updating.
+            let health_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                health::run_health(health_handle).await;
+            });
+
+            // Last-known values (lastknown.json): restored as stale at startup, kept current from the bus.
+            let lastknown_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                lastknown::run_last_known(lastknown_handle).await;
+            });
+
+            // Synthetic generators (synthetic.json): demo/test signals on the normal telemetry path.
+            let synthetic_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                synthetic::run_synthetic(synthetic_handle).await;
+            });
+
+            // Derived sensors (derived.json): rolling stats over every source, published as `derived.*`.
+            let derived_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                derived::run_derived(derived_handle).await;
+            });
+
+            // Threshold alerts (alerts.json): `alert.*.active`, log records, desktop notifications.
+            let alerts_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                alerts::run_alerts(alerts_handle).await;
+            });
+
+            // Backend automations (automations.json): triggers → actions with no window required.
+            let automations_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                automations::run_automations(automations_handle).await;
+            });
+
             // Agent-control server: OPT-IN (off unless LlmConfig.agent_control is true). Started on
             // demand so a fresh install never opens a port.
             let control_handle = app.handle().clone();
@@ -271,6 +525,17 @@ async fn main() -> Result<(), ()> {
                 control::start_if_enabled(control_handle.clone(), &state).await;
             });
 
+            // Metrics exporter (exporter.json): OPT-IN Prometheus `/metrics` of the live telemetry.
+            let exporter_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = exporter_handle.state::<exporter::ExporterState>();
+                if let Err(err) = exporter::restart(exporter_handle.clone(), &state).await {
+                    log::error("exporter", "failed to start metrics exporter")
+                        .field("error", err)
+                        .emit();
+                }
+            });
+
             if let Err(err) = command::watch_layout(app.handle().clone()) {
                 log::error("startup", "failed to start layout watcher")
                     .field("error", err)
@@ -284,6 +549,13 @@ async fn main() -> Result<(), ()> {
                     .emit();
             }
 
+            // Automation rules (automations.json): reload the engine on external edits.
+            if let Err(err) = command::watch_automations(app.handle().clone()) {
+                log::error("startup", "failed to start automations watcher")
+                    .field("error", err)
+                    .emit();
+            }
+
             // Themes (Phase 7c): seed example themes on first run + watch the folder.
             command::seed_themes(&app.handle().clone());
             if let Err(err) = command::watch_themes(app.handle().clone()) {
@@ -387,8 +659,16 @@ async fn main() -> Result<(), ()> {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Flush the last-known cache so the next launch restores what was on screen, and the
+            // energy total so the integral since its last periodic write isn't lost.
+            if let tauri::RunEvent::Exit = event {
+                lastknown::persist(app);
+                energy::persist(app);
+            }
+        });
 
     Ok(())
 }
@@ -18,9 +18,11 @@ use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, TlsConfiguration, Tr
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tauri::async_runtime::{JoinHandle, Mutex};
-use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tauri::{AppHandle, Manager, Runtime, State};
 
-use crate::sensors::{SensorSample, SensorValue, TELEMETRY_EVENT};
+use crate::bus;
+use crate::health;
+use crate::sensors::{SensorSample, SensorValue};
 
 /// The conventional HA MQTT discovery topic prefix (`homeassistant/<component>/.../config`).
 const DISCOVERY_PREFIX: &str = "homeassistant";
@@ -80,10 +82,12 @@ pub struct MqttCatalogEntry {
 
 /// Managed state: the running client task + a live catalog of seen/discovered topics (read by the
 /// `mqtt_catalog` command). The catalog is a plain Mutex map so the command reads it cheaply.
+/// `client` is the running task's publish handle (automations' MQTT publish action).
 #[derive(Default)]
 pub struct MqttState {
     handle: Mutex<Option<JoinHandle<()>>>,
     catalog: Arc<StdMutex<BTreeMap<String, MqttCatalogEntry>>>,
+    client: StdMutex<Option<AsyncClient>>,
 }
 
 fn now_ms() -> u64 {
@@ -133,6 +137,7 @@ fn payload_to_samples(topic: &str, payload: &str, ts_ms: u64) -> Vec<SensorSampl
         sensor: base.clone(),
         ts_ms,
         value: SensorValue::Text(payload.to_string()),
+        stale: false,
     }];
     if let Ok(n) = payload.trim().parse::<f64>() {
         out.push(SensorSample::scalar(format!("{base}.value"), ts_ms, n));
@@ -144,6 +149,7 @@ fn payload_to_samples(topic: &str, payload: &str, ts_ms: u64) -> Vec<SensorSampl
                 sensor: format!("{base}.json"),
                 ts_ms,
                 value: SensorValue::Json(v.clone()),
+                stale: false,
             });
         }
         if let Some(obj) = v.as_object() {
@@ -156,12 +162,14 @@ fn payload_to_samples(topic: &str, payload: &str, ts_ms: u64) -> Vec<SensorSampl
                         sensor: id,
                         ts_ms,
                         value: SensorValue::Text(s.to_string()),
+                        stale: false,
                     });
                 } else if let Some(b) = val.as_bool() {
                     out.push(SensorSample {
                         sensor: id,
                         ts_ms,
                         value: SensorValue::Text(b.to_string()),
+                        stale: false,
                     });
                 }
             }
@@ -203,17 +211,50 @@ fn is_concrete_topic(topic: &str) -> bool {
     !topic.contains('+') && !topic.contains('#')
 }
 
+/// A well-formed topic filter: `+` only as a whole level, `#` only as the whole last level.
+pub fn valid_filter(filter: &str) -> bool {
+    let levels: Vec<&str> = filter.split('/').collect();
+    !filter.is_empty()
+        && levels.iter().enumerate().all(|(i, level)| match *level {
+            "+" => true,
+            "#" => i == levels.len() - 1,
+            l => !l.contains(['+', '#']),
+        })
+}
+
+/// Whether `topic` matches the subscription-style `filter` (MQTT semantics): `+` is exactly one
+/// level, a trailing `#` is any number of levels (the parent included — `a/#` matches `a`).
+/// Wildcards never match a leading `$SYS`-style level, as a broker wouldn't.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    if topic.starts_with('$') && filter.starts_with(['+', '#']) {
+        return false;
+    }
+    let mut f = filter.split('/');
+    let mut t = topic.split('/');
+    loop {
+        match (f.next(), t.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(a), Some(b)) if a == b => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
 // ---- telemetry emission ----
 
 /// Surface the connection state to widgets as an `mqtt.status` text sample (a Text meter bound to
 /// `mqtt.status` shows it) — mirrors ha.rs's single-status-transport design.
 fn emit_status<R: Runtime>(app: &AppHandle<R>, status: &str) {
+    health::report_status(app, "mqtt", status);
     let batch = vec![SensorSample {
         sensor: "mqtt.status".to_string(),
         ts_ms: now_ms(),
         value: SensorValue::Text(status.to_string()),
+        stale: false,
     }];
-    let _ = app.emit(TELEMETRY_EVENT, &batch);
+    let _ = bus::publish(app, &batch);
 }
 
 // ---- connection task ----
@@ -270,6 +311,11 @@ pub async fn run_mqtt_client<R: Runtime>(
     }
 
     let (client, mut eventloop) = AsyncClient::new(opts, 32);
+    if let Some(state) = app.try_state::<MqttState>()
+        && let Ok(mut slot) = state.client.lock()
+    {
+        *slot = Some(client.clone());
+    }
     emit_status(&app, "connecting");
 
     loop {
@@ -315,7 +361,7 @@ pub async fn run_mqtt_client<R: Runtime>(
                     });
                 }
                 let batch = payload_to_samples(&p.topic, &payload, now_ms());
-                let _ = app.emit(TELEMETRY_EVENT, &batch);
+                let _ = bus::publish(&app, &batch);
             }
             Ok(_) => {}
             Err(err) => {
@@ -431,9 +477,30 @@ pub async fn mqtt_disconnect(state: State<'_, MqttState>) -> Result<(), String>
     if let Some(handle) = state.handle.lock().await.take() {
         handle.abort();
     }
+    if let Ok(mut slot) = state.client.lock() {
+        *slot = None;
+    }
     Ok(())
 }
 
+/// Publish `payload` to `topic` on the running client (QoS 0). Errors when MQTT isn't connected —
+/// the request is queued on the client's channel, so "Ok" means accepted, not delivered.
+pub async fn publish_message<R: Runtime>(
+    app: &AppHandle<R>,
+    topic: &str,
+    payload: &str,
+    retain: bool,
+) -> Result<(), String> {
+    let client = app
+        .try_state::<MqttState>()
+        .and_then(|state| state.client.lock().ok().and_then(|slot| slot.clone()))
+        .ok_or("MQTT not connected")?;
+    client
+        .publish(topic, QoS::AtMostOnce, retain, payload.as_bytes().to_vec())
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// The catalog of seen + discovered topics (id + friendly label + unit) for the inspector dropdown.
 #[tauri::command]
 pub fn mqtt_catalog(state: State<'_, MqttState>) -> Result<Vec<MqttCatalogEntry>, String> {
@@ -539,6 +606,32 @@ mod tests {
         assert!(cfg.topics.is_empty());
     }
 
+    #[test]
+    fn topic_filters_follow_mqtt_wildcards() {
+        assert!(topic_matches("home/+/doorbell", "home/front/doorbell"));
+        assert!(!topic_matches(
+            "home/+/doorbell",
+            "home/front/porch/doorbell"
+        ));
+        assert!(!topic_matches("home/+", "home"));
+        assert!(topic_matches("home/#", "home"));
+        assert!(topic_matches("home/#", "home/front/doorbell"));
+        assert!(topic_matches("#", "anything/at/all"));
+        assert!(topic_matches("a/b", "a/b"));
+        assert!(!topic_matches("a/b", "a/b/c"));
+        // No glob semantics: `*` and `.` are literal characters of a level.
+        assert!(!topic_matches("home/*", "home/front"));
+        assert!(!topic_matches("home/front.x", "home/front/x"));
+        assert!(!topic_matches("#", "$SYS/broker/uptime"));
+        assert!(topic_matches("$SYS/#", "$SYS/broker/uptime"));
+
+        assert!(valid_filter("home/+/doorbell") && valid_filter("#") && valid_filter("a/#"));
+        assert!(!valid_filter("home/#/doorbell"));
+        assert!(!valid_filter("home/fr+nt"));
+        assert!(!valid_filter("home/#x"));
+        assert!(!valid_filter(""));
+    }
+
     #[test]
     fn concrete_topic_excludes_wildcards() {
         assert!(is_concrete_topic("a/b/c"));
@@ -0,0 +1,540 @@
+//! Network latency / reachability probes — "is my internet / VPN / game server OK" next to the
+//! `net.state` link sensors. Raw ICMP needs privileges, so each target is probed with a TCP
+//! connect (`host:port`) or, for an `http(s)://` address, an HTTP HEAD through httppoll.rs' client.
+//!
+//! Configured in `plugins/netprobe.json`:
+//! `{ "targets": [{ "name": "gateway", "address": "192.168.1.1:443", "interval_secs": 5 },
+//!                { "name": "vpn", "address": "https://intranet.example/health", "window": 60 }] }`
+//! Each target publishes, under `net.latency.` so a name can't shadow `net.up.total`:
+//!   `net.latency.<name>`         last round-trip (ms) — only on a successful probe
+//!   `net.latency.<name>.jitter`  mean change between consecutive round-trips in the window (ms)
+//!   `net.latency.<name>.loss`    failed probes over the last `window` probes (%)
+//!   `net.latency.<name>.up`      1 while any of the last `DOWN_AFTER` probes succeeded, else 0
+//! The TCP time covers the connect only (DNS is resolved first); the HTTP time is the whole HEAD.
+//! Probing reuses httppoll.rs' demand gating and `always` opt-out. The pure seam (`Window`) and a
+//! connect against a local listener are unit-tested.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::httppoll::http_client;
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Probe cadence guardrails (seconds).
+const MIN_INTERVAL: u64 = 1;
+const MAX_INTERVAL: u64 = 3_600;
+/// Largest loss/jitter window (probes).
+const MAX_WINDOW: usize = 1_000;
+/// A target is down once this many probes in a row have failed.
+const DOWN_AFTER: usize = 3;
+/// Re-check the demand gate this often while a target is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+
+fn default_interval() -> u64 {
+    5
+}
+fn default_timeout_ms() -> u64 {
+    2_000
+}
+fn default_window() -> usize {
+    20
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One probed target.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProbeTarget {
+    pub name: String,
+    /// `host:port` (TCP connect) or an `http(s)://` URL (HTTP HEAD).
+    pub address: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Probes the loss and jitter figures cover.
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Accept a self-signed certificate on an HTTPS target.
+    #[serde(default)]
+    pub insecure: bool,
+    /// Probe even when no window shows this target (for derived/alerts/automations).
+    #[serde(default)]
+    pub always: bool,
+}
+
+impl Supervised for ProbeTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/netprobe.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProbeConfig {
+    #[serde(default)]
+    pub targets: Vec<ProbeTarget>,
+}
+
+/// Managed state: the live targets plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct NetProbe {
+    config: Mutex<ProbeConfig>,
+    generation: AtomicU64,
+}
+
+impl NetProbe {
+    fn replace(&self, cfg: ProbeConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("netprobe.json"))
+}
+
+pub fn load_probe_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<ProbeConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed targets from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_probe_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("netprobe", "skipping probe target")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<NetProbe>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("netprobe", "failed to read netprobe.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// How a target is probed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Probe {
+    Tcp { host: String, port: u16 },
+    Http(String),
+}
+
+impl Probe {
+    /// `https://…` / `http://…` → HEAD; `host:port` / `[v6]:port` → connect.
+    pub fn parse(address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        if address.starts_with("http://") || address.starts_with("https://") {
+            return Ok(Probe::Http(address.to_string()));
+        }
+        let (host, port) = address
+            .rsplit_once(':')
+            .ok_or_else(|| format!("`{address}`: expected host:port or an http(s) URL"))?;
+        let port: u16 = port
+            .parse()
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| format!("`{address}`: bad port"))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(format!("`{address}`: missing host"));
+        }
+        Ok(Probe::Tcp {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// Slug names, clamp the knobs, reject duplicates and bad addresses.
+fn normalize_config(cfg: ProbeConfig) -> (ProbeConfig, Vec<String>) {
+    let mut kept: Vec<ProbeTarget> = Vec::new();
+    let mut errors = Vec::new();
+    for t in cfg.targets {
+        let Some(name) = id_segment(&t.name) else {
+            errors.push(format!("`{}`: invalid name", t.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        if let Err(err) = Probe::parse(&t.address) {
+            errors.push(format!("`{name}`: {err}"));
+            continue;
+        }
+        kept.push(ProbeTarget {
+            name,
+            address: t.address.trim().to_string(),
+            interval_secs: t.interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+            timeout_ms: t.timeout_ms.clamp(100, 30_000),
+            window: t.window.clamp(DOWN_AFTER, MAX_WINDOW),
+            ..t
+        });
+    }
+    (ProbeConfig { targets: kept }, errors)
+}
+
+// ---- window (pure) ----
+
+/// The last `cap` probe results (`Some(rtt_ms)` or `None` for a failure).
+#[derive(Debug)]
+pub struct Window {
+    cap: usize,
+    results: VecDeque<Option<f64>>,
+}
+
+impl Window {
+    pub fn new(cap: usize) -> Self {
+        Window {
+            cap: cap.max(1),
+            results: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, result: Option<f64>) {
+        if self.results.len() == self.cap {
+            self.results.pop_front();
+        }
+        self.results.push_back(result);
+    }
+
+    /// Failed share of the window, 0..100.
+    pub fn loss_pct(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        let failed = self.results.iter().filter(|r| r.is_none()).count();
+        failed as f64 * 100.0 / self.results.len() as f64
+    }
+
+    /// Mean absolute difference between consecutive successful round-trips (RFC 3550's idea,
+    /// unsmoothed). `None` until two successes are in the window.
+    pub fn jitter_ms(&self) -> Option<f64> {
+        let rtts: Vec<f64> = self.results.iter().flatten().copied().collect();
+        (rtts.len() >= 2).then(|| {
+            let sum: f64 = rtts.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+            sum / (rtts.len() - 1) as f64
+        })
+    }
+
+    /// Up unless the last `DOWN_AFTER` probes all failed.
+    pub fn up(&self) -> bool {
+        self.results
+            .iter()
+            .rev()
+            .take(DOWN_AFTER)
+            .any(Option::is_some)
+    }
+
+    /// The samples for this window after a probe at `ts`.
+    pub fn samples(&self, name: &str, ts: u64) -> Vec<SensorSample> {
+        let base = format!("net.latency.{name}");
+        let mut out = Vec::new();
+        if let Some(Some(rtt)) = self.results.back() {
+            out.push(SensorSample::scalar(base.clone(), ts, *rtt));
+        }
+        if let Some(jitter) = self.jitter_ms() {
+            out.push(SensorSample::scalar(format!("{base}.jitter"), ts, jitter));
+        }
+        out.push(SensorSample::scalar(
+            format!("{base}.loss"),
+            ts,
+            self.loss_pct(),
+        ));
+        out.push(SensorSample::scalar(
+            format!("{base}.up"),
+            ts,
+            f64::from(u8::from(self.up())),
+        ));
+        out
+    }
+}
+
+// ---- probing ----
+
+/// Time one TCP connect (after resolving `host`). Errors on resolve/connect failure or timeout.
+async fn probe_tcp(host: &str, port: u16, timeout: Duration) -> Result<f64, String> {
+    let addr = tokio::time::timeout(timeout, tokio::net::lookup_host((host, port)))
+        .await
+        .map_err(|_| "resolve timed out".to_string())?
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("`{host}` did not resolve"))?;
+    let started = Instant::now();
+    tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr))
+        .await
+        .map_err(|_| "connect timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    Ok(started.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// Time one HEAD request. Any HTTP status counts as reachable — the server answered.
+async fn probe_http(client: &reqwest::Client, url: &str) -> Result<f64, String> {
+    let started = Instant::now();
+    client.head(url).send().await.map_err(|e| e.to_string())?;
+    Ok(started.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// True while any window is consuming one of this target's sensors. Default OFF before the first
+/// report, like `endpoint_wanted`.
+fn target_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active = app.state::<ActiveSensors>();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let base = format!("net.latency.{name}");
+    crate::sensors::any_wanted(&guard, |id| {
+        id.strip_prefix(&base)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+/// Probe one target until aborted. A failure isn't retried sooner or later — the loss figure is
+/// only meaningful on a steady cadence. The window restarts after an idle spell.
+async fn probe_target<R: Runtime>(app: AppHandle<R>, target: ProbeTarget) {
+    let Ok(probe) = Probe::parse(&target.address) else {
+        return;
+    };
+    let timeout = Duration::from_millis(target.timeout_ms);
+    let client = match &probe {
+        Probe::Http(_) => {
+            let secs = target.timeout_ms.div_ceil(1000);
+            match http_client(secs, target.insecure) {
+                Ok(c) => Some(c),
+                Err(err) => {
+                    log::warn("netprobe", "client build failed")
+                        .field("name", target.name.clone())
+                        .field("error", err)
+                        .emit();
+                    return;
+                }
+            }
+        }
+        Probe::Tcp { .. } => None,
+    };
+    let mut window = Window::new(target.window);
+    let mut ticker = tokio::time::interval(Duration::from_secs(target.interval_secs));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        if !target.always && !target_wanted(&app, &target.name) {
+            window = Window::new(target.window);
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        ticker.tick().await;
+        let result = match (&probe, &client) {
+            (Probe::Tcp { host, port }, _) => probe_tcp(host, *port, timeout).await,
+            (Probe::Http(url), Some(client)) => probe_http(client, url).await,
+            (Probe::Http(_), None) => return,
+        };
+        let was_up = window.up();
+        window.push(result.as_ref().ok().copied());
+        if was_up && !window.up() {
+            log::warn("netprobe", "target unreachable")
+                .field("name", target.name.clone())
+                .field("error", result.err().unwrap_or_default())
+                .emit();
+        }
+        let _ = bus::publish(&app, &window.samples(&target.name, now_ms()));
+    }
+}
+
+/// The supervisor: one probe task per target, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_netprobe<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<NetProbe> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .targets
+                .clone()
+        },
+        |t| tauri::async_runtime::spawn(probe_target(app.clone(), t)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/netprobe.json` and restart the changed probes. Studio-window-guarded; any
+/// invalid target rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_netprobe_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, NetProbe>,
+    targets: Vec<ProbeTarget>,
+) -> Result<Vec<ProbeTarget>, String> {
+    if window.label() != "studio" {
+        return Err("save_netprobe_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(ProbeConfig { targets });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.targets.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured probe targets.
+#[tauri::command]
+pub fn netprobe_config_status(state: State<'_, NetProbe>) -> Vec<ProbeTarget> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .targets
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    fn scalar(batch: &[SensorSample], id: &str) -> Option<f64> {
+        batch
+            .iter()
+            .find(|s| s.sensor == id)
+            .map(|s| match s.value {
+                SensorValue::Scalar(v) => v,
+                ref other => panic!("{id}: not a scalar: {other:?}"),
+            })
+    }
+
+    #[test]
+    fn parses_tcp_and_http_addresses() {
+        assert_eq!(
+            Probe::parse("1.1.1.1:443").unwrap(),
+            Probe::Tcp {
+                host: "1.1.1.1".into(),
+                port: 443
+            }
+        );
+        assert_eq!(
+            Probe::parse("[::1]:22").unwrap(),
+            Probe::Tcp {
+                host: "::1".into(),
+                port: 22
+            }
+        );
+        assert_eq!(
+            Probe::parse(" https://example.com/health ").unwrap(),
+            Probe::Http("https://example.com/health".into())
+        );
+        assert!(Probe::parse("example.com").is_err());
+        assert!(Probe::parse("example.com:0").is_err());
+        assert!(Probe::parse(":80").is_err());
+    }
+
+    #[test]
+    fn window_reports_loss_jitter_and_state() {
+        let mut w = Window::new(4);
+        for r in [Some(10.0), Some(14.0), None, Some(12.0)] {
+            w.push(r);
+        }
+        assert_eq!(w.loss_pct(), 25.0);
+        assert_eq!(w.jitter_ms(), Some(3.0));
+        assert!(w.up());
+        let batch = w.samples("gw", 1);
+        assert_eq!(scalar(&batch, "net.latency.gw"), Some(12.0));
+        assert_eq!(scalar(&batch, "net.latency.gw.up"), Some(1.0));
+
+        for _ in 0..DOWN_AFTER {
+            w.push(None);
+        }
+        assert!(!w.up());
+        assert_eq!(w.loss_pct(), 75.0);
+        let batch = w.samples("gw", 2);
+        // No fresh round-trip on a failed probe; the last one isn't repeated.
+        assert_eq!(scalar(&batch, "net.latency.gw"), None);
+        assert_eq!(scalar(&batch, "net.latency.gw.up"), Some(0.0));
+        assert_eq!(scalar(&batch, "net.latency.gw.jitter"), None);
+    }
+
+    #[test]
+    fn normalize_slugs_and_rejects_bad_targets() {
+        let target = |name: &str, address: &str| ProbeTarget {
+            name: name.into(),
+            address: address.into(),
+            interval_secs: 0,
+            timeout_ms: 10,
+            window: 1,
+            insecure: false,
+            always: false,
+        };
+        let (cfg, errors) = normalize_config(ProbeConfig {
+            targets: vec![
+                target("Game Server", "eu.example.net:27015"),
+                target("game server", "other:1"),
+                target("bad", "nope"),
+            ],
+        });
+        assert_eq!(cfg.targets.len(), 1);
+        let t = &cfg.targets[0];
+        assert_eq!(t.name, "game_server");
+        assert_eq!(
+            (t.interval_secs, t.timeout_ms, t.window),
+            (1, 100, DOWN_AFTER)
+        );
+        assert_eq!(errors.len(), 2, "{errors:?}");
+    }
+
+    #[tokio::test]
+    async fn times_a_tcp_connect_to_a_local_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let rtt = probe_tcp("127.0.0.1", port, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert!(rtt >= 0.0);
+        drop(listener);
+        assert!(
+            probe_tcp("127.0.0.1", port, Duration::from_secs(2))
+                .await
+                .is_err()
+        );
+    }
+}
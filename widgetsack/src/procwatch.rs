@@ -0,0 +1,450 @@
+//! Process watch list: per-named-process sensors, aggregated across every matching PID.
+//!
+//! `proc.cpu.top.*` / `proc.mem.top.*` (sensors.rs) only ever show the single busiest process. This
+//! adds a configured watch list (`plugins/procwatch.json`) of process names or exe-path globs — e.g.
+//! `obs64`, `cargo`, `C:\Games\*` — each under an alias, emitting:
+//!   `proc.<alias>.cpu`       Scalar  summed CPU, % of the whole machine (like `proc.cpu.top.pct`)
+//!   `proc.<alias>.mem`       Scalar  summed resident memory (bytes)
+//!   `proc.<alias>.count`     Scalar  matching process count
+//!   `proc.<alias>.running`   Scalar  1 when at least one match is alive, else 0
+//!   `proc.<alias>.io.read`   Scalar  summed disk read (bytes/s)
+//!   `proc.<alias>.io.write`  Scalar  summed disk write (bytes/s)
+//! plus `proc.top` (Json) — the top-N processes by CPU for a task-manager style table.
+//!
+//! The sysinfo walk stays in `run_system_sensors` behind the existing `proc.*` demand gate; this
+//! module owns the config and the pure seams (`matches`, `watch_samples`, `top_table`), which are
+//! unit-tested on plain `ProcRow`s without touching the process table.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+
+/// Rows in `proc.top` when the config doesn't say. Clamped to `MAX_TOP_N` so a bad config can't
+/// emit the whole process table every second.
+const DEFAULT_TOP_N: usize = 10;
+const MAX_TOP_N: usize = 50;
+
+/// Aliases the fixed `proc.*` ids already own (`proc.cpu.top.*`, `proc.mem.top.*`, `proc.top`) — a
+/// watch entry may not shadow them.
+const RESERVED_ALIASES: &[&str] = &["cpu", "mem", "top"];
+
+fn default_top_n() -> usize {
+    DEFAULT_TOP_N
+}
+
+/// One watched process group: every process whose name or exe path matches any of `patterns` is
+/// aggregated under `proc.<alias>.*`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcWatchEntry {
+    pub alias: String,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// `plugins/procwatch.json`. `#[serde(default)]` everywhere so a partial file parses.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcWatchConfig {
+    #[serde(default)]
+    pub watch: Vec<ProcWatchEntry>,
+    #[serde(default = "default_top_n")]
+    pub top_n: usize,
+}
+
+impl Default for ProcWatchConfig {
+    fn default() -> Self {
+        ProcWatchConfig {
+            watch: Vec::new(),
+            top_n: DEFAULT_TOP_N,
+        }
+    }
+}
+
+/// Managed state: the live watch list, read by the sensors loop each gated tick and replaced by
+/// `save_procwatch_config`. A plain std Mutex (brief, synchronous locks — never held across I/O).
+#[derive(Default)]
+pub struct ProcWatch(pub Mutex<ProcWatchConfig>);
+
+/// One process as the sensors loop saw it this tick. `cpu` is already machine-normalised (÷ logical
+/// cores); `read`/`written` are the bytes moved since the previous process refresh.
+#[derive(Clone, Debug, Default)]
+pub struct ProcRow {
+    pub pid: u32,
+    pub name: String,
+    pub exe: String,
+    pub cpu: f64,
+    pub mem: u64,
+    pub read: u64,
+    pub written: u64,
+}
+
+// ---- config I/O ----
+
+fn procwatch_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("procwatch.json"))
+}
+
+pub fn load_procwatch_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<ProcWatchConfig>, String> {
+    let path = procwatch_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed watch list from disk. Called once when the sensors loop starts; a missing or
+/// unreadable file leaves the (empty) default in place.
+pub fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    if let Ok(Some(cfg)) = load_procwatch_config(app) {
+        let state: State<ProcWatch> = app.state();
+        *state.0.lock().unwrap_or_else(|e| e.into_inner()) = normalize_config(cfg);
+    }
+}
+
+// ---- pure seams (unit-tested, no I/O) ----
+
+/// Case-insensitive glob match supporting `*` (any run) and `?` (one char). Iterative with a single
+/// backtrack point, so a pathological pattern stays linear-ish rather than exponential.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// A process name without a trailing `.exe`, lowercased — so `obs64` matches `obs64.exe`.
+fn bare_name(name: &str) -> String {
+    let lower = name.to_lowercase();
+    lower
+        .strip_suffix(".exe")
+        .map(str::to_string)
+        .unwrap_or(lower)
+}
+
+/// Whether `row` matches `pattern`. A plain pattern is an exact (case-insensitive, `.exe`-optional)
+/// process-name match; a pattern with `*`/`?` is globbed against the name AND the full exe path.
+fn matches(pattern: &str, row: &ProcRow) -> bool {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return false;
+    }
+    if pattern.contains('*') || pattern.contains('?') {
+        glob_match(pattern, &row.name)
+            || glob_match(pattern, &bare_name(&row.name))
+            || (!row.exe.is_empty() && glob_match(pattern, &row.exe))
+    } else {
+        bare_name(&row.name) == bare_name(pattern)
+    }
+}
+
+/// The alias as a sensor-id segment (see `sensors::id_segment`). `None` when nothing survives or
+/// the alias is reserved.
+fn sanitize_alias(alias: &str) -> Option<String> {
+    id_segment(alias).filter(|slug| !RESERVED_ALIASES.contains(&slug.as_str()))
+}
+
+/// Sanitize aliases, drop empty patterns + unusable/duplicate entries, clamp `top_n`. Applied on
+/// load and save so the sensors loop only ever sees a clean list.
+fn normalize_config(cfg: ProcWatchConfig) -> ProcWatchConfig {
+    let mut watch: Vec<ProcWatchEntry> = Vec::new();
+    for entry in cfg.watch {
+        let patterns: Vec<String> = entry
+            .patterns
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        let Some(alias) = sanitize_alias(&entry.alias) else {
+            continue;
+        };
+        if patterns.is_empty() || watch.iter().any(|w| w.alias == alias) {
+            continue;
+        }
+        watch.push(ProcWatchEntry { alias, patterns });
+    }
+    ProcWatchConfig {
+        watch,
+        top_n: cfg.top_n.clamp(1, MAX_TOP_N),
+    }
+}
+
+/// `bytes` moved over `elapsed_ms` as bytes/s (0 when no time has passed).
+fn per_sec(bytes: u64, elapsed_ms: u64) -> f64 {
+    if elapsed_ms == 0 {
+        0.0
+    } else {
+        bytes as f64 * 1000.0 / elapsed_ms as f64
+    }
+}
+
+/// The `proc.<alias>.*` samples for every watch entry, aggregated over all matching rows. An entry
+/// with no live match still emits (count 0, running 0, zeros) so a "is OBS running?" widget reads a
+/// definite no rather than a stale yes. `elapsed_ms` is the real time since the previous process
+/// refresh — I/O deltas accrue over it, so a gated gap doesn't spike the rate.
+pub fn watch_samples(
+    cfg: &ProcWatchConfig,
+    rows: &[ProcRow],
+    ts: u64,
+    elapsed_ms: u64,
+) -> Vec<SensorSample> {
+    let mut out = Vec::with_capacity(cfg.watch.len() * 6);
+    for entry in &cfg.watch {
+        let (mut cpu, mut mem, mut count, mut read, mut written) = (0.0, 0u64, 0u32, 0u64, 0u64);
+        for row in rows
+            .iter()
+            .filter(|r| entry.patterns.iter().any(|p| matches(p, r)))
+        {
+            cpu += row.cpu;
+            mem = mem.saturating_add(row.mem);
+            count += 1;
+            read = read.saturating_add(row.read);
+            written = written.saturating_add(row.written);
+        }
+        let base = format!("proc.{}", entry.alias);
+        out.push(SensorSample::scalar(format!("{base}.cpu"), ts, cpu));
+        out.push(SensorSample::scalar(format!("{base}.mem"), ts, mem as f64));
+        out.push(SensorSample::scalar(
+            format!("{base}.count"),
+            ts,
+            f64::from(count),
+        ));
+        out.push(SensorSample::scalar(
+            format!("{base}.running"),
+            ts,
+            if count > 0 { 1.0 } else { 0.0 },
+        ));
+        out.push(SensorSample::scalar(
+            format!("{base}.io.read"),
+            ts,
+            per_sec(read, elapsed_ms),
+        ));
+        out.push(SensorSample::scalar(
+            format!("{base}.io.write"),
+            ts,
+            per_sec(written, elapsed_ms),
+        ));
+    }
+    out
+}
+
+/// `proc.top`: the `n` busiest processes by CPU (memory breaks ties), as a Json array of
+/// `{ pid, name, cpu, mem }` rows — the task-manager table. Pure.
+pub fn top_table(rows: &[ProcRow], n: usize, ts: u64) -> SensorSample {
+    let mut sorted: Vec<&ProcRow> = rows.iter().filter(|r| !r.name.is_empty()).collect();
+    sorted.sort_by(|a, b| {
+        b.cpu
+            .partial_cmp(&a.cpu)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.mem.cmp(&a.mem))
+    });
+    let table: Vec<serde_json::Value> = sorted
+        .into_iter()
+        .take(n)
+        .map(|r| json!({ "pid": r.pid, "name": r.name, "cpu": r.cpu, "mem": r.mem }))
+        .collect();
+    SensorSample {
+        sensor: "proc.top".to_string(),
+        ts_ms: ts,
+        value: SensorValue::Json(serde_json::Value::Array(table)),
+        stale: false,
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/procwatch.json` and swap the live list (the next gated tick picks it up).
+/// Studio-window-guarded like the other plugin configs. Aliases are sanitized server-side; the
+/// normalized config is returned so the settings form can show what was actually kept.
+#[tauri::command]
+pub async fn save_procwatch_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, ProcWatch>,
+    watch: Vec<ProcWatchEntry>,
+    top_n: Option<usize>,
+) -> Result<ProcWatchConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_procwatch_config is only allowed from the studio window".into());
+    }
+    let path = procwatch_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let cfg = normalize_config(ProcWatchConfig {
+        watch,
+        top_n: top_n.unwrap_or(DEFAULT_TOP_N),
+    });
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    *state.0.lock().unwrap_or_else(|e| e.into_inner()) = cfg.clone();
+    Ok(cfg)
+}
+
+/// The live watch list (nothing secret in it).
+#[tauri::command]
+pub fn procwatch_config_status(state: State<'_, ProcWatch>) -> ProcWatchConfig {
+    state.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pid: u32, name: &str, exe: &str, cpu: f64, mem: u64) -> ProcRow {
+        ProcRow {
+            pid,
+            name: name.to_string(),
+            exe: exe.to_string(),
+            cpu,
+            mem,
+            read: 0,
+            written: 0,
+        }
+    }
+
+    fn value(samples: &[SensorSample], id: &str) -> f64 {
+        match samples.iter().find(|s| s.sensor == id).map(|s| &s.value) {
+            Some(SensorValue::Scalar(v)) => *v,
+            _ => f64::NAN,
+        }
+    }
+
+    #[test]
+    fn glob_match_handles_star_question_and_case() {
+        assert!(glob_match("fire*", "firefox.exe"));
+        assert!(glob_match("*FOX*", "firefox.exe"));
+        assert!(glob_match("obs??", "obs64"));
+        assert!(!glob_match("obs?", "obs64"));
+        assert!(glob_match("c:\\games\\*", "C:\\Games\\steam\\x.exe"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("a*b", "acd"));
+    }
+
+    #[test]
+    fn plain_pattern_is_an_exact_name_match_with_optional_exe() {
+        let obs = row(
+            1,
+            "obs64.exe",
+            "C:\\Program Files\\obs-studio\\bin\\obs64.exe",
+            0.0,
+            0,
+        );
+        assert!(matches("obs64", &obs));
+        assert!(matches("OBS64.exe", &obs));
+        assert!(!matches("obs", &obs)); // no substring match without a glob
+        assert!(matches("*obs-studio*", &obs)); // globs also see the exe path
+        assert!(!matches("  ", &obs));
+    }
+
+    #[test]
+    fn watch_samples_aggregate_across_matching_pids() {
+        let cfg = normalize_config(ProcWatchConfig {
+            watch: vec![ProcWatchEntry {
+                alias: "Cargo".into(),
+                patterns: vec!["cargo".into(), "rustc".into()],
+            }],
+            top_n: 10,
+        });
+        let mut a = row(1, "cargo.exe", "", 5.0, 100);
+        a.read = 2000;
+        let mut b = row(2, "rustc.exe", "", 20.0, 300);
+        b.written = 1000;
+        let rows = vec![a, b, row(3, "firefox.exe", "", 50.0, 9000)];
+        let s = watch_samples(&cfg, &rows, 7, 2000);
+        assert_eq!(value(&s, "proc.cargo.cpu"), 25.0);
+        assert_eq!(value(&s, "proc.cargo.mem"), 400.0);
+        assert_eq!(value(&s, "proc.cargo.count"), 2.0);
+        assert_eq!(value(&s, "proc.cargo.running"), 1.0);
+        // Bytes are spread over the real elapsed time (2s), not per tick.
+        assert_eq!(value(&s, "proc.cargo.io.read"), 1000.0);
+        assert_eq!(value(&s, "proc.cargo.io.write"), 500.0);
+    }
+
+    #[test]
+    fn absent_process_reads_as_not_running_not_missing() {
+        let cfg = normalize_config(ProcWatchConfig {
+            watch: vec![ProcWatchEntry {
+                alias: "obs".into(),
+                patterns: vec!["obs64".into()],
+            }],
+            top_n: 10,
+        });
+        let s = watch_samples(&cfg, &[row(1, "explorer.exe", "", 1.0, 1)], 0, 1000);
+        assert_eq!(value(&s, "proc.obs.running"), 0.0);
+        assert_eq!(value(&s, "proc.obs.count"), 0.0);
+        assert_eq!(value(&s, "proc.obs.io.read"), 0.0);
+    }
+
+    #[test]
+    fn normalize_sanitizes_aliases_and_drops_bad_entries() {
+        let entry = |alias: &str, pats: &[&str]| ProcWatchEntry {
+            alias: alias.into(),
+            patterns: pats.iter().map(|p| p.to_string()).collect(),
+        };
+        let cfg = normalize_config(ProcWatchConfig {
+            watch: vec![
+                entry("My Game.v2", &["game"]),
+                entry("top", &["x"]),        // reserved: would shadow proc.top
+                entry("cpu", &["x"]),        // reserved: would shadow proc.cpu.top.*
+                entry("!!", &["x"]),         // nothing survives sanitizing
+                entry("empty", &["  "]),     // no usable pattern
+                entry("my game v2", &["y"]), // duplicate alias after sanitizing
+            ],
+            top_n: 1000,
+        });
+        assert_eq!(cfg.watch.len(), 1);
+        assert_eq!(cfg.watch[0].alias, "my_game_v2");
+        assert_eq!(cfg.top_n, MAX_TOP_N);
+    }
+
+    #[test]
+    fn top_table_sorts_by_cpu_and_truncates() {
+        let rows = vec![
+            row(1, "a.exe", "", 1.0, 10),
+            row(2, "b.exe", "", 9.0, 10),
+            row(3, "c.exe", "", 9.0, 99), // same cpu as b, more memory → first
+            row(4, "", "", 50.0, 1),      // unnamed rows are skipped
+        ];
+        let s = top_table(&rows, 2, 5);
+        let v = serde_json::to_value(&s).unwrap();
+        assert_eq!(v["sensor"], "proc.top");
+        assert_eq!(v["value"]["kind"], "json");
+        let table = v["value"]["value"].as_array().unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[0]["name"], "c.exe");
+        assert_eq!(table[1]["pid"], 2);
+    }
+
+    #[test]
+    fn config_defaults_keep_a_minimal_json_valid() {
+        let cfg: ProcWatchConfig = serde_json::from_str("{}").unwrap();
+        assert!(cfg.watch.is_empty());
+        assert_eq!(cfg.top_n, DEFAULT_TOP_N);
+    }
+}
@@ -0,0 +1,845 @@
+//! Prometheus scrape source — node_exporter, in-house services, the exporter on the NAS. Each
+//! configured target's text exposition is fetched on its interval (through httppoll.rs' reqwest
+//! client, so `insecure` means the same thing) and selected metric+label sets are published as
+//! `prom.<alias>` samples on the telemetry bus.
+//!
+//! Configured in `plugins/prom.json`:
+//! `{ "targets": [{ "name": "nas", "url": "http://nas:9100/metrics", "interval_secs": 15,
+//!    "series": [{ "alias": "nas_cpu_busy", "select": "node_cpu_seconds_total{mode!=\"idle\"}",
+//!                 "rate": true },
+//!               { "alias": "nas_load1", "select": "node_load1" }] }] }`
+//! A selector is a PromQL instant-vector selector: a metric name plus optional `=`, `!=`, `=~`,
+//! `!~` label matchers (regexes are fully anchored, as in Prometheus). Every matching series is
+//! combined with `aggregate` (`sum` by default, or `avg` / `min` / `max` / `count`). With `rate`
+//! set, each series first becomes its per-second increase since the previous scrape — a counter
+//! reset (value going down) or a brand-new series contributes nothing that round — so a `rate`
+//! alias first appears on the second scrape.
+//!
+//! Aliases are unique across targets; each target also publishes `prom.<name>.status` (`ok` /
+//! `error` / `HTTP 503`). Scraping reuses httppoll.rs' demand gating, `always` opt-out and backoff.
+//! Pure seams (`parse_exposition`, `Selector`, `Evaluator`) are unit-tested, plus one scrape of a
+//! local fixture server.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::httppoll::{http_client, read_capped};
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// Scrape cadence guardrails (seconds).
+const MIN_INTERVAL: u64 = 5;
+const MAX_INTERVAL: u64 = 86_400;
+/// Re-check the demand gate this often while a target is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+/// Largest exposition read (bytes); node_exporter on a busy box is a few hundred KiB.
+const MAX_BODY: usize = 8 * 1024 * 1024;
+
+fn default_interval() -> u64 {
+    15
+}
+fn default_timeout() -> u64 {
+    10
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// How the series matching one selector become one value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+/// One published value: `prom.<alias>`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PromSeries {
+    pub alias: String,
+    /// Instant-vector selector, e.g. `node_filesystem_avail_bytes{mountpoint="/"}`.
+    pub select: String,
+    /// Per-second rate of a counter instead of its raw value.
+    #[serde(default)]
+    pub rate: bool,
+    #[serde(default)]
+    pub aggregate: Aggregate,
+}
+
+/// One scraped endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PromTarget {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+    /// Accept self-signed certificates, like `HttpEndpoint.insecure`.
+    #[serde(default)]
+    pub insecure: bool,
+    /// Scrape even when no window shows this target's sensors (for derived/alerts/automations).
+    #[serde(default)]
+    pub always: bool,
+    #[serde(default)]
+    pub series: Vec<PromSeries>,
+}
+
+impl Supervised for PromTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/prom.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PromConfig {
+    #[serde(default)]
+    pub targets: Vec<PromTarget>,
+}
+
+/// Managed state: the live targets plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct PromScrape {
+    config: Mutex<PromConfig>,
+    generation: AtomicU64,
+}
+
+impl PromScrape {
+    fn replace(&self, cfg: PromConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn prom_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("prom.json"))
+}
+
+pub fn load_prom_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<PromConfig>, String> {
+    let path = prom_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed targets from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_prom_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("prom", "skipping prometheus target")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<PromScrape>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("prom", "failed to read prom.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names and aliases, clamp intervals, and reject duplicates (aliases across ALL targets,
+/// since they share the `prom.` namespace), bad URLs and bad selectors.
+fn normalize_config(cfg: PromConfig) -> (PromConfig, Vec<String>) {
+    let mut kept: Vec<PromTarget> = Vec::new();
+    let mut errors = Vec::new();
+    for target in cfg.targets {
+        let Some(name) = id_segment(&target.name) else {
+            errors.push(format!("`{}`: invalid name", target.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        match normalize_target(PromTarget { name, ..target }) {
+            Ok(t) => {
+                let taken = t.series.iter().find(|s| {
+                    kept.iter()
+                        .flat_map(|k| &k.series)
+                        .any(|o| o.alias == s.alias)
+                });
+                match taken {
+                    Some(s) => errors.push(format!("`{}`: duplicate alias `{}`", t.name, s.alias)),
+                    None => kept.push(t),
+                }
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    (PromConfig { targets: kept }, errors)
+}
+
+fn normalize_target(t: PromTarget) -> Result<PromTarget, String> {
+    let name = t.name.clone();
+    let url = t.url.trim().to_string();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("`{name}`: url must be http:// or https://"));
+    }
+    let mut series: Vec<PromSeries> = Vec::new();
+    for s in t.series {
+        let alias =
+            id_segment(&s.alias).ok_or_else(|| format!("`{name}`: invalid alias `{}`", s.alias))?;
+        if series.iter().any(|o| o.alias == alias) {
+            return Err(format!("`{name}`: duplicate alias `{alias}`"));
+        }
+        let select = s.select.trim().to_string();
+        Selector::parse(&select).map_err(|e| format!("`{name}.{alias}`: {e}"))?;
+        series.push(PromSeries { alias, select, ..s });
+    }
+    if series.is_empty() {
+        return Err(format!("`{name}`: no series"));
+    }
+    Ok(PromTarget {
+        url,
+        interval_secs: t.interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+        timeout_secs: t.timeout_secs.clamp(1, 120),
+        series,
+        ..t
+    })
+}
+
+// ---- exposition + selectors (pure) ----
+
+/// One sample line of a text exposition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub metric: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// Parse the Prometheus text format. Comments (`# HELP` / `# TYPE`), blank lines and malformed
+/// lines are skipped; timestamps are ignored (the scrape time stands in).
+pub fn parse_exposition(text: &str) -> Vec<Sample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(parse_line)
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<Sample> {
+    let end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let metric = line[..end].to_string();
+    let mut rest = &line[end..];
+    let mut labels = BTreeMap::new();
+    if let Some(body) = rest.strip_prefix('{') {
+        let (parsed, after) = parse_labels(body)?;
+        labels = parsed;
+        rest = after;
+    }
+    let value = parse_value(rest.split_whitespace().next()?)?;
+    Some(Sample {
+        metric,
+        labels,
+        value,
+    })
+}
+
+/// `k="v",k2="v\"2"}` → the labels and whatever follows the closing brace.
+fn parse_labels(mut s: &str) -> Option<(BTreeMap<String, String>, &str)> {
+    let mut labels = BTreeMap::new();
+    loop {
+        s = s.trim_start();
+        if let Some(after) = s.strip_prefix('}') {
+            return Some((labels, after));
+        }
+        let eq = s.find('=')?;
+        let key = s[..eq].trim().to_string();
+        let (value, after) = parse_quoted(s[eq + 1..].trim_start())?;
+        labels.insert(key, value);
+        s = after.trim_start();
+        s = s.strip_prefix(',').unwrap_or(s);
+    }
+}
+
+/// A double-quoted string with `\\`, `\"` and `\n` escapes → the value and the remainder.
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let body = s.strip_prefix('"')?;
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &body[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => out.push('\n'),
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn parse_value(token: &str) -> Option<f64> {
+    match token {
+        "+Inf" | "Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        t => t.parse().ok(),
+    }
+}
+
+/// One label matcher of a selector.
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    Eq(String, String),
+    Ne(String, String),
+    Re(Regex, String),
+    NotRe(Regex, String),
+}
+
+impl Matcher {
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        // An absent label matches as the empty string, as in Prometheus.
+        let get = |k: &str| labels.get(k).map(String::as_str).unwrap_or("");
+        match self {
+            Matcher::Eq(k, v) => get(k) == v,
+            Matcher::Ne(k, v) => get(k) != v,
+            Matcher::Re(re, k) => re.is_match(get(k)),
+            Matcher::NotRe(re, k) => !re.is_match(get(k)),
+        }
+    }
+}
+
+/// A parsed instant-vector selector: `metric{label op "value", …}`.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    pub metric: String,
+    pub matchers: Vec<Matcher>,
+}
+
+impl Selector {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let src = src.trim();
+        let end = src.find('{').unwrap_or(src.len());
+        let metric = src[..end].trim().to_string();
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == ':';
+        if metric.is_empty() || !metric.chars().all(valid) {
+            return Err(format!("bad metric name in `{src}`"));
+        }
+        let mut matchers = Vec::new();
+        if end < src.len() {
+            let mut s = &src[end + 1..];
+            loop {
+                s = s.trim_start();
+                if let Some(after) = s.strip_prefix('}') {
+                    if !after.trim().is_empty() {
+                        return Err(format!("trailing text after `}}` in `{src}`"));
+                    }
+                    break;
+                }
+                let op_at = s
+                    .find(['=', '!'])
+                    .ok_or_else(|| format!("expected a label matcher in `{src}`"))?;
+                let key = s[..op_at].trim().to_string();
+                if key.is_empty() || !key.chars().all(valid) {
+                    return Err(format!("bad label name in `{src}`"));
+                }
+                let after_key = &s[op_at..];
+                let (op, after_op) = ["=~", "!~", "!=", "="]
+                    .iter()
+                    .find_map(|op| after_key.strip_prefix(op).map(|r| (*op, r)))
+                    .ok_or_else(|| format!("bad matcher operator in `{src}`"))?;
+                let (value, after) = parse_quoted(after_op.trim_start())
+                    .ok_or_else(|| format!("unterminated label value in `{src}`"))?;
+                let anchored = |key: &str| {
+                    Regex::new(&format!("^(?:{value})$"))
+                        .map_err(|e| format!("bad regex for `{key}`: {e}"))
+                };
+                matchers.push(match op {
+                    "=~" => Matcher::Re(anchored(&key)?, key),
+                    "!~" => Matcher::NotRe(anchored(&key)?, key),
+                    "!=" => Matcher::Ne(key, value),
+                    _ => Matcher::Eq(key, value),
+                });
+                s = after.trim_start();
+                s = s.strip_prefix(',').unwrap_or(s);
+            }
+        }
+        Ok(Selector { metric, matchers })
+    }
+
+    pub fn matches(&self, sample: &Sample) -> bool {
+        sample.metric == self.metric && self.matchers.iter().all(|m| m.matches(&sample.labels))
+    }
+}
+
+/// Turns successive scrapes of one target into `prom.<alias>` samples, remembering each counter
+/// series' previous value for `rate`.
+pub struct Evaluator {
+    series: Vec<(PromSeries, Selector)>,
+    /// (alias, label set) → (scrape time, raw value) of the previous scrape.
+    previous: HashMap<(String, BTreeMap<String, String>), (u64, f64)>,
+}
+
+impl Evaluator {
+    /// Selectors were validated at save/load; an unparseable one is dropped here.
+    pub fn new(series: &[PromSeries]) -> Self {
+        Evaluator {
+            series: series
+                .iter()
+                .filter_map(|s| Selector::parse(&s.select).ok().map(|sel| (s.clone(), sel)))
+                .collect(),
+            previous: HashMap::new(),
+        }
+    }
+
+    /// One scrape at `ts_ms` → a sample per alias that has a finite value this round.
+    pub fn evaluate(&mut self, samples: &[Sample], ts_ms: u64) -> Vec<SensorSample> {
+        let mut out = Vec::new();
+        let mut previous = HashMap::new();
+        for (def, sel) in &self.series {
+            let mut values = Vec::new();
+            for s in samples.iter().filter(|s| sel.matches(s)) {
+                if !def.rate {
+                    values.push(s.value);
+                    continue;
+                }
+                let key = (def.alias.clone(), s.labels.clone());
+                if let Some(&(t0, v0)) = self.previous.get(&key) {
+                    let secs = ts_ms.saturating_sub(t0) as f64 / 1000.0;
+                    if secs > 0.0 && s.value >= v0 {
+                        values.push((s.value - v0) / secs);
+                    }
+                }
+                previous.insert(key, (ts_ms, s.value));
+            }
+            if let Some(v) = aggregate(def.aggregate, &values).filter(|v| v.is_finite()) {
+                out.push(SensorSample::scalar(
+                    format!("prom.{}", def.alias),
+                    ts_ms,
+                    v,
+                ));
+            }
+        }
+        // Only series seen this scrape are kept, so a vanished label set doesn't linger.
+        self.previous = previous;
+        out
+    }
+}
+
+fn aggregate(agg: Aggregate, values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return (agg == Aggregate::Count).then_some(0.0);
+    }
+    Some(match agg {
+        Aggregate::Sum => values.iter().sum(),
+        Aggregate::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        Aggregate::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregate::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Aggregate::Count => values.len() as f64,
+    })
+}
+
+// ---- scraping ----
+
+fn status_sample(name: &str, status: &str) -> SensorSample {
+    SensorSample::text(format!("prom.{name}.status"), now_ms(), status)
+}
+
+/// True while any window is consuming one of this target's aliases or its status. Default OFF
+/// before the first report, like `endpoint_wanted`.
+fn target_wanted<R: Runtime>(app: &AppHandle<R>, target: &PromTarget) -> bool {
+    let active = app.state::<ActiveSensors>();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let status = format!("prom.{}.status", target.name);
+    crate::sensors::any_wanted(&guard, |id| {
+        id == status
+            || id
+                .strip_prefix("prom.")
+                .is_some_and(|alias| target.series.iter().any(|s| s.alias == alias))
+    })
+}
+
+/// Fetch one exposition: the body text on a 2xx, else an error naming the status.
+async fn scrape(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let resp = client
+        .get(url)
+        .header("Accept", "text/plain;version=0.0.4")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    // Streamed against the cap, so a huge exposition is never buffered whole.
+    let bytes = read_capped(resp, MAX_BODY).await?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Scrape one target until aborted, with httppoll's gating and backoff. Rate state is reset when
+/// the target idles, so the first scrape after a pause doesn't average over the whole pause.
+async fn scrape_target<R: Runtime>(app: AppHandle<R>, target: PromTarget) {
+    let client = match http_client(target.timeout_secs, target.insecure) {
+        Ok(c) => c,
+        Err(err) => {
+            log::warn("prom", "client build failed")
+                .field("name", target.name.clone())
+                .field("error", err)
+                .emit();
+            let _ = bus::publish(&app, &[status_sample(&target.name, "error")]);
+            return;
+        }
+    };
+    let mut eval = Evaluator::new(&target.series);
+    let interval = Duration::from_secs(target.interval_secs);
+    let mut fails: u32 = 0;
+    loop {
+        if !target.always && !target_wanted(&app, &target) {
+            eval = Evaluator::new(&target.series);
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        match scrape(&client, &target.url).await {
+            Ok(body) => {
+                fails = 0;
+                let mut batch = eval.evaluate(&parse_exposition(&body), now_ms());
+                batch.push(status_sample(&target.name, "ok"));
+                let _ = bus::publish(&app, &batch);
+            }
+            Err(err) => {
+                fails = (fails + 1).min(5);
+                let status = if err.starts_with("HTTP ") {
+                    err.as_str()
+                } else {
+                    "error"
+                };
+                let _ = bus::publish(&app, &[status_sample(&target.name, status)]);
+                log::warn("prom", "scrape failed")
+                    .field("name", target.name.clone())
+                    .field("error", err.clone())
+                    .emit();
+            }
+        }
+        let wait = if fails == 0 {
+            interval
+        } else {
+            interval
+                .saturating_mul(1u32 << fails.min(4))
+                .min(Duration::from_secs(1800))
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// The supervisor: one scrape task per target, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_prom<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<PromScrape> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .targets
+                .clone()
+        },
+        |t| tauri::async_runtime::spawn(scrape_target(app.clone(), t)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/prom.json` and restart the changed scrapers. Studio-window-guarded; any
+/// invalid target rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_prom_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, PromScrape>,
+    targets: Vec<PromTarget>,
+) -> Result<Vec<PromTarget>, String> {
+    if window.label() != "studio" {
+        return Err("save_prom_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(PromConfig { targets });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = prom_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.targets.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured targets (nothing secret in them).
+#[tauri::command]
+pub fn prom_config_status(state: State<'_, PromScrape>) -> Vec<PromTarget> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .targets
+        .clone()
+}
+
+/// Scrape an UNSAVED target once and list every series it exposes that its selectors match, with
+/// the raw values, so the settings form can check labels before saving.
+#[tauri::command]
+pub async fn prom_test_target(
+    window: tauri::WebviewWindow,
+    target: PromTarget,
+) -> Result<Vec<SensorSample>, String> {
+    if window.label() != "studio" {
+        return Err("prom_test_target is only allowed from the studio window".into());
+    }
+    let name = id_segment(&target.name).unwrap_or_else(|| "test".to_string());
+    let t = normalize_target(PromTarget { name, ..target })?;
+    let body = scrape(&http_client(t.timeout_secs, t.insecure)?, &t.url).await?;
+    let raw: Vec<PromSeries> = t
+        .series
+        .iter()
+        .map(|s| PromSeries {
+            rate: false,
+            ..s.clone()
+        })
+        .collect();
+    Ok(Evaluator::new(&raw).evaluate(&parse_exposition(&body), now_ms()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    const FIXTURE: &str = r#"# HELP node_cpu_seconds_total Seconds the CPUs spent in each mode.
+# TYPE node_cpu_seconds_total counter
+node_cpu_seconds_total{cpu="0",mode="idle"} 100
+node_cpu_seconds_total{cpu="0",mode="user"} 10
+node_cpu_seconds_total{cpu="1",mode="idle"} 200
+node_cpu_seconds_total{cpu="1",mode="user"} 20.5
+node_filesystem_avail_bytes{mountpoint="/",fstype="ext4"} 1.5e+09
+node_filesystem_avail_bytes{mountpoint="/mnt/a \"b\"",fstype="zfs"} 3e9 1700000000000
+node_load1 0.42
+go_gc_duration_seconds{quantile="0.5"} NaN
+"#;
+
+    fn series(alias: &str, select: &str, rate: bool, aggregate: Aggregate) -> PromSeries {
+        PromSeries {
+            alias: alias.into(),
+            select: select.into(),
+            rate,
+            aggregate,
+        }
+    }
+
+    fn scalar(batch: &[SensorSample], id: &str) -> Option<f64> {
+        batch
+            .iter()
+            .find(|s| s.sensor == id)
+            .map(|s| match s.value {
+                SensorValue::Scalar(v) => v,
+                ref other => panic!("{id}: not a scalar: {other:?}"),
+            })
+    }
+
+    #[test]
+    fn parses_the_text_exposition() {
+        let samples = parse_exposition(FIXTURE);
+        assert_eq!(samples.len(), 8);
+        assert_eq!(samples[0].metric, "node_cpu_seconds_total");
+        assert_eq!(samples[0].labels["mode"], "idle");
+        assert_eq!(samples[5].labels["mountpoint"], "/mnt/a \"b\"");
+        assert_eq!(samples[5].value, 3e9);
+        assert_eq!(samples[6].metric, "node_load1");
+        assert!(samples[6].labels.is_empty());
+        assert!(samples[7].value.is_nan());
+        assert!(parse_exposition("broken{mode=\"x\" 1\n").is_empty());
+    }
+
+    #[test]
+    fn selectors_support_all_four_matchers() {
+        let samples = parse_exposition(FIXTURE);
+        let count = |sel: &str| {
+            let sel = Selector::parse(sel).unwrap();
+            samples.iter().filter(|s| sel.matches(s)).count()
+        };
+        assert_eq!(count("node_cpu_seconds_total"), 4);
+        assert_eq!(count(r#"node_cpu_seconds_total{mode="idle"}"#), 2);
+        assert_eq!(count(r#"node_cpu_seconds_total{mode!="idle", cpu="1"}"#), 1);
+        // Regexes are anchored: `use` doesn't match `user`.
+        assert_eq!(count(r#"node_cpu_seconds_total{mode=~"use"}"#), 0);
+        assert_eq!(
+            count(r#"node_cpu_seconds_total{mode=~"user|idle",cpu!~"0"}"#),
+            2
+        );
+        // An absent label is the empty string.
+        assert_eq!(count(r#"node_load1{job=""}"#), 1);
+        assert!(Selector::parse("").is_err());
+        assert!(Selector::parse(r#"x{mode="idle""#).is_err());
+        assert!(Selector::parse(r#"x{mode=~"("}"#).is_err());
+        assert!(Selector::parse(r#"x{mode=="a"}"#).is_err());
+    }
+
+    #[test]
+    fn aggregates_and_rates_counters_across_scrapes() {
+        let mut eval = Evaluator::new(&[
+            series(
+                "cpu_user",
+                r#"node_cpu_seconds_total{mode="user"}"#,
+                true,
+                Aggregate::Sum,
+            ),
+            series(
+                "root_free",
+                r#"node_filesystem_avail_bytes{mountpoint="/"}"#,
+                false,
+                Aggregate::Sum,
+            ),
+            series(
+                "idle_max",
+                r#"node_cpu_seconds_total{mode="idle"}"#,
+                false,
+                Aggregate::Max,
+            ),
+            series(
+                "cpus",
+                r#"node_cpu_seconds_total{mode="idle"}"#,
+                false,
+                Aggregate::Count,
+            ),
+            series("gc", "go_gc_duration_seconds", false, Aggregate::Avg),
+        ]);
+        let first = eval.evaluate(&parse_exposition(FIXTURE), 0);
+        // Rates need two scrapes; NaN isn't published.
+        assert_eq!(scalar(&first, "prom.cpu_user"), None);
+        assert_eq!(scalar(&first, "prom.gc"), None);
+        assert_eq!(scalar(&first, "prom.root_free"), Some(1.5e9));
+        assert_eq!(scalar(&first, "prom.idle_max"), Some(200.0));
+        assert_eq!(scalar(&first, "prom.cpus"), Some(2.0));
+
+        let next = FIXTURE
+            .replace(r#"cpu="0",mode="user"} 10"#, r#"cpu="0",mode="user"} 15"#)
+            .replace(
+                r#"cpu="1",mode="user"} 20.5"#,
+                r#"cpu="1",mode="user"} 30.5"#,
+            );
+        let second = eval.evaluate(&parse_exposition(&next), 10_000);
+        assert_eq!(scalar(&second, "prom.cpu_user"), Some(1.5));
+
+        // A counter reset drops that series for one round instead of going negative.
+        let reset = next.replace(r#"cpu="1",mode="user"} 30.5"#, r#"cpu="1",mode="user"} 1"#);
+        let third = eval.evaluate(&parse_exposition(&reset), 20_000);
+        assert_eq!(scalar(&third, "prom.cpu_user"), Some(0.0));
+    }
+
+    #[test]
+    fn normalize_rejects_bad_targets_and_shared_aliases() {
+        let target = |name: &str, alias: &str, select: &str| PromTarget {
+            name: name.into(),
+            url: "http://nas:9100/metrics".into(),
+            interval_secs: 1,
+            timeout_secs: 10,
+            insecure: false,
+            always: false,
+            series: vec![series(alias, select, false, Aggregate::Sum)],
+        };
+        let (cfg, errors) = normalize_config(PromConfig {
+            targets: vec![
+                target("NAS Box", "Load 1", "node_load1"),
+                target("other", "load_1", "node_load1"),
+                target("bad", "x", "node_load1{"),
+            ],
+        });
+        assert_eq!(cfg.targets.len(), 1);
+        assert_eq!(cfg.targets[0].name, "nas_box");
+        assert_eq!(cfg.targets[0].series[0].alias, "load_1");
+        assert_eq!(cfg.targets[0].interval_secs, MIN_INTERVAL);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].contains("duplicate alias"));
+    }
+
+    #[tokio::test]
+    async fn scrapes_a_local_fixture_server() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = sock.read(&mut buf).await;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n",
+                FIXTURE.len()
+            );
+            sock.write_all(head.as_bytes()).await.unwrap();
+            sock.write_all(FIXTURE.as_bytes()).await.unwrap();
+        });
+
+        let client = http_client(5, false).unwrap();
+        let body = scrape(&client, &format!("http://{addr}/metrics"))
+            .await
+            .unwrap();
+        let mut eval = Evaluator::new(&[series("load", "node_load1", false, Aggregate::Sum)]);
+        let batch = eval.evaluate(&parse_exposition(&body), 0);
+        assert_eq!(scalar(&batch, "prom.load"), Some(0.42));
+    }
+
+    #[tokio::test]
+    async fn an_endless_body_stops_at_the_cap() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = sock.read(&mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\n";
+            sock.write_all(head.as_bytes()).await.unwrap();
+            // No length and no end: only a cap enforced while reading can return.
+            let line = "x 1\n".repeat(16 * 1024);
+            while sock.write_all(line.as_bytes()).await.is_ok() {}
+        });
+
+        let client = http_client(30, false).unwrap();
+        let err = scrape(&client, &format!("http://{addr}/metrics"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("larger than"), "{err}");
+    }
+}
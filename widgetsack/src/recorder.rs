@@ -0,0 +1,433 @@
+//! Record and replay telemetry sessions: capture the bus (system, HA, MQTT, stocks, derived, …)
+//! plus the media-session deltas to a timestamped JSONL file, and later feed it back through the
+//! SAME emit path at 1× or faster — so a layout built on a gaming PC can be reproduced on a laptop
+//! with none of that hardware (widget debugging, screenshots).
+//!
+//! Files live in `<app_config_dir>/recordings/<name>.jsonl`, one `Entry` per line:
+//!   `{"kind":"telemetry","t":<ms>,"batch":[SensorSample, …]}`
+//!   `{"kind":"media","t":<ms>,"event":"session_update","record":{…}}`
+//! While a replay runs, `bus::publish` drops live batches and main.rs stops forwarding live media
+//! deltas, so the recording REPLACES the live sources rather than interleaving with them. Replayed
+//! samples are re-stamped with the current time so sparklines and staleness read naturally.
+//!
+//! Pure seams (`parse_entry`, `replay_delay`, `restamp`) are unit-tested.
+
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::bus;
+use crate::command::valid_name;
+use crate::log;
+use crate::sensors::SensorSample;
+
+/// The longest pause a replay honours between two entries (before the speed-up), so a recording
+/// that sat idle for an hour doesn't look frozen on playback.
+const MAX_GAP_MS: u64 = 10_000;
+
+/// Fastest accepted playback speed.
+const MAX_SPEED: f64 = 100.0;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// One recorded line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Telemetry {
+        t: u64,
+        batch: Vec<SensorSample>,
+    },
+    /// A media-session delta: the bridge event name and the `SessionRecord` it carried.
+    Media {
+        t: u64,
+        event: String,
+        record: Value,
+    },
+}
+
+impl Entry {
+    fn t(&self) -> u64 {
+        match self {
+            Entry::Telemetry { t, .. } | Entry::Media { t, .. } => *t,
+        }
+    }
+}
+
+struct Recording {
+    name: String,
+    started_ms: u64,
+    /// Media entries into the writer task; dropping it ends the recording (the writer flushes).
+    tx: mpsc::UnboundedSender<Entry>,
+}
+
+struct Replay {
+    name: String,
+    speed: f64,
+    task: JoinHandle<()>,
+}
+
+/// Managed state: the active recording and/or replay (at most one of each).
+#[derive(Default)]
+pub struct Recorder {
+    recording: Mutex<Option<Recording>>,
+    replay: Mutex<Option<Replay>>,
+}
+
+/// What the studio's recorder panel shows.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecorderStatus {
+    pub recording: Option<String>,
+    pub recording_since_ms: Option<u64>,
+    pub replaying: Option<String>,
+    pub speed: Option<f64>,
+}
+
+fn recordings_dir<R: tauri::Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("recordings"))
+}
+
+fn recording_path<R: tauri::Runtime>(app: &AppHandle<R>, name: &str) -> Result<PathBuf, String> {
+    if !valid_name(name) {
+        return Err("invalid recording name".to_string());
+    }
+    Ok(recordings_dir(app)?.join(format!("{name}.jsonl")))
+}
+
+/// Append a media-session delta to the active recording, if any (called from the session loop).
+pub fn record_media<R: tauri::Runtime>(app: &AppHandle<R>, event: &str, record: &impl Serialize) {
+    let Some(state) = app.try_state::<Recorder>() else {
+        return;
+    };
+    let guard = state.recording.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(rec) = guard.as_ref()
+        && let Ok(record) = serde_json::to_value(record)
+    {
+        let _ = rec.tx.send(Entry::Media {
+            t: now_ms(),
+            event: event.to_string(),
+            record,
+        });
+    }
+}
+
+// ---- pure seams ----
+
+/// Parse one JSONL line; blank lines are skipped (`None`), malformed ones are an error.
+pub fn parse_entry(line: &str) -> Option<Result<Entry, String>> {
+    let line = line.trim();
+    (!line.is_empty()).then(|| serde_json::from_str(line).map_err(|e| e.to_string()))
+}
+
+/// How long to wait before playing an entry stamped `t` after one stamped `prev`, at `speed`×.
+/// Gaps are capped at `MAX_GAP_MS`; out-of-order stamps play immediately.
+pub fn replay_delay(prev: u64, t: u64, speed: f64) -> Duration {
+    let gap = t.saturating_sub(prev).min(MAX_GAP_MS) as f64;
+    Duration::from_secs_f64(gap / speed.clamp(0.01, MAX_SPEED) / 1000.0)
+}
+
+/// A recorded batch re-stamped to `now`.
+pub fn restamp(batch: &[SensorSample], now: u64) -> Vec<SensorSample> {
+    batch
+        .iter()
+        .map(|s| SensorSample {
+            ts_ms: now,
+            ..s.clone()
+        })
+        .collect()
+}
+
+// ---- tasks ----
+
+/// The writer: every bus batch plus the media entries from `rx`, one JSON line each. Ends (after a
+/// flush) when the recording is stopped and `rx` closes.
+async fn write_recording<R: tauri::Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    mut rx: mpsc::UnboundedReceiver<Entry>,
+) {
+    let Some(mut bus_rx) = bus::subscribe(&app) else {
+        return;
+    };
+    let file = match std::fs::File::create(&path) {
+        Ok(f) => f,
+        Err(err) => {
+            log::warn("recorder", "failed to create recording")
+                .field("error", err.to_string())
+                .emit();
+            return;
+        }
+    };
+    let mut out = BufWriter::new(file);
+    let mut flush = tokio::time::interval(Duration::from_secs(2));
+    loop {
+        let entry = tokio::select! {
+            msg = bus_rx.recv() => match msg {
+                Ok(batch) => Entry::Telemetry { t: now_ms(), batch: batch.to_vec() },
+                Err(RecvError::Lagged(n)) => {
+                    log::warn("recorder", "recording dropped batches")
+                        .field("skipped_batches", n)
+                        .emit();
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            media = rx.recv() => match media {
+                Some(entry) => entry,
+                None => break,
+            },
+            _ = flush.tick() => {
+                let _ = out.flush();
+                continue;
+            }
+        };
+        if let Ok(line) = serde_json::to_string(&entry)
+            && writeln!(out, "{line}").is_err()
+        {
+            log::warn("recorder", "failed to write recording").emit();
+            break;
+        }
+    }
+    let _ = out.flush();
+}
+
+/// Play `path` through the emit paths at `speed`×, optionally looping, then unmute the live sources.
+async fn play<R: tauri::Runtime>(app: AppHandle<R>, path: PathBuf, speed: f64, looped: bool) {
+    bus::set_replaying(&app, true);
+    'outer: loop {
+        let file = match std::fs::File::open(&path) {
+            Ok(f) => f,
+            Err(err) => {
+                log::warn("recorder", "failed to open recording")
+                    .field("error", err.to_string())
+                    .emit();
+                break;
+            }
+        };
+        let mut prev: Option<u64> = None;
+        for (n, line) in BufReader::new(file).lines().enumerate() {
+            let Ok(line) = line else {
+                break 'outer;
+            };
+            let entry = match parse_entry(&line) {
+                None => continue,
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => {
+                    log::warn("recorder", "skipping malformed recording line")
+                        .field("line", n + 1)
+                        .field("error", err)
+                        .emit();
+                    continue;
+                }
+            };
+            if let Some(prev) = prev {
+                tokio::time::sleep(replay_delay(prev, entry.t(), speed)).await;
+            }
+            prev = Some(entry.t());
+            match entry {
+                Entry::Telemetry { batch, .. } => {
+                    let _ = bus::publish_replayed(&app, &restamp(&batch, now_ms()));
+                }
+                Entry::Media { event, record, .. } => {
+                    let _ = app.emit(&event, record);
+                }
+            }
+        }
+        if !looped {
+            break;
+        }
+    }
+    bus::set_replaying(&app, false);
+    if let Some(state) = app.try_state::<Recorder>() {
+        *state.replay.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Start recording to `recordings/<name>.jsonl` (default: a local timestamp). Returns the name.
+/// Studio-window-guarded; one recording at a time.
+#[tauri::command]
+pub fn start_recording(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Recorder>,
+    name: Option<String>,
+) -> Result<String, String> {
+    if window.label() != "studio" {
+        return Err("start_recording is only allowed from the studio window".into());
+    }
+    let mut guard = state.recording.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(rec) = guard.as_ref() {
+        return Err(format!("already recording `{}`", rec.name));
+    }
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string());
+    let path = recording_path(&app, &name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let (tx, rx) = mpsc::unbounded_channel();
+    tauri::async_runtime::spawn(write_recording(app.clone(), path, rx));
+    *guard = Some(Recording {
+        name: name.clone(),
+        started_ms: now_ms(),
+        tx,
+    });
+    Ok(name)
+}
+
+/// Stop the active recording (the writer flushes and closes the file). Returns its name, if any.
+#[tauri::command]
+pub fn stop_recording(state: State<'_, Recorder>) -> Option<String> {
+    let rec = state
+        .recording
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+    rec.map(|r| r.name)
+}
+
+/// The saved recording names (file stems of `recordings/*.jsonl`), sorted.
+#[tauri::command]
+pub fn list_recordings(app: AppHandle) -> Result<Vec<String>, String> {
+    let dir = recordings_dir(&app)?;
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|x| x.to_str()) == Some("jsonl"))
+                .filter_map(|p| p.file_stem().and_then(|s| s.to_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    Ok(names)
+}
+
+/// Replay recording `name` at `speed`× (default 1, max 100), looping if asked. Live sources are
+/// muted until it ends or `stop_replay`. Studio-window-guarded; replaces any running replay.
+#[tauri::command]
+pub fn start_replay(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Recorder>,
+    name: String,
+    speed: Option<f64>,
+    looped: Option<bool>,
+) -> Result<(), String> {
+    if window.label() != "studio" {
+        return Err("start_replay is only allowed from the studio window".into());
+    }
+    let path = recording_path(&app, &name)?;
+    if !path.exists() {
+        return Err(format!("no recording named `{name}`"));
+    }
+    let speed = speed
+        .filter(|s| s.is_finite() && *s > 0.0)
+        .unwrap_or(1.0)
+        .min(MAX_SPEED);
+    let mut guard = state.replay.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(old) = guard.take() {
+        old.task.abort();
+    }
+    let task = tauri::async_runtime::spawn(play(app.clone(), path, speed, looped.unwrap_or(false)));
+    *guard = Some(Replay { name, speed, task });
+    Ok(())
+}
+
+/// Stop the running replay (if any) and unmute the live sources.
+#[tauri::command]
+pub fn stop_replay(app: AppHandle, state: State<'_, Recorder>) {
+    if let Some(replay) = state
+        .replay
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    {
+        replay.task.abort();
+    }
+    bus::set_replaying(&app, false);
+}
+
+/// What is recording / replaying right now.
+#[tauri::command]
+pub fn recorder_status(state: State<'_, Recorder>) -> RecorderStatus {
+    let rec = state.recording.lock().unwrap_or_else(|e| e.into_inner());
+    let replay = state.replay.lock().unwrap_or_else(|e| e.into_inner());
+    RecorderStatus {
+        recording: rec.as_ref().map(|r| r.name.clone()),
+        recording_since_ms: rec.as_ref().map(|r| r.started_ms),
+        replaying: replay.as_ref().map(|r| r.name.clone()),
+        speed: replay.as_ref().map(|r| r.speed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::SensorValue;
+
+    #[test]
+    fn entries_round_trip_through_jsonl() {
+        let entry = Entry::Telemetry {
+            t: 5,
+            batch: vec![
+                SensorSample::scalar("cpu.total", 5, 42.0),
+                SensorSample::text("ha.status", 5, "connected"),
+            ],
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert!(line.starts_with(r#"{"kind":"telemetry","t":5"#));
+        let Some(Ok(Entry::Telemetry { t, batch })) = parse_entry(&line) else {
+            panic!("telemetry entry should parse back");
+        };
+        assert_eq!(t, 5);
+        assert!(matches!(batch[0].value, SensorValue::Scalar(v) if v == 42.0));
+        assert!(matches!(&batch[1].value, SensorValue::Text(s) if s == "connected"));
+
+        let media = r#"{"kind":"media","t":9,"event":"session_update","record":{"session_id":1}}"#;
+        assert!(matches!(
+            parse_entry(media),
+            Some(Ok(Entry::Media { t: 9, .. }))
+        ));
+        assert!(parse_entry("   ").is_none());
+        assert!(matches!(parse_entry("{not json"), Some(Err(_))));
+    }
+
+    #[test]
+    fn replay_delay_scales_and_caps_gaps() {
+        assert_eq!(replay_delay(1_000, 2_000, 1.0), Duration::from_secs(1));
+        assert_eq!(replay_delay(1_000, 2_000, 4.0), Duration::from_millis(250));
+        assert_eq!(
+            replay_delay(0, 3_600_000, 1.0),
+            Duration::from_millis(MAX_GAP_MS)
+        );
+        assert_eq!(replay_delay(5_000, 1_000, 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn restamp_moves_samples_to_now() {
+        let out = restamp(&[SensorSample::scalar("cpu.total", 1, 3.0)], 99);
+        assert_eq!(out[0].ts_ms, 99);
+        assert_eq!(out[0].sensor, "cpu.total");
+    }
+}
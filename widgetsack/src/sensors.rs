@@ -24,11 +24,16 @@
 //!   `host.handles` / `host.threads` (counts, Windows, gated).
 //! - Processes (gated): the busiest process — `proc.cpu.top.name` (text) + `proc.cpu.top.pct` (% of the
 //!   whole machine), and the hungriest — `proc.mem.top.name` (text) + `proc.mem.top.bytes` (RSS bytes).
+//!   The configured watch list (procwatch.rs) adds `proc.<alias>.{cpu,mem,count,running,io.read,
+//!   io.write}` per alias, and `proc.top` (Json) carries the top-N table.
 //! - GPU (gated, NVIDIA/NVML): `gpu.util` / `gpu.mem.util` / `gpu.fan` (%), `gpu.vram` (%),
 //!   `gpu.vram.{total,used,free}` (bytes), `gpu.temp` (°C), `gpu.clock.{core,mem}` (MHz),
 //!   `gpu.power` / `gpu.power.limit` (W — NVML reports mW, divided here), `gpu.name` (text).
 //! - Battery (Windows, only when present): `battery.percent` (%), `battery.state` (text),
 //!   `battery.time` (s), `battery.rate` (W, signed), `battery.capacity.{full,remaining}` (Wh).
+//! - Power/energy (energy.rs, every tick): `power.cpu` (W, RAPL — Linux powercap only), `power.gpu`
+//!   (W), `power.system.estimate` (W), `energy.today.kwh` (persisted, resets at local midnight) +
+//!   `energy.today.cost` (× the configured tariff).
 //!
 //! The percent ids (`mem.used`, `swap.used`, `gpu.vram`) are kept for backward compat — the byte
 //! absolutes are ADDED alongside, never renamed (templates + the ported skins bind the percents).
@@ -36,25 +41,24 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Mutex;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use nvml_wrapper::{enum_wrappers::device::{Clock, TemperatureSensor}, Nvml};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sysinfo::{Disks, Networks, ProcessesToUpdate, System};
-use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri::{AppHandle, Manager, Runtime};
 
+use crate::energy::EnergyMeter;
 use crate::log;
-
-/// The `telemetry` event name on the Tauri bridge (re-exported so ha/mqtt/stocks keep importing
-/// it from here; the string itself lives in bridge.rs with the rest of the contract).
-pub use crate::bridge::TELEMETRY_EVENT;
+use crate::{bus, catalog};
+use crate::procwatch::{self, ProcRow, ProcWatch};
 
 /// A single metric value. Mirrors `SensorValue` in `core/telemetry.ts`.
 ///
 /// `Series` / `Json` are part of the bridge contract but not produced here yet (per-core
 /// series, media JSON), hence `dead_code` is allowed.
 #[allow(dead_code)]
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "kind", content = "value", rename_all = "lowercase")]
 pub enum SensorValue {
     Scalar(f64),
@@ -63,12 +67,34 @@ pub enum SensorValue {
     Json(serde_json::Value),
 }
 
-/// One sample from one sensor. Mirrors `SensorSample` in `core/telemetry.ts`.
-#[derive(Clone, Debug, Serialize)]
+impl SensorValue {
+    /// The wire `kind` tag (`"scalar"` / `"text"` / `"series"` / `"json"`), as serialized above.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SensorValue::Scalar(_) => "scalar",
+            SensorValue::Text(_) => "text",
+            SensorValue::Series(_) => "series",
+            SensorValue::Json(_) => "json",
+        }
+    }
+}
+
+/// One sample from one sensor. Mirrors `SensorSample` in `core/telemetry.ts`. Deserialize is for
+/// replaying recorded sessions (recorder.rs).
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SensorSample {
     pub sensor: String,
     pub ts_ms: u64,
     pub value: SensorValue,
+    /// Not live: a last-known value restored from disk after a restart (lastknown.rs), or a value
+    /// re-sent because its source dropped or it stopped updating (health.rs). Omitted from the wire
+    /// when false, so live samples are unchanged.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub stale: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
 }
 
 impl SensorSample {
@@ -77,6 +103,7 @@ impl SensorSample {
             sensor: sensor.into(),
             ts_ms,
             value: SensorValue::Scalar(value),
+            stale: false,
         }
     }
 
@@ -85,6 +112,7 @@ impl SensorSample {
             sensor: sensor.into(),
             ts_ms,
             value: SensorValue::Text(value.into()),
+            stale: false,
         }
     }
 }
@@ -122,6 +150,23 @@ fn core_sensor_id(index: usize) -> String {
     format!("cpu.core.{index}")
 }
 
+/// A safe sensor-id segment from a user-supplied name (a process-watch alias, a derived sensor's
+/// name, …): lowercased, `[a-z0-9_-]` kept, spaces/dots → `_`, everything else dropped. `None` when
+/// nothing survives.
+pub(crate) fn id_segment(name: &str) -> Option<String> {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' | '_' | '-' => Some(c),
+            ' ' | '.' => Some('_'),
+            _ => None,
+        })
+        .collect();
+    (!slug.is_empty()).then_some(slug)
+}
+
 /// The `(name, value)` with the greatest value (NaN-safe), or `None` when empty. Pure seam for
 /// picking the top process by CPU or memory.
 fn top_of(items: &[(String, f64)]) -> Option<&(String, f64)> {
@@ -130,6 +175,15 @@ fn top_of(items: &[(String, f64)]) -> Option<&(String, f64)> {
         .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
 }
 
+/// The Scalar value of `id` in this tick's batch, if it was sampled. Lets later per-tick consumers
+/// (energy accounting) reuse a reading instead of querying the hardware twice.
+fn scalar_in(batch: &[SensorSample], id: &str) -> Option<f64> {
+    batch.iter().find(|s| s.sensor == id).and_then(|s| match s.value {
+        SensorValue::Scalar(v) => Some(v),
+        _ => None,
+    })
+}
+
 /// Flatten the latest-value map to a `{ id: number|string }` JSON object — Scalar and Text only
 /// (Series/Json are dropped; not useful in a flat snapshot). Pure seam for the MCP live-state file.
 fn flatten_latest(latest: &HashMap<String, SensorValue>) -> serde_json::Map<String, serde_json::Value> {
@@ -673,6 +727,12 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
     let mut disks = Disks::new_with_refreshed_list();
     // Previous per-volume I/O counters, keyed by drive letter — disk rates/active-time are deltas.
     let mut disk_io_prev: HashMap<String, DiskIo> = HashMap::new();
+    // When the process table was last refreshed — per-process I/O rates divide by the real gap.
+    let mut last_proc_refresh: Option<Instant> = None;
+    // Seed the process watch list (plugins/procwatch.json) into managed state.
+    procwatch::load_into_state(&app);
+    // RAPL snapshot + today's persisted energy total (plugins/energy.json seeds the tariff).
+    let mut energy_meter = EnergyMeter::new(&app);
 
     // Static host facts, read once.
     let cpu_brand = sys
@@ -699,9 +759,8 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
         .and_then(|d| d.name().ok())
         .filter(|s| !s.is_empty());
 
-    // Latest value per sensor id, mirrored to <config>/mcp/state.json every few ticks for the MCP
-    // server's read_sensors tool (live readings for an external agent).
-    let mut latest: HashMap<String, SensorValue> = HashMap::new();
+    // The bus's latest value per sensor id (every source, not just this loop) is mirrored to
+    // <config>/mcp/state.json every few ticks for the MCP server's read_sensors tool.
     let mut snap_tick: u32 = 0;
 
     let mut ticker = tokio::time::interval(Duration::from_millis(INTERVAL_MS));
@@ -763,7 +822,7 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
         // (NVML, process enumeration, disk refresh, frequency refresh) — the std Mutex must never be
         // held across an await or a blocking driver call.
         #[allow(clippy::type_complexity)]
-        let (want_gpu, want_disks, want_disk_io, want_procs, want_proctop, want_freq, want_perf, want_cpufreq, want_netlink) = {
+        let (want_gpu, want_disks, want_disk_io, want_procs, want_proctop, want_proctable, want_freq, want_perf, want_cpufreq, want_netlink, want_energy) = {
             let active: tauri::State<ActiveSensors> = app.state();
             let g = active.0.lock().unwrap_or_else(|e| e.into_inner());
             (
@@ -772,10 +831,12 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
                 any_wanted(&g, is_disk_io_id),
                 any_wanted(&g, |id| id == "host.procs"),
                 any_wanted(&g, |id| id.starts_with("proc.")),
+                any_wanted(&g, |id| id == "proc.top"),
                 any_wanted(&g, |id| id == "cpu.freq"),
                 any_wanted(&g, is_perf_id),
                 any_wanted(&g, is_cpufreq_id),
                 any_wanted(&g, is_netlink_id),
+                any_wanted(&g, |id| id.starts_with("power.") || id.starts_with("energy.")),
             )
         };
 
@@ -796,6 +857,13 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
             if want_procs {
                 batch.push(SensorSample::scalar("host.procs", ts, n as f64));
             }
+            // Real time since the previous process refresh — per-process disk I/O accrues over it,
+            // so the first tick after a gated gap isn't read as a one-second spike.
+            let now = Instant::now();
+            let proc_elapsed_ms = last_proc_refresh
+                .map(|t| now.duration_since(t).as_millis() as u64)
+                .unwrap_or(INTERVAL_MS);
+            last_proc_refresh = Some(now);
             if want_proctop {
                 // Highest-CPU + highest-memory process — the "what's eating my machine" sensors.
                 // sysinfo's per-process cpu_usage sums across cores (can exceed 100%); divide by the
@@ -803,13 +871,38 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
                 let ncpu = sys.cpus().len().max(1) as f64;
                 let mut by_cpu: Vec<(String, f64)> = Vec::new();
                 let mut by_mem: Vec<(String, f64)> = Vec::new();
-                for proc in sys.processes().values() {
+                let mut rows: Vec<ProcRow> = Vec::new();
+                for (pid, proc) in sys.processes() {
                     let name = proc.name().to_string_lossy().to_string();
                     if name.is_empty() {
                         continue;
                     }
-                    by_cpu.push((name.clone(), proc.cpu_usage() as f64 / ncpu));
-                    by_mem.push((name, proc.memory() as f64));
+                    let cpu = proc.cpu_usage() as f64 / ncpu;
+                    by_cpu.push((name.clone(), cpu));
+                    by_mem.push((name.clone(), proc.memory() as f64));
+                    let io = proc.disk_usage();
+                    rows.push(ProcRow {
+                        pid: pid.as_u32(),
+                        name,
+                        exe: proc
+                            .exe()
+                            .map(|p| p.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        cpu,
+                        mem: proc.memory(),
+                        read: io.read_bytes,
+                        written: io.written_bytes,
+                    });
+                }
+                // The configured watch list (procwatch.rs) + the task-manager table, from the same
+                // walk. The config is cloned out under a brief lock (a handful of short strings).
+                let watch_cfg = {
+                    let watch: tauri::State<ProcWatch> = app.state();
+                    watch.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+                };
+                batch.extend(procwatch::watch_samples(&watch_cfg, &rows, ts, proc_elapsed_ms));
+                if want_proctable {
+                    batch.push(procwatch::top_table(&rows, watch_cfg.top_n, ts));
                 }
                 // Skip the first gated tick's misleading sample: sysinfo CPU% needs two refreshes, so
                 // the first reads 0 for every process and top_of would pick an arbitrary one. Emit only
@@ -905,19 +998,32 @@ pub async fn run_system_sensors<R: Runtime>(app: AppHandle<R>) {
         batch.extend(battery_samples(ts));
         batch.extend(battery_power_samples(ts));
 
-        if let Err(err) = app.emit(TELEMETRY_EVENT, &batch) {
+        // Power/energy accounting runs every tick (an integral can't have gated holes). It reuses
+        // this tick's gpu.power / battery.rate; when no gpu.* meter is mounted the NVML block was
+        // skipped, so read just the board power for the estimate (not emitted as gpu.power) — every
+        // tick while a power.*/energy.* sensor is on screen, otherwise at a slower held cadence.
+        let gpu_watts = match scalar_in(&batch, "gpu.power") {
+            Some(w) => Some(w),
+            None => energy_meter.gpu_watts(want_energy, || {
+                gpu.as_ref()
+                    .and_then(|d| d.power_usage().ok())
+                    .map(|mw| f64::from(mw) / 1000.0)
+            }),
+        };
+        let battery_rate = scalar_in(&batch, "battery.rate");
+        batch.extend(energy_meter.tick(&app, ts, gpu_watts, battery_rate));
+
+        if let Err(err) = bus::publish(&app, &batch) {
             log::error("sensors", "failed to emit telemetry")
                 .field("error", err)
                 .emit();
         }
 
         // Mirror the latest values to the MCP live-state snapshot (~every 3s — cheap, small file).
-        for s in &batch {
-            latest.insert(s.sensor.clone(), s.value.clone());
-        }
         snap_tick = snap_tick.wrapping_add(1);
         if snap_tick.is_multiple_of(3) {
-            write_state_snapshot(&app, &latest);
+            write_state_snapshot(&app, &bus::latest(&app));
+            catalog::write_catalog_snapshot(&app);
         }
     }
 }
@@ -972,6 +1078,13 @@ mod tests {
         assert_eq!(core_sensor_id(7), "cpu.core.7");
     }
 
+    #[test]
+    fn id_segment_slugs_user_names() {
+        assert_eq!(id_segment(" OBS Studio ").as_deref(), Some("obs_studio"));
+        assert_eq!(id_segment("cpu.avg-1m").as_deref(), Some("cpu_avg-1m"));
+        assert_eq!(id_segment("!!"), None);
+    }
+
     #[test]
     fn disk_letter_lowercases_the_drive_letter() {
         assert_eq!(disk_letter(Path::new("C:\\")).as_deref(), Some("c"));
@@ -1180,4 +1293,15 @@ mod tests {
         assert_eq!(top_of(&items).map(|(n, _)| n.as_str()), Some("b.exe"));
         assert!(top_of(&[]).is_none());
     }
+
+    #[test]
+    fn scalar_in_finds_only_scalar_readings() {
+        let batch = vec![
+            SensorSample::scalar("gpu.power", 1, 120.5),
+            SensorSample::text("battery.rate", 1, "n/a"),
+        ];
+        assert_eq!(scalar_in(&batch, "gpu.power"), Some(120.5));
+        assert_eq!(scalar_in(&batch, "battery.rate"), None);
+        assert_eq!(scalar_in(&batch, "cpu.total"), None);
+    }
 }
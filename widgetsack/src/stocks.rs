@@ -20,9 +20,11 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tauri::async_runtime::{JoinHandle, Mutex};
-use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tauri::{AppHandle, Manager, Runtime, State};
 
-use crate::sensors::{ActiveSensors, SensorSample, SensorValue, TELEMETRY_EVENT};
+use crate::bus;
+use crate::health;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue};
 
 /// Poll cadence guardrails (seconds). Clamped server-side so a bad config can't hammer the provider.
 const MIN_INTERVAL: u64 = 15;
@@ -168,6 +170,7 @@ fn quote_to_samples(symbol: &str, chart: &Value, ts_ms: u64) -> Vec<SensorSample
                 sensor: format!("{base}.series"),
                 ts_ms,
                 value: SensorValue::Series(series),
+                stale: false,
             });
         }
     }
@@ -224,8 +227,9 @@ fn merge_symbols(configured: &[String], active: &[String]) -> Vec<String> {
 /// Surface the feed state to widgets as a `stocks.status` text sample (a Text meter / the settings
 /// badge bind it) — mirrors mqtt.rs / ha.rs's single-status-transport design.
 fn emit_status<R: Runtime>(app: &AppHandle<R>, status: &str) {
+    health::report_status(app, "stocks", status);
     let batch = vec![SensorSample::text("stocks.status", now_ms(), status)];
-    let _ = app.emit(TELEMETRY_EVENT, &batch);
+    let _ = bus::publish(app, &batch);
 }
 
 /// True while any window is consuming a `stocks.*` sensor (a ticker is mounted). Lets the poll loop
@@ -339,7 +343,7 @@ pub async fn run_stocks_client<R: Runtime>(app: AppHandle<R>, cfg: StocksConfig)
                     fetched = true;
                     let batch = quote_to_samples(symbol, &json, now_ms());
                     if !batch.is_empty() {
-                        let _ = app.emit(TELEMETRY_EVENT, &batch);
+                        let _ = bus::publish(&app, &batch);
                     }
                 }
                 Err(err) => eprintln!("stocks: fetch {err}"),
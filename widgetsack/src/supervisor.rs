@@ -0,0 +1,164 @@
+//! The restart-on-save loop behind every source that runs one task per configured entry (HTTP
+//! endpoints, commands, scrape targets, watched folders, …). Each such source keeps its definitions
+//! in managed state next to a generation counter that a save bumps; `supervise` polls the counter
+//! once a second and, when it moves, restarts only the definitions that changed (matched by name),
+//! starts new ones and stops the removed ones, so saving one entry doesn't reset every other
+//! entry's backoff or watch. The diff (`reconcile`) is unit-tested.
+//!
+//! Also home to `settle`, the event-burst coalescing the notify-based sources (folder.rs,
+//! gitrepo.rs, tasks.rs) share.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+
+/// How often the generation counter is checked.
+const POLL: Duration = Duration::from_secs(1);
+
+/// One configured entry the supervisor runs a task for. Names are unique within a source
+/// (`normalize_config` rejects duplicates).
+pub trait Supervised: Clone + PartialEq {
+    fn name(&self) -> &str;
+}
+
+/// Bring `running` in line with `defs`: an unchanged definition keeps its task; a changed one is
+/// `stop`ped and `start`ed again; a new one is started; whatever is left over is stopped.
+fn reconcile<D: Supervised, H>(
+    running: &mut HashMap<String, (D, H)>,
+    defs: Vec<D>,
+    mut start: impl FnMut(&D) -> H,
+    mut stop: impl FnMut(H),
+) {
+    let mut next = HashMap::new();
+    for def in defs {
+        match running.remove(def.name()) {
+            Some((old, task)) if old == def => {
+                next.insert(def.name().to_string(), (old, task));
+            }
+            other => {
+                if let Some((_, task)) = other {
+                    stop(task);
+                }
+                let task = start(&def);
+                next.insert(def.name().to_string(), (def, task));
+            }
+        }
+    }
+    for (_, (_, task)) in running.drain() {
+        stop(task);
+    }
+    *running = next;
+}
+
+/// Run one task per definition for the app's lifetime. `generation` reads the state's counter,
+/// `defs` snapshots the definitions to run (called only when the counter moves), and `start`
+/// spawns the task for one of them.
+pub async fn supervise<D: Supervised>(
+    generation: impl Fn() -> u64,
+    defs: impl Fn() -> Vec<D>,
+    start: impl Fn(D) -> JoinHandle<()>,
+) {
+    let mut running: HashMap<String, (D, JoinHandle<()>)> = HashMap::new();
+    let mut seen = u64::MAX;
+    loop {
+        let current = generation();
+        if current != seen {
+            seen = current;
+            reconcile(
+                &mut running,
+                defs(),
+                |d| start(d.clone()),
+                |task| task.abort(),
+            );
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+/// Coalesce a burst of watch events: return once `quiet` passes with no further event, or `max`
+/// after the call at the latest.
+pub(crate) async fn settle<T>(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<T>,
+    quiet: Duration,
+    max: Duration,
+) {
+    let deadline = tokio::time::Instant::now() + max;
+    loop {
+        let until = deadline.min(tokio::time::Instant::now() + quiet);
+        match tokio::time::timeout_at(until, rx.recv()).await {
+            Ok(Some(_)) if tokio::time::Instant::now() < deadline => {}
+            _ => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Def(&'static str, u32);
+
+    impl Supervised for Def {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn reconcile_restarts_only_what_changed() {
+        let mut running = HashMap::new();
+        let mut started = Vec::new();
+        let mut stopped = Vec::new();
+        let mut next_id = 0;
+        let mut apply = |running: &mut HashMap<String, (Def, u32)>, defs: Vec<Def>| {
+            reconcile(
+                running,
+                defs,
+                |d| {
+                    next_id += 1;
+                    started.push(d.0);
+                    next_id
+                },
+                |task| stopped.push(task),
+            );
+        };
+        apply(&mut running, vec![Def("a", 1), Def("b", 1), Def("c", 1)]);
+        // `a` unchanged, `b` edited, `c` removed, `d` added.
+        apply(&mut running, vec![Def("a", 1), Def("b", 2), Def("d", 1)]);
+        assert_eq!(started, ["a", "b", "c", "b", "d"]);
+        stopped.sort_unstable();
+        assert_eq!(stopped, [2, 3]);
+        assert_eq!(running["a"], (Def("a", 1), 1));
+        assert_eq!(running["b"], (Def("b", 2), 4));
+        assert!(!running.contains_key("c"));
+    }
+
+    #[tokio::test]
+    async fn settle_gives_up_on_a_burst_that_never_ends() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let writer = tokio::spawn(async move {
+            while tx.send(()).is_ok() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+        let start = std::time::Instant::now();
+        settle(
+            &mut rx,
+            Duration::from_millis(50),
+            Duration::from_millis(200),
+        )
+        .await;
+        let waited = start.elapsed();
+        assert!(waited >= Duration::from_millis(200) && waited < Duration::from_secs(2));
+        writer.abort();
+
+        // A burst that stops settles after the quiet period.
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(()).unwrap();
+        let start = std::time::Instant::now();
+        settle(&mut rx, Duration::from_millis(50), Duration::from_secs(5)).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
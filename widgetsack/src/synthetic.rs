@@ -0,0 +1,622 @@
+//! Synthetic sensors: built-in signal generators published on the normal telemetry path, so gallery
+//! screenshots, e2e runs and theme previews get realistic MOVING data on a machine without NVML,
+//! GSMTC or a live Home Assistant (the JS `devMock` only covers the plain-browser case).
+//!
+//! Declared in `plugins/synthetic.json` as
+//! `{ "sensors": [{ "id": "gpu", "as": "gpu.temp", "signal": { "kind": "sine", "min": 40,
+//! "max": 80, "period": "2m" } }] }`.
+//! Each entry publishes `synthetic.<id>` — or, with `as`, the given real sensor id instead, so an
+//! existing layout previews unchanged. Signals (`kind`):
+//!   `sine` / `sawtooth`   `min`..`max` over `period`
+//!   `random_walk`         starts mid-range, moves up to `step` per tick, clamped to `min`..`max`
+//!   `noise`               uniform in `min`..`max` every tick
+//!   `step`                cycles through `values`, advancing `every`
+//!   `text`                cycles through the strings in `values`, advancing `every`
+//! The random signals are seeded from the id, so a run is reproducible (stable screenshots).
+//!
+//! Published once a second. Pure seams (`Signal::validate`, `Generator`) are unit-tested.
+
+use std::f64::consts::TAU;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::derived::parse_duration;
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+
+/// Publish cadence — matches the system loop's 1 Hz.
+const TICK_MS: u64 = 1000;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+fn default_min() -> f64 {
+    0.0
+}
+
+fn default_max() -> f64 {
+    100.0
+}
+
+fn default_period() -> String {
+    "60s".to_string()
+}
+
+fn default_every() -> String {
+    "5s".to_string()
+}
+
+fn default_step() -> f64 {
+    1.0
+}
+
+/// One generator's shape (see module docs).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Signal {
+    Sine {
+        #[serde(default = "default_min")]
+        min: f64,
+        #[serde(default = "default_max")]
+        max: f64,
+        #[serde(default = "default_period")]
+        period: String,
+    },
+    Sawtooth {
+        #[serde(default = "default_min")]
+        min: f64,
+        #[serde(default = "default_max")]
+        max: f64,
+        #[serde(default = "default_period")]
+        period: String,
+    },
+    RandomWalk {
+        #[serde(default = "default_min")]
+        min: f64,
+        #[serde(default = "default_max")]
+        max: f64,
+        #[serde(default = "default_step")]
+        step: f64,
+    },
+    Noise {
+        #[serde(default = "default_min")]
+        min: f64,
+        #[serde(default = "default_max")]
+        max: f64,
+    },
+    Step {
+        values: Vec<f64>,
+        #[serde(default = "default_every")]
+        every: String,
+    },
+    Text {
+        values: Vec<String>,
+        #[serde(default = "default_every")]
+        every: String,
+    },
+}
+
+impl Signal {
+    /// Check ranges, durations and value lists. Errors name what is wrong, for the settings form.
+    pub fn validate(&self) -> Result<(), String> {
+        let range = |min: f64, max: f64| {
+            if !(min.is_finite() && max.is_finite()) || min > max {
+                Err(format!("bad range {min}..{max}"))
+            } else {
+                Ok(())
+            }
+        };
+        let duration = |s: &str| {
+            parse_duration(s)
+                .map(|_| ())
+                .ok_or_else(|| format!("bad duration `{s}`"))
+        };
+        match self {
+            Signal::Sine { min, max, period } | Signal::Sawtooth { min, max, period } => {
+                range(*min, *max)?;
+                duration(period)
+            }
+            Signal::RandomWalk { min, max, step } => {
+                range(*min, *max)?;
+                if step.is_finite() && *step > 0.0 {
+                    Ok(())
+                } else {
+                    Err(format!("bad step {step}"))
+                }
+            }
+            Signal::Noise { min, max } => range(*min, *max),
+            Signal::Step { values, every } => {
+                if values.is_empty() || values.iter().any(|v| !v.is_finite()) {
+                    return Err("`values` needs at least one number".into());
+                }
+                duration(every)
+            }
+            Signal::Text { values, every } => {
+                if values.is_empty() {
+                    return Err("`values` needs at least one string".into());
+                }
+                duration(every)
+            }
+        }
+    }
+}
+
+/// One declared generator: published as `synthetic.<id>`, or as `as` when set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticDef {
+    pub id: String,
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub publish_as: Option<String>,
+    pub signal: Signal,
+}
+
+impl SyntheticDef {
+    /// The sensor id this generator publishes under.
+    pub fn sensor_id(&self) -> String {
+        self.publish_as
+            .clone()
+            .unwrap_or_else(|| format!("synthetic.{}", self.id))
+    }
+}
+
+/// `plugins/synthetic.json`. `#[serde(default)]` so a partial file parses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SyntheticConfig {
+    #[serde(default)]
+    pub sensors: Vec<SyntheticDef>,
+}
+
+/// Managed state: the live generators plus a generation counter the task polls, so a save swaps
+/// them on the next tick without restarting the task.
+#[derive(Default)]
+pub struct Synthetic {
+    config: Mutex<SyntheticConfig>,
+    generation: AtomicU64,
+}
+
+impl Synthetic {
+    fn replace(&self, cfg: SyntheticConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn synthetic_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("synthetic.json"))
+}
+
+pub fn load_synthetic_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<SyntheticConfig>, String> {
+    let path = synthetic_config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed generators from disk; each rejected entry is logged (the studio save path
+/// rejects them outright instead).
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_synthetic_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("synthetic", "skipping synthetic sensor")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<Synthetic>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("synthetic", "failed to read synthetic.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// An `as` override must look like a sensor id: dot-separated `[A-Za-z0-9_-]` segments.
+fn valid_sensor_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.split('.').all(|seg| {
+            !seg.is_empty()
+                && seg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+/// Slug ids, reject duplicates / bad overrides / invalid signals. Returns the kept entries plus one
+/// message per rejected entry.
+fn normalize_config(cfg: SyntheticConfig) -> (SyntheticConfig, Vec<String>) {
+    let mut kept: Vec<SyntheticDef> = Vec::new();
+    let mut errors = Vec::new();
+    for def in cfg.sensors {
+        let Some(id) = id_segment(&def.id) else {
+            errors.push(format!("`{}`: invalid id", def.id));
+            continue;
+        };
+        let publish_as = def
+            .publish_as
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        if let Some(target) = &publish_as
+            && !valid_sensor_id(target)
+        {
+            errors.push(format!("`{id}`: `as` is not a sensor id: `{target}`"));
+            continue;
+        }
+        let def = SyntheticDef {
+            id,
+            publish_as,
+            signal: def.signal,
+        };
+        if kept.iter().any(|d| d.sensor_id() == def.sensor_id()) {
+            errors.push(format!(
+                "`{}`: duplicate sensor `{}`",
+                def.id,
+                def.sensor_id()
+            ));
+            continue;
+        }
+        if let Err(err) = def.signal.validate() {
+            errors.push(format!("`{}`: {err}", def.id));
+            continue;
+        }
+        kept.push(def);
+    }
+    (SyntheticConfig { sensors: kept }, errors)
+}
+
+// ---- generators (pure) ----
+
+/// A tiny xorshift64* — enough for demo noise, and seedable for reproducible runs.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    /// Seeded from the id (FNV-1a), never zero.
+    fn seeded(id: &str) -> Self {
+        let hash = id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        Rng(hash.max(1))
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// One generator's running state. `started_ms` anchors the waves and sequences, so every
+/// (re)configured generator starts from its first value.
+#[derive(Debug)]
+pub struct Generator {
+    def: SyntheticDef,
+    started_ms: u64,
+    rng: Rng,
+    walk: Option<f64>,
+}
+
+impl Generator {
+    pub fn new(def: SyntheticDef, started_ms: u64) -> Self {
+        let rng = Rng::seeded(&def.id);
+        Generator {
+            def,
+            started_ms,
+            rng,
+            walk: None,
+        }
+    }
+
+    /// The value at `now_ms`. Random signals advance once per call.
+    pub fn sample(&mut self, now_ms: u64) -> SensorValue {
+        let elapsed = now_ms.saturating_sub(self.started_ms);
+        let span = |s: &str| parse_duration(s).unwrap_or(60_000);
+        match &self.def.signal {
+            Signal::Sine { min, max, period } => {
+                let phase = (elapsed % span(period)) as f64 / span(period) as f64;
+                let unit = (1.0 + (phase * TAU).sin()) / 2.0;
+                SensorValue::Scalar(min + (max - min) * unit)
+            }
+            Signal::Sawtooth { min, max, period } => {
+                let phase = (elapsed % span(period)) as f64 / span(period) as f64;
+                SensorValue::Scalar(min + (max - min) * phase)
+            }
+            Signal::RandomWalk { min, max, step } => {
+                let next = match self.walk {
+                    None => (min + max) / 2.0,
+                    Some(v) => (v + (self.rng.next_f64() * 2.0 - 1.0) * step).clamp(*min, *max),
+                };
+                self.walk = Some(next);
+                SensorValue::Scalar(next)
+            }
+            Signal::Noise { min, max } => {
+                SensorValue::Scalar(min + (max - min) * self.rng.next_f64())
+            }
+            Signal::Step { values, every } => {
+                let i = (elapsed / span(every)) as usize % values.len();
+                SensorValue::Scalar(values[i])
+            }
+            Signal::Text { values, every } => {
+                let i = (elapsed / span(every)) as usize % values.len();
+                SensorValue::Text(values[i].clone())
+            }
+        }
+    }
+
+    pub fn sample_at(&mut self, now_ms: u64) -> SensorSample {
+        SensorSample {
+            sensor: self.def.sensor_id(),
+            ts_ms: now_ms,
+            value: self.sample(now_ms),
+            stale: false,
+        }
+    }
+}
+
+/// Rebuild the generator set for `defs`, keeping the running state of any unchanged definition so
+/// a save doesn't restart every wave.
+fn reconfigure(old: Vec<Generator>, defs: &[SyntheticDef], now_ms: u64) -> Vec<Generator> {
+    let mut old = old;
+    defs.iter()
+        .map(|def| match old.iter().position(|g| &g.def == def) {
+            Some(i) => old.swap_remove(i),
+            None => Generator::new(def.clone(), now_ms),
+        })
+        .collect()
+}
+
+/// The generator task: publish every configured signal once a second. Idles (no batches) while
+/// nothing is configured. Runs for the app's lifetime.
+pub async fn run_synthetic<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let mut generators: Vec<Generator> = Vec::new();
+    let mut generation = u64::MAX;
+    let mut ticker = tokio::time::interval(Duration::from_millis(TICK_MS));
+    loop {
+        ticker.tick().await;
+        let now = now_ms();
+        let state: State<Synthetic> = app.state();
+        let current = state.generation.load(Ordering::Relaxed);
+        if current != generation {
+            let defs = state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .sensors
+                .clone();
+            generators = reconfigure(generators, &defs, now);
+            generation = current;
+        }
+        if generators.is_empty() {
+            continue;
+        }
+        let batch: Vec<SensorSample> = generators.iter_mut().map(|g| g.sample_at(now)).collect();
+        let _ = bus::publish(&app, &batch);
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/synthetic.json` and swap the live generators (picked up within a second).
+/// Studio-window-guarded like the other plugin configs. Any invalid entry rejects the whole save
+/// with every problem listed.
+#[tauri::command]
+pub async fn save_synthetic_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, Synthetic>,
+    sensors: Vec<SyntheticDef>,
+) -> Result<SyntheticConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_synthetic_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(SyntheticConfig { sensors });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = synthetic_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    state.replace(cfg.clone());
+    Ok(cfg)
+}
+
+/// The live synthetic generators.
+#[tauri::command]
+pub fn synthetic_config_status(state: State<'_, Synthetic>) -> SyntheticConfig {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(id: &str, signal: Signal) -> SyntheticDef {
+        SyntheticDef {
+            id: id.into(),
+            publish_as: None,
+            signal,
+        }
+    }
+
+    fn scalar(v: SensorValue) -> f64 {
+        match v {
+            SensorValue::Scalar(v) => v,
+            other => panic!("expected a scalar, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_the_documented_config_with_defaults() {
+        let cfg: SyntheticConfig = serde_json::from_str(
+            r#"{ "sensors": [
+                { "id": "gpu", "as": "gpu.temp", "signal": { "kind": "sine", "min": 40, "max": 80, "period": "2m" } },
+                { "id": "walk", "signal": { "kind": "random_walk" } },
+                { "id": "status", "signal": { "kind": "text", "values": ["idle", "busy"] } }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.sensors[0].sensor_id(), "gpu.temp");
+        assert_eq!(cfg.sensors[1].sensor_id(), "synthetic.walk");
+        assert_eq!(
+            cfg.sensors[1].signal,
+            Signal::RandomWalk {
+                min: 0.0,
+                max: 100.0,
+                step: 1.0
+            }
+        );
+        assert!(matches!(&cfg.sensors[2].signal, Signal::Text { every, .. } if every == "5s"));
+    }
+
+    #[test]
+    fn waves_follow_their_period() {
+        let sine = Signal::Sine {
+            min: 0.0,
+            max: 100.0,
+            period: "4s".into(),
+        };
+        let mut g = Generator::new(def("s", sine), 1_000);
+        assert!((scalar(g.sample(1_000)) - 50.0).abs() < 1e-9);
+        assert!((scalar(g.sample(2_000)) - 100.0).abs() < 1e-9);
+        assert!((scalar(g.sample(4_000)) - 0.0).abs() < 1e-9);
+        assert!((scalar(g.sample(5_000)) - 50.0).abs() < 1e-9);
+
+        let saw = Signal::Sawtooth {
+            min: 10.0,
+            max: 20.0,
+            period: "10s".into(),
+        };
+        let mut g = Generator::new(def("w", saw), 0);
+        assert_eq!(scalar(g.sample(5_000)), 15.0);
+        assert_eq!(scalar(g.sample(10_000)), 10.0);
+    }
+
+    #[test]
+    fn sequences_advance_every_interval_and_wrap() {
+        let step = Signal::Step {
+            values: vec![1.0, 2.0, 3.0],
+            every: "1s".into(),
+        };
+        let mut g = Generator::new(def("st", step), 0);
+        let got: Vec<f64> = [0, 999, 1_000, 2_500, 3_000]
+            .iter()
+            .map(|&t| scalar(g.sample(t)))
+            .collect();
+        assert_eq!(got, vec![1.0, 1.0, 2.0, 3.0, 1.0]);
+
+        let text = Signal::Text {
+            values: vec!["idle".into(), "busy".into()],
+            every: "2s".into(),
+        };
+        let mut g = Generator::new(def("tx", text), 0);
+        assert!(matches!(g.sample(2_000), SensorValue::Text(s) if s == "busy"));
+    }
+
+    #[test]
+    fn random_signals_stay_in_range_and_are_reproducible() {
+        let walk = Signal::RandomWalk {
+            min: 0.0,
+            max: 10.0,
+            step: 3.0,
+        };
+        let run = || {
+            let mut g = Generator::new(def("walk", walk.clone()), 0);
+            (0..200).map(|t| scalar(g.sample(t))).collect::<Vec<_>>()
+        };
+        let a = run();
+        assert_eq!(a[0], 5.0);
+        assert!(a.iter().all(|v| (0.0..=10.0).contains(v)));
+        assert!(a.windows(2).all(|w| (w[1] - w[0]).abs() <= 3.0));
+        assert_eq!(a, run());
+
+        let mut g = Generator::new(
+            def(
+                "n",
+                Signal::Noise {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            ),
+            0,
+        );
+        assert!((0..200).all(|t| (-1.0..1.0).contains(&scalar(g.sample(t)))));
+    }
+
+    #[test]
+    fn normalize_config_reports_each_bad_entry() {
+        let noise = Signal::Noise { min: 0.0, max: 1.0 };
+        let (cfg, errors) = normalize_config(SyntheticConfig {
+            sensors: vec![
+                def("CPU Demo", noise.clone()),
+                def("cpu_demo", noise.clone()),
+                SyntheticDef {
+                    publish_as: Some("gpu temp".into()),
+                    ..def("gpu", noise.clone())
+                },
+                def(
+                    "bad",
+                    Signal::Sine {
+                        min: 5.0,
+                        max: 1.0,
+                        period: "1s".into(),
+                    },
+                ),
+                def(
+                    "empty",
+                    Signal::Step {
+                        values: vec![],
+                        every: "1s".into(),
+                    },
+                ),
+            ],
+        });
+        assert_eq!(cfg.sensors.len(), 1);
+        assert_eq!(cfg.sensors[0].sensor_id(), "synthetic.cpu_demo");
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].contains("duplicate"));
+        assert!(errors[1].contains("not a sensor id"));
+    }
+
+    #[test]
+    fn reconfigure_keeps_unchanged_generators() {
+        let saw = Signal::Sawtooth {
+            min: 0.0,
+            max: 10.0,
+            period: "10s".into(),
+        };
+        let gens = reconfigure(Vec::new(), &[def("a", saw.clone())], 0);
+        let mut gens = reconfigure(gens, &[def("a", saw.clone()), def("b", saw.clone())], 5_000);
+        assert_eq!(scalar(gens[0].sample(5_000)), 5.0);
+        assert_eq!(scalar(gens[1].sample(5_000)), 0.0);
+    }
+}
@@ -0,0 +1,663 @@
+//! Log-file tail source — a game server's player count, a build log's error tally. Each configured
+//! file is followed like `tail -F`: appended lines are read as they land, a rotated file (new
+//! identity at the path) or a truncated one (shorter than the read position) is picked up from its
+//! start. Every line is run through the file's named regexes and published as:
+//!   `tail.<name>.line`                the last complete line
+//!   `tail.<name>.<pattern>.count`     lines matching `<pattern>` in the current file
+//!   `tail.<name>.<pattern>`           capture group 1 of the latest match (no named groups)
+//!   `tail.<name>.<pattern>.<group>`   each named group of the latest match
+//!   `tail.<name>.status`              `following`, or `missing` while the file can't be opened
+//! A captured value that parses as a number is a Scalar, otherwise Text. Counts restart at 0 on
+//! rotation/truncation (a new build log, a new server session).
+//!
+//! Configured in `plugins/tail.json`:
+//! `{ "files": [{ "name": "mc", "path": "/srv/mc/logs/latest.log", "patterns": [
+//!     { "name": "players", "regex": "There are (?P<online>\\d+) of a max of (?P<max>\\d+)" },
+//!     { "name": "errors", "regex": "(?i)\\berror\\b" }] }] }`
+//! Following starts at the end of the file unless `from_start`. Like the other proxy sources,
+//! reading pauses while no window shows a `tail.<name>.*` sensor (`always` opts out) — the read
+//! position is kept, so the backlog (and the counts) catch up when one does. The file is polled
+//! rather than watched: a stat per second also catches rotation on filesystems whose change events
+//! don't. The pure seams (`Extractor`, `Follower` over a temp file) are unit-tested.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, supervise};
+
+/// How often each file is checked for new lines.
+const POLL: Duration = Duration::from_secs(1);
+/// Most bytes read per poll, so a huge backlog is worked through a slice at a time.
+const MAX_READ: usize = 1 << 20;
+/// A line longer than this is cut and published in pieces rather than buffered forever.
+const MAX_LINE: usize = 64 * 1024;
+/// `.line` samples are clipped to this many chars.
+const MAX_LINE_SAMPLE: usize = 512;
+/// Re-check the demand gate this often while a file is idle.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+/// Ids a pattern or group name would shadow.
+const RESERVED: [&str; 3] = ["line", "status", "count"];
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config ----
+
+/// One named regex.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TailPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+/// One followed file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TailFile {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub patterns: Vec<TailPattern>,
+    /// Read the existing contents first instead of starting at the end.
+    #[serde(default)]
+    pub from_start: bool,
+    /// Follow even while no window shows this file's sensors.
+    #[serde(default)]
+    pub always: bool,
+}
+
+impl Supervised for TailFile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// `plugins/tail.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TailConfig {
+    #[serde(default)]
+    pub files: Vec<TailFile>,
+}
+
+/// Managed state: the live definitions plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct TailSource {
+    config: Mutex<TailConfig>,
+    generation: AtomicU64,
+}
+
+impl TailSource {
+    fn replace(&self, cfg: TailConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("tail.json"))
+}
+
+pub fn load_tail_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<TailConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_tail_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("tail", "skipping tailed file")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<TailSource>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("tail", "failed to read tail.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug file and pattern names, reject duplicates, relative paths, bad regexes and names that
+/// would shadow a fixed id.
+fn normalize_config(cfg: TailConfig) -> (TailConfig, Vec<String>) {
+    let mut kept: Vec<TailFile> = Vec::new();
+    let mut errors = Vec::new();
+    for f in cfg.files {
+        let Some(name) = id_segment(&f.name) else {
+            errors.push(format!("`{}`: invalid name", f.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let path = f.path.trim().to_string();
+        if !Path::new(&path).is_absolute() {
+            errors.push(format!("`{name}`: path must be absolute"));
+            continue;
+        }
+        let mut patterns: Vec<TailPattern> = Vec::new();
+        let mut problems = Vec::new();
+        for p in &f.patterns {
+            let Some(pname) = id_segment(&p.name).filter(|n| !RESERVED.contains(&n.as_str()))
+            else {
+                problems.push(format!("`{name}`: invalid pattern name `{}`", p.name));
+                continue;
+            };
+            if patterns.iter().any(|k| k.name == pname) {
+                problems.push(format!("`{name}`: duplicate pattern `{pname}`"));
+                continue;
+            }
+            match Regex::new(&p.regex) {
+                Ok(re) if re.capture_names().flatten().any(|g| RESERVED.contains(&g)) => {
+                    problems.push(format!("`{name}.{pname}`: reserved group name"));
+                }
+                Ok(_) => patterns.push(TailPattern {
+                    name: pname,
+                    regex: p.regex.clone(),
+                }),
+                Err(err) => problems.push(format!("`{name}.{pname}`: {err}")),
+            }
+        }
+        if !problems.is_empty() {
+            errors.extend(problems);
+            continue;
+        }
+        kept.push(TailFile {
+            name,
+            path,
+            patterns,
+            ..f
+        });
+    }
+    (TailConfig { files: kept }, errors)
+}
+
+// ---- extraction (pure) ----
+
+/// A captured string as a sensor value: a number when it parses as one.
+fn capture_value(s: &str) -> SensorValue {
+    match s.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => SensorValue::Scalar(v),
+        _ => SensorValue::Text(s.to_string()),
+    }
+}
+
+/// Per-file line state: the last line, match counts and latest captures.
+pub struct Extractor {
+    base: String,
+    patterns: Vec<(String, Regex)>,
+    counts: Vec<u64>,
+    last_line: Option<String>,
+    /// Captures since the last `samples` call, by id (latest wins).
+    captured: HashMap<String, SensorValue>,
+}
+
+impl Extractor {
+    /// Build from a normalized file definition (regexes already validated).
+    pub fn new(file: &TailFile) -> Self {
+        let patterns: Vec<(String, Regex)> = file
+            .patterns
+            .iter()
+            .filter_map(|p| Some((p.name.clone(), Regex::new(&p.regex).ok()?)))
+            .collect();
+        Self {
+            base: format!("tail.{}", file.name),
+            counts: vec![0; patterns.len()],
+            patterns,
+            last_line: None,
+            captured: HashMap::new(),
+        }
+    }
+
+    /// A new file took over the path: counts restart.
+    pub fn reset(&mut self) {
+        self.counts.iter_mut().for_each(|c| *c = 0);
+    }
+
+    pub fn feed(&mut self, line: &str) {
+        for ((pname, re), count) in self.patterns.iter().zip(&mut self.counts) {
+            let Some(caps) = re.captures(line) else {
+                continue;
+            };
+            *count += 1;
+            let id = format!("{}.{pname}", self.base);
+            let mut named = false;
+            for group in re.capture_names().flatten() {
+                named = true;
+                if let Some(m) = caps.name(group) {
+                    self.captured
+                        .insert(format!("{id}.{group}"), capture_value(m.as_str()));
+                }
+            }
+            if let (false, Some(m)) = (named, caps.get(1)) {
+                self.captured.insert(id, capture_value(m.as_str()));
+            }
+        }
+        self.last_line = Some(line.chars().take(MAX_LINE_SAMPLE).collect());
+    }
+
+    /// The current sensors: the last line, every count, and what was captured since last time.
+    pub fn samples(&mut self, ts: u64) -> Vec<SensorSample> {
+        let mut out = Vec::new();
+        if let Some(line) = &self.last_line {
+            out.push(SensorSample::text(
+                format!("{}.line", self.base),
+                ts,
+                line.clone(),
+            ));
+        }
+        for ((pname, _), count) in self.patterns.iter().zip(&self.counts) {
+            let id = format!("{}.{pname}.count", self.base);
+            out.push(SensorSample::scalar(id, ts, *count as f64));
+        }
+        let mut captured: Vec<_> = self.captured.drain().collect();
+        captured.sort_by(|a, b| a.0.cmp(&b.0));
+        out.extend(captured.into_iter().map(|(sensor, value)| SensorSample {
+            sensor,
+            ts_ms: ts,
+            value,
+            stale: false,
+        }));
+        out
+    }
+}
+
+// ---- following ----
+
+/// What identifies "the same file" across polls: the inode on Unix, the NTFS file index on
+/// Windows. Not the creation time: NTFS tunnelling hands a file re-created under a just-renamed
+/// name (exactly what log rotation does) the old file's creation time.
+#[cfg(unix)]
+fn identity(_file: &File, meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(windows)]
+fn identity(file: &File, _meta: &std::fs::Metadata) -> u64 {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        BY_HANDLE_FILE_INFORMATION, GetFileInformationByHandle,
+    };
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    // SAFETY: the handle is open for the duration of the call (borrowed from `file`), and `info`
+    // is a valid owned struct the call fills.
+    if unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }.is_err() {
+        return 0;
+    }
+    (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow)
+}
+
+/// One poll's result.
+#[derive(Debug, Default, PartialEq)]
+pub struct Chunk {
+    /// The file was replaced or truncated since the last poll (reading restarted at 0).
+    pub reset: bool,
+    pub lines: Vec<String>,
+}
+
+/// Read position in a followed file.
+pub struct Follower {
+    path: PathBuf,
+    /// `(identity, position)` once the file has been opened.
+    at: Option<(u64, u64)>,
+    from_start: bool,
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    pub fn new(path: impl Into<PathBuf>, from_start: bool) -> Self {
+        Self {
+            path: path.into(),
+            at: None,
+            from_start,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Read what was appended since the last poll. Errors when the file can't be opened; the
+    /// position survives, so a briefly missing file (mid-rotation) resumes where it should.
+    pub fn poll(&mut self) -> std::io::Result<Chunk> {
+        let mut file = File::open(&self.path)?;
+        let meta = file.metadata()?;
+        let id = identity(&file, &meta);
+        let mut chunk = Chunk::default();
+        let pos = match self.at {
+            // First open: the existing contents are history unless asked for.
+            None => {
+                if self.from_start {
+                    0
+                } else {
+                    meta.len()
+                }
+            }
+            Some((old, pos)) if old == id && meta.len() >= pos => pos,
+            Some(_) => {
+                chunk.reset = true;
+                self.partial.clear();
+                0
+            }
+        };
+        file.seek(SeekFrom::Start(pos))?;
+        let mut buf = Vec::new();
+        let read = file.take(MAX_READ as u64).read_to_end(&mut buf)?;
+        self.at = Some((id, pos + read as u64));
+        for byte in buf {
+            if byte == b'\n' {
+                chunk.lines.push(self.take_line());
+            } else {
+                self.partial.push(byte);
+                if self.partial.len() >= MAX_LINE {
+                    chunk.lines.push(self.take_line());
+                }
+            }
+        }
+        Ok(chunk)
+    }
+
+    fn take_line(&mut self) -> String {
+        let line = String::from_utf8_lossy(&self.partial)
+            .trim_end_matches('\r')
+            .to_string();
+        self.partial.clear();
+        line
+    }
+}
+
+// ---- task ----
+
+/// True while any window is consuming one of this file's sensors. Default OFF before the first
+/// report, like `endpoint_wanted`.
+fn file_wanted<R: Runtime>(app: &AppHandle<R>, name: &str) -> bool {
+    let active = app.state::<ActiveSensors>();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    let prefix = format!("tail.{name}.");
+    crate::sensors::any_wanted(&guard, |id| id.starts_with(&prefix))
+}
+
+/// Publish `tail.<name>.status` on change only.
+fn emit_status<R: Runtime>(app: &AppHandle<R>, name: &str, status: &str, last: &mut String) {
+    if last != status {
+        *last = status.to_string();
+        let id = format!("tail.{name}.status");
+        let _ = bus::publish(app, &[SensorSample::text(id, now_ms(), status)]);
+    }
+}
+
+/// Follow one file until aborted.
+async fn follow_file<R: Runtime>(app: AppHandle<R>, file: TailFile) {
+    let mut extractor = Extractor::new(&file);
+    let mut follower = Follower::new(&file.path, file.from_start);
+    let mut status = String::new();
+    loop {
+        if !file.always && !file_wanted(&app, &file.name) {
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        // Up to MAX_READ of file I/O (a slow share, a big backlog): off the async workers. The
+        // follower travels with the read and comes back with its result.
+        let polled = tokio::task::spawn_blocking(move || {
+            let chunk = follower.poll();
+            (follower, chunk)
+        })
+        .await;
+        let chunk = match polled {
+            Ok((f, chunk)) => {
+                follower = f;
+                chunk
+            }
+            Err(err) => {
+                log::error("tail", "tail read task failed")
+                    .field("name", file.name.clone())
+                    .field("error", err.to_string())
+                    .emit();
+                return;
+            }
+        };
+        match chunk {
+            Ok(chunk) => {
+                emit_status(&app, &file.name, "following", &mut status);
+                if chunk.reset {
+                    extractor.reset();
+                }
+                for line in &chunk.lines {
+                    extractor.feed(line);
+                }
+                if chunk.reset || !chunk.lines.is_empty() {
+                    let _ = bus::publish(&app, &extractor.samples(now_ms()));
+                }
+            }
+            Err(err) => {
+                if status != "missing" {
+                    log::warn("tail", "cannot open tailed file")
+                        .field("name", file.name.clone())
+                        .field("error", err.to_string())
+                        .emit();
+                }
+                emit_status(&app, &file.name, "missing", &mut status);
+            }
+        }
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+/// The supervisor: one follower per file, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_tail<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<TailSource> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .files
+                .clone()
+        },
+        |f| tauri::async_runtime::spawn(follow_file(app.clone(), f)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/tail.json` and restart the changed followers. Studio-window-guarded; any
+/// invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_tail_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, TailSource>,
+    files: Vec<TailFile>,
+) -> Result<Vec<TailFile>, String> {
+    if window.label() != "studio" {
+        return Err("save_tail_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(TailConfig { files });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.files.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured tailed files.
+#[tauri::command]
+pub fn tail_config_status(state: State<'_, TailSource>) -> Vec<TailFile> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .files
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file(patterns: &[(&str, &str)]) -> TailFile {
+        TailFile {
+            name: "mc".into(),
+            path: "/srv/mc/latest.log".into(),
+            patterns: patterns
+                .iter()
+                .map(|(n, r)| TailPattern {
+                    name: n.to_string(),
+                    regex: r.to_string(),
+                })
+                .collect(),
+            from_start: false,
+            always: false,
+        }
+    }
+
+    fn scalar(batch: &[SensorSample], id: &str) -> Option<f64> {
+        match batch.iter().find(|s| s.sensor == id).map(|s| &s.value) {
+            Some(SensorValue::Scalar(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn text(batch: &[SensorSample], id: &str) -> Option<String> {
+        match batch.iter().find(|s| s.sensor == id).map(|s| &s.value) {
+            Some(SensorValue::Text(s)) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn extractor_counts_and_captures() {
+        let mut x = Extractor::new(&file(&[
+            (
+                "players",
+                r"There are (?P<online>\d+) of a max of (?P<max>\d+)",
+            ),
+            ("errors", r"(?i)\berror\b"),
+            ("map", r"Loading map (\S+)"),
+        ]));
+        x.feed("[12:00] There are 3 of a max of 20 players online");
+        x.feed("[12:01] ERROR: chunk save failed");
+        x.feed("[12:02] Loading map lobby");
+        let batch = x.samples(1);
+        assert_eq!(
+            text(&batch, "tail.mc.line").as_deref(),
+            Some("[12:02] Loading map lobby")
+        );
+        assert_eq!(scalar(&batch, "tail.mc.players.online"), Some(3.0));
+        assert_eq!(scalar(&batch, "tail.mc.players.max"), Some(20.0));
+        assert_eq!(scalar(&batch, "tail.mc.errors.count"), Some(1.0));
+        assert_eq!(text(&batch, "tail.mc.map").as_deref(), Some("lobby"));
+        // Captures are only re-sent when matched again; counts always are, and reset to 0.
+        x.reset();
+        let batch = x.samples(2);
+        assert_eq!(text(&batch, "tail.mc.map"), None);
+        assert_eq!(scalar(&batch, "tail.mc.players.count"), Some(0.0));
+    }
+
+    #[test]
+    fn follower_handles_partial_lines_truncation_and_rotation() {
+        let dir = std::env::temp_dir().join(format!("widgetsack-tail-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        std::fs::write(&path, "old history\n").unwrap();
+        let append = |s: &str| {
+            let mut f = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+            f.write_all(s.as_bytes()).unwrap();
+        };
+
+        let mut f = Follower::new(&path, false);
+        assert_eq!(f.poll().unwrap(), Chunk::default());
+        append("one\r\ntw");
+        assert_eq!(f.poll().unwrap().lines, vec!["one"]);
+        append("o\n");
+        assert_eq!(f.poll().unwrap().lines, vec!["two"]);
+
+        // Truncated in place (a new build log).
+        std::fs::write(&path, "fresh\n").unwrap();
+        let chunk = f.poll().unwrap();
+        assert!(chunk.reset);
+        assert_eq!(chunk.lines, vec!["fresh"]);
+
+        // Rotated: renamed away, a new file at the path (longer than the old position).
+        std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+        assert!(f.poll().is_err());
+        std::fs::write(&path, "rotated line one\nrotated line two\n").unwrap();
+        let chunk = f.poll().unwrap();
+        assert!(chunk.reset);
+        assert_eq!(chunk.lines.len(), 2);
+
+        let mut from_start = Follower::new(&path, true);
+        assert_eq!(from_start.poll().unwrap().lines.len(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn normalize_rejects_bad_regexes_and_reserved_names() {
+        let (cfg, errors) = normalize_config(TailConfig {
+            files: vec![
+                file(&[("Errors", "error"), ("bad", "(")]),
+                TailFile {
+                    name: "build".into(),
+                    ..file(&[("count", "x")])
+                },
+                TailFile {
+                    name: "ok".into(),
+                    ..file(&[("Warn Lines", "(?P<count>warn)")])
+                },
+                TailFile {
+                    name: "fine".into(),
+                    ..file(&[("Warn Lines", "warn")])
+                },
+            ],
+        });
+        assert_eq!(errors.len(), 3);
+        assert_eq!(cfg.files.len(), 1);
+        assert_eq!(cfg.files[0].patterns[0].name, "warn_lines");
+    }
+}
@@ -0,0 +1,777 @@
+//! Task list source — a `todo.txt` file or a Markdown checklist (`- [ ] …`, an Obsidian or
+//! Logseq note) on the overlay, with a button that checks items off. Each configured file is
+//! watched with the same `notify` watcher folder.rs uses, so an edit in any editor (or a sync
+//! client replacing the file) shows up immediately. Publishes:
+//!   `tasks.<name>.open`       open tasks
+//!   `tasks.<name>.overdue`    open tasks due before today
+//!   `tasks.<name>.due_today`  open tasks due today
+//!   `tasks.<name>.done`       completed tasks still in the file
+//!   `tasks.<name>.items`      Json array of the next `items` open tasks — by due date, then
+//!                             priority, then file order (`{ id, text, priority, due, overdue }`)
+//!   `tasks.<name>.next`       the first of those as text (empty when nothing is open)
+//!   `tasks.<name>.status`     `ok`, or `missing` while the file can't be read
+//!
+//! Configured in `plugins/tasks.json`:
+//! `{ "lists": [{ "name": "home", "path": "C:\\Users\\me\\todo.txt" },
+//!              { "name": "work", "path": "D:\\notes\\Work.md", "items": 5 }] }`
+//! `format` is `auto` (by extension: `.md` / `.markdown` are Markdown), `todotxt` or `markdown`.
+//! todo.txt follows the usual conventions: `x ` marks done, `(A) ` is the priority, `due:` the due
+//! date, and completing a task records the date and moves the priority to `pri:`. In Markdown a
+//! due date is `due:2026-10-20` or the Tasks plugin's `📅 2026-10-20`.
+//!
+//! `task_toggle` flips one task (by its `id` from `items`) and rewrites the file with
+//! `atomic_write`, changing only that line. An id is the line number plus a hash of the line, so a
+//! toggle against a file that changed since it was published fails instead of hitting the wrong
+//! task. Counts are re-evaluated when the local date changes. The parser, the toggle and the
+//! samples are unit-tested.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate};
+use notify::Watcher;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::command::atomic_write;
+use crate::log;
+use crate::sensors::{SensorSample, SensorValue, id_segment};
+use crate::supervisor::{Supervised, settle, supervise};
+
+/// Quiet period after the last event before re-reading.
+const SETTLE: Duration = Duration::from_millis(500);
+/// Longest a burst is coalesced before re-reading anyway.
+const MAX_SETTLE: Duration = Duration::from_millis(2_500);
+/// How often a missing (or unwatchable) file is looked for again.
+const MISSING_RETRY: Duration = Duration::from_secs(30);
+/// How often the date is checked for a midnight rollover.
+const DATE_TICK: Duration = Duration::from_secs(60);
+/// Larger files are refused — a task list, not a log.
+const MAX_FILE: u64 = 1024 * 1024;
+const MAX_ITEMS: usize = 100;
+
+fn default_items() -> usize {
+    10
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// ---- config ----
+
+/// How a file's lines are read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskFormat {
+    /// Markdown for `.md` / `.markdown`, todo.txt otherwise.
+    #[default]
+    Auto,
+    Todotxt,
+    Markdown,
+}
+
+/// One watched task file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskList {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub format: TaskFormat,
+    /// How many open tasks `tasks.<name>.items` carries.
+    #[serde(default = "default_items")]
+    pub items: usize,
+}
+
+impl Supervised for TaskList {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TaskList {
+    /// The concrete format (`Auto` resolved by extension).
+    fn format(&self) -> TaskFormat {
+        match self.format {
+            TaskFormat::Auto => {
+                let ext = Path::new(&self.path)
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase());
+                match ext.as_deref() {
+                    Some("md" | "markdown") => TaskFormat::Markdown,
+                    _ => TaskFormat::Todotxt,
+                }
+            }
+            f => f,
+        }
+    }
+}
+
+/// `plugins/tasks.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TasksConfig {
+    #[serde(default)]
+    pub lists: Vec<TaskList>,
+}
+
+/// Managed state: the live definitions plus the generation counter the supervisor polls.
+#[derive(Default)]
+pub struct TaskLists {
+    config: Mutex<TasksConfig>,
+    generation: AtomicU64,
+}
+
+impl TaskLists {
+    fn replace(&self, cfg: TasksConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = cfg;
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn def(&self, name: &str) -> Option<TaskList> {
+        self.config
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .lists
+            .iter()
+            .find(|l| l.name == name)
+            .cloned()
+    }
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("tasks.json"))
+}
+
+pub fn load_tasks_config<R: Runtime>(app: &AppHandle<R>) -> Result<Option<TasksConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Seed the managed definitions from disk, logging each rejected entry.
+fn load_into_state<R: Runtime>(app: &AppHandle<R>) {
+    match load_tasks_config(app) {
+        Ok(Some(cfg)) => {
+            let (kept, errors) = normalize_config(cfg);
+            for err in errors {
+                log::warn("tasks", "skipping task list")
+                    .field("error", err)
+                    .emit();
+            }
+            app.state::<TaskLists>().replace(kept);
+        }
+        Ok(None) => {}
+        Err(err) => log::warn("tasks", "failed to read tasks.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+/// Slug names, clamp the item count, reject duplicates and relative paths.
+fn normalize_config(cfg: TasksConfig) -> (TasksConfig, Vec<String>) {
+    let mut kept: Vec<TaskList> = Vec::new();
+    let mut errors = Vec::new();
+    for l in cfg.lists {
+        let Some(name) = id_segment(&l.name) else {
+            errors.push(format!("`{}`: invalid name", l.name));
+            continue;
+        };
+        if kept.iter().any(|k| k.name == name) {
+            errors.push(format!("`{name}`: duplicate name"));
+            continue;
+        }
+        let path = l.path.trim().to_string();
+        if !Path::new(&path).is_absolute() {
+            errors.push(format!("`{name}`: path must be absolute"));
+            continue;
+        }
+        kept.push(TaskList {
+            name,
+            path,
+            items: l.items.clamp(1, MAX_ITEMS),
+            ..l
+        });
+    }
+    (TasksConfig { lists: kept }, errors)
+}
+
+// ---- parsing (pure) ----
+
+/// One task line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    /// `<line>:<hash>` — see the module docs.
+    pub id: String,
+    pub done: bool,
+    /// The description without the done marker, priority, dates and `due:`.
+    pub text: String,
+    pub priority: Option<char>,
+    pub due: Option<NaiveDate>,
+}
+
+/// The file's lines (a leading BOM dropped) as `(content, ending)`, endings preserved.
+fn lines(content: &str) -> impl Iterator<Item = (&str, &str)> {
+    let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+    body.split_inclusive('\n').map(|raw| {
+        let text = raw.trim_end_matches(['\r', '\n']);
+        (text, &raw[text.len()..])
+    })
+}
+
+/// A stable id for line `index` with this content (FNV-1a of the content).
+fn line_id(index: usize, text: &str) -> String {
+    let hash = text.bytes().fold(0x811c_9dc5_u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    });
+    format!("{index}:{hash:08x}")
+}
+
+fn date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+/// `(A) ` → `A`.
+fn priority_prefix(s: &str) -> Option<char> {
+    let b = s.as_bytes();
+    (b.len() >= 4 && b[0] == b'(' && b[1].is_ascii_uppercase() && b[2] == b')' && b[3] == b' ')
+        .then(|| b[1] as char)
+}
+
+/// A todo.txt line: `x`, `(A)`, the leading dates, then the description with `due:` / `pri:` tags.
+fn parse_todotxt(line: &str) -> Option<(bool, Option<char>, String, Option<NaiveDate>)> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let (done, mut rest) = match line.strip_prefix("x ") {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, line.trim_start()),
+    };
+    let mut priority = None;
+    if let Some(p) = priority_prefix(rest) {
+        priority = Some(p);
+        rest = &rest[4..];
+    }
+    let mut due = None;
+    let mut words = Vec::new();
+    let mut leading = true;
+    for word in rest.split_whitespace() {
+        // Completion / creation dates come first.
+        if leading && date(word).is_some() {
+            continue;
+        }
+        leading = false;
+        if let Some(d) = word.strip_prefix("due:").and_then(date) {
+            due = Some(d);
+        } else if let Some(p) = word.strip_prefix("pri:").filter(|p| p.len() == 1) {
+            priority = priority.or(p.chars().next().filter(char::is_ascii_uppercase));
+        } else {
+            words.push(word);
+        }
+    }
+    Some((done, priority, words.join(" "), due))
+}
+
+/// The byte offset of a Markdown checkbox (`[ ]` / `[x]`) after the bullet, if this is a task line.
+fn markdown_box(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    if !["- ", "* ", "+ "].iter().any(|b| rest.starts_with(b)) {
+        return None;
+    }
+    let after = &rest.as_bytes()[2..];
+    (after.len() >= 4
+        && after[0] == b'['
+        && matches!(after[1], b' ' | b'x' | b'X')
+        && after[2] == b']'
+        && after[3] == b' ')
+        .then_some(indent + 2)
+}
+
+/// A Markdown checklist line, with `due:` or the Tasks plugin's `📅 date`.
+fn parse_markdown(line: &str) -> Option<(bool, Option<char>, String, Option<NaiveDate>)> {
+    let at = markdown_box(line)?;
+    let done = line.as_bytes()[at + 1] != b' ';
+    let mut due = None;
+    let mut words = Vec::new();
+    let mut iter = line[at + 4..].split_whitespace();
+    while let Some(word) = iter.next() {
+        if let Some(d) = word.strip_prefix("due:").and_then(date) {
+            due = Some(d);
+        } else if word == "📅" {
+            due = iter.next().and_then(date).or(due);
+        } else {
+            words.push(word);
+        }
+    }
+    let text = words.join(" ");
+    (!text.is_empty()).then_some((done, None, text, due))
+}
+
+/// Every task in the file.
+fn parse_tasks(content: &str, format: TaskFormat) -> Vec<Task> {
+    lines(content)
+        .enumerate()
+        .filter_map(|(i, (line, _))| {
+            let (done, priority, text, due) = match format {
+                TaskFormat::Markdown => parse_markdown(line)?,
+                _ => parse_todotxt(line)?,
+            };
+            Some(Task {
+                id: line_id(i, line),
+                done,
+                text,
+                priority,
+                due,
+            })
+        })
+        .collect()
+}
+
+/// `line` with its task flipped, or `None` when it isn't a task.
+fn toggle_line(line: &str, format: TaskFormat, today: NaiveDate) -> Option<String> {
+    if format == TaskFormat::Markdown {
+        let at = markdown_box(line)?;
+        let mark = if line.as_bytes()[at + 1] == b' ' {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        return Some(format!("{}{mark}{}", &line[..at], &line[at + 3..]));
+    }
+    let (done, ..) = parse_todotxt(line)?;
+    if done {
+        // Drop `x` and the completion date; a `pri:` tag goes back to the front.
+        let mut rest = line[2..].trim_start();
+        if let Some((first, after)) = rest.split_once(' ')
+            && date(first).is_some()
+        {
+            rest = after;
+        }
+        let mut pri = None;
+        let words: Vec<&str> = rest
+            .split(' ')
+            .filter(|w| match w.strip_prefix("pri:") {
+                Some(p) if p.len() == 1 && pri.is_none() => {
+                    pri = p.chars().next();
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+        let rest = words.join(" ");
+        Some(match pri {
+            Some(p) => format!("({p}) {rest}"),
+            None => rest,
+        })
+    } else {
+        let rest = line.trim_start();
+        Some(match priority_prefix(rest) {
+            Some(p) => format!("x {today} {} pri:{p}", &rest[4..]),
+            None => format!("x {today} {rest}"),
+        })
+    }
+}
+
+/// Flip task `id` in `content`: the new content plus whether the task is now done.
+fn toggle_in(
+    content: &str,
+    id: &str,
+    format: TaskFormat,
+    today: NaiveDate,
+) -> Result<(String, bool), String> {
+    let mut out = String::with_capacity(content.len() + 16);
+    if content.starts_with('\u{feff}') {
+        out.push('\u{feff}');
+    }
+    let mut done = None;
+    for (i, (line, ending)) in lines(content).enumerate() {
+        if done.is_none() && line_id(i, line) == id {
+            let toggled = toggle_line(line, format, today).ok_or("that line isn't a task")?;
+            let (now_done, ..) = match format {
+                TaskFormat::Markdown => parse_markdown(&toggled),
+                _ => parse_todotxt(&toggled),
+            }
+            .ok_or("that line isn't a task")?;
+            out.push_str(&toggled);
+            done = Some(now_done);
+        } else {
+            out.push_str(line);
+        }
+        out.push_str(ending);
+    }
+    let done = done.ok_or("task not found — the file changed since it was read")?;
+    Ok((out, done))
+}
+
+/// The sensors for one list as of `today`.
+fn samples(def: &TaskList, tasks: &[Task], today: NaiveDate, ts: u64) -> Vec<SensorSample> {
+    let base = format!("tasks.{}", def.name);
+    let mut open: Vec<&Task> = tasks.iter().filter(|t| !t.done).collect();
+    let overdue = open
+        .iter()
+        .filter(|t| t.due.is_some_and(|d| d < today))
+        .count();
+    let due_today = open.iter().filter(|t| t.due == Some(today)).count();
+    // Dated first (soonest first), then by priority (A first, none last); stable for file order.
+    open.sort_by_key(|t| (t.due.is_none(), t.due, t.priority.is_none(), t.priority));
+    let items: Vec<_> = open
+        .iter()
+        .take(def.items)
+        .map(|t| {
+            json!({
+                "id": t.id,
+                "text": t.text,
+                "priority": t.priority.map(String::from),
+                "due": t.due.map(|d| d.to_string()),
+                "overdue": t.due.is_some_and(|d| d < today),
+            })
+        })
+        .collect();
+    vec![
+        SensorSample::scalar(format!("{base}.open"), ts, open.len() as f64),
+        SensorSample::scalar(format!("{base}.overdue"), ts, overdue as f64),
+        SensorSample::scalar(format!("{base}.due_today"), ts, due_today as f64),
+        SensorSample::scalar(
+            format!("{base}.done"),
+            ts,
+            (tasks.len() - open.len()) as f64,
+        ),
+        SensorSample {
+            sensor: format!("{base}.items"),
+            ts_ms: ts,
+            value: SensorValue::Json(serde_json::Value::Array(items)),
+            stale: false,
+        },
+        SensorSample::text(
+            format!("{base}.next"),
+            ts,
+            open.first().map(|t| t.text.clone()).unwrap_or_default(),
+        ),
+        SensorSample::text(format!("{base}.status"), ts, "ok"),
+    ]
+}
+
+// ---- runtime ----
+
+fn read(def: &TaskList) -> Result<String, String> {
+    let len = std::fs::metadata(&def.path)
+        .map_err(|e| e.to_string())?
+        .len();
+    if len > MAX_FILE {
+        return Err(format!("file is over {MAX_FILE} bytes"));
+    }
+    std::fs::read_to_string(&def.path).map_err(|e| e.to_string())
+}
+
+/// Read, parse and publish; `false` when the file couldn't be read (`status` is then `missing`).
+fn read_and_publish<R: Runtime>(app: &AppHandle<R>, def: &TaskList) -> bool {
+    match read(def) {
+        Ok(content) => {
+            let tasks = parse_tasks(&content, def.format());
+            let _ = bus::publish(app, &samples(def, &tasks, today(), now_ms()));
+            true
+        }
+        Err(err) => {
+            log::warn("tasks", "task file unreadable")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            let status = format!("tasks.{}.status", def.name);
+            let _ = bus::publish(app, &[SensorSample::text(status, now_ms(), "missing")]);
+            false
+        }
+    }
+}
+
+/// Watch one file until aborted: read once, then after each settled burst of events touching it,
+/// and when the date rolls over. The file's directory is watched (editors and sync clients
+/// replace files by rename), so the watcher lives in this task and aborting it stops the OS watch.
+async fn watch_list<R: Runtime>(app: AppHandle<R>, def: TaskList) {
+    let path = PathBuf::from(&def.path);
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let file_name = file_name.to_os_string();
+    loop {
+        if !read_and_publish(&app, &def) {
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        }
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let target = file_name.clone();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(e) = res
+                && !e.kind.is_access()
+                && e.paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(target.as_os_str()))
+            {
+                let _ = tx.send(());
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                log::error("tasks", "task watcher init failed")
+                    .field("error", err)
+                    .emit();
+                return;
+            }
+        };
+        if let Err(err) = watcher.watch(dir, notify::RecursiveMode::NonRecursive) {
+            log::warn("tasks", "task file watch failed")
+                .field("name", def.name.clone())
+                .field("error", err)
+                .emit();
+            tokio::time::sleep(MISSING_RETRY).await;
+            continue;
+        }
+        let mut day = today();
+        loop {
+            tokio::select! {
+                ev = rx.recv() => {
+                    if ev.is_none() {
+                        break;
+                    }
+                    settle(&mut rx, SETTLE, MAX_SETTLE).await;
+                }
+                _ = tokio::time::sleep(DATE_TICK) => {
+                    if today() == day {
+                        continue;
+                    }
+                }
+            }
+            day = today();
+            if !read_and_publish(&app, &def) {
+                // The file went away; drop the watch and wait for it to return.
+                break;
+            }
+        }
+        drop(watcher);
+        tokio::time::sleep(MISSING_RETRY).await;
+    }
+}
+
+/// The supervisor: one watch task per list, restarted whenever a save bumps the generation.
+/// Runs for the app's lifetime.
+pub async fn run_tasks<R: Runtime>(app: AppHandle<R>) {
+    load_into_state(&app);
+    let state: State<TaskLists> = app.state();
+    supervise(
+        || state.generation.load(Ordering::Relaxed),
+        || {
+            state
+                .config
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .lists
+                .clone()
+        },
+        |l| tauri::async_runtime::spawn(watch_list(app.clone(), l)),
+    )
+    .await;
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/tasks.json` and restart the changed watches. Studio-window-guarded; any
+/// invalid entry rejects the whole save with every problem listed.
+#[tauri::command]
+pub async fn save_tasks_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, TaskLists>,
+    lists: Vec<TaskList>,
+) -> Result<Vec<TaskList>, String> {
+    if window.label() != "studio" {
+        return Err("save_tasks_config is only allowed from the studio window".into());
+    }
+    let (cfg, errors) = normalize_config(TasksConfig { lists });
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    let saved = cfg.lists.clone();
+    state.replace(cfg);
+    Ok(saved)
+}
+
+/// The configured task lists.
+#[tauri::command]
+pub fn tasks_config_status(state: State<'_, TaskLists>) -> Vec<TaskList> {
+    state
+        .config
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .lists
+        .clone()
+}
+
+/// Check off (or reopen) task `id` from `tasks.<name>.items`, rewriting the file in place, and
+/// republish. Returns whether the task is now done. Callable from any window, so an overlay
+/// button can tick items.
+#[tauri::command]
+pub fn task_toggle(
+    app: AppHandle,
+    state: State<'_, TaskLists>,
+    name: String,
+    id: String,
+) -> Result<bool, String> {
+    let def = state
+        .def(&name)
+        .ok_or_else(|| format!("no task list named `{name}`"))?;
+    let content = read(&def)?;
+    let (updated, done) = toggle_in(&content, &id, def.format(), today())?;
+    atomic_write(Path::new(&def.path), &updated)?;
+    read_and_publish(&app, &def);
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        date(s).unwrap()
+    }
+
+    fn list(path: &str) -> TaskList {
+        serde_json::from_value(json!({ "name": "t", "path": path })).unwrap()
+    }
+
+    const TODO: &str = "\
+(A) 2026-10-01 Call the bank +money @phone due:2026-10-18\r
+2026-10-02 Water plants due:2026-10-19\r
+\r
+(C) Buy milk @shop\r
+x 2026-10-17 2026-10-10 File taxes pri:B\r
+Book flights due:2026-11-02\r
+";
+
+    #[test]
+    fn todotxt_parses_done_priority_dates_and_due() {
+        let tasks = parse_tasks(&format!("\u{feff}{TODO}"), TaskFormat::Todotxt);
+        assert_eq!(tasks.len(), 5);
+        assert_eq!(tasks[0].text, "Call the bank +money @phone");
+        assert_eq!(tasks[0].priority, Some('A'));
+        assert_eq!(tasks[0].due, Some(day("2026-10-18")));
+        assert!(tasks[0].id.starts_with("0:"));
+        assert_eq!(tasks[2].id.split(':').next(), Some("3"));
+        assert!(tasks[3].done);
+        assert_eq!(
+            (tasks[3].text.as_str(), tasks[3].priority),
+            ("File taxes", Some('B'))
+        );
+        assert_eq!(list("/x/todo.txt").format(), TaskFormat::Todotxt);
+    }
+
+    #[test]
+    fn markdown_checklists_and_due_dates() {
+        let md = "# Work\n- [ ] Ship it due:2026-10-19\n  * [x] Write tests\n- [ ] Review 📅 \
+                  2026-10-30 #pr\n- plain bullet\n- [ ] \n1. [ ] numbered\n";
+        let tasks = parse_tasks(md, TaskFormat::Markdown);
+        let texts: Vec<&str> = tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["Ship it", "Write tests", "Review #pr"]);
+        assert!(tasks[1].done);
+        assert_eq!(tasks[2].due, Some(day("2026-10-30")));
+        assert_eq!(list("/notes/Work.MD").format(), TaskFormat::Markdown);
+    }
+
+    #[test]
+    fn toggle_round_trips_and_keeps_the_rest_of_the_file() {
+        let today = day("2026-10-19");
+        let content = format!("\u{feff}{TODO}");
+        let tasks = parse_tasks(&content, TaskFormat::Todotxt);
+
+        let (done, now_done) =
+            toggle_in(&content, &tasks[0].id, TaskFormat::Todotxt, today).unwrap();
+        assert!(now_done);
+        assert!(done.starts_with(
+            "\u{feff}x 2026-10-19 2026-10-01 Call the bank +money @phone due:2026-10-18 pri:A\r\n"
+        ));
+        assert_eq!(done.lines().count(), content.lines().count());
+        assert!(done.ends_with("Book flights due:2026-11-02\r\n"));
+        // Undo restores the line exactly.
+        let id = parse_tasks(&done, TaskFormat::Todotxt)[0].id.clone();
+        let (back, now_done) = toggle_in(&done, &id, TaskFormat::Todotxt, today).unwrap();
+        assert!(!now_done);
+        assert_eq!(back, content);
+        // A stale id (the line changed) is refused.
+        assert!(toggle_in(&done, &tasks[0].id, TaskFormat::Todotxt, today).is_err());
+
+        let (reopened, now_done) =
+            toggle_in(&content, &tasks[3].id, TaskFormat::Todotxt, today).unwrap();
+        assert!(!now_done);
+        assert!(reopened.contains("\r\n(B) 2026-10-10 File taxes\r\n"));
+
+        let md = "- [ ] a\n  - [x] b\n";
+        let ids: Vec<String> = parse_tasks(md, TaskFormat::Markdown)
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        let (md, _) = toggle_in(md, &ids[1], TaskFormat::Markdown, today).unwrap();
+        assert_eq!(md, "- [ ] a\n  - [ ] b\n");
+        let (md, now_done) = toggle_in(&md, &ids[0], TaskFormat::Markdown, today).unwrap();
+        assert!(now_done);
+        assert_eq!(md, "- [x] a\n  - [ ] b\n");
+    }
+
+    #[test]
+    fn samples_count_due_and_order_the_next_items() {
+        let tasks = parse_tasks(TODO, TaskFormat::Todotxt);
+        let def = TaskList {
+            items: 3,
+            ..list("/x/todo.txt")
+        };
+        let s = samples(&def, &tasks, day("2026-10-19"), 1);
+        let get = |id: &str| {
+            let v = serde_json::to_value(s.iter().find(|x| x.sensor == id).unwrap()).unwrap();
+            v["value"]["value"].clone()
+        };
+        assert_eq!(get("tasks.t.open"), 4.0);
+        assert_eq!(get("tasks.t.overdue"), 1.0);
+        assert_eq!(get("tasks.t.due_today"), 1.0);
+        assert_eq!(get("tasks.t.done"), 1.0);
+        assert_eq!(get("tasks.t.next"), "Call the bank +money @phone");
+        let items = get("tasks.t.items");
+        let texts: Vec<&str> = items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "Call the bank +money @phone",
+                "Water plants",
+                "Book flights"
+            ]
+        );
+        assert_eq!(items[0]["overdue"], true);
+        assert_eq!(items[0]["priority"], "A");
+        assert_eq!(items[1]["due"], "2026-10-19");
+    }
+}
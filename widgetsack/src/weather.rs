@@ -0,0 +1,803 @@
+//! Weather source — a PEER to stocks.rs, built the same way: one server-side reqwest poller for a
+//! configured location, forwarding over the EXISTING `telemetry` event as `weather.*` samples so
+//! any Text/Gauge/Sparkline meter binds them with no extra wiring.
+//!
+//! Provider: Open-Meteo's `/v1/forecast` — keyless, and one call returns the current conditions,
+//! the hourly forecast AND the daily forecast. `base_url` points it at any Open-Meteo-compatible
+//! server (a self-hosted instance); the config has a `provider` field (default "open-meteo") so a
+//! keyed provider can be added later — until then any other value is rejected on save. Publishes:
+//!   `weather.temp` / `.feels_like`   current temperature / apparent temperature
+//!   `weather.condition`              WMO weather code as text ("Partly cloudy")
+//!   `weather.code`                   the raw WMO code, for icon sets
+//!   `weather.wind` / `.wind.dir`     wind speed / direction (degrees)
+//!   `weather.humidity`               relative humidity, %
+//!   `weather.precip.prob`            precipitation probability, %
+//!   `weather.is_day`                 1 between sunrise and sunset
+//!   `weather.temp.unit` / `.wind.unit`  "°C" / "km/h" (or the imperial units)
+//!   `weather.hourly.temp` / `.hourly.precip.prob`   Series over the next `hours` hours
+//!   `weather.daily.max` / `.daily.min`              Series over the next `days` days
+//!   `weather.hourly` / `weather.daily`              the same forecasts as Json arrays
+//!   `weather.status`                 connecting | connected | error
+//!
+//! Configured in `plugins/weather.json`:
+//! `{ "latitude": 52.52, "longitude": 13.41, "units": "metric", "poll_interval_secs": 900 }`
+//! Demand-gated like stocks.rs (with an `always` opt-out for automations), and failures back off
+//! the same way. The last good response is cached in `weather/last.json`: a restart within one
+//! interval serves it instead of re-fetching, and while the provider is failing the cached
+//! forecast keeps rolling forward (flagged stale). The pure seams (`forecast_url`, `condition`,
+//! `forecast_to_samples`, `normalize_config`) are unit-tested without network.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::async_runtime::{JoinHandle, Mutex};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+use crate::bus;
+use crate::command::atomic_write;
+use crate::health;
+use crate::httppoll::http_client;
+use crate::log;
+use crate::sensors::{ActiveSensors, SensorSample, SensorValue};
+
+/// Poll cadence guardrails (seconds). Forecasts update hourly at best, so faster is just load on a
+/// free service.
+const MIN_INTERVAL: u64 = 300;
+const MAX_INTERVAL: u64 = 21_600;
+const MAX_HOURS: usize = 48;
+const MAX_DAYS: usize = 16;
+const FETCH_TIMEOUT_SECS: u64 = 15;
+/// Re-check the demand gate this often while nothing shows the weather.
+const IDLE_RECHECK: Duration = Duration::from_secs(3);
+/// Between fetches the samples are re-derived from the cached response this often, so the hourly
+/// window and `is_day` roll forward.
+const TICK: Duration = Duration::from_secs(60);
+const OPEN_METEO: &str = "https://api.open-meteo.com/v1/forecast";
+
+fn default_provider() -> String {
+    "open-meteo".to_string()
+}
+fn default_interval() -> u64 {
+    900
+}
+fn default_hours() -> usize {
+    24
+}
+fn default_days() -> usize {
+    7
+}
+
+/// Unit system requested from the provider.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// °C, km/h, mm.
+    #[default]
+    Metric,
+    /// °F, mph, inch.
+    Imperial,
+}
+
+/// Server-side weather config (`plugins/weather.json`). Nothing here is secret.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeatherConfig {
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// An Open-Meteo-compatible forecast endpoint; the public API when absent.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub units: Units,
+    #[serde(default = "default_interval")]
+    pub poll_interval_secs: u64,
+    /// Hours carried by the hourly forecast.
+    #[serde(default = "default_hours")]
+    pub hours: usize,
+    /// Days carried by the daily forecast.
+    #[serde(default = "default_days")]
+    pub days: usize,
+    /// Keep polling while no window shows the weather (for automations).
+    #[serde(default)]
+    pub always: bool,
+}
+
+/// Managed state: the running poll task.
+#[derive(Default)]
+pub struct WeatherState {
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// The last good response, with the URL it answered so a moved location doesn't reuse it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    fetched_ms: u64,
+    body: Value,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ---- config I/O (server-side) ----
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("plugins").join("weather.json"))
+}
+
+fn cache_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("weather").join("last.json"))
+}
+
+pub fn load_weather_config<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<WeatherConfig>, String> {
+    let path = config_path(app)?;
+    match std::fs::read_to_string(&path) {
+        Ok(txt) => serde_json::from_str(&txt)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn load_cache<R: Runtime>(app: &AppHandle<R>) -> Option<Cached> {
+    let txt = std::fs::read_to_string(cache_path(app).ok()?).ok()?;
+    serde_json::from_str(&txt).ok()
+}
+
+fn save_cache<R: Runtime>(app: &AppHandle<R>, cached: &Cached) -> Result<(), String> {
+    let path = cache_path(app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string(cached).map_err(|e| e.to_string())?;
+    atomic_write(&path, &txt)
+}
+
+// ---- pure seams (unit-tested, no I/O) ----
+
+/// Validate the location and endpoint, clamp the interval and forecast lengths.
+fn normalize_config(cfg: WeatherConfig) -> Result<WeatherConfig, String> {
+    if !cfg.latitude.is_finite() || !(-90.0..=90.0).contains(&cfg.latitude) {
+        return Err("latitude must be between -90 and 90".into());
+    }
+    if !cfg.longitude.is_finite() || !(-180.0..=180.0).contains(&cfg.longitude) {
+        return Err("longitude must be between -180 and 180".into());
+    }
+    let base_url = cfg
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(str::to_string);
+    if let Some(url) = &base_url
+        && !url.starts_with("http://")
+        && !url.starts_with("https://")
+    {
+        return Err("base_url must be http(s)".into());
+    }
+    let provider = match cfg.provider.trim() {
+        "" => default_provider(),
+        p => p.to_lowercase(),
+    };
+    if provider != "open-meteo" {
+        return Err(format!(
+            "unknown weather provider `{}` (supported: open-meteo)",
+            cfg.provider.trim()
+        ));
+    }
+    Ok(WeatherConfig {
+        provider,
+        base_url,
+        poll_interval_secs: cfg.poll_interval_secs.clamp(MIN_INTERVAL, MAX_INTERVAL),
+        hours: cfg.hours.clamp(1, MAX_HOURS),
+        days: cfg.days.clamp(1, MAX_DAYS),
+        ..cfg
+    })
+}
+
+/// The Open-Meteo forecast request for `cfg`: current conditions plus enough hourly/daily data to
+/// fill `hours` and `days` from now. Times come back as Unix seconds, days split at the location's
+/// own midnight (`timezone=auto`).
+fn forecast_url(cfg: &WeatherConfig) -> String {
+    let base = cfg.base_url.as_deref().unwrap_or(OPEN_METEO);
+    // The hourly series starts at today's midnight, so the last hours need tomorrow (or later).
+    let forecast_days = cfg.days.max(cfg.hours.div_ceil(24) + 1).min(MAX_DAYS);
+    let mut url = format!(
+        "{base}?latitude={}&longitude={}\
+         &current=temperature_2m,apparent_temperature,relative_humidity_2m,\
+         precipitation_probability,weather_code,is_day,wind_speed_10m,wind_direction_10m\
+         &hourly=temperature_2m,precipitation_probability,weather_code\
+         &daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max,\
+         sunrise,sunset\
+         &timezone=auto&timeformat=unixtime&forecast_days={forecast_days}",
+        cfg.latitude, cfg.longitude
+    );
+    if cfg.units == Units::Imperial {
+        url.push_str("&temperature_unit=fahrenheit&wind_speed_unit=mph&precipitation_unit=inch");
+    }
+    url
+}
+
+/// WMO weather interpretation code → a short description.
+fn condition(code: i64) -> &'static str {
+    match code {
+        0 => "Clear",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 => "Drizzle",
+        56 | 57 => "Freezing drizzle",
+        61 => "Light rain",
+        63 => "Rain",
+        65 => "Heavy rain",
+        66 | 67 => "Freezing rain",
+        71 => "Light snow",
+        73 => "Snow",
+        75 => "Heavy snow",
+        77 => "Snow grains",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95 => "Thunderstorm",
+        96 | 99 => "Thunderstorm with hail",
+        _ => "Unknown",
+    }
+}
+
+/// `block[key][i]` as a number.
+fn at(block: &Value, key: &str, i: usize) -> Option<f64> {
+    block[key][i].as_f64()
+}
+
+/// The indices of `block.time` (Unix seconds, each the start of a `step`-second slot) from the
+/// slot containing `now_s`, at most `n` of them.
+fn upcoming(block: &Value, step: i64, now_s: i64, n: usize) -> Vec<(usize, i64)> {
+    let times = block["time"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    times
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| t.as_i64().map(|t| (i, t)))
+        .filter(|&(_, t)| t + step > now_s)
+        .take(n)
+        .collect()
+}
+
+/// Map an Open-Meteo forecast body to telemetry samples as of `now_ms`. Pure: the caller does the
+/// fetch. Every id keeps a stable kind; a missing/null current value emits no sample for that id
+/// (the meter shows its null state rather than a misleading 0), and series drop nulls.
+fn forecast_to_samples(body: &Value, hours: usize, days: usize, now_ms: u64) -> Vec<SensorSample> {
+    let mut out = Vec::new();
+    let current = &body["current"];
+    for (key, id) in [
+        ("temperature_2m", "weather.temp"),
+        ("apparent_temperature", "weather.feels_like"),
+        ("relative_humidity_2m", "weather.humidity"),
+        ("precipitation_probability", "weather.precip.prob"),
+        ("wind_speed_10m", "weather.wind"),
+        ("wind_direction_10m", "weather.wind.dir"),
+        ("is_day", "weather.is_day"),
+    ] {
+        if let Some(v) = current[key].as_f64() {
+            out.push(SensorSample::scalar(id, now_ms, v));
+        }
+    }
+    if let Some(code) = current["weather_code"].as_i64() {
+        out.push(SensorSample::scalar("weather.code", now_ms, code as f64));
+        out.push(SensorSample::text(
+            "weather.condition",
+            now_ms,
+            condition(code),
+        ));
+    }
+    let units = &body["current_units"];
+    for (key, id) in [
+        ("temperature_2m", "weather.temp.unit"),
+        ("wind_speed_10m", "weather.wind.unit"),
+    ] {
+        if let Some(u) = units[key].as_str() {
+            out.push(SensorSample::text(id, now_ms, u));
+        }
+    }
+
+    let now_s = (now_ms / 1000) as i64;
+    let hourly = &body["hourly"];
+    if hourly.is_object() {
+        let slots = upcoming(hourly, 3600, now_s, hours);
+        let series = |key| -> Vec<f64> {
+            slots
+                .iter()
+                .filter_map(|&(i, _)| at(hourly, key, i))
+                .collect()
+        };
+        let items: Vec<Value> = slots
+            .iter()
+            .map(|&(i, t)| {
+                let code = hourly["weather_code"][i].as_i64();
+                json!({
+                    "time": t * 1000,
+                    "temp": at(hourly, "temperature_2m", i),
+                    "precipProb": at(hourly, "precipitation_probability", i),
+                    "code": code,
+                    "condition": code.map(condition),
+                })
+            })
+            .collect();
+        out.push(series_sample(
+            "weather.hourly.temp",
+            now_ms,
+            series("temperature_2m"),
+        ));
+        out.push(series_sample(
+            "weather.hourly.precip.prob",
+            now_ms,
+            series("precipitation_probability"),
+        ));
+        out.push(json_sample("weather.hourly", now_ms, items));
+    }
+
+    let daily = &body["daily"];
+    if daily.is_object() {
+        let slots = upcoming(daily, 86_400, now_s, days);
+        let series = |key| -> Vec<f64> {
+            slots
+                .iter()
+                .filter_map(|&(i, _)| at(daily, key, i))
+                .collect()
+        };
+        let ms = |key, i| daily[key][i].as_i64().map(|s: i64| s * 1000);
+        let items: Vec<Value> = slots
+            .iter()
+            .map(|&(i, t)| {
+                let code = daily["weather_code"][i].as_i64();
+                json!({
+                    "date": t * 1000,
+                    "max": at(daily, "temperature_2m_max", i),
+                    "min": at(daily, "temperature_2m_min", i),
+                    "precipProb": at(daily, "precipitation_probability_max", i),
+                    "code": code,
+                    "condition": code.map(condition),
+                    "sunrise": ms("sunrise", i),
+                    "sunset": ms("sunset", i),
+                })
+            })
+            .collect();
+        out.push(series_sample(
+            "weather.daily.max",
+            now_ms,
+            series("temperature_2m_max"),
+        ));
+        out.push(series_sample(
+            "weather.daily.min",
+            now_ms,
+            series("temperature_2m_min"),
+        ));
+        out.push(json_sample("weather.daily", now_ms, items));
+    }
+    out
+}
+
+fn series_sample(id: &str, ts_ms: u64, values: Vec<f64>) -> SensorSample {
+    SensorSample {
+        sensor: id.to_string(),
+        ts_ms,
+        value: SensorValue::Series(values),
+        stale: false,
+    }
+}
+
+fn json_sample(id: &str, ts_ms: u64, items: Vec<Value>) -> SensorSample {
+    SensorSample {
+        sensor: id.to_string(),
+        ts_ms,
+        value: SensorValue::Json(Value::Array(items)),
+        stale: false,
+    }
+}
+
+// ---- telemetry emission ----
+
+/// `weather.status`, mirrored into health like stocks.rs does.
+fn emit_status<R: Runtime>(app: &AppHandle<R>, status: &str) {
+    health::report_status(app, "weather", status);
+    let batch = vec![SensorSample::text("weather.status", now_ms(), status)];
+    let _ = bus::publish(app, &batch);
+}
+
+/// True while any window is consuming a `weather.*` sensor. Default OFF until a window has
+/// reported, like `stocks_wanted`, so startup doesn't call the provider for nobody.
+fn weather_wanted<R: Runtime>(app: &AppHandle<R>) -> bool {
+    let active: State<ActiveSensors> = app.state();
+    let guard = active.0.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.values().all(|ids| ids.is_empty()) {
+        return false;
+    }
+    crate::sensors::any_wanted(&guard, |id| id.starts_with("weather."))
+}
+
+/// Publish the samples derived from `cached`, flagged stale while the provider is failing.
+fn publish_cached<R: Runtime>(
+    app: &AppHandle<R>,
+    cfg: &WeatherConfig,
+    cached: &Cached,
+    stale: bool,
+) {
+    let mut batch = forecast_to_samples(&cached.body, cfg.hours, cfg.days, now_ms());
+    for s in &mut batch {
+        s.stale = stale;
+    }
+    if !batch.is_empty() {
+        let _ = bus::publish(app, &batch);
+    }
+}
+
+// ---- poll task ----
+
+async fn fetch_forecast(client: &reqwest::Client, url: &str) -> Result<Value, String> {
+    let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status().as_u16()));
+    }
+    let body = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+    // Open-Meteo answers a bad parameter with `{ "error": true, "reason": … }`.
+    if body["error"].as_bool() == Some(true) {
+        return Err(body["reason"]
+            .as_str()
+            .unwrap_or("provider error")
+            .to_string());
+    }
+    Ok(body)
+}
+
+/// Poll the forecast on the interval, emitting `weather.*` telemetry. Demand-gated unless
+/// `always`; between fetches the cached response is re-published every `TICK`. Runs until aborted
+/// by `weather_disconnect` or a config save.
+pub async fn run_weather_client<R: Runtime>(app: AppHandle<R>, cfg: WeatherConfig) {
+    let client = match http_client(FETCH_TIMEOUT_SECS, false) {
+        Ok(c) => c,
+        Err(err) => {
+            log::warn("weather", "client build failed")
+                .field("error", err)
+                .emit();
+            emit_status(&app, "error");
+            return;
+        }
+    };
+    let url = forecast_url(&cfg);
+    let interval_ms = cfg.poll_interval_secs * 1000;
+
+    // A cached response for this same request, younger than one interval, stands in for the first
+    // fetch (an app restart doesn't re-hit the provider).
+    let mut cached = load_cache(&app).filter(|c| c.url == url);
+    let mut next_fetch = cached.as_ref().map_or(0, |c| c.fetched_ms + interval_ms);
+    let mut idle = true;
+    let mut fails: u32 = 0;
+    loop {
+        if !cfg.always && !weather_wanted(&app) {
+            idle = true;
+            tokio::time::sleep(IDLE_RECHECK).await;
+            continue;
+        }
+        if idle {
+            let fresh = cached.is_some() && now_ms() < next_fetch;
+            emit_status(&app, if fresh { "connected" } else { "connecting" });
+            idle = false;
+        }
+        if now_ms() >= next_fetch {
+            match fetch_forecast(&client, &url).await {
+                Ok(body) => {
+                    fails = 0;
+                    let fresh = Cached {
+                        url: url.clone(),
+                        fetched_ms: now_ms(),
+                        body,
+                    };
+                    if let Err(err) = save_cache(&app, &fresh) {
+                        log::warn("weather", "failed to save the forecast cache")
+                            .field("error", err)
+                            .emit();
+                    }
+                    cached = Some(fresh);
+                    emit_status(&app, "connected");
+                }
+                Err(err) => {
+                    fails = (fails + 1).min(5);
+                    log::warn("weather", "forecast fetch failed")
+                        .field("error", err)
+                        .emit();
+                    emit_status(&app, "error");
+                }
+            }
+            // stocks.rs' backoff: 2× … 16× the interval on a run of failures, capped at 30 min (or
+            // the interval itself when that's longer).
+            let wait = if fails == 0 {
+                interval_ms
+            } else {
+                interval_ms
+                    .saturating_mul(1u64 << fails.min(4))
+                    .min(1_800_000.max(interval_ms))
+            };
+            next_fetch = now_ms() + wait;
+        }
+        if let Some(c) = &cached {
+            publish_cached(&app, &cfg, c, fails > 0);
+        }
+        let until_fetch = Duration::from_millis(next_fetch.saturating_sub(now_ms()));
+        tokio::time::sleep(TICK.min(until_fetch)).await;
+    }
+}
+
+/// Replace the running poll task (if any) with one for `cfg`.
+async fn restart<R: Runtime>(app: &AppHandle<R>, state: &WeatherState, cfg: WeatherConfig) {
+    let mut guard = state.handle.lock().await;
+    if let Some(handle) = guard.take() {
+        handle.abort();
+    }
+    let app_for_task = app.clone();
+    *guard = Some(tauri::async_runtime::spawn(async move {
+        run_weather_client(app_for_task, cfg).await;
+    }));
+}
+
+/// Start polling at launch when `weather.json` exists, so an overlay shows the weather without a
+/// settings visit first.
+pub async fn connect_on_startup<R: Runtime>(app: AppHandle<R>) {
+    match load_weather_config(&app).and_then(|c| c.map(normalize_config).transpose()) {
+        Ok(Some(cfg)) => restart(&app, &app.state::<WeatherState>(), cfg).await,
+        Ok(None) => {}
+        Err(err) => log::warn("weather", "invalid weather.json")
+            .field("error", err)
+            .emit(),
+    }
+}
+
+// ---- Tauri commands ----
+
+/// Persist `plugins/weather.json` and restart the poller with it. Studio-window-guarded like the
+/// other plugin configs; returns the config as normalized.
+#[tauri::command]
+pub async fn save_weather_config(
+    window: tauri::WebviewWindow,
+    app: AppHandle,
+    state: State<'_, WeatherState>,
+    config: WeatherConfig,
+) -> Result<WeatherConfig, String> {
+    if window.label() != "studio" {
+        return Err("save_weather_config is only allowed from the studio window".into());
+    }
+    let cfg = normalize_config(config)?;
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let txt = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+    std::fs::write(&path, txt).map_err(|e| e.to_string())?;
+    restart(&app, &state, cfg.clone()).await;
+    Ok(cfg)
+}
+
+/// The saved config, or `None` before a location has been set.
+#[tauri::command]
+pub fn weather_config_status<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Option<WeatherConfig>, String> {
+    load_weather_config(&app)
+}
+
+/// Start the poll task if not already running. Idempotent; fails until a location is configured.
+#[tauri::command]
+pub async fn weather_connect<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, WeatherState>,
+) -> Result<(), String> {
+    let cfg = load_weather_config(&app)?.ok_or("no weather location configured")?;
+    let cfg = normalize_config(cfg)?;
+    if state.handle.lock().await.is_some() {
+        return Ok(());
+    }
+    restart(&app, &state, cfg).await;
+    Ok(())
+}
+
+/// Stop the poll task (if any).
+#[tauri::command]
+pub async fn weather_disconnect(state: State<'_, WeatherState>) -> Result<(), String> {
+    if let Some(handle) = state.handle.lock().await.take() {
+        handle.abort();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WeatherConfig {
+        serde_json::from_str(r#"{ "latitude": 52.52, "longitude": 13.41 }"#).unwrap()
+    }
+
+    fn find<'a>(samples: &'a [SensorSample], id: &str) -> Option<&'a SensorSample> {
+        samples.iter().find(|s| s.sensor == id)
+    }
+
+    fn value(samples: &[SensorSample], id: &str) -> Value {
+        serde_json::to_value(find(samples, id).unwrap()).unwrap()["value"]["value"].clone()
+    }
+
+    // 2026-10-19 00:00 UTC; the body's days start at the location's midnight.
+    const MIDNIGHT: i64 = 1_792_368_000;
+
+    fn sample_body() -> Value {
+        let hours: Vec<i64> = (0..48).map(|h| MIDNIGHT + h * 3600).collect();
+        let temps: Vec<Value> = (0..48)
+            .map(|h| {
+                if h == 15 {
+                    Value::Null
+                } else {
+                    json!(h as f64)
+                }
+            })
+            .collect();
+        json!({
+            "current_units": { "temperature_2m": "°C", "wind_speed_10m": "km/h" },
+            "current": {
+                "time": MIDNIGHT + 14 * 3600,
+                "temperature_2m": 12.5,
+                "apparent_temperature": 10.0,
+                "relative_humidity_2m": 81,
+                "precipitation_probability": 40,
+                "weather_code": 61,
+                "is_day": 1,
+                "wind_speed_10m": 14.2,
+                "wind_direction_10m": 230,
+            },
+            "hourly": {
+                "time": hours,
+                "temperature_2m": temps,
+                "precipitation_probability": vec![10; 48],
+                "weather_code": vec![3; 48],
+            },
+            "daily": {
+                "time": [MIDNIGHT, MIDNIGHT + 86_400],
+                "weather_code": [61, 0],
+                "temperature_2m_max": [14.0, 17.0],
+                "temperature_2m_min": [6.0, 8.0],
+                "precipitation_probability_max": [80, 5],
+                "sunrise": [MIDNIGHT + 6 * 3600, MIDNIGHT + 30 * 3600],
+                "sunset": [MIDNIGHT + 17 * 3600, MIDNIGHT + 41 * 3600],
+            }
+        })
+    }
+
+    #[test]
+    fn url_requests_current_hourly_daily_and_units() {
+        let url = forecast_url(&config());
+        assert!(
+            url.starts_with(
+                "https://api.open-meteo.com/v1/forecast?latitude=52.52&longitude=13.41&"
+            )
+        );
+        assert!(url.contains("&current=temperature_2m,"));
+        assert!(url.contains("&timeformat=unixtime&forecast_days=7"));
+        assert!(!url.contains("fahrenheit"));
+
+        let imperial = WeatherConfig {
+            units: Units::Imperial,
+            base_url: Some("http://meteo.lan:8080/v1/forecast".into()),
+            hours: 48,
+            days: 1,
+            ..config()
+        };
+        let url = forecast_url(&imperial);
+        assert!(url.starts_with("http://meteo.lan:8080/v1/forecast?"));
+        assert!(url.contains("temperature_unit=fahrenheit&wind_speed_unit=mph"));
+        // 48 hours from today's midnight reach into the third day.
+        assert!(url.contains("forecast_days=3"));
+    }
+
+    #[test]
+    fn normalize_checks_location_and_clamps() {
+        let cfg = normalize_config(WeatherConfig {
+            provider: " ".into(),
+            poll_interval_secs: 5,
+            hours: 500,
+            days: 0,
+            base_url: Some("  ".into()),
+            ..config()
+        })
+        .unwrap();
+        assert_eq!(cfg.provider, "open-meteo");
+        assert_eq!(cfg.poll_interval_secs, MIN_INTERVAL);
+        assert_eq!((cfg.hours, cfg.days), (MAX_HOURS, 1));
+        assert_eq!(cfg.base_url, None);
+
+        assert!(
+            normalize_config(WeatherConfig {
+                latitude: 91.0,
+                ..config()
+            })
+            .is_err()
+        );
+        assert!(
+            normalize_config(WeatherConfig {
+                longitude: f64::NAN,
+                ..config()
+            })
+            .is_err()
+        );
+        let ftp = WeatherConfig {
+            base_url: Some("ftp://x".into()),
+            ..config()
+        };
+        assert!(normalize_config(ftp).is_err());
+        let err = normalize_config(WeatherConfig {
+            provider: "darksky".into(),
+            ..config()
+        })
+        .unwrap_err();
+        assert!(err.contains("darksky"));
+    }
+
+    #[test]
+    fn wmo_codes_map_to_conditions() {
+        assert_eq!(condition(0), "Clear");
+        assert_eq!(condition(2), "Partly cloudy");
+        assert_eq!(condition(81), "Rain showers");
+        assert_eq!(condition(99), "Thunderstorm with hail");
+        assert_eq!(condition(42), "Unknown");
+    }
+
+    #[test]
+    fn forecast_emits_current_and_rolls_the_hourly_window() {
+        // 14:30 — the 14:00 slot is the first hourly entry.
+        let now = ((MIDNIGHT + 14 * 3600 + 1800) * 1000) as u64;
+        let s = forecast_to_samples(&sample_body(), 4, 7, now);
+
+        assert_eq!(value(&s, "weather.temp"), 12.5);
+        assert_eq!(value(&s, "weather.humidity"), 81.0);
+        assert_eq!(value(&s, "weather.precip.prob"), 40.0);
+        assert_eq!(value(&s, "weather.condition"), "Light rain");
+        assert_eq!(value(&s, "weather.is_day"), 1.0);
+        assert_eq!(value(&s, "weather.temp.unit"), "°C");
+
+        // The null at 15:00 is dropped from the series but kept (as null) in the Json.
+        assert_eq!(value(&s, "weather.hourly.temp"), json!([14.0, 16.0, 17.0]));
+        let hourly = value(&s, "weather.hourly");
+        assert_eq!(hourly.as_array().unwrap().len(), 4);
+        assert_eq!(hourly[0]["time"], (MIDNIGHT + 14 * 3600) * 1000);
+        assert_eq!(hourly[1]["temp"], Value::Null);
+        assert_eq!(hourly[0]["condition"], "Overcast");
+
+        // Today is still the first day until midnight.
+        assert_eq!(value(&s, "weather.daily.max"), json!([14.0, 17.0]));
+        let daily = value(&s, "weather.daily");
+        assert_eq!(daily[0]["sunset"], (MIDNIGHT + 17 * 3600) * 1000);
+        assert_eq!(daily[1]["condition"], "Clear");
+
+        // Past midnight, today drops out.
+        let tomorrow = ((MIDNIGHT + 86_400 + 60) * 1000) as u64;
+        let s = forecast_to_samples(&sample_body(), 4, 7, tomorrow);
+        assert_eq!(value(&s, "weather.daily.min"), json!([8.0]));
+    }
+
+    #[test]
+    fn missing_blocks_emit_nothing_for_them() {
+        let body = json!({ "current": { "temperature_2m": null, "relative_humidity_2m": 50 } });
+        let s = forecast_to_samples(&body, 24, 7, 0);
+        assert!(find(&s, "weather.temp").is_none());
+        assert!(find(&s, "weather.condition").is_none());
+        assert!(find(&s, "weather.hourly").is_none());
+        assert_eq!(value(&s, "weather.humidity"), 50.0);
+    }
+}
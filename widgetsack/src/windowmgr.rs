@@ -557,7 +557,7 @@ unsafe extern "system" fn win_event_proc(
 // ---- Windows implementation ----
 
 #[cfg(target_os = "windows")]
-fn list_arrangeable() -> Result<Vec<WindowDescriptor>, String> {
+pub(crate) fn list_arrangeable() -> Result<Vec<WindowDescriptor>, String> {
     use std::ffi::c_void;
     use std::mem::size_of;
     use windows::core::BOOL;
@@ -776,7 +776,7 @@ fn snap(hwnd: i64, zone: ScreenRect) -> Result<(), String> {
 }
 
 #[cfg(not(target_os = "windows"))]
-fn list_arrangeable() -> Result<Vec<WindowDescriptor>, String> {
+pub(crate) fn list_arrangeable() -> Result<Vec<WindowDescriptor>, String> {
     Err("window management is only available on Windows".to_string())
 }
 